@@ -0,0 +1,70 @@
+
+//! Procedural macros for glsl-cookbook-rs.
+//!
+//! Use them through the re-exports of the main crate (e.g. `glsl_cookbook_rs::layout::uniform_block`)
+//! instead of depending on this crate directly, since the generated code refers to `::glsl_cookbook_rs`.
+
+extern crate proc_macro;
+
+mod uniform_block;
+mod vertex;
+
+use proc_macro::TokenStream;
+
+
+/// Lay out a struct as a std140 (default) or std430 uniform block.
+///
+/// The padding fields required by the layout are inserted automatically,
+/// `glium::uniforms::UniformBlock` is implemented for the struct,
+/// and the size of the struct is checked against the layout at compile time.
+///
+/// ```ignore
+/// #[uniform_block]
+/// #[derive(Debug, Clone, Copy, Default)]
+/// struct LightInfo {
+///     Position: [f32; 4],
+///     La: [f32; 3],
+///     L : [f32; 3],
+/// }
+///
+/// #[uniform_block(std430)]
+/// #[derive(Debug, Clone, Copy, Default)]
+/// struct Particle { ... }
+/// ```
+///
+/// See `glsl_cookbook_rs::layout` for the supported field types.
+#[proc_macro_attribute]
+pub fn uniform_block(args: TokenStream, input: TokenStream) -> TokenStream {
+
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let input = syn::parse_macro_input!(input as syn::ItemStruct);
+
+    uniform_block::expand(args, input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Implement `glium::Vertex` for a `#[repr(C)]` struct, without any padding field.
+///
+/// The name of each attribute is the name of field by default. Use `#[vertex(rename = "...")]` to bind the field
+/// to another attribute, and `#[vertex(normalize)]` to map the integers to [0, 1] or [-1, 1] in shader.
+///
+/// ```ignore
+/// #[repr(C)]
+/// #[derive(Debug, Clone, Copy, Vertex)]
+/// struct ColorVertex {
+///     #[vertex(rename = "VertexPosition")]
+///     position: [f32; 3],
+///     #[vertex(rename = "VertexColor", normalize)]
+///     color: [u8; 4],
+/// }
+/// ```
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    vertex::expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
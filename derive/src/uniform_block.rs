@@ -0,0 +1,158 @@
+
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, format_ident};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Error, Field, Fields, Ident, ItemStruct, Meta, NestedMeta, Result, Token, Visibility};
+
+
+#[derive(Debug, Clone, Copy)]
+enum BlockLayout {
+    Std140,
+    Std430,
+}
+
+impl BlockLayout {
+
+    fn from_args(args: &[NestedMeta]) -> Result<BlockLayout> {
+
+        match args {
+            | [] => Ok(BlockLayout::Std140),
+            | [NestedMeta::Meta(Meta::Path(path))] if path.is_ident("std140") => Ok(BlockLayout::Std140),
+            | [NestedMeta::Meta(Meta::Path(path))] if path.is_ident("std430") => Ok(BlockLayout::Std430),
+            | _ => Err(Error::new(args[0].span(), "Expect `std140` or `std430` as the layout of uniform block.")),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            | BlockLayout::Std140 => "std140",
+            | BlockLayout::Std430 => "std430",
+        }
+    }
+
+    fn trait_ident(&self) -> Ident {
+        match self {
+            | BlockLayout::Std140 => format_ident!("Std140"),
+            | BlockLayout::Std430 => format_ident!("Std430"),
+        }
+    }
+}
+
+pub fn expand(args: Vec<NestedMeta>, mut input: ItemStruct) -> Result<TokenStream> {
+
+    let layout = BlockLayout::from_args(&args)?;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "Generic struct is not supported as uniform block."))
+    }
+
+    let fields: Vec<Field> = match &input.fields {
+        | Fields::Named(named) if !named.named.is_empty() => named.named.iter().cloned().collect(),
+        | _ => return Err(Error::new(input.ident.span(), "Uniform block must be a struct with named fields.")),
+    };
+
+    let krate = quote!(::glsl_cookbook_rs::layout);
+    let layout_trait = {
+        let trait_ident = layout.trait_ident();
+        quote!(#krate::#trait_ident)
+    };
+
+    let name = input.ident.clone();
+    let vis = input.vis.clone();
+    let const_prefix = format!("__{}_{}", name, layout.name());
+    let align_const = format_ident!("{}_align", const_prefix);
+    let size_const  = format_ident!("{}_size", const_prefix);
+
+    // Compute the offset of each field as hidden constants, and insert padding between fields.
+    let mut consts = Vec::with_capacity(fields.len() + 2);
+    let mut padded_fields: Punctuated<Field, Token![,]> = Punctuated::new();
+    let mut previous_end = quote!(0);
+
+    for (i, field) in fields.iter().enumerate() {
+
+        let ty = &field.ty;
+        let offset_const = format_ident!("{}_offset_{}", const_prefix, i);
+
+        consts.push(quote! {
+            const #offset_const: usize = #krate::align_up(#previous_end, <#ty as #layout_trait>::ALIGN);
+        });
+
+        if i > 0 {
+            padded_fields.push(padding_field(&vis, i, quote!(#offset_const - (#previous_end)))?);
+        }
+        padded_fields.push(field.clone());
+
+        previous_end = quote!(#offset_const + <#ty as #layout_trait>::SIZE);
+    }
+
+    // The alignment of the whole struct is rounded up to vec4 in std140, but not in std430.
+    let struct_align = match layout {
+        | BlockLayout::Std140 => quote!(#krate::STD140_STRUCT_ALIGN),
+        | BlockLayout::Std430 => fields.iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(<#ty as #layout_trait>::ALIGN)
+            })
+            .fold(quote!(0), |max, align| quote!(#krate::max_align(#max, #align))),
+    };
+    consts.push(quote! {
+        const #align_const: usize = #struct_align;
+    });
+    consts.push(quote! {
+        const #size_const: usize = #krate::align_up(#previous_end, #align_const);
+    });
+    padded_fields.push(padding_field(&vis, fields.len(), quote!(#size_const - (#previous_end)))?);
+
+    if let Fields::Named(named) = &mut input.fields {
+        named.named = padded_fields;
+    }
+
+    if !input.attrs.iter().any(|attr| attr.path.is_ident("repr")) {
+        input.attrs.push(syn::parse_quote!(#[repr(C)]));
+    }
+
+    // The size of each field in Rust must be the same as its size in the block.
+    let field_size_checks = fields.iter().map(|field| {
+        let ty = &field.ty;
+        quote_spanned! { ty.span() =>
+            const _: [(); <#ty as #layout_trait>::SIZE] = [(); ::std::mem::size_of::<#ty>()];
+        }
+    });
+    let field_names = fields.iter().map(|field| field.ident.as_ref().unwrap());
+
+    let expanded = quote! {
+        #input
+
+        #(
+            #[doc(hidden)]
+            #[allow(non_upper_case_globals)]
+            #consts
+        )*
+
+        impl #layout_trait for #name {
+            const ALIGN: usize = #align_const;
+            const SIZE : usize = #size_const;
+        }
+
+        #(#field_size_checks)*
+        const _: [(); #size_const] = [(); ::std::mem::size_of::<#name>()];
+
+        const _: () = {
+            use #krate::__private::implement_uniform_block;
+            implement_uniform_block!(#name, #(#field_names),*);
+        };
+    };
+
+    Ok(expanded)
+}
+
+fn padding_field(vis: &Visibility, index: usize, length: TokenStream) -> Result<Field> {
+
+    let ident = format_ident!("_padding{}", index);
+    Field::parse_named.parse2(quote! {
+        #[doc(hidden)]
+        #vis #ident: [u8; #length]
+    })
+}
@@ -0,0 +1,134 @@
+
+use proc_macro2::TokenStream;
+use quote::{quote, format_ident};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Lit, LitStr, Meta, NestedMeta, Result};
+
+
+/// The options given by `#[vertex(...)]` on a field.
+#[derive(Default)]
+struct AttributeOptions {
+    rename: Option<LitStr>,
+    normalize: bool,
+}
+
+impl AttributeOptions {
+
+    fn from_attrs(attrs: &[Attribute]) -> Result<AttributeOptions> {
+
+        let mut options = AttributeOptions::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("vertex")) {
+            let list = match attr.parse_meta()? {
+                | Meta::List(list) => list,
+                | meta => return Err(Error::new(meta.span(), "Expect `#[vertex(rename = \"...\", normalize)]`.")),
+            };
+
+            for nested in list.nested.iter() {
+                match nested {
+                    | NestedMeta::Meta(Meta::NameValue(pair)) if pair.path.is_ident("rename") => {
+                        match &pair.lit {
+                            | Lit::Str(name) => options.rename = Some(name.clone()),
+                            | lit => return Err(Error::new(lit.span(), "Expect a string literal as the name of vertex attribute.")),
+                        }
+                    },
+                    | NestedMeta::Meta(Meta::Path(path)) if path.is_ident("normalize") => {
+                        options.normalize = true;
+                    },
+                    | _ => return Err(Error::new(nested.span(), "Unknown vertex option, expect `rename = \"...\"` or `normalize`.")),
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// The memory layout declared by `#[repr(...)]`.
+enum Repr {
+    C,
+    Packed,
+}
+
+fn parse_repr(input: &DeriveInput) -> Result<Repr> {
+
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            let is_repr = |name: &str| list.nested.iter().any(|nested| match nested {
+                | NestedMeta::Meta(Meta::Path(path)) => path.is_ident(name),
+                | _ => false,
+            });
+
+            if is_repr("C") {
+                return Ok(if is_repr("packed") { Repr::Packed } else { Repr::C })
+            }
+        }
+    }
+
+    Err(Error::new(input.ident.span(), "Vertex struct must be declared with `#[repr(C)]` or `#[repr(C, packed)]`, so that the offsets of its fields are well defined."))
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(input.generics.span(), "Generic struct is not supported as vertex."))
+    }
+
+    let fields = match &input.data {
+        | Data::Struct(data) => match &data.fields {
+            | Fields::Named(named) if !named.named.is_empty() => named.named.iter().collect::<Vec<_>>(),
+            | _ => return Err(Error::new(input.ident.span(), "Vertex must be a struct with named fields.")),
+        },
+        | _ => return Err(Error::new(input.ident.span(), "Vertex must be a struct with named fields.")),
+    };
+
+    let repr = parse_repr(&input)?;
+    let name = &input.ident;
+
+    // Compute the offset of each field according to the rules of `#[repr(C)]`.
+    let mut offsets = Vec::with_capacity(fields.len());
+    let mut bindings = Vec::with_capacity(fields.len());
+    let mut previous_end = quote!(0);
+
+    for (i, field) in fields.iter().enumerate() {
+
+        let options = AttributeOptions::from_attrs(&field.attrs)?;
+        let ty = &field.ty;
+        let offset = format_ident!("offset{}", i);
+
+        offsets.push(match repr {
+            | Repr::C      => quote! { let #offset = ::glsl_cookbook_rs::layout::align_up(#previous_end, ::std::mem::align_of::<#ty>()); },
+            | Repr::Packed => quote! { let #offset = #previous_end; },
+        });
+
+        let attribute_name = match options.rename {
+            | Some(rename) => rename,
+            | None => {
+                let ident = field.ident.as_ref().unwrap();
+                LitStr::new(&ident.to_string(), ident.span())
+            },
+        };
+        let normalize = options.normalize;
+
+        bindings.push(quote! {
+            (Cow::Borrowed(#attribute_name), #offset, <#ty as ::glium::vertex::Attribute>::get_type(), #normalize)
+        });
+
+        previous_end = quote!(#offset + ::std::mem::size_of::<#ty>());
+    }
+
+    let expanded = quote! {
+        impl ::glium::vertex::Vertex for #name {
+
+            fn build_bindings() -> ::glium::vertex::VertexFormat {
+                use ::std::borrow::Cow;
+
+                #(#offsets)*
+
+                Cow::Owned(vec![#(#bindings),*])
+            }
+        }
+    };
+
+    Ok(expanded)
+}
@@ -1,5 +1,6 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
+use cookbook::spirv::{SpirvModule, SpirvShader, GLSpirvCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 
 use glium::backend::Facade;
@@ -38,7 +39,7 @@ impl Scene for SceneBasic {
         // Choose one of the following options for the shader program.
         //  1)  Compile the shader program normally
         //  2)  Load a binary (pre-compiled) shader program.  (file: "shader/program.bin")
-        //  3)  Load a SPIR-V shader program. (files: "shader/vert.spv" and "shader/frag.spv")
+        //  3)  Load a SPIR-V shader program. (files: "shaders/basic.vert.spv" and "shaders/basic.frag.spv")
         //
         // Optionally, you may attempt to write out the shader program binary using the function writeShaderBinary().
         // **************************************************************************************
@@ -51,8 +52,8 @@ impl Scene for SceneBasic {
         // let shaderFormat: i32 = 36385;
         // let program = load_shader_binary(shaderFormat);
 
-        // (3) Load a SPIR-V shader
-        // let program = load_spriv_shader();
+        // (3) Load a SPIR-V shader. The GLSL sources are used if ARB_gl_spirv is not supported.
+        // let program = SceneBasic::_load_spriv_shader(display)?;
 
         // Optional: use this to write the shader binary out to a file.
         // let program = write_shader_binary();
@@ -124,8 +125,27 @@ impl SceneBasic {
         unimplemented!()
     }
 
-    fn _load_spriv_shader() -> Result<Program, ProgramCreationError>  {
-        unimplemented!()
+    fn _load_spriv_shader(display: &impl Facade) -> GLResult<Program> {
+
+        println!("Loading SPIR-V shaders: shaders/basic.vert.spv, shaders/basic.frag.spv");
+
+        // The SPIR-V modules can be generated by glslangValidator, for example:
+        // $ glslangValidator -G -S vert -o basic.vert.spv basic.vert.glsl
+        let vertex_module   = SpirvModule::load("examples/chapter01/shaders/basic.vert.spv")?;
+        let fragment_module = SpirvModule::load("examples/chapter01/shaders/basic.frag.spv")?;
+
+        let spirv = GLSpirvCode::new(
+            SpirvShader::new(&vertex_module, "main"),
+            SpirvShader::new(&fragment_module, "main"),
+        );
+
+        let sources = GLSourceCode::new(include_str!("shaders/basic.vert.glsl"), include_str!("shaders/basic.frag.glsl"))
+            .with_srgb_output(true)
+            .with_spirv(spirv);
+        let program = glium::Program::new(display, sources.for_context(display))
+            .map_err(GLErrorKind::CreateProgram)?;
+
+        Ok(program)
     }
 
     fn _write_shader_binary() -> Result<Program, ProgramCreationError>  {
@@ -1,14 +1,17 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+use cookbook::layout::{uniform_block, verify_uniform_block};
 use cookbook::objects::Teapot;
+use cookbook::subroutine::SubroutineSelection;
+use cookbook::uniforms::DynamicUniforms;
 use cookbook::{Mat4F, Mat3F, Vec3F};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
-use glium::program::{Program, ProgramCreationError};
+use glium::program::{Program, ProgramCreationError, ShaderStage};
 use glium::uniforms::UniformBuffer;
-use glium::{Surface, uniform, implement_uniform_block};
+use glium::Surface;
 
 
 #[derive(Debug)]
@@ -20,27 +23,30 @@ pub struct SceneSubroutine {
     material_buffer: UniformBuffer<MaterialInfo>,
     light_buffer   : UniformBuffer<LightInfo>,
 
+    /// The shading models of the left and right teapots.
+    shade_models: [SubroutineSelection; 2],
+
     view       : Mat4F,
     model      : Mat4F,
     projection : Mat4F,
 }
 
+#[uniform_block]
 #[allow(non_snake_case)]
-#[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 struct LightInfo {
     LightPosition: [f32; 4],
-    La: [f32; 3], _padding1: f32,
-    Ld: [f32; 3], _padding2: f32,
+    La: [f32; 3],
+    Ld: [f32; 3],
     Ls: [f32; 3],
 }
 
+#[uniform_block]
 #[allow(non_snake_case)]
-#[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 struct MaterialInfo {
-    Ka: [f32; 3], _padding1: f32,
-    Kd: [f32; 3], _padding2: f32,
+    Ka: [f32; 3],
+    Kd: [f32; 3],
     Ks: [f32; 3],
     Shininess: f32,
 }
@@ -53,6 +59,17 @@ impl Scene for SceneSubroutine {
         // Shader Program ------------------------------------------------------------
         let program = SceneSubroutine::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
+        verify_uniform_block::<LightInfo>(&program, "LightInfo")?;
+        verify_uniform_block::<MaterialInfo>(&program, "MaterialInfo")?;
+
+        let shade_models = [
+            SubroutineSelection::new().select("shadeModel", ShaderStage::Vertex, "phongModel"),
+            SubroutineSelection::new().select("shadeModel", ShaderStage::Vertex, "diffuseOnly"),
+        ];
+        for shade_model in shade_models.iter() {
+            shade_model.verify(&program)?;
+        }
+        // cookbook::utils::print_active_subroutines(&program);
         // ----------------------------------------------------------------------------
 
 
@@ -69,7 +86,6 @@ impl Scene for SceneSubroutine {
 
 
         // Initialize Uniforms --------------------------------------------------------
-        glium::implement_uniform_block!(LightInfo, LightPosition, La, Ld, Ls);
         let light_buffer = UniformBuffer::immutable(display, LightInfo {
             LightPosition: [0.0, 0.0, 0.0, 1.0],
             La: [0.4_f32, 0.4, 0.4],
@@ -77,7 +93,6 @@ impl Scene for SceneSubroutine {
             Ls: [1.0_f32, 1.0, 1.0], ..Default::default()
         }).map_err(BufferCreationErrorKind::UniformBlock)?;
 
-        glium::implement_uniform_block!(MaterialInfo, Ka, Kd, Ks, Shininess);
         let material_buffer = UniformBuffer::immutable(display, MaterialInfo {
             Ka: [0.9_f32, 0.5, 0.3],
             Kd: [0.9_f32, 0.5, 0.3],
@@ -91,6 +106,7 @@ impl Scene for SceneSubroutine {
             program,
             teapot,
             material_buffer, light_buffer,
+            shade_models,
             view, model, projection,
         };
         Ok(scene)
@@ -115,42 +131,28 @@ impl Scene for SceneSubroutine {
         frame.clear_depth(1.0);
 
 
-        // Render teapot with Phong shading. --------------------------
-        let model = Mat4F::rotation_x(-90.0_f32.to_radians())
-            .translated_3d(Vec3F::new(-3.0, -1.5, 0.0));
-        let mv: Mat4F = self.view * model;
-
-        let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
-            MaterialInfo: &self.material_buffer,
-            ModelViewMatrix: mv.clone().into_col_arrays(),
-            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-            MVP: (self.projection * mv).into_col_arrays(),
-            // Set Subroutine
-            shadeModel: ("phongModel", glium::program::ShaderStage::Vertex),
-        };
-
-        self.teapot.render(frame, &self.program, &draw_params, &uniforms)?;
-        // -------------------------------------------------------------
+        // Render teapot with Phong shading on the left, and Diffuse shading on the right. --------
+        for (shade_model, offset_x) in self.shade_models.iter().zip([-3.0, 3.0].iter()) {
 
+            let model = Mat4F::rotation_x(-90.0_f32.to_radians())
+                .translated_3d(Vec3F::new(*offset_x, -1.5, 0.0));
+            let mv: Mat4F = self.view * model;
 
-        // Render teapot with Diffuse shading. --------------------------
-        let model = Mat4F::rotation_x(-90.0_f32.to_radians())
-            .translated_3d(Vec3F::new(3.0, -1.5, 0.0));
-        let mv: Mat4F = self.view * model;
+            let mut uniforms = DynamicUniforms::new();
+            uniforms
+                .add("LightInfo", &self.light_buffer)
+                .add("MaterialInfo", &self.material_buffer)
+                .add("ModelViewMatrix", mv)
+                .add("NormalMatrix", Mat3F::from(mv))
+                .add("MVP", self.projection * mv)
+                // Set Subroutine
+                .add_subroutines(shade_model);
 
-        let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
-            MaterialInfo: &self.material_buffer,
-            ModelViewMatrix: mv.clone().into_col_arrays(),
-            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-            MVP: (self.projection * mv).into_col_arrays(),
-            // Set Subroutine
-            shadeModel: ("diffuseOnly", glium::program::ShaderStage::Vertex),
-        };
+            self.teapot.render(frame, &self.program, &draw_params, &uniforms)?;
+        }
+        // -----------------------------------------------------------------------------------------
 
-        self.teapot.render(frame, &self.program, &draw_params, &uniforms)
-        // -------------------------------------------------------------
+        Ok(())
     }
 
     fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
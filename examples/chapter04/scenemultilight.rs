@@ -2,13 +2,16 @@
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{ObjMesh, ObjMeshConfiguration, Plane};
-use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
+use cookbook::lighting::{PointLight, PhongMaterial, LightElement, MaterialInfo, pack_light_array};
+use cookbook::layout::verify_uniform_block;
+use cookbook::uniforms::DynamicUniforms;
+use cookbook::{Mat4F, Mat3F, Vec3F};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
-use glium::{Surface, uniform, implement_uniform_block};
+use glium::Surface;
 
 
 
@@ -23,41 +26,15 @@ pub struct SceneMultilight {
     plane_material: MaterialInfo,
 
     material_buffer: UniformBuffer<MaterialInfo>,
-    light_buffer   : UniformBuffer<LightsWrapper>,
+    light_buffer   : UniformBuffer<[LightElement; 5]>,
 
-    light_data: LightsWrapper,
+    light_data: [LightElement; 5],
 
     view       : Mat4F,
     projection : Mat4F,
 }
 
 
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct LightsWrapper {
-    lights: [LightInfo; 5],
-}
-
-#[allow(non_snake_case)]
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct LightInfo {
-    Position: [f32; 4],
-    La: [f32; 3], _padding1: f32,
-    L : [f32; 3], _padding2: f32,
-}
-
-#[allow(non_snake_case)]
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct MaterialInfo {
-    Ka: [f32; 3], _padding1: f32,
-    Kd: [f32; 3], _padding2: f32,
-    Ks: [f32; 3],
-    Shininess: f32,
-}
-
-
 impl Scene for SceneMultilight {
 
     fn new(display: &impl Facade) -> GLResult<SceneMultilight> {
@@ -65,29 +42,23 @@ impl Scene for SceneMultilight {
         // Shader Program ------------------------------------------------------------
         let program = SceneMultilight::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
+        verify_uniform_block::<[LightElement; 5]>(&program, "LightsWrapper")?;
+        verify_uniform_block::<MaterialInfo>(&program, "MaterialInfo")?;
         // ----------------------------------------------------------------------------
 
 
         // Initialize Mesh ------------------------------------------------------------
         let plane = Plane::new(display, 10.0, 10.0, 100, 100, 1.0, 1.0)?;
-        let plane_material = MaterialInfo {
-            Ka: [0.1_f32, 0.1, 0.1],
-            Kd: [0.1_f32, 0.1, 0.1],
-            Ks: [0.9_f32, 0.9, 0.9],
-            Shininess: 180.0_f32, ..Default::default()
-        };
+        let plane_material = PhongMaterial::new(Vec3F::broadcast(0.1), Vec3F::broadcast(0.1), Vec3F::broadcast(0.9), 180.0)
+            .to_block();
         let mesh = ObjMesh::load(display, "media/pig_triangulated.obj", ObjMeshConfiguration {
             is_with_adjacency: false,
             is_gen_tangents: false,
             is_center: true,
             is_print_load_message: true,
         })?;
-        let mesh_material = MaterialInfo {
-            Ka: [0.5_f32, 0.5, 0.5],
-            Kd: [0.4_f32, 0.4, 0.4],
-            Ks: [0.9_f32, 0.9, 0.9],
-            Shininess: 180.0_f32, ..Default::default()
-        };
+        let mesh_material = PhongMaterial::new(Vec3F::broadcast(0.5), Vec3F::broadcast(0.4), Vec3F::broadcast(0.9), 180.0)
+            .to_block();
         // ----------------------------------------------------------------------------
 
 
@@ -98,48 +69,40 @@ impl Scene for SceneMultilight {
 
 
         // Initialize Uniforms --------------------------------------------------------
-        let mut lights: LightsWrapper = Default::default();
-        for i in 0..5 {
+        const LIGHT_COLORS: [[f32; 3]; 5] = [
+            [0.0, 0.8, 0.8],
+            [0.0, 0.0, 0.8],
+            [0.8, 0.0, 0.0],
+            [0.0, 0.8, 0.0],
+            [0.8, 0.8, 0.8],
+        ];
+
+        let lights = LIGHT_COLORS.iter().enumerate().map(|(i, &color)| {
             const TWO_PI: f32 = std::f32::consts::PI * 2.0;
             let x = 2.0 * ((TWO_PI / 5.0) * (i as f32)).cos();
             let z = 2.0 * ((TWO_PI / 5.0) * (i as f32)).sin();
 
-            let light = LightInfo {
-                Position: (view * Vec4F::new(x, 1.2, z + 1.0, 1.0)).into_array(),
-                ..Default::default()
-            };
-            lights.lights[i] = light;
-        }
-
-        lights.lights[0].L = [0.0, 0.8, 0.8];
-        lights.lights[1].L = [0.0, 0.0, 0.8];
-        lights.lights[2].L = [0.8, 0.0, 0.0];
-        lights.lights[3].L = [0.0, 0.8, 0.0];
-        lights.lights[4].L = [0.8, 0.8, 0.8];
-
-        lights.lights[0].La = [0.0, 0.2, 0.2];
-        lights.lights[1].La = [0.0, 0.0, 0.2];
-        lights.lights[2].La = [0.2, 0.0, 0.0];
-        lights.lights[3].La = [0.0, 0.2, 0.0];
-        lights.lights[4].La = [0.2, 0.2, 0.2];
-
-        glium::implement_uniform_block!(LightInfo, Position, La, L);
-        glium::implement_uniform_block!(LightsWrapper, lights);
+            let intensity = Vec3F::from(color);
+            PointLight::new(Vec3F::new(x, 1.2, z + 1.0), intensity)
+                .with_ambient(intensity * 0.25)
+                .to_element(&view)
+        });
+        let light_data: [LightElement; 5] = pack_light_array(lights)?;
+
         let light_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
 
-        glium::implement_uniform_block!(MaterialInfo, Ka, Kd, Ks, Shininess);
         let material_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
 
-        // cookbook::utils::print_active_uniform_blocks(&program);
+        // cookbook::utils::print_active_uniforms(&program);
         // ----------------------------------------------------------------------------
 
 
         let scene = SceneMultilight {
             program,
             plane, mesh, plane_material, mesh_material,
-            material_buffer, light_buffer, light_data: lights,
+            material_buffer, light_buffer, light_data,
             view, projection,
         };
         Ok(scene)
@@ -170,13 +133,14 @@ impl Scene for SceneMultilight {
 
         let model = Mat4F::rotation_y(90.0_f32.to_radians());
         let mv: Mat4F = self.view * model;
-        let uniforms = uniform! {
-            LightsWrapper: &self.light_buffer,
-            MaterialInfo: &self.material_buffer,
-            ModelViewMatrix: mv.clone().into_col_arrays(),
-            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-            MVP: (self.projection * mv).into_col_arrays(),
-        };
+        let mut uniforms = DynamicUniforms::new();
+        uniforms
+            .add("LightsWrapper", &self.light_buffer)
+            .add("MaterialInfo", &self.material_buffer)
+            .add("ModelViewMatrix", mv)
+            .add("NormalMatrix", Mat3F::from(mv))
+            .add("MVP", self.projection * mv);
+        uniforms.warn_inactive(&self.program);
 
         self.mesh.render(frame, &self.program, &draw_params, &uniforms)?;
         // -------------------------------------------------------------------------
@@ -187,13 +151,14 @@ impl Scene for SceneMultilight {
 
         let model = Mat4F::translation_3d(Vec3F::new(0.0, -0.45, 0.0));
         let mv: Mat4F = self.view * model;
-        let uniforms = uniform! {
-            LightsWrapper: &self.light_buffer,
-            MaterialInfo: &self.material_buffer,
-            ModelViewMatrix: mv.clone().into_col_arrays(),
-            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-            MVP: (self.projection * mv).into_col_arrays(),
-        };
+        let mut uniforms = DynamicUniforms::new();
+        uniforms
+            .add("LightsWrapper", &self.light_buffer)
+            .add("MaterialInfo", &self.material_buffer)
+            .add("ModelViewMatrix", mv)
+            .add("NormalMatrix", Mat3F::from(mv))
+            .add("MVP", self.projection * mv);
+        uniforms.warn_inactive(&self.program);
 
         self.plane.render(frame, &self.program, &draw_params, &uniforms)
         // -------------------------------------------------------------------------
@@ -2,13 +2,15 @@
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{ObjMesh, ObjMeshConfiguration, Plane};
+use cookbook::lighting::{PointLight, PbrMaterial, PbrLightElement, PbrMaterialInfo, PBR_PRESETS, pack_light_array};
+use cookbook::layout::verify_uniform_block;
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
-use glium::{Surface, uniform, implement_uniform_block};
+use glium::{Surface, uniform};
 
 
 
@@ -20,13 +22,13 @@ pub struct ScenePbr {
     mesh: ObjMesh,
     plane: Plane,
 
-    material_buffer: UniformBuffer<MaterialInfo>,
+    material_buffer: UniformBuffer<PbrMaterialInfo>,
     // Due to glium::uniforms::UniformBlock is not implement for [T; 3], but implment for [T; 5],
     // here just force its to 5 element, but actually 3 is used.
-    light_buffer   : UniformBuffer<[LightInfo; 5]>,
+    light_buffer   : UniformBuffer<[PbrLightElement; 5]>,
 
     light_pos: Vec4F,
-    light_data: [LightInfo; 5],
+    light_data: [PbrLightElement; 5],
 
     view       : Mat4F,
     projection : Mat4F,
@@ -36,24 +38,6 @@ pub struct ScenePbr {
     is_animate: bool,
 }
 
-
-#[allow(non_snake_case)]
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct LightInfo {
-    Position: [f32; 4],
-    L : [f32; 3], _padding1: f32,
-}
-
-#[allow(non_snake_case)]
-#[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
-struct MaterialInfo {
-    MaterialColor: [f32; 3],
-    MaterialRough: f32,
-    IsMetal: bool,
-}
-
 impl Scene for ScenePbr {
 
     fn new(display: &impl Facade) -> GLResult<ScenePbr> {
@@ -61,6 +45,8 @@ impl Scene for ScenePbr {
         // Shader Program ------------------------------------------------------------
         let program = ScenePbr::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
+        verify_uniform_block::<[PbrLightElement; 5]>(&program, "LightsBlock")?;
+        verify_uniform_block::<PbrMaterialInfo>(&program, "MaterialInfo")?;
         // ----------------------------------------------------------------------------
 
 
@@ -86,21 +72,16 @@ impl Scene for ScenePbr {
 
 
         // Initialize Uniforms --------------------------------------------------------
-        let mut light_data: [LightInfo; 5] = Default::default();
+        let light_data: [PbrLightElement; 5] = pack_light_array(vec![
+            PointLight::new(Vec3F::from(light_pos), Vec3F::broadcast(45.0)).to_pbr_element(&view),
+            // A directional light given in camera space.
+            PbrLightElement { Position: [0.0, 0.15, -1.0, 0.0], L: [0.3, 0.3, 0.3], ..Default::default() },
+            PointLight::new(Vec3F::new(-7.0, 3.0, 7.0), Vec3F::broadcast(45.0)).to_pbr_element(&view),
+        ])?;
 
-        light_data[0].L = [45.0, 45.0, 45.0];
-        light_data[1].L = [ 0.3,  0.3,  0.3];
-        light_data[2].L = [45.0, 45.0, 45.0];
-
-        light_data[0].Position = (view * light_pos).into_array();
-        light_data[1].Position = [0.0, 0.15, -1.0, 0.0];
-        light_data[2].Position = (view * Vec4F::new(-7.0, 3.0, 7.0, 1.0)).into_array();
-
-        glium::implement_uniform_block!(LightInfo, Position, L);
         let light_buffer = UniformBuffer::immutable(display, light_data)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
 
-        glium::implement_uniform_block!(MaterialInfo, MaterialColor, MaterialRough, IsMetal);
         let material_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
 
@@ -156,21 +137,16 @@ impl Scene for ScenePbr {
         for i in 0..NUM_COWS {
             let cow_x = (i as f32) * (10.0 / (NUM_COWS - 1) as f32) - 5.0;
             let rough = (i as f32 + 1.0) * (1.0 / NUM_COWS as f32);
-            self.draw_spot(frame, &draw_params, Vec3F::new(cow_x, 0.0, 0.0), rough, false, COW_BASE_COLOR)?;
+            self.draw_spot(frame, &draw_params, Vec3F::new(cow_x, 0.0, 0.0), &PbrMaterial::dielectric(COW_BASE_COLOR, rough))?;
         }
 
-        // Draw metal cows
-        const METAL_ROUGH: f32 = 0.43;
-        // Gold
-        self.draw_spot(frame, &draw_params, Vec3F::new(-3.0, 0.0, 3.0), METAL_ROUGH, true, Vec3F::new(1.0, 0.71, 0.29))?;
-        // Copper
-        self.draw_spot(frame, &draw_params, Vec3F::new(-1.5, 0.0, 3.0), METAL_ROUGH, true, Vec3F::new(0.95, 0.64, 0.54))?;
-        // Aluminum
-        self.draw_spot(frame, &draw_params, Vec3F::new( 0.0, 0.0, 3.0), METAL_ROUGH, true, Vec3F::new(0.91, 0.92, 0.92))?;
-        // Titanium
-        self.draw_spot(frame, &draw_params, Vec3F::new( 1.5, 0.0, 3.0), METAL_ROUGH, true, Vec3F::new(0.542, 0.497, 0.449))?;
-        // Silver
-        self.draw_spot(frame, &draw_params, Vec3F::new( 3.0, 0.0, 3.0), METAL_ROUGH, true, Vec3F::new(0.95, 0.93, 0.88))
+        // Draw metal cows(gold, copper, aluminum, titanium and silver)
+        for (i, (_name, metal)) in PBR_PRESETS.iter().enumerate() {
+            let cow_x = (i as f32) * 1.5 - 3.0;
+            self.draw_spot(frame, &draw_params, Vec3F::new(cow_x, 0.0, 3.0), metal)?;
+        }
+
+        Ok(())
         // ----------------------------------------------------------------------------
     }
 
@@ -204,11 +180,7 @@ impl ScenePbr {
 
     fn draw_floor(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        self.material_buffer.write(&MaterialInfo {
-            MaterialColor: [0.2, 0.2, 0.2],
-            MaterialRough: 0.9,
-            IsMetal: false,
-        });
+        self.material_buffer.write(&PbrMaterial::dielectric(Vec3F::broadcast(0.2), 0.9).to_block());
         self.light_buffer.write(&self.light_data);
 
         let model = Mat4F::translation_3d(Vec3F::new(0.0, -0.75, 0.0));
@@ -224,13 +196,9 @@ impl ScenePbr {
         self.plane.render(frame, &self.program, draw_params, &uniforms)
     }
 
-    fn draw_spot(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters, pos: Vec3F, rough: f32, is_metal: bool, color: Vec3F) -> GLResult<()> {
+    fn draw_spot(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters, pos: Vec3F, material: &PbrMaterial) -> GLResult<()> {
 
-        self.material_buffer.write(&MaterialInfo {
-            MaterialColor: color.into_array(),
-            MaterialRough: rough,
-            IsMetal: is_metal,
-        });
+        self.material_buffer.write(&material.to_block());
 
         let model = Mat4F::rotation_y(180.0_f32.to_radians())
             .translated_3d(pos);
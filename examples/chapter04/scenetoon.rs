@@ -171,7 +171,7 @@ impl Scene for SceneToon {
         let model = Mat4F::identity();
         let mv: Mat4F = self.view * model;
         let uniforms = uniform! {
-            SpotLightInfo: &self.light_buffer,
+            LightInfo: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
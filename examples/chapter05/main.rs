@@ -13,6 +13,7 @@ mod sceneprojtex;
 mod scenerendertotex;
 mod scenesamplerobj;
 mod scenediffibl;
+mod scenespecibl;
 
 use scenetexture::SceneTexture;
 use scenemultitex::SceneMultiTex;
@@ -25,6 +26,7 @@ use sceneprojtex::SceneProjTex;
 use scenerendertotex::SceneRenderToTex;
 use scenesamplerobj::SceneSamplerObj;
 use scenediffibl::SceneDiffIbl;
+use scenespecibl::SceneSpecIbl;
 
 use cookbook::scenerunner::SceneRunner;
 use cookbook::scene::Scene;
@@ -53,6 +55,7 @@ lazy_static! {
 		m.insert("sampler-obj".into(), "Sampler objects".into());
 		m.insert("texture".into(), "Basic texture mapping".into());
 		m.insert("diff-ibl".into(), "Diffuse image based lighting".into());
+		m.insert("spec-ibl".into(), "Specular image based lighting with PBR materials".into());
 		m.insert("parallax".into(), "Parallax mapping".into());
         m
     };
@@ -78,6 +81,7 @@ fn main() -> GLResult<()> {
         | "sampler-obj"   => run::<SceneSamplerObj>(title),
         | "texture"       => run::<SceneTexture>(title),
         | "diff-ibl"      => run::<SceneDiffIbl>(title),
+        | "spec-ibl"      => run::<SceneSpecIbl>(title),
         | "parallax"      => run::<SceneParallax>(title),
         | _ => unreachable!(),
     }
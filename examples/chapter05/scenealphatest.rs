@@ -2,7 +2,8 @@
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::Teapot;
-use cookbook::texture::load_texture;
+use cookbook::texture::{load_texture, load_texture_with_mipmaps};
+use cookbook::mipmap::{MipmapGenerator, MipFilter};
 use cookbook::{Mat4F, Mat3F, Vec3F};
 use cookbook::Drawable;
 
@@ -65,7 +66,11 @@ impl Scene for SceneAlphaTest {
 
         // Initialize Textures --------------------------------------------------------
         let cement = load_texture(display, "media/texture/cement.png")?;
-        let moss   = load_texture(display, "media/texture/moss.png")?;
+        // Keep the moss covering the same area in the distance, alphatest.frag.glsl discards the alpha below 0.15.
+        let moss_mipmaps = MipmapGenerator::new(MipFilter::Kaiser)
+            .with_srgb(true)
+            .with_alpha_coverage(0.15);
+        let moss   = load_texture_with_mipmaps(display, "media/texture/moss.png", &moss_mipmaps)?;
         // ----------------------------------------------------------------------------
 
         // Initialize MVP -------------------------------------------------------------
@@ -137,7 +142,7 @@ impl Scene for SceneAlphaTest {
                 .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
                 .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
             AlphaTex: self.moss.sampled()
-                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
                 .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
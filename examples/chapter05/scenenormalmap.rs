@@ -2,7 +2,7 @@
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{ObjMesh, ObjMeshConfiguration};
-use cookbook::texture::load_texture;
+use cookbook::texture::{load_texture, load_srgb_texture};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
 use cookbook::Drawable;
 
@@ -10,6 +10,7 @@ use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
 use glium::texture::texture2d::Texture2d;
+use glium::texture::srgb_texture2d::SrgbTexture2d;
 use glium::{Surface, uniform, implement_uniform_block};
 
 
@@ -19,7 +20,7 @@ pub struct SceneNormalMap {
     program: glium::Program,
 
     ogre: ObjMesh,
-    diffuse_tex: Texture2d,
+    diffuse_tex: SrgbTexture2d,
     normal_tex: Texture2d,
 
     material_buffer: UniformBuffer<MaterialInfo>,
@@ -70,7 +71,8 @@ impl Scene for SceneNormalMap {
         // ----------------------------------------------------------------------------
 
         // Initialize Textures --------------------------------------------------------
-        let diffuse_tex = load_texture(display, "media/texture/ogre_diffuse.png")?;
+        // The diffuse colors are sRGB encoded, while the normal map stores directions that must not be decoded.
+        let diffuse_tex = load_srgb_texture(display, "media/texture/ogre_diffuse.png")?;
         let normal_tex  = load_texture(display, "media/texture/ogre_normalmap.png")?;
         // ----------------------------------------------------------------------------
 
@@ -190,16 +190,11 @@ impl SceneRenderToTex {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        // Avoid use self in the following closure.
-        let spot    = &self.spot;
-        let program = &self.program;
+        let mut framebuffer = self.fbo.framebuffer()?;
 
-        self.fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
-            framebuffer.clear_depth(1.0);
-            spot.render(framebuffer, program, &draw_params, &uniforms)
-        })
+        framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
+        framebuffer.clear_depth(1.0);
+        self.spot.render(&mut framebuffer, &self.program, &draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 
@@ -228,21 +223,18 @@ impl SceneRenderToTex {
         let model = Mat4F::identity();
         let mv: Mat4F = view * model;
 
-        self.fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                LightInfo: &self.light_buffer,
-                MaterialInfo: &self.material_buffer,
-                RenderTex: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
-                ModelViewMatrix: mv.clone().into_col_arrays(),
-                NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-                MVP: (self.projection * mv).into_col_arrays(),
-            };
-
-            self.cube.render(frame, &self.program, &draw_params, &uniforms)
-        })
+        let uniforms = uniform! {
+            LightInfo: &self.light_buffer,
+            MaterialInfo: &self.material_buffer,
+            RenderTex: self.fbo.attachment.color.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear),
+            ModelViewMatrix: mv.clone().into_col_arrays(),
+            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
+            MVP: (self.projection * mv).into_col_arrays(),
+        };
+
+        self.cube.render(frame, &self.program, &draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 }
@@ -0,0 +1,231 @@
+
+use cookbook::scene::{Scene, GLSourceCode};
+use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+use cookbook::objects::{SkyBox, ObjMesh, ObjMeshConfiguration};
+use cookbook::ibl::CubeFaces;
+use cookbook::ibl::irradiance::ShCoefficients;
+use cookbook::ibl::specular::SpecularBaker;
+use cookbook::lighting::{PbrMaterial, PbrMaterialInfo, PBR_PRESETS};
+use cookbook::layout::verify_uniform_block;
+use cookbook::{Mat4F, Vec3F};
+use cookbook::Drawable;
+
+use glium::backend::Facade;
+use glium::program::{Program, ProgramCreationError};
+use glium::uniforms::{UniformBuffer, MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
+use glium::texture::cubemap::Cubemap;
+use glium::texture::texture2d::Texture2d;
+use glium::{Surface, uniform};
+
+const PREFILTER_SIZE: u32 = 128;
+const PREFILTER_LEVELS: u32 = 6;
+const SAMPLE_COUNT: u32 = 512;
+
+
+#[derive(Debug)]
+pub struct SceneSpecIbl {
+
+    program: glium::Program,
+    sky_prog: glium::Program,
+
+    spot: ObjMesh,
+    skybox: SkyBox,
+
+    cube: Cubemap,
+    diff_cube: Cubemap,
+    prefilter_cube: Cubemap,
+    brdf_lut: Texture2d,
+
+    material_buffer: UniformBuffer<PbrMaterialInfo>,
+
+    camera_pos: Vec3F,
+    view: Mat4F,
+    projection : Mat4F,
+
+    camera_angle: f32,
+    is_animate: bool,
+}
+
+
+impl Scene for SceneSpecIbl {
+
+    fn new(display: &impl Facade) -> GLResult<SceneSpecIbl> {
+
+        // Shader Program ------------------------------------------------------------
+        let program = SceneSpecIbl::compile_shader_program(display)
+            .map_err(GLErrorKind::CreateProgram)?;
+        let sky_prog = SceneSpecIbl::compile_sky_program(display)
+            .map_err(GLErrorKind::CreateProgram)?;
+        verify_uniform_block::<PbrMaterialInfo>(&program, "MaterialInfo")?;
+        // ----------------------------------------------------------------------------
+
+
+        // Initialize Mesh ------------------------------------------------------------
+        let spot = ObjMesh::load(display, "media/spot/spot_triangulated.obj", ObjMeshConfiguration {
+            is_with_adjacency: false,
+            is_gen_tangents: false,
+            is_center: false,
+            is_print_load_message: true,
+        })?;
+        let skybox = SkyBox::new(display, 100.0)?;
+        // ----------------------------------------------------------------------------
+
+        // Initialize Textures --------------------------------------------------------
+        // Keep the environment in floating-point, the prefiltering averages the bright light sources.
+        let environment = CubeFaces::load_hdr("media/texture/cube/grace/grace")?;
+        let cube = environment.upload(display)?;
+        let diff_cube = ShCoefficients::from_faces(&environment).to_faces(32)?.upload(display)?;
+
+        let baker = SpecularBaker::new(display)?;
+        let prefilter_cube = baker.prefilter(display, &cube, PREFILTER_SIZE, PREFILTER_LEVELS, SAMPLE_COUNT)?;
+        let brdf_lut = baker.brdf_lut(display, 256, SAMPLE_COUNT)?;
+        // ----------------------------------------------------------------------------
+
+        // Initialize MVP -------------------------------------------------------------
+        let projection = Mat4F::identity();
+        let camera_pos = Vec3F::new(0.0, 4.0, 7.0);
+        let view = Mat4F::identity();
+        let camera_angle = 90.0_f32.to_radians();
+        let is_animate = true;
+        // ----------------------------------------------------------------------------
+
+
+        // Initialize Uniforms --------------------------------------------------------
+        let material_buffer = UniformBuffer::empty_immutable(display)
+            .map_err(BufferCreationErrorKind::UniformBlock)?;
+        // ----------------------------------------------------------------------------
+
+        let scene = SceneSpecIbl {
+            program, sky_prog,
+            spot, skybox, cube, diff_cube, prefilter_cube, brdf_lut,
+            material_buffer,
+            projection, view, camera_pos, camera_angle, is_animate,
+        };
+        Ok(scene)
+    }
+
+    fn update(&mut self, delta_time: f32) {
+
+        const TWO_PI: f32 = std::f32::consts::PI * 2.0;
+        const ROTATE_SPEED: f32 = 0.3;
+
+        if self.is_animating() {
+            self.camera_angle = (self.camera_angle + delta_time * ROTATE_SPEED) % TWO_PI;
+            self.camera_pos = Vec3F::new(self.camera_angle.cos() * 9.0, 3.0, self.camera_angle.sin() * 9.0);
+            self.view = Mat4F::look_at_rh(self.camera_pos, Vec3F::new(0.0, 0.0, 1.5), Vec3F::unit_y());
+        }
+    }
+
+    fn render(&mut self, frame: &mut glium::Frame) -> GLResult<()> {
+
+        frame.clear_color_srgb(0.5, 0.5, 0.5, 1.0);
+        frame.clear_depth(1.0);
+
+        let draw_params = glium::draw_parameters::DrawParameters {
+            depth: glium::Depth {
+                test: glium::DepthTest::IfLess,
+                write: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // Render Sky -------------------------------------------------------------
+        let mv: Mat4F = self.view * Mat4F::identity();
+
+        let uniforms = uniform! {
+            SkyBoxTex: self.cube.sampled()
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Linear),
+            MVP: (self.projection * mv).into_col_arrays(),
+        };
+        self.skybox.render(frame, &self.sky_prog, &draw_params, &uniforms)?;
+        // -------------------------------------------------------------------------
+
+        // Draw dielectric cows with varying roughness -----------------------------
+        const NUM_COWS: usize = 9;
+        const COW_BASE_COLOR: Vec3F = Vec3F::new(0.1, 0.33, 0.17);
+
+        for i in 0..NUM_COWS {
+            let cow_x = (i as f32) * (10.0 / (NUM_COWS - 1) as f32) - 5.0;
+            let rough = (i as f32) / (NUM_COWS - 1) as f32;
+            self.draw_spot(frame, &draw_params, Vec3F::new(cow_x, 0.0, 0.0), &PbrMaterial::dielectric(COW_BASE_COLOR, rough))?;
+        }
+        // -------------------------------------------------------------------------
+
+        // Draw metal cows(gold, copper, aluminum, titanium and silver) ------------
+        for (i, (_name, metal)) in PBR_PRESETS.iter().enumerate() {
+            let cow_x = (i as f32) * 1.5 - 3.0;
+            self.draw_spot(frame, &draw_params, Vec3F::new(cow_x, 0.0, 3.0), metal)?;
+        }
+
+        Ok(())
+        // -------------------------------------------------------------------------
+    }
+
+    fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
+
+        self.projection = Mat4F::perspective_rh_zo(50.0_f32.to_radians(), width as f32 / height as f32, 0.3, 100.0);
+        Ok(())
+    }
+
+    fn is_animating(&self) -> bool {
+        self.is_animate
+    }
+    fn toggle_animation(&mut self) {
+        self.is_animate = !self.is_animate;
+    }
+}
+
+
+impl SceneSpecIbl {
+
+    fn compile_shader_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
+
+        let vertex_shader_code   = include_str!("shaders/specibl.vert.glsl");
+        let fragment_shader_code = include_str!("shaders/specibl.frag.glsl");
+
+        let sources = GLSourceCode::new(vertex_shader_code, fragment_shader_code)
+            .with_srgb_output(true);
+        glium::Program::new(display, sources)
+    }
+
+    fn compile_sky_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
+
+        let vertex_shader_code   = include_str!("shaders/skybox.vert.glsl");
+        let fragment_shader_code = include_str!("shaders/skybox.frag.glsl");
+
+        let sources = GLSourceCode::new(vertex_shader_code, fragment_shader_code)
+            .with_srgb_output(true);
+        glium::Program::new(display, sources)
+    }
+
+    fn draw_spot(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters, pos: Vec3F, material: &PbrMaterial) -> GLResult<()> {
+
+        self.material_buffer.write(&material.to_block());
+
+        let model = Mat4F::rotation_y(180.0_f32.to_radians())
+            .translated_3d(pos);
+        let mv: Mat4F = self.view * model;
+
+        let uniforms = uniform! {
+            MaterialInfo: &self.material_buffer,
+            CamPos: self.camera_pos.into_array(),
+            DiffLightTex: self.diff_cube.sampled()
+                .minify_filter(MinifySamplerFilter::Linear)
+                .magnify_filter(MagnifySamplerFilter::Linear),
+            PrefilterTex: self.prefilter_cube.sampled()
+                .minify_filter(MinifySamplerFilter::LinearMipmapLinear)
+                .magnify_filter(MagnifySamplerFilter::Linear),
+            BrdfLutTex: self.brdf_lut.sampled()
+                .wrap_function(SamplerWrapFunction::Clamp)
+                .minify_filter(MinifySamplerFilter::Linear)
+                .magnify_filter(MagnifySamplerFilter::Linear),
+            MaxLevel: (PREFILTER_LEVELS - 1) as f32,
+            ModelMatrix: model.into_col_arrays(),
+            MVP: (self.projection * mv).into_col_arrays(),
+        };
+
+        self.spot.render(frame, &self.program, draw_params, &uniforms)
+    }
+}
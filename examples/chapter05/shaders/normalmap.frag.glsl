@@ -55,5 +55,7 @@ void main() {
     // There is no standard convention for z coordinate.
     norm.xy = 2.0 * norm.xy - 1.0;
 
-    FragColor = vec4(blinnPhong(norm), 1.0);
+    // ColorTex is an sRGB texture, so the lighting is computed in linear space and gamma encoded here.
+    vec3 color = blinnPhong(norm);
+    FragColor = vec4(pow(color, vec3(1.0 / 2.2)), 1.0);
 }
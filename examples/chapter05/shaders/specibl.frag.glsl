@@ -0,0 +1,51 @@
+
+#version 410
+
+layout (location = 0) in vec3 Position;
+layout (location = 1) in vec3 Normal;  // World coords.
+
+uniform vec3 CamPos;
+
+uniform samplerCube DiffLightTex;   // Irradiance / PI
+uniform samplerCube PrefilterTex;   // GGX prefiltered radiance, the roughness increases with the mip level.
+uniform sampler2D BrdfLutTex;       // Indexed by (dot(n, v), roughness)
+uniform float MaxLevel;
+
+uniform MaterialInfo {
+    vec3 MaterialColor;   // Diffuse color for dielectrics, f0 for metallic
+    float MaterialRough;  // Roughness
+    bool IsMetal;         // Metallic (true) or dielectric (false)
+};
+
+layout (location = 0) out vec4 FragColor;
+
+
+void main() {
+
+    vec3 n = normalize(Normal);
+    vec3 v = normalize(CamPos - Position);
+    vec3 r = reflect(-v, n);
+    float nDotV = max(dot(n, v), 0.0);
+
+    vec3 f0 = vec3(0.04);
+    vec3 diffuseColor = MaterialColor;
+    if (IsMetal) {
+        f0 = MaterialColor;
+        diffuseColor = vec3(0.0);
+    }
+
+    // Split-sum approximation of the specular lighting.
+    vec3 prefiltered = textureLod(PrefilterTex, r, MaterialRough * MaxLevel).rgb;
+    vec2 brdf = texture(BrdfLutTex, vec2(nDotV, MaterialRough)).rg;
+    vec3 specular = prefiltered * (f0 * brdf.x + brdf.y);
+
+    // The light reflected by the specular layer does not reach the diffuse layer.
+    vec3 diffuse = texture(DiffLightTex, n).rgb * diffuseColor * (1.0 - (f0 * brdf.x + brdf.y));
+
+    vec3 color = diffuse + specular;
+
+    // Gamma
+    color = pow(color, vec3(1.0 / 2.2));
+
+    FragColor = vec4(color, 1.0);
+}
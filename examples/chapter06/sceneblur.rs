@@ -1,9 +1,10 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Teapot, Plane, Torus, Quad};
+use cookbook::objects::{Teapot, Plane, Torus};
 use cookbook::{Mat4F, Mat3F, Vec3F};
-use cookbook::framebuffer::{ColorDepthAttachment, ColorAttachment, GLFrameBuffer};
+use cookbook::framebuffer::{ColorDepthAttachment, GLFrameBuffer};
+use cookbook::postprocess::{PostProcessChain, PassInput, stages};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -15,19 +16,15 @@ use glium::{Surface, uniform, implement_uniform_block};
 
 pub struct SceneBlur {
 
-    programs: [glium::Program; 3],
+    program: glium::Program,
 
     teapot: Teapot,
     plane: Plane,
     torus: Torus,
-    fs_quad: Quad,
 
-    render_fbo      : GLFrameBuffer<ColorDepthAttachment>,
-    intermediate_fbo: GLFrameBuffer<ColorAttachment>,
+    render_fbo: GLFrameBuffer<ColorDepthAttachment>,
+    blur_chain: PostProcessChain,
 
-    weights: [f32; 5],
-
-    weight_buffer: UniformBuffer<[f32; 5]>,
     material_buffer: UniformBuffer<MaterialInfo>,
     light_buffer: UniformBuffer<LightInfo>,
 
@@ -64,34 +61,15 @@ impl Scene for SceneBlur {
         let aspect_ratio = (screen_width as f32) / (screen_height as f32);
 
         // Shader Program ------------------------------------------------------------
-        let programs = SceneBlur::compile_shader_program(display)
+        let program = SceneBlur::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
         // ----------------------------------------------------------------------------
 
 
-        // Compute and sum the weights ------------------------------------------------
-        let mut weights: [f32; 5] = Default::default();
-        let sigma2 = 8.0;
-
-        weights[0] = gauss(0.0, sigma2);
-        let mut sum = weights[0];
-
-        for i in 1..5 {
-            weights[i] = gauss(i as f32, sigma2);
-            sum += 2.0 * weights[i];
-        }
-
-        // Normalize the weights and set the uniform
-        for i in 0..5 {
-            weights[i] = weights[i] / sum;
-        }
-        // ----------------------------------------------------------------------------
-
         // Initialize Mesh ------------------------------------------------------------
         let teapot = Teapot::new(display, 14, Mat4F::identity())?;
         let plane = Plane::new(display, 50.0, 50.0, 1, 1, 1.0, 1.0)?;
         let torus = Torus::new(display, 0.7 * 1.5, 0.3 * 1.5, 50, 50)?;
-        let fs_quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
         // Initialize Textures --------------------------------------------------------
@@ -105,7 +83,8 @@ impl Scene for SceneBlur {
 
         // Initialize Uniforms --------------------------------------------------------
         let render_fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::U8U8U8U8)?;
-        let intermediate_fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::U8U8U8U8)?;
+        let blur_passes = stages::gaussian_blur(display, "Blur", PassInput::Source, 5, 8.0, UncompressedFloatFormat::U8U8U8U8, 1.0)?;
+        let blur_chain = PostProcessChain::new(display, screen_width, screen_height, blur_passes)?;
 
         glium::implement_uniform_block!(LightInfo, LightPosition, L, La);
         let light_buffer = UniformBuffer::immutable(display, LightInfo {
@@ -116,15 +95,12 @@ impl Scene for SceneBlur {
         glium::implement_uniform_block!(MaterialInfo, Ka, Kd, Ks, Shininess);
         let material_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
-
-        let weight_buffer = UniformBuffer::empty_immutable(display)
-            .map_err(BufferCreationErrorKind::UniformBlock)?;
         // ----------------------------------------------------------------------------
 
         let scene = SceneBlur {
-            programs, render_fbo, intermediate_fbo,
-            teapot, torus, plane, fs_quad, weights,
-            weight_buffer, material_buffer, light_buffer,
+            program, render_fbo, blur_chain,
+            teapot, torus, plane,
+            material_buffer, light_buffer,
             aspect_ratio, angle, is_animate,
         };
         Ok(scene)
@@ -152,14 +128,13 @@ impl Scene for SceneBlur {
         };
 
         self.pass1(&draw_params)?;
-        self.pass2()?;
-        self.pass3(frame, &draw_params)
+        self.pass2(frame)
     }
 
     fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
         self.render_fbo = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::U8U8U8U8)?;
-        self.intermediate_fbo = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::U8U8U8U8)?;
+        self.blur_chain.resize(width, height)?;
         Ok(())
     }
 
@@ -174,48 +149,17 @@ impl Scene for SceneBlur {
 
 impl SceneBlur {
 
-    #[cfg(not(target_os = "macos"))]
-    fn compile_shader_program(display: &impl Facade) -> Result<[Program; 3], ProgramCreationError> {
-
-        let pass1_vertex   = include_str!("shaders/blur/pass1.vert.glsl");
-        let pass1_fragment = include_str!("shaders/blur/pass1.frag.glsl");
-
-        let pass2_vertex   = include_str!("shaders/blur/pass2.vert.glsl");
-        let pass2_fragment = include_str!("shaders/blur/pass2.frag.glsl");
-
-        let pass3_vertex   = include_str!("shaders/blur/pass3.vert.glsl");
-        let pass3_fragment = include_str!("shaders/blur/pass3.frag.glsl");
-
-        let pass1 = glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))?;
-        let pass2 = glium::Program::new(display, GLSourceCode::new(pass2_vertex, pass2_fragment).with_srgb_output(false))?;
-        let pass3 = glium::Program::new(display, GLSourceCode::new(pass3_vertex, pass3_fragment).with_srgb_output(true))?;
-        Ok([pass1, pass2, pass3])
-    }
-
-    // There is a issue when transfering the weights to shader on macOS.
-    // See https://github.com/unknownue/GLSLCookbook.rs/issues/5 for detail.
-    // Here we use a shader that pre-calcualtes the weights in it.
-    #[cfg(target_os = "macos")]
-    fn compile_shader_program(display: &impl Facade) -> Result<[Program; 3], ProgramCreationError> {
+    fn compile_shader_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
 
         let pass1_vertex   = include_str!("shaders/blur/pass1.vert.glsl");
         let pass1_fragment = include_str!("shaders/blur/pass1.frag.glsl");
 
-        let pass2_vertex   = include_str!("shaders/blur/pass2.vert.glsl");
-        let pass2_fragment = include_str!("shaders/blur/pass2_macOS.frag.glsl");
-
-        let pass3_vertex   = include_str!("shaders/blur/pass3.vert.glsl");
-        let pass3_fragment = include_str!("shaders/blur/pass3_macOS.frag.glsl");
-
-        let pass1 = glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))?;
-        let pass2 = glium::Program::new(display, GLSourceCode::new(pass2_vertex, pass2_fragment).with_srgb_output(false))?;
-        let pass3 = glium::Program::new(display, GLSourceCode::new(pass3_vertex, pass3_fragment).with_srgb_output(true))?;
-        Ok([pass1, pass2, pass3])
+        glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))
     }
 
     fn pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        let program = &self.programs[0];
+        let program = &self.program;
 
         let view = Mat4F::look_at_rh(Vec3F::new(7.0 * self.angle.cos(), 4.0, 7.0 * self.angle.sin()), Vec3F::zero(), Vec3F::unit_y());
         let projection = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), self.aspect_ratio, 0.3, 100.0);
@@ -240,14 +184,11 @@ impl SceneBlur {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-            framebuffer.clear_depth(1.0);
+        let mut framebuffer = self.render_fbo.framebuffer()?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            teapot.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.teapot.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render plane ------------------------------------------------------------
@@ -269,10 +210,7 @@ impl SceneBlur {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render torus ------------------------------------------------------------
@@ -295,67 +233,16 @@ impl SceneBlur {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let torus = &self.torus;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            torus.render(framebuffer, program, draw_params, &uniforms)
-        })
+        self.torus.render(&mut framebuffer, program, draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 
-    fn pass2(&mut self) -> GLResult<()> {
-
-        let render_fbo = &self.render_fbo;
-        let fs_quad = &self.fs_quad;
-        let program = &self.programs[1];
-
-        self.weight_buffer.write(&self.weights);
-        let weight_buffer = &self.weight_buffer;
-
-        self.intermediate_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-
-            render_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    WeightBlock: weight_buffer,
-                    Texture0: attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                };
-
-                // Disable depth test
-                fs_quad.render(framebuffer, program, &Default::default(), &uniforms)
-            })
-        })
-    }
-
-    fn pass3(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters) -> GLResult<()> {
+    fn pass2(&self, frame: &mut glium::Frame) -> GLResult<()> {
 
         frame.clear_color(0.0, 0.0, 0.0, 1.0);
         frame.clear_depth(1.0);
 
-        self.intermediate_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                WeightBlock: &self.weight_buffer,
-                Texture0: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
-
-            self.fs_quad.render(frame, &self.programs[2], draw_params, &uniforms)
-        })
+        // Blur horizontally into an intermediate target, then vertically into the frame.
+        self.blur_chain.run(&self.render_fbo.attachment.color, frame, |_, _| {})
     }
 }
-
-fn gauss(x: f32, sigma2: f32) -> f32 {
-    const TWO_PI: f64 = std::f64::consts::PI * 2.0;
-    let sigma2 = sigma2 as f64;
-    let x = x as f64;
-
-	let coeff: f64 = 1.0 / (TWO_PI * sigma2);
-    let expon: f64 = -(x * x) / (2.0 * sigma2);
-
-    (coeff * expon.exp()) as f32
-}
@@ -3,12 +3,13 @@ use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{Teapot, Plane, Torus, Quad};
 use cookbook::{Mat4F, Mat3F, Vec3F};
-use cookbook::framebuffer::{DeferredPNCAttachment, GLDeferredFrameBuffer};
+use cookbook::framebuffer::{GBufferAttachment, GBufferLayout, GLDeferredFrameBuffer};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
+use glium::texture::{UncompressedFloatFormat, DepthFormat};
 use glium::{Surface, uniform, implement_uniform_block};
 
 
@@ -21,7 +22,7 @@ pub struct SceneDeferred {
     torus   : Torus,
     fs_quad : Quad,
 
-    deferred_fbo: GLDeferredFrameBuffer::<DeferredPNCAttachment>,
+    deferred_fbo: GLDeferredFrameBuffer::<GBufferAttachment>,
 
     material_buffer : UniformBuffer<MaterialInfo>,
     light_buffer    : UniformBuffer<LightInfo>,
@@ -68,7 +69,13 @@ impl Scene for SceneDeferred {
         // ----------------------------------------------------------------------------
 
         // Initialize FrameBuffer Objects ---------------------------------------------
-        let deferred_fbo = GLDeferredFrameBuffer::setup(display, screen_width, screen_height)?;
+        let gbuffer_layout = GBufferLayout::new()
+            .with_output("PositionData", UncompressedFloatFormat::F32F32F32)
+            .with_output("NormalData",   UncompressedFloatFormat::F32F32F32)
+            .with_output("ColorData",    UncompressedFloatFormat::U8U8U8)
+            .with_depth_buffer(DepthFormat::F32);
+        gbuffer_layout.verify(&programs[0])?;
+        let deferred_fbo = gbuffer_layout.build(display, screen_width, screen_height)?;
         // ----------------------------------------------------------------------------
 
         // Initialize MVP -------------------------------------------------------------
@@ -114,8 +121,8 @@ impl Scene for SceneDeferred {
         self.pass2(frame)
     }
 
-    fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
-        self.deferred_fbo = GLDeferredFrameBuffer::setup(display, width, height)?;
+    fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
+        self.deferred_fbo.resize(width, height)?;
         self.projection   = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), width as f32 / height as f32, 0.3, 100.0);
         Ok(())
     }
@@ -172,14 +179,11 @@ impl SceneDeferred {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+        let mut framebuffer = self.deferred_fbo.framebuffer()?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-            framebuffer.clear_depth(1.0);
-
-            teapot.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.teapot.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Plane ------------------------------------------------------------
@@ -195,10 +199,7 @@ impl SceneDeferred {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Torus ------------------------------------------------------------
@@ -215,10 +216,7 @@ impl SceneDeferred {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let torus = &self.torus;
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            torus.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.torus.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Write the material one more time
@@ -233,22 +231,21 @@ impl SceneDeferred {
         frame.clear_color(0.5, 0.5, 0.5, 1.0);
         frame.clear_depth(1.0);
 
-        self.deferred_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                LightInfo: &self.light_buffer,
-                PositionTex: attachment.position.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                NormalTex: attachment.normal.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                ColorTex: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
-
-            self.fs_quad.render(frame, &self.programs[1], &Default::default(), &uniforms)
-        })
+        let attachment = &self.deferred_fbo.attachment;
+
+        let uniforms = uniform! {
+            LightInfo: &self.light_buffer,
+            PositionTex: attachment.texture("PositionData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            NormalTex: attachment.texture("NormalData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            ColorTex: attachment.texture("ColorData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
+
+        self.fs_quad.render(frame, &self.programs[1], &Default::default(), &uniforms)
     }
 }
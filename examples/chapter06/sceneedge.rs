@@ -1,9 +1,10 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Teapot, Plane, Torus, Quad};
+use cookbook::objects::{Teapot, Plane, Torus};
 use cookbook::{Mat4F, Mat3F, Vec3F};
 use cookbook::framebuffer::{ColorDepthAttachment, GLFrameBuffer};
+use cookbook::postprocess::{PostProcessChain, PassInput, stages};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -15,14 +16,14 @@ use glium::{Surface, uniform, implement_uniform_block};
 
 pub struct SceneEdge {
 
-    programs: [glium::Program; 2],
+    program: glium::Program,
 
     teapot: Teapot,
     plane: Plane,
     torus: Torus,
-    fs_quad: Quad,
 
     fbo: GLFrameBuffer<ColorDepthAttachment>,
+    edge_chain: PostProcessChain,
     material_buffer: UniformBuffer<MaterialInfo>,
     light_buffer: UniformBuffer<LightInfo>,
 
@@ -59,7 +60,7 @@ impl Scene for SceneEdge {
         let aspect_ratio = (screen_width as f32) / (screen_height as f32);
 
         // Shader Program ------------------------------------------------------------
-        let programs = SceneEdge::compile_shader_program(display)
+        let program = SceneEdge::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
         // ----------------------------------------------------------------------------
 
@@ -68,7 +69,6 @@ impl Scene for SceneEdge {
         let teapot = Teapot::new(display, 14, Mat4F::identity())?;
         let plane = Plane::new(display, 50.0, 50.0, 1, 1, 1.0, 1.0)?;
         let torus = Torus::new(display, 0.7 * 1.5, 0.3 * 1.5, 50, 50)?;
-        let fs_quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
         // Initialize Textures --------------------------------------------------------
@@ -82,6 +82,8 @@ impl Scene for SceneEdge {
 
         // Initialize Uniforms --------------------------------------------------------
         let fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::U8U8U8U8)?;
+        let edge_pass = stages::edge_detection(display, "Edge", PassInput::Source, 0.05)?;
+        let edge_chain = PostProcessChain::new(display, screen_width, screen_height, vec![edge_pass])?;
 
         glium::implement_uniform_block!(LightInfo, LightPosition, L, La);
         let light_buffer = UniformBuffer::immutable(display, LightInfo {
@@ -96,8 +98,8 @@ impl Scene for SceneEdge {
         // ----------------------------------------------------------------------------
 
         let scene = SceneEdge {
-            programs, fbo,
-            teapot, torus, plane, fs_quad, 
+            program, fbo, edge_chain,
+            teapot, torus, plane,
             material_buffer, light_buffer,
             aspect_ratio, angle, is_animate,
         };
@@ -126,12 +128,13 @@ impl Scene for SceneEdge {
         };
 
         self.pass1(&draw_params)?;
-        self.pass2(frame, &draw_params)
+        self.pass2(frame)
     }
 
     fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
         self.fbo = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::U8U8U8U8)?;
+        self.edge_chain.resize(width, height)?;
         Ok(())
     }
 
@@ -146,22 +149,18 @@ impl Scene for SceneEdge {
 
 impl SceneEdge {
 
-    fn compile_shader_program(display: &impl Facade) -> Result<[Program; 2], ProgramCreationError> {
+    fn compile_shader_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
 
         let pass1_vertex   = include_str!("shaders/edge/pass1.vert.glsl");
         let pass1_fragment = include_str!("shaders/edge/pass1.frag.glsl");
 
-        let pass2_vertex   = include_str!("shaders/edge/pass2.vert.glsl");
-        let pass2_fragment = include_str!("shaders/edge/pass2.frag.glsl");
-
-        let pass1 = glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))?;
-        let pass2 = glium::Program::new(display, GLSourceCode::new(pass2_vertex, pass2_fragment).with_srgb_output(true))?;
-        Ok([pass1, pass2])
+        glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))
     }
 
     fn pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        let program = &self.programs[0];
+        let program = &self.program;
+        let mut framebuffer = self.fbo.framebuffer()?;
 
         let view = Mat4F::look_at_rh(Vec3F::new(7.0 * self.angle.cos(), 4.0, 7.0 * self.angle.sin()), Vec3F::zero(), Vec3F::unit_y());
         let projection = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), self.aspect_ratio, 0.3, 100.0);
@@ -186,14 +185,10 @@ impl SceneEdge {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(1.0, 0.0, 0.0, 1.0);
-            framebuffer.clear_depth(1.0);
+        framebuffer.clear_color(1.0, 0.0, 0.0, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            teapot.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.teapot.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render plane ------------------------------------------------------------
@@ -215,10 +210,7 @@ impl SceneEdge {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render torus ------------------------------------------------------------
@@ -241,28 +233,15 @@ impl SceneEdge {
             MVP: (projection * mv).into_col_arrays(),
         };
 
-        let torus = &self.torus;
-        self.fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            torus.render(framebuffer, program, draw_params, &uniforms)
-        })
+        self.torus.render(&mut framebuffer, program, draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 
-    fn pass2(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters) -> GLResult<()> {
+    fn pass2(&self, frame: &mut glium::Frame) -> GLResult<()> {
 
         frame.clear_color(0.5, 0.5, 0.5, 1.0);
         frame.clear_depth(1.0);
 
-        self.fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                EdgeThreshold: 0.05_f32,
-                RenderTex: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
-
-            self.fs_quad.render(frame, &self.programs[1], draw_params, &uniforms)
-        })
+        self.edge_chain.run(&self.fbo.attachment.color, frame, |_, _| {})
     }
 }
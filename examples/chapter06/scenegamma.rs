@@ -3,20 +3,25 @@ use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{ObjMesh, ObjMeshConfiguration};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
+use cookbook::framebuffer::{ColorDepthAttachment, GLFrameBuffer};
+use cookbook::postprocess::{PostProcessChain, PassInput, stages};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
+use glium::texture::UncompressedFloatFormat;
 use glium::{Surface, uniform, implement_uniform_block};
 
 
-#[derive(Debug)]
 pub struct SceneGamma {
 
     program: glium::Program,
 
     ogre: ObjMesh,
+
+    fbo: GLFrameBuffer<ColorDepthAttachment>,
+    gamma_chain: PostProcessChain,
     
     material_buffer: UniformBuffer<MaterialInfo>,
     light_buffer   : UniformBuffer<LightInfo>,
@@ -50,6 +55,8 @@ impl Scene for SceneGamma {
 
     fn new(display: &impl Facade) -> GLResult<SceneGamma> {
 
+        let (screen_width, screen_height) = display.get_context().get_framebuffer_dimensions();
+
         // Shader Program ------------------------------------------------------------
         let program = SceneGamma::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
@@ -74,6 +81,11 @@ impl Scene for SceneGamma {
 
 
         // Initialize Uniforms --------------------------------------------------------
+        // Render the linear colors into a floating-point target, and encode them in the gamma correction pass.
+        let fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::F16F16F16F16)?;
+        let gamma_pass = stages::gamma_correction(display, "Gamma", PassInput::Source, 2.2)?;
+        let gamma_chain = PostProcessChain::new(display, screen_width, screen_height, vec![gamma_pass])?;
+
         glium::implement_uniform_block!(LightInfo, LightPosition, Intensity);
         let light_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
@@ -90,7 +102,7 @@ impl Scene for SceneGamma {
 
         let scene = SceneGamma {
             program,
-            ogre, material_buffer, light_buffer,
+            ogre, fbo, gamma_chain, material_buffer, light_buffer,
             projection, is_animate, angle,
         };
         Ok(scene)
@@ -132,16 +144,27 @@ impl Scene for SceneGamma {
         let uniforms = uniform! {
             LightInfo: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
-            Gamma: 2.2_f32,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.ogre.render(frame, &self.program, &draw_params, &uniforms)
+        {
+            let mut framebuffer = self.fbo.framebuffer()?;
+            // The linear value of the gray background 0.5 after gamma correction.
+            framebuffer.clear_color(0.218, 0.218, 0.218, 1.0);
+            framebuffer.clear_depth(1.0);
+
+            self.ogre.render(&mut framebuffer, &self.program, &draw_params, &uniforms)?;
+        }
+
+        self.gamma_chain.run(&self.fbo.attachment.color, frame, |_, _| {})
     }
 
-    fn resize(&mut self, _display: &impl Facade, _width: u32, _height: u32) -> GLResult<()> {
+    fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
+
+        self.fbo = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::F16F16F16F16)?;
+        self.gamma_chain.resize(width, height)?;
 
         const C: f32 = 2.5;
         self.projection = Mat4F::orthographic_rh_zo(vek::FrustumPlanes {
@@ -168,7 +191,7 @@ impl SceneGamma {
         let fragment_shader_code = include_str!("shaders/gamma.frag.glsl");
 
         let sources = GLSourceCode::new(vertex_shader_code, fragment_shader_code)
-            .with_srgb_output(true);
+            .with_srgb_output(false);
         glium::Program::new(display, sources)
     }
 }
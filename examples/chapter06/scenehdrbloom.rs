@@ -1,9 +1,11 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Teapot, Plane, Sphere, Quad};
+use cookbook::objects::{Teapot, Plane, Sphere};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
-use cookbook::framebuffer::{ColorDepthAttachment, ColorAttachment, GLFrameBuffer};
+use cookbook::framebuffer::pool::{RenderTargetPool, TargetDescriptor, TargetHandle};
+use cookbook::postprocess::{PostProcessChain, PassInput, stages};
+use cookbook::postprocess::luminance::{LuminanceReduction, EyeAdaptation, PendingLuminance};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -13,26 +15,30 @@ use glium::texture::UncompressedFloatFormat;
 use glium::{Surface, uniform, implement_uniform_block};
 
 
+/// The sample count of the HDR render target.
+const MSAA_SAMPLES: u32 = 4;
+/// How fast the exposure adapts to the change of luminance, in 1/second.
+const EYE_ADAPTATION_SPEED: f32 = 1.5;
+
 pub struct SceneHdrBloom {
 
-    programs: [glium::Program; 5],
+    program: glium::Program,
 
     teapot  : Teapot,
     plane   : Plane,
     sphere  : Sphere,
-    fs_quad : Quad,
 
-    hdr_fbo   : GLFrameBuffer::<ColorDepthAttachment>,
-    blur_fbo1 : GLFrameBuffer::<ColorAttachment>,
-    blur_fbo2 : GLFrameBuffer::<ColorAttachment>,
+    targets    : RenderTargetPool,
+    bloom_chain: PostProcessChain,
+    luminance  : LuminanceReduction,
+    adaptation : EyeAdaptation,
+    pending_luminance: Option<PendingLuminance>,
 
     material_buffer : UniformBuffer<MaterialInfo>,
     light_buffer    : UniformBuffer<[LightInfo; 5]>,
-    weight_buffer   : UniformBuffer<[f32; 10]>,
 
     ave_lum: f32,
-    screen_width : u32,
-    screen_height: u32,
+    delta_time: f32,
 
     aspect_ratio: f32,
     view: Mat4F,
@@ -65,12 +71,10 @@ impl Scene for SceneHdrBloom {
     fn new(display: &impl Facade) -> GLResult<SceneHdrBloom> {
 
         let (screen_width, screen_height) = display.get_context().get_framebuffer_dimensions();
-        let bloom_buffer_width  = screen_width / 8;
-        let bloom_buffer_height = screen_height / 8;
         let aspect_ratio = (screen_width as f32) / (screen_height as f32);
 
         // Shader Program ------------------------------------------------------------
-        let programs = SceneHdrBloom::compile_shader_program(display)
+        let program = SceneHdrBloom::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
         // ----------------------------------------------------------------------------
 
@@ -79,33 +83,20 @@ impl Scene for SceneHdrBloom {
         let teapot = Teapot::new(display, 14, Mat4F::identity())?;
         let plane = Plane::new(display, 20.0, 10.0, 1, 1, 1.0, 1.0)?;
         let sphere = Sphere::new(display, 2.0, 50, 50)?;
-        let fs_quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
         // Initialize FrameBuffer Objects ---------------------------------------------
-        let hdr_fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::F32F32F32)?;
-        // Create an FBO for the bright-pass filter and blur
-        let blur_fbo1 = GLFrameBuffer::setup(display, bloom_buffer_width, bloom_buffer_height, UncompressedFloatFormat::F32F32F32)?;
-        let blur_fbo2 = GLFrameBuffer::setup(display, bloom_buffer_width, bloom_buffer_height, UncompressedFloatFormat::F32F32F32)?;
+        // The HDR target is acquired every frame, and follows the size of window automatically.
+        let targets = RenderTargetPool::new(display);
         // ----------------------------------------------------------------------------
 
-        // Compute and sum the weights ------------------------------------------------
-        let mut weights: [f32; 10] = Default::default();
-        let sigma2 = 25.0;
-
-        weights[0] = gauss(0.0, sigma2);
-        let mut sum = weights[0];
-
-        for i in 1..10 {
-            weights[i] = gauss(i as f32, sigma2);
-            sum += 2.0 * weights[i];
-        }
-
-        // Normalize the weights and set the uniform
-        for i in 0..10 {
-            weights[i] = weights[i] / sum;
-        }
-        // println!("{:?}", weights);
+        // Initialize Post-processing -------------------------------------------------
+        // The bright-pass filter and blur run in 1/8 resolution, then composite with the tone mapped HDR image.
+        let mut bloom_passes = stages::bloom(display, "Bloom", PassInput::Source, 1.7, 25.0, 0.125)?;
+        bloom_passes.push(stages::bloom_composite(display, "Composite", PassInput::Source, PassInput::pass("Bloom"), 0.35, 0.928)?);
+        let bloom_chain = PostProcessChain::new(display, screen_width, screen_height, bloom_passes)?;
+        let luminance = LuminanceReduction::new(display, screen_width, screen_height)?;
+        let adaptation = EyeAdaptation::new(EYE_ADAPTATION_SPEED);
         // ----------------------------------------------------------------------------
 
         // Initialize MVP -------------------------------------------------------------
@@ -123,23 +114,21 @@ impl Scene for SceneHdrBloom {
         glium::implement_uniform_block!(MaterialInfo, Ka, Kd, Ks, Shininess);
         let material_buffer = UniformBuffer::empty_immutable(display)
             .map_err(BufferCreationErrorKind::UniformBlock)?;
-
-        let weight_buffer = UniformBuffer::immutable(display, weights)
-            .map_err(BufferCreationErrorKind::UniformBlock)?;
         // ----------------------------------------------------------------------------
 
         let scene = SceneHdrBloom {
-            programs, hdr_fbo, blur_fbo1, blur_fbo2,
-            teapot, sphere, plane, fs_quad,
-            material_buffer, light_buffer, weight_buffer,
-            screen_width, screen_height,
+            program, targets, bloom_chain, luminance, adaptation,
+            pending_luminance: None,
+            teapot, sphere, plane,
+            material_buffer, light_buffer,
             aspect_ratio, view, projection, ave_lum,
+            delta_time: 0.0,
         };
         Ok(scene)
     }
 
-    fn update(&mut self, _delta_time: f32) {
-        // Nothing to do, leave it empty...
+    fn update(&mut self, delta_time: f32) {
+        self.delta_time = delta_time;
     }
 
     fn render(&mut self, frame: &mut glium::Frame) -> GLResult<()> {
@@ -153,22 +142,20 @@ impl Scene for SceneHdrBloom {
             ..Default::default()
         };
 
-        self.pass1(&draw_params)?;
-        self.compute_log_ave_luminance();
-        self.pass2()?;
-        self.pass3()?;
-        self.pass4()?;
-        self.pass5(frame, &draw_params)
+        self.targets.begin_frame();
+        let hdr = self.targets.acquire(SceneHdrBloom::hdr_target_descriptor())?;
+
+        self.pass1(hdr, &draw_params)?;
+        self.targets.resolve(hdr)?;
+        self.compute_log_ave_luminance(hdr)?;
+        self.pass2(hdr, frame)
     }
 
-    fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
+    fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
-        self.hdr_fbo      = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::F32F32F32)?;
-        self.blur_fbo1    = GLFrameBuffer::setup(display, width / 8, height / 8, UncompressedFloatFormat::F32F32F32)?;
-        self.blur_fbo2    = GLFrameBuffer::setup(display, width / 8, height / 8, UncompressedFloatFormat::F32F32F32)?;
-        self.projection   = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), self.aspect_ratio, 0.3, 100.0);
-        self.screen_width  = width;
-        self.screen_height = height;
+        self.bloom_chain.resize(width, height)?;
+        self.luminance.resize(width, height)?;
+        self.projection = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), self.aspect_ratio, 0.3, 100.0);
         Ok(())
     }
 
@@ -179,175 +166,54 @@ impl Scene for SceneHdrBloom {
 
 impl SceneHdrBloom {
 
-    fn compile_shader_program(display: &impl Facade) -> Result<[Program; 5], ProgramCreationError> {
+    fn compile_shader_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
 
         let pass1_vertex   = include_str!("shaders/hdrbloom/pass1.vert.glsl");
         let pass1_fragment = include_str!("shaders/hdrbloom/pass1.frag.glsl");
 
-        let pass2_vertex   = include_str!("shaders/hdrbloom/pass2.vert.glsl");
-        let pass2_fragment = include_str!("shaders/hdrbloom/pass2.frag.glsl");
-
-        let pass3_vertex   = include_str!("shaders/hdrbloom/pass3.vert.glsl");
-        let pass3_fragment = include_str!("shaders/hdrbloom/pass3.frag.glsl");
-
-        let pass4_vertex   = include_str!("shaders/hdrbloom/pass4.vert.glsl");
-        let pass4_fragment = include_str!("shaders/hdrbloom/pass4.frag.glsl");
-
-        let pass5_vertex   = include_str!("shaders/hdrbloom/pass5.vert.glsl");
-        let pass5_fragment = include_str!("shaders/hdrbloom/pass5.frag.glsl");
-
-        let pass1 = glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))?;
-        let pass2 = glium::Program::new(display, GLSourceCode::new(pass2_vertex, pass2_fragment).with_srgb_output(false))?;
-        let pass3 = glium::Program::new(display, GLSourceCode::new(pass3_vertex, pass3_fragment).with_srgb_output(false))?;
-        let pass4 = glium::Program::new(display, GLSourceCode::new(pass4_vertex, pass4_fragment).with_srgb_output(false))?;
-        let pass5 = glium::Program::new(display, GLSourceCode::new(pass5_vertex, pass5_fragment).with_srgb_output(true))?;
-        Ok([pass1, pass2, pass3, pass4, pass5])
+        glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))
     }
 
-    fn pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
-        self.draw_scene(draw_params)
-    }
-
-    fn compute_log_ave_luminance(&mut self) {
-
-        // For accurate estimation, we must calculate from `hdr_texture` every frame. -----------------
-        // This process is very very slow on CPU.
-        // let mut sum = 0.0;
-
-        // self.hdr_fbo.rent(|(_, attachment)| {
-        //     unsafe {
-        //         let pixels: glium::texture::pixel_buffer::PixelBuffer<(f32, f32, f32, f32)> = attachment.color.unchecked_read_to_pixel_buffer();
-        //         let pixels: Vec<(f32, f32, f32, f32)> = pixels.read_as_texture_1d()
-        //             .expect("Failed to read as texture 1d");
-
-        //         for pixel in pixels {
-        //             let lum = pixel.0 * 0.2126 + pixel.1 * 0.7152 + pixel.2 * 0.0722;
-        //             sum += (lum + 0.00001).ln();
-        //         }
-        //     }
-        // });
-
-        // self.ave_lum = (sum / (self.screen_width as f32 * self.screen_height as f32)).exp();
-        // println!("Ave lum: {}", self.ave_lum);
-        // ------------------------------------------------------------------------------------------
-
-        // For static scene. Just hard code this value may reduce the above heavy computation. ------
-        self.ave_lum = 0.581015;
-        // ------------------------------------------------------------------------------------------
-    }
-
-    fn pass2(&mut self) -> GLResult<()> {
-
-        let hdr_fbo = &self.hdr_fbo;
-        let program = &self.programs[1];
-        let fs_quad = &self.fs_quad;
-
-        self.blur_fbo1.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.0, 0.0, 0.0, 0.0);
-
-            hdr_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    LumThresh: 1.7_f32,
-                    HdrTex: attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
-                };
-
-                // Disable depth test
-                let draw_params: glium::DrawParameters = Default::default();
-
-                fs_quad.render(framebuffer, program, &draw_params, &uniforms)
-            })
-        })
+    fn hdr_target_descriptor() -> TargetDescriptor {
+        TargetDescriptor::new(UncompressedFloatFormat::F32F32F32)
+            .with_depth()
+            .with_samples(MSAA_SAMPLES)
     }
 
-    fn pass3(&mut self) -> GLResult<()> {
-
-        let blur_fbo1 = &self.blur_fbo1;
-        let program = &self.programs[2];
-        let fs_quad = &self.fs_quad;
-        let weight_buffer = &self.weight_buffer;
-
-        self.blur_fbo2.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            blur_fbo1.rent(|(_, attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    WeightBlock: weight_buffer,
-                    BlurTex1: attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
-                };
-
-                // Disable depth test
-                let draw_params: glium::DrawParameters = Default::default();
-
-                fs_quad.render(framebuffer, program, &draw_params, &uniforms)
-            })
-        })
+    fn pass1(&mut self, hdr: TargetHandle, draw_params: &glium::DrawParameters) -> GLResult<()> {
+        self.draw_scene(hdr, draw_params)
     }
 
-    fn pass4(&mut self) -> GLResult<()> {
-
-        let blur_fbo2 = &self.blur_fbo2;
-        let program = &self.programs[3];
-        let fs_quad = &self.fs_quad;
-        let weight_buffer = &self.weight_buffer;
-
-        self.blur_fbo1.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            blur_fbo2.rent(|(_, attachment)| -> GLResult<()> {
+    fn compute_log_ave_luminance(&mut self, hdr: TargetHandle) -> GLResult<()> {
 
-                let uniforms = uniform! {
-                    WeightBlock: weight_buffer,
-                    BlurTex2: attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
-                };
-
-                // Disable depth test
-                let draw_params: glium::DrawParameters = Default::default();
-
-                fs_quad.render(framebuffer, program, &draw_params, &uniforms)
-            })
-        })
+        // Reduce the resolved `hdr_texture` on GPU, and let the exposure adapt to it gradually.
+        // The statistics of the previous frame are used, so reading them back does not stall the pipeline.
+        let hdr_texture = self.targets.color(hdr)?;
+        let stats = match self.pending_luminance.take() {
+            | Some(pending) => pending.finish()?,
+            // there is no previous frame yet, so wait for the current one.
+            | None => self.luminance.compute(hdr_texture)?,
+        };
+        self.pending_luminance = Some(self.luminance.compute_async(hdr_texture)?);
+        self.ave_lum = self.adaptation.update(stats.log_average, self.delta_time);
+        Ok(())
     }
 
-    fn pass5(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters) -> GLResult<()> {
-
-        // In the pass, we're reading from blur_fbo1, and we want linear sampling to get an extra blur
+    fn pass2(&self, hdr: TargetHandle, frame: &mut glium::Frame) -> GLResult<()> {
 
         frame.clear_color(0.5, 0.5, 0.5, 1.0);
         frame.clear_depth(1.0);
 
-        self.hdr_fbo.rent(|(_, hdr_attachment)| -> GLResult<()> {
-            self.blur_fbo1.rent(|(_, blur_attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    AveLum: self.ave_lum,
-                    HdrTex: hdr_attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
-                    BlurTex1: blur_attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp),
-                };
-
-                self.fs_quad.render(frame, &self.programs[4], draw_params, &uniforms)
-            })
+        self.bloom_chain.run(self.targets.color(hdr)?, frame, |pass, uniforms| {
+            if pass == "Composite" {
+                uniforms.add("AveLum", self.ave_lum);
+            }
         })
     }
 
-    fn draw_scene(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
+    fn draw_scene(&mut self, hdr: TargetHandle, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        let program = &self.programs[0];
+        let program = &self.program;
 
         let light_data = [
             LightInfo {
@@ -389,14 +255,11 @@ impl SceneHdrBloom {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
-            framebuffer.clear_depth(1.0);
+        let mut framebuffer = self.targets.framebuffer(hdr)?;
+        framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render bottom plane -----------------------------------------------------
@@ -404,16 +267,14 @@ impl SceneHdrBloom {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render top plane --------------------------------------------------------
@@ -422,16 +283,14 @@ impl SceneHdrBloom {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render sphere -----------------------------------------------------------
@@ -446,17 +305,14 @@ impl SceneHdrBloom {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let sphere = &self.sphere;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            sphere.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.sphere.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // -----------------------------------------------------------------------
 
         // Render teapot ---------------------------------------------------------
@@ -472,28 +328,14 @@ impl SceneHdrBloom {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            teapot.render(framebuffer, program, draw_params, &uniforms)
-        })
+        self.teapot.render(&mut framebuffer, program, draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 }
-
-fn gauss(x: f32, sigma2: f32) -> f32 {
-    const TWO_PI: f64 = std::f64::consts::PI * 2.0;
-    let sigma2 = sigma2 as f64;
-    let x = x as f64;
-
-	let coeff: f64 = 1.0 / (TWO_PI * sigma2);
-    let expon: f64 = -(x * x) / (2.0 * sigma2);
-
-    (coeff * expon.exp()) as f32
-}
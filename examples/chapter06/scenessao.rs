@@ -4,13 +4,13 @@ use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
 use cookbook::objects::{Plane, Quad, ObjMesh, ObjMeshConfiguration};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
 use cookbook::texture::{load_texture, load_custom_texture};
-use cookbook::framebuffer::{DeferredPNCAttachment, ColorAttachment, GLDeferredFrameBuffer, GLFrameBuffer};
+use cookbook::framebuffer::{GBufferAttachment, GBufferLayout, ColorAttachment, GLDeferredFrameBuffer, GLFrameBuffer};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
 use glium::program::{Program, ProgramCreationError};
 use glium::uniforms::UniformBuffer;
-use glium::texture::{UncompressedFloatFormat, MipmapsOption};
+use glium::texture::{UncompressedFloatFormat, DepthFormat, MipmapsOption};
 use glium::texture::texture2d::Texture2d;
 use glium::{Surface, uniform, implement_uniform_block};
 
@@ -26,7 +26,7 @@ pub struct SceneSsao {
     plane  : Plane,
     quad   : Quad,
 
-    deferred_fbo: GLDeferredFrameBuffer::<DeferredPNCAttachment>,
+    deferred_fbo: GLDeferredFrameBuffer::<GBufferAttachment>,
     ssao_fbo1: GLFrameBuffer<ColorAttachment>,
     ssao_fbo2: GLFrameBuffer<ColorAttachment>,
 
@@ -92,7 +92,13 @@ impl Scene for SceneSsao {
         // ---------------------------------------------------------------------------
 
         // Initialize FrameBuffer Objects ---------------------------------------------
-        let deferred_fbo = GLDeferredFrameBuffer::setup(display, screen_width, screen_height)?;
+        let gbuffer_layout = GBufferLayout::new()
+            .with_output("PositionData", UncompressedFloatFormat::F32F32F32)
+            .with_output("NormalData",   UncompressedFloatFormat::F32F32F32)
+            .with_output("ColorData",    UncompressedFloatFormat::U8U8U8)
+            .with_depth_buffer(DepthFormat::F32);
+        gbuffer_layout.verify(&programs[0])?;
+        let deferred_fbo = gbuffer_layout.build(display, screen_width, screen_height)?;
         let ssao_fbo1 = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::F16)?;
         let ssao_fbo2 = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::F16)?;
         // ----------------------------------------------------------------------------
@@ -148,7 +154,7 @@ impl Scene for SceneSsao {
     }
 
     fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
-        self.deferred_fbo = GLDeferredFrameBuffer::setup(display, width, height)?;
+        self.deferred_fbo.resize(width, height)?;
         self.ssao_fbo1 = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::F16)?;
         self.ssao_fbo2 = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::F16)?;
         self.projection   = Mat4F::perspective_rh_zo(50.0_f32.to_radians(), width as f32 / height as f32, 0.3, 100.0);
@@ -227,7 +233,6 @@ impl SceneSsao {
         // Render Walls ----------------------------------------------------------
         // Plane1
         self.material_buffer.write(&MaterialInfo { UseTex: true, ..Default::default() });
-        let plane = &self.plane;
 
         let model = Mat4F::identity();
         let mv: Mat4F = view * model;
@@ -242,13 +247,11 @@ impl SceneSsao {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+        let mut framebuffer = self.deferred_fbo.framebuffer()?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-            framebuffer.clear_depth(1.0);
-
-            plane.render(framebuffer, program, &draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Plane2 ------------------------------------------------------------
@@ -266,9 +269,7 @@ impl SceneSsao {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, &draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Plane3 ------------------------------------------------------------
@@ -287,9 +288,7 @@ impl SceneSsao {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, &draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Mesh -------------------------------------------------------------
@@ -310,100 +309,74 @@ impl SceneSsao {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let bunny = &self.bunny;
-        self.deferred_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            bunny.render(framebuffer, program, &draw_params, &uniforms)
-        })
+        self.bunny.render(&mut framebuffer, program, &draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 
     fn pass2(&mut self) -> GLResult<()> {
 
-        let deferred_fbo = &self.deferred_fbo;
-        let projection = self.projection.into_col_arrays();
-        let kernel_buffer = &self.kernel_buffer;
-        let rand_tex = &self.rand_tex;
-        let quad = &self.quad;
-        let program = &self.programs[1];
-
-        self.ssao_fbo1.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
-
-            deferred_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    ProjectionMatrix: projection,
-                    SampleKernel: kernel_buffer,
-                    PositionTex: attachment.position.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                    NormalTex: attachment.normal.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                    RandTex: rand_tex.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                };
-
-                quad.render(framebuffer, program, &Default::default(), &uniforms)
-            })
-        })
-    }
+        let mut framebuffer = self.ssao_fbo1.framebuffer()?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
 
-    fn pass3(&mut self) -> GLResult<()> {
+        let attachment = &self.deferred_fbo.attachment;
 
-        let ao_tex = &self.ssao_fbo1;
-        let quad = &self.quad;
-        let program = &self.programs[2];
+        let uniforms = uniform! {
+            ProjectionMatrix: self.projection.into_col_arrays(),
+            SampleKernel: &self.kernel_buffer,
+            PositionTex: attachment.texture("PositionData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            NormalTex: attachment.texture("NormalData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            RandTex: self.rand_tex.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
+
+        self.quad.render(&mut framebuffer, &self.programs[1], &Default::default(), &uniforms)
+    }
 
-        self.ssao_fbo2.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+    fn pass3(&mut self) -> GLResult<()> {
 
-            framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        let mut framebuffer = self.ssao_fbo2.framebuffer()?;
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
 
-            ao_tex.rent(|(_, attachment)| -> GLResult<()> {
+        let attachment = &self.ssao_fbo1.attachment;
 
-                let uniforms = uniform! {
-                    RandTex: attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                };
+        let uniforms = uniform! {
+            RandTex: attachment.color.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
 
-                quad.render(framebuffer, program, &Default::default(), &uniforms)
-            })
-        })
+        self.quad.render(&mut framebuffer, &self.programs[2], &Default::default(), &uniforms)
     }
 
     fn pass4(&self, frame: &mut glium::Frame) -> GLResult<()> {
 
-        let deferred_fbo = &self.deferred_fbo;
-        let ssao_fbo2 = &self.ssao_fbo2;
-
         frame.clear_color(0.5, 0.5, 0.5, 1.0);
 
-        deferred_fbo.rent(|(_, deferred_attachment)| -> GLResult<()> {
-
-            ssao_fbo2.rent(|(_, ao_attachment)| -> GLResult<()> {
-
-                let uniforms = uniform! {
-                    LightInfo: &self.light_buffer,
-                    PositionTex: deferred_attachment.position.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                    NormalTex: deferred_attachment.normal.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                    ColorTex: deferred_attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                    AoTex: ao_attachment.color.sampled()
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                };
-
-                self.quad.render(frame, &self.programs[3], &Default::default(), &uniforms)
-            })
-        })
+        let deferred_attachment = &self.deferred_fbo.attachment;
+        let ao_attachment = &self.ssao_fbo2.attachment;
+
+        let uniforms = uniform! {
+            LightInfo: &self.light_buffer,
+            PositionTex: deferred_attachment.texture("PositionData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            NormalTex: deferred_attachment.texture("NormalData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            ColorTex: deferred_attachment.texture("ColorData")?.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            AoTex: ao_attachment.color.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
+
+        self.quad.render(frame, &self.programs[3], &Default::default(), &uniforms)
     }
 }
 
@@ -1,9 +1,11 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Teapot, Plane, Sphere, Quad};
+use cookbook::objects::{Teapot, Plane, Sphere};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
 use cookbook::framebuffer::{ColorDepthAttachment, GLFrameBuffer};
+use cookbook::postprocess::{PostProcessChain, PassInput, stages};
+use cookbook::postprocess::luminance::LuminanceReduction;
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -15,21 +17,20 @@ use glium::{Surface, uniform, implement_uniform_block};
 
 pub struct SceneToneMap {
 
-    programs: [glium::Program; 2],
+    program: glium::Program,
 
     teapot: Teapot,
     plane: Plane,
     sphere: Sphere,
-    quad: Quad,
 
     hdr_fbo: GLFrameBuffer::<ColorDepthAttachment>,
+    tone_map_chain: PostProcessChain,
+    luminance: LuminanceReduction,
 
     material_buffer: UniformBuffer<MaterialInfo>,
     light_buffer: UniformBuffer<[LightInfo; 5]>,
 
     ave_lum: f32,
-    screen_width : u32,
-    screen_height: u32,
 
     aspect_ratio: f32,
     view: Mat4F,
@@ -65,7 +66,7 @@ impl Scene for SceneToneMap {
         let aspect_ratio = (screen_width as f32) / (screen_height as f32);
 
         // Shader Program ------------------------------------------------------------
-        let programs = SceneToneMap::compile_shader_program(display)
+        let program = SceneToneMap::compile_shader_program(display)
             .map_err(GLErrorKind::CreateProgram)?;
         // ----------------------------------------------------------------------------
 
@@ -74,11 +75,13 @@ impl Scene for SceneToneMap {
         let teapot = Teapot::new(display, 14, Mat4F::identity())?;
         let plane = Plane::new(display, 20.0, 10.0, 1, 1, 1.0, 1.0)?;
         let sphere = Sphere::new(display, 2.0, 50, 50)?;
-        let quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
         // Initialize FrameBuffer Objects ---------------------------------------------
         let hdr_fbo = GLFrameBuffer::setup(display, screen_width, screen_height, UncompressedFloatFormat::F32F32F32)?;
+        let tone_map_pass = stages::tone_map(display, "ToneMap", PassInput::Source, 0.35, 0.928)?;
+        let tone_map_chain = PostProcessChain::new(display, screen_width, screen_height, vec![tone_map_pass])?;
+        let luminance = LuminanceReduction::new(display, screen_width, screen_height)?;
         // ----------------------------------------------------------------------------
 
         // Initialize MVP -------------------------------------------------------------
@@ -99,10 +102,9 @@ impl Scene for SceneToneMap {
         // ----------------------------------------------------------------------------
 
         let scene = SceneToneMap {
-            programs, hdr_fbo,
-            teapot, sphere, plane, quad,
+            program, hdr_fbo, tone_map_chain, luminance,
+            teapot, sphere, plane,
             material_buffer, light_buffer,
-            screen_width, screen_height,
             aspect_ratio, view, projection, ave_lum,
         };
         Ok(scene)
@@ -124,16 +126,16 @@ impl Scene for SceneToneMap {
         };
 
         self.pass1(&draw_params)?;
-        self.compute_log_ave_luminance();
-        self.pass2(frame, &draw_params)
+        self.compute_log_ave_luminance()?;
+        self.pass2(frame)
     }
 
     fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
         self.hdr_fbo = GLFrameBuffer::setup(display, width, height, UncompressedFloatFormat::F32F32F32)?;
+        self.tone_map_chain.resize(width, height)?;
+        self.luminance.resize(width, height)?;
         self.projection = Mat4F::perspective_rh_zo(60.0_f32.to_radians(), self.aspect_ratio, 0.3, 100.0);
-        self.screen_width  = width;
-        self.screen_height = height;
         Ok(())
     }
 
@@ -144,22 +146,17 @@ impl Scene for SceneToneMap {
 
 impl SceneToneMap {
 
-    fn compile_shader_program(display: &impl Facade) -> Result<[Program; 2], ProgramCreationError> {
+    fn compile_shader_program(display: &impl Facade) -> Result<Program, ProgramCreationError> {
 
         let pass1_vertex   = include_str!("shaders/tonemap/pass1.vert.glsl");
         let pass1_fragment = include_str!("shaders/tonemap/pass1.frag.glsl");
 
-        let pass2_vertex   = include_str!("shaders/tonemap/pass2.vert.glsl");
-        let pass2_fragment = include_str!("shaders/tonemap/pass2.frag.glsl");
-
-        let pass1 = glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))?;
-        let pass2 = glium::Program::new(display, GLSourceCode::new(pass2_vertex, pass2_fragment).with_srgb_output(true))?;
-        Ok([pass1, pass2])
+        glium::Program::new(display, GLSourceCode::new(pass1_vertex, pass1_fragment).with_srgb_output(false))
     }
 
     fn pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        let program = &self.programs[0];
+        let program = &self.program;
 
         let light_data = [
             LightInfo {
@@ -201,14 +198,11 @@ impl SceneToneMap {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-
-            framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
-            framebuffer.clear_depth(1.0);
+        let mut framebuffer = self.hdr_fbo.framebuffer()?;
+        framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render bottom plane -----------------------------------------------------
@@ -216,16 +210,14 @@ impl SceneToneMap {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render top plane --------------------------------------------------------
@@ -234,16 +226,14 @@ impl SceneToneMap {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render sphere -----------------------------------------------------------
@@ -258,17 +248,14 @@ impl SceneToneMap {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let sphere = &self.sphere;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            sphere.render(framebuffer, program, draw_params, &uniforms)
-        })?;
+        self.sphere.render(&mut framebuffer, program, draw_params, &uniforms)?;
         // -----------------------------------------------------------------------
 
         // Render teapot ---------------------------------------------------------
@@ -284,62 +271,32 @@ impl SceneToneMap {
         let mv: Mat4F = self.view * model;
 
         let uniforms = uniform! {
-            LightInfo: &self.light_buffer,
+            LightBlock: &self.light_buffer,
             MaterialInfo: &self.material_buffer,
             ModelViewMatrix: mv.clone().into_col_arrays(),
             NormalMatrix: Mat3F::from(mv).into_col_arrays(),
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.hdr_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            teapot.render(framebuffer, program, draw_params, &uniforms)
-        })
+        self.teapot.render(&mut framebuffer, program, draw_params, &uniforms)
         // ------------------------------------------------------------------------- 
     }
 
-    fn compute_log_ave_luminance(&mut self) {
+    fn compute_log_ave_luminance(&mut self) -> GLResult<()> {
 
-        // For accurate estimation, we must calculate from `hdr_texture` every frame. -----------------
-        // This process is very very slow on CPU.
-        // let mut sum = 0.0;
-
-        // self.hdr_fbo.rent(|(_, attachment)| {
-        //     unsafe {
-        //         let pixels: glium::texture::pixel_buffer::PixelBuffer<(f32, f32, f32, f32)> = attachment.color.unchecked_read_to_pixel_buffer();
-        //         let pixels: Vec<(f32, f32, f32, f32)> = pixels.read_as_texture_1d()
-        //             .expect("Failed to read as texture 1d");
-
-        //         for pixel in pixels {
-        //             let lum = pixel.0 * 0.2126 + pixel.1 * 0.7152 + pixel.2 * 0.0722;
-        //             sum += (lum + 0.00001).ln();
-        //         }
-        //     }
-        // });
-
-        // self.ave_lum = (sum / (self.screen_width as f32 * self.screen_height as f32)).exp();
-        // ------------------------------------------------------------------------------------------
-
-        // For static scene. Just hard code this value may reduce the above heavy computation. ------
-        self.ave_lum = 0.581015;
-        // ------------------------------------------------------------------------------------------
+        // Reduce `hdr_texture` on GPU, so that the tone mapping follows the content of every frame.
+        let stats = self.luminance.compute(&self.hdr_fbo.attachment.color)?;
+        self.ave_lum = stats.log_average;
+        Ok(())
     }
 
-    fn pass2(&self, frame: &mut glium::Frame, draw_params: &glium::DrawParameters) -> GLResult<()> {
+    fn pass2(&self, frame: &mut glium::Frame) -> GLResult<()> {
 
         frame.clear_color(0.0, 0.0, 0.0, 1.0);
         frame.clear_depth(1.0);
 
-        self.hdr_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                AveLum: self.ave_lum,
-                HdrTex: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
-
-            self.quad.render(frame, &self.programs[1], draw_params, &uniforms)
+        self.tone_map_chain.run(&self.hdr_fbo.attachment.color, frame, |_, uniforms| {
+            uniforms.add("AveLum", self.ave_lum);
         })
     }
 }
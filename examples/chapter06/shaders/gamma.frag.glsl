@@ -5,9 +5,6 @@ layout (location = 0) in vec3 Position;
 layout (location = 1) in vec3 Normal;
 layout (location = 2) in vec2 TexCoord;
 
-
-uniform float Gamma;
-
 uniform LightInfo {
     vec4 LightPosition;  // Light position in eye coords.
     vec3 Intensity;      // A, D, S intensity
@@ -42,6 +39,7 @@ vec3 phongModel(vec3 position, vec3 n) {
 
 void main() {
 
+    // The gamma correction is applied by a post-processing pass.
     vec3 color = phongModel(Position, Normal);
-    FragColor = vec4(pow(color, vec3(1.0 / Gamma)), 1.0);
+    FragColor = vec4(color, 1.0);
 }
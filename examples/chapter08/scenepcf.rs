@@ -1,9 +1,9 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Plane, Frustum, ObjMesh, Quad, ObjMeshConfiguration};
+use cookbook::objects::{Plane, ObjMesh, Quad, ObjMeshConfiguration};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
-use cookbook::framebuffer::{ShadowDepthAttachment, GLFrameBuffer};
+use cookbook::shadow::{self, ShadowMap, ShadowFilter};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -18,11 +18,10 @@ pub struct ScenePcf {
     programs: [glium::Program; 3],
 
     building: ObjMesh,
-    frustum: Frustum,
     plane: Plane,
     quad: Quad,
 
-    shadow_fbo: GLFrameBuffer<ShadowDepthAttachment>,
+    shadow_map: ShadowMap,
 
     material_buffer : UniformBuffer<MaterialInfo>,
     light_buffer    : UniformBuffer<LightInfo>,
@@ -31,7 +30,6 @@ pub struct ScenePcf {
     is_animate: bool,
     aspect_ratio: f32,
 
-    light_pv: Mat4F,
 }
 
 
@@ -72,27 +70,16 @@ impl Scene for ScenePcf {
             is_center: false,
             is_print_load_message: true,
         })?;
-        let mut frustum = Frustum::new(display)?;
         let quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
-        // Initialize FrameBuffer Objects ---------------------------------------------
-        let shadow_fbo = GLFrameBuffer::setup_depth(display, 512, 512, DepthFormat::I24)?;
+        // Initialize Shadow Map ------------------------------------------------------
+        let mut shadow_map = ShadowMap::new(display, 512, DepthFormat::I24)?;
+        let light_pos = Vec3F::new(-2.5, -2.0, -2.5); // World coords
+        shadow_map.set_spot_light(light_pos, Vec3F::zero(), Vec3F::unit_y(), 40.0, 0.1, 100.0);
         // ----------------------------------------------------------------------------
 
         // Initialize MVP -------------------------------------------------------------
-        let shadow_scale = Mat4F::new(
-            0.5, 0.0, 0.0, 0.5,
-            0.0, 0.5, 0.0, 0.5,
-            0.0, 0.0, 0.5, 0.5,
-            0.0, 0.0, 0.0, 1.0,
-        );
-        let light_pos = Vec3F::new(-2.5, -2.0, -2.5); // World coords
-        frustum.orient(light_pos, Vec3F::zero(), Vec3F::unit_y());
-        frustum.set_perspective(40.0, 1.0, 0.1, 100.0);
-
-        let light_pv = shadow_scale * frustum.get_projection_matrix() * frustum.get_view_matrix();
-        
         let angle = std::f32::consts::PI * 2.0 * 0.85;
         let is_animate = true;
         let aspect_ratio = 0.0;
@@ -110,10 +97,10 @@ impl Scene for ScenePcf {
         // ----------------------------------------------------------------------------
 
         let scene = ScenePcf {
-            programs, shadow_fbo,
-            plane, building, frustum, quad,
+            programs, shadow_map,
+            plane, building, quad,
             material_buffer, light_buffer,
-            angle, is_animate, aspect_ratio, light_pv,
+            angle, is_animate, aspect_ratio,
         };
         Ok(scene)
     }
@@ -137,8 +124,7 @@ impl Scene for ScenePcf {
         self.pass3(frame)
     }
 
-    fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
-        self.shadow_fbo = GLFrameBuffer::setup_depth(display, 512, 512, DepthFormat::I24)?;
+    fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
         Ok(())
     }
@@ -174,18 +160,9 @@ impl ScenePcf {
 
     fn pass1(&mut self) -> GLResult<()> {
 
-        let draw_params = glium::draw_parameters::DrawParameters {
-            depth: glium::Depth {
-                test: glium::DepthTest::IfLess,
-                write: true,
-                ..Default::default()
-            },
-            backface_culling: glium::draw_parameters::BackfaceCullingMode::CullCounterClockwise,
-            ..Default::default()
-        };
-        
-        // glPolygonOffset(2.5, 10.0); is not support in glium.
+        // glPolygonOffset(2.5, 10.0); is not support in glium, so the front faces are culled instead.
         // See https://github.com/glium/glium/issues/826
+        let draw_params = shadow::depth_pass_parameters();
 
         self.draw_scene_pass1(&draw_params)?;
 
@@ -221,43 +198,36 @@ impl ScenePcf {
             ..Default::default()
         };
 
-        self.shadow_fbo.rent(|(_, shadowmap)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                ShadowTex: shadowmap.depth.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
+        let uniforms = uniform! {
+            ShadowTex: self.shadow_map.depth().sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
 
-            self.quad.render(frame, &self.programs[2], &draw_params, &uniforms)
-        })
+        self.quad.render(frame, &self.programs[2], &draw_params, &uniforms)
     }
 
     fn draw_scene_pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
         let program    = &self.programs[0];
-        let buidling   = &self.building;
-        let plane      = &self.plane;
-        let view       = self.frustum.get_view_matrix();
-        let projection = self.frustum.get_projection_matrix();
+        let view_projection = self.shadow_map.view_projection();
 
-        self.shadow_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+        let mut framebuffer = self.shadow_map.framebuffer()?;
 
-            let model = Mat4F::identity();
+        let model = Mat4F::identity();
 
-            let uniforms = uniform! {
-                MVP: (projection * view * model).into_col_arrays(),
-            };
-            framebuffer.clear_depth(1.0);
+        let uniforms = uniform! {
+            MVP: (view_projection * model).into_col_arrays(),
+        };
+        framebuffer.clear_depth(1.0);
 
-            // Render building --------------------------------------------------------
-            buidling.render(framebuffer, program, draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
+        // Render building --------------------------------------------------------
+        self.building.render(&mut framebuffer, program, draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
 
-            // Render Plane ------------------------------------------------------------
-            plane.render(framebuffer, program, draw_params, &uniforms)
-            // ------------------------------------------------------------------------- 
-        })
+        // Render Plane ------------------------------------------------------------
+        self.plane.render(&mut framebuffer, program, draw_params, &uniforms)
+        // ------------------------------------------------------------------------- 
     }
 
     fn draw_scene_pass2(&self, framebuffer: &mut impl Surface, draw_params: &glium::DrawParameters) -> GLResult<()> {
@@ -266,54 +236,47 @@ impl ScenePcf {
         let view = Mat4F::look_at_rh(camera_pos, Vec3F::new(0.0, -0.175, 0.0), Vec3F::unit_y());
         let projection = Mat4F::perspective_rh_zo(50.0_f32.to_radians(), self.aspect_ratio, 0.1, 100.0);
 
-        let frustum_origin = self.frustum.get_origin();
+        let frustum_origin = self.shadow_map.frustum().get_origin();
         let light_pos = view * Vec4F::new(frustum_origin.x, frustum_origin.y, frustum_origin.z, 1.0);
         self.light_buffer.write(&LightInfo {
             LightPosition: light_pos.into_array(),
             Intensity: [0.85, 0.85, 0.85], ..Default::default()
         });
 
-        self.shadow_fbo.rent(|(_, shadowmap)| -> GLResult<()> {
-
-            let model = Mat4F::identity();
-            let mv: Mat4F = view * model;
-
-            let uniforms = uniform! {
-                MaterialInfo: &self.material_buffer,
-                LightInfo: &self.light_buffer,
-                ShadowMap: shadowmap.depth.sampled()
-                    .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                    .depth_texture_comparison(Some(glium::uniforms::DepthTextureComparison::LessOrEqual)),
-                ModelViewMatrix: mv.clone().into_col_arrays(),
-                NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-                MVP: (projection * mv).into_col_arrays(),
-                ShadowMatrix: (self.light_pv * model).into_col_arrays(),
-            };
-
-            // Render building --------------------------------------------------------
-            self.material_buffer.write(&MaterialInfo {
-                Ka: [1.0 * 0.1, 0.85 * 0.1, 0.55 * 0.1],
-                Kd: [1.0, 0.85, 0.55],
-                Ks: [0.0, 0.0, 0.0],
-                Shininess: 1.0, ..Default::default()
-            });
-
-            self.building.render(framebuffer, &self.programs[1], draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
-
-            // Render Plane ------------------------------------------------------------
-            self.material_buffer.write(&MaterialInfo {
-                Ka: [0.05, 0.05, 0.05],
-                Kd: [0.25, 0.25, 0.25],
-                Ks: [0.0, 0.0, 0.0],
-                Shininess: 1.0, ..Default::default()
-            });
-
-            self.plane.render(framebuffer, &self.programs[1], draw_params, &uniforms)
-            // ------------------------------------------------------------------------- 
-        })
+        let model = Mat4F::identity();
+        let mv: Mat4F = view * model;
+
+        let uniforms = uniform! {
+            MaterialInfo: &self.material_buffer,
+            LightInfo: &self.light_buffer,
+            ShadowMap: self.shadow_map.sampler(ShadowFilter::Nearest),
+            ModelViewMatrix: mv.clone().into_col_arrays(),
+            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
+            MVP: (projection * mv).into_col_arrays(),
+            ShadowMatrix: (self.shadow_map.shadow_matrix() * model).into_col_arrays(),
+        };
+
+        // Render building --------------------------------------------------------
+        self.material_buffer.write(&MaterialInfo {
+            Ka: [1.0 * 0.1, 0.85 * 0.1, 0.55 * 0.1],
+            Kd: [1.0, 0.85, 0.55],
+            Ks: [0.0, 0.0, 0.0],
+            Shininess: 1.0, ..Default::default()
+        });
+
+        self.building.render(framebuffer, &self.programs[1], draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
+
+        // Render Plane ------------------------------------------------------------
+        self.material_buffer.write(&MaterialInfo {
+            Ka: [0.05, 0.05, 0.05],
+            Kd: [0.25, 0.25, 0.25],
+            Ks: [0.0, 0.0, 0.0],
+            Shininess: 1.0, ..Default::default()
+        });
+
+        self.plane.render(framebuffer, &self.programs[1], draw_params, &uniforms)
+        // ------------------------------------------------------------------------- 
     }
 
     #[allow(dead_code)]
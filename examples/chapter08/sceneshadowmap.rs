@@ -1,9 +1,9 @@
 
 use cookbook::scene::{Scene, GLSourceCode};
 use cookbook::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
-use cookbook::objects::{Teapot, Plane, Torus, Frustum, Quad};
+use cookbook::objects::{Teapot, Plane, Torus, Quad};
 use cookbook::{Mat4F, Mat3F, Vec3F, Vec4F};
-use cookbook::framebuffer::{ShadowDepthAttachment, GLFrameBuffer};
+use cookbook::shadow::{self, ShadowMap, ShadowFilter};
 use cookbook::Drawable;
 
 use glium::backend::Facade;
@@ -24,10 +24,9 @@ pub struct SceneShadowMap {
     teapot  : Teapot,
     plane   : Plane,
     torus   : Torus,
-    frustum : Frustum,
     quad: Quad,
 
-    shadow_fbo: GLFrameBuffer<ShadowDepthAttachment>,
+    shadow_map: ShadowMap,
 
     material_buffer : UniformBuffer<MaterialInfo>,
     light_buffer    : UniformBuffer<LightInfo>,
@@ -36,7 +35,6 @@ pub struct SceneShadowMap {
     is_animate: bool,
     aspect_ratio: f32,
 
-    light_pv: Mat4F,
     view: Mat4F,
     projection: Mat4F,
 }
@@ -77,28 +75,17 @@ impl Scene for SceneShadowMap {
         let teapot = Teapot::new(display, 14, Mat4F::identity())?;
         let plane = Plane::new(display, 40.0, 40.0, 2, 2, 1.0, 1.0)?;
         let torus = Torus::new(display, 0.7 * 2.0, 0.3 * 2.0, 50, 50)?;
-        let mut frustum = Frustum::new(display)?;
         let quad = Quad::new(display)?;
         // ----------------------------------------------------------------------------
 
-        // Initialize FrameBuffer Objects ---------------------------------------------
-        let shadow_fbo = GLFrameBuffer::setup_depth(display, 512, 512, DepthFormat::I24)?;
-        // ----------------------------------------------------------------------------
-
-        // Initialize MVP -------------------------------------------------------------
-        let shadow_bias = Mat4F::new(
-            0.5, 0.0, 0.0, 0.5,
-            0.0, 0.5, 0.0, 0.5,
-            0.0, 0.0, 0.5, 0.5,
-            0.0, 0.0, 0.0, 1.0,
-        );
+        // Initialize Shadow Map ------------------------------------------------------
+        let mut shadow_map = ShadowMap::new(display, 512, DepthFormat::I24)?;
         let c: f32 = 1.65;
         let light_pos = Vec3F::new(0.0, c * 5.25, c * 7.5); // World coords
-        frustum.orient(light_pos, Vec3F::zero(), Vec3F::unit_y());
-        frustum.set_perspective(50.0, 1.0, 1.0, 25.0);
+        shadow_map.set_spot_light(light_pos, Vec3F::zero(), Vec3F::unit_y(), 50.0, 1.0, 25.0);
+        // ----------------------------------------------------------------------------
 
-        let light_pv = shadow_bias * frustum.get_projection_matrix() * frustum.get_view_matrix();
-        
+        // Initialize MVP -------------------------------------------------------------
         let view = Mat4F::identity();
         let projection = Mat4F::identity();
         let angle = std::f32::consts::FRAC_PI_4;
@@ -118,11 +105,11 @@ impl Scene for SceneShadowMap {
         // ----------------------------------------------------------------------------
 
         let scene = SceneShadowMap {
-            programs, solid_program, shadow_fbo,
-            teapot, torus, plane, frustum, quad,
+            programs, solid_program, shadow_map,
+            teapot, torus, plane, quad,
             material_buffer, light_buffer,
             angle, is_animate, aspect_ratio,
-            light_pv, view, projection,
+            view, projection,
         };
         Ok(scene)
     }
@@ -148,8 +135,7 @@ impl Scene for SceneShadowMap {
         self.draw_light_frustum(frame)
     }
 
-    fn resize(&mut self, display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
-        self.shadow_fbo = GLFrameBuffer::setup_depth(display, 512, 512, DepthFormat::I24)?;
+    fn resize(&mut self, _display: &impl Facade, width: u32, height: u32) -> GLResult<()> {
         self.aspect_ratio = width as f32 / height as f32;
         Ok(())
     }
@@ -194,21 +180,12 @@ impl SceneShadowMap {
 
     fn pass1(&mut self) -> GLResult<()> {
 
-        self.view       = self.frustum.get_view_matrix();
-        self.projection = self.frustum.get_projection_matrix();
+        self.view       = self.shadow_map.view_matrix();
+        self.projection = self.shadow_map.projection_matrix();
 
-        let draw_params = glium::draw_parameters::DrawParameters {
-            depth: glium::Depth {
-                test: glium::DepthTest::IfLess,
-                write: true,
-                ..Default::default()
-            },
-            backface_culling: glium::draw_parameters::BackfaceCullingMode::CullCounterClockwise,
-            ..Default::default()
-        };
-        
-        // glPolygonOffset(2.5, 10.0); is not support in glium.
+        // glPolygonOffset(2.5, 10.0); is not support in glium, so the front faces are culled instead.
         // See https://github.com/glium/glium/issues/826
+        let draw_params = shadow::depth_pass_parameters();
 
         self.draw_scene_pass1(&draw_params)?;
 
@@ -224,7 +201,7 @@ impl SceneShadowMap {
         self.view = Mat4F::look_at_rh(camera_pos, Vec3F::zero(), Vec3F::unit_y());
         self.projection = Mat4F::perspective_rh_zo(50.0_f32.to_radians(), self.aspect_ratio, 0.1, 100.0);
 
-        let frustum_origin = self.frustum.get_origin();
+        let frustum_origin = self.shadow_map.frustum().get_origin();
         let light_pos = self.view * Vec4F::new(frustum_origin.x, frustum_origin.y, frustum_origin.z, 1.0);
         self.light_buffer.write(&LightInfo {
             LightPosition: light_pos.into_array(),
@@ -256,166 +233,146 @@ impl SceneShadowMap {
             ..Default::default()
         };
 
-        self.shadow_fbo.rent(|(_, shadowmap)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                ShadowTex: shadowmap.depth.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-            };
+        let uniforms = uniform! {
+            ShadowTex: self.shadow_map.depth().sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+        };
 
-            self.quad.render(frame, &self.programs[2], &draw_params, &uniforms)
-        })
+        self.quad.render(frame, &self.programs[2], &draw_params, &uniforms)
     }
 
     fn draw_scene_pass1(&mut self, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
         let program    = &self.programs[0];
-        let teapot     = &self.teapot;
-        let torus      = &self.torus;
-        let plane      = &self.plane;
         let projection = self.projection.clone();
         let view       = self.view.clone();
 
-        self.shadow_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+        let mut framebuffer = self.shadow_map.framebuffer()?;
 
-            // Render Teapot --------------------------------------------------------
-            let model = Mat4F::rotation_x(-90.0_f32.to_radians());
+        // Render Teapot --------------------------------------------------------
+        let model = Mat4F::rotation_x(-90.0_f32.to_radians());
 
-            let uniforms = uniform! {
-                MVP: (projection * view * model).into_col_arrays(),
-            };
+        let uniforms = uniform! {
+            MVP: (projection * view * model).into_col_arrays(),
+        };
+
+        framebuffer.clear_depth(1.0);
+        self.teapot.render(&mut framebuffer, program, draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
 
-            framebuffer.clear_depth(1.0);
-            teapot.render(framebuffer, program, draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
+        // Render Torus ------------------------------------------------------------
+        let model = Mat4F::rotation_x(-45.0_f32.to_radians())
+            .translated_3d(Vec3F::new(0.0, 2.0, 5.0));
+
+        let uniforms = uniform! {
+            MVP: (projection * view * model).into_col_arrays(),
+        };
 
-            // Render Torus ------------------------------------------------------------
-            let model = Mat4F::rotation_x(-45.0_f32.to_radians())
-                .translated_3d(Vec3F::new(0.0, 2.0, 5.0));
+        self.torus.render(&mut framebuffer, program, draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
 
+        // Render three Plane -------------------------------------------------------
+        let models: [Mat4F; 3] = [
+            Mat4F::identity(),
+            Mat4F::rotation_z(-90.0_f32.to_radians())
+                .translated_3d(Vec3F::new(-5.0, 5.0, 0.0)),
+            Mat4F::rotation_x(90.0_f32.to_radians())
+                .translated_3d(Vec3F::new(0.0, 5.0, -5.0)),
+        ];
+
+        for &model in models.into_iter() {
             let uniforms = uniform! {
                 MVP: (projection * view * model).into_col_arrays(),
             };
 
-            torus.render(framebuffer, program, draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
-
-            // Render three Plane -------------------------------------------------------
-            let models: [Mat4F; 3] = [
-                Mat4F::identity(),
-                Mat4F::rotation_z(-90.0_f32.to_radians())
-                    .translated_3d(Vec3F::new(-5.0, 5.0, 0.0)),
-                Mat4F::rotation_x(90.0_f32.to_radians())
-                    .translated_3d(Vec3F::new(0.0, 5.0, -5.0)),
-            ];
-
-            for &model in models.into_iter() {
-                let uniforms = uniform! {
-                    MVP: (projection * view * model).into_col_arrays(),
-                };
-
-                plane.render(framebuffer, program, draw_params, &uniforms)?;
-            }
-            // ------------------------------------------------------------------------- 
-
-            Ok(())
-        })
+            self.plane.render(&mut framebuffer, program, draw_params, &uniforms)?;
+        }
+        // ------------------------------------------------------------------------- 
+
+        Ok(())
     }
 
     fn draw_scene_pass2(&self, framebuffer: &mut impl Surface, program: &glium::Program, draw_params: &glium::DrawParameters) -> GLResult<()> {
 
-        self.shadow_fbo.rent(|(_, shadowmap)| -> GLResult<()> {
+        let shadow_matrix = self.shadow_map.shadow_matrix();
 
-            // Render Teapot --------------------------------------------------------
-            let model = Mat4F::rotation_x(-90.0_f32.to_radians());
-            let mv: Mat4F = self.view * model;
+        // Render Teapot --------------------------------------------------------
+        let model = Mat4F::rotation_x(-90.0_f32.to_radians());
+        let mv: Mat4F = self.view * model;
 
-            self.material_buffer.write(&MaterialInfo {
-                Ka: [0.7 * 0.05, 0.5 * 0.05, 0.3 * 0.05],
-                Kd: [0.7, 0.5, 0.3],
-                Ks: [0.9, 0.9, 0.9],
-                Shininess: 150.0, ..Default::default()
-            });
+        self.material_buffer.write(&MaterialInfo {
+            Ka: [0.7 * 0.05, 0.5 * 0.05, 0.3 * 0.05],
+            Kd: [0.7, 0.5, 0.3],
+            Ks: [0.9, 0.9, 0.9],
+            Shininess: 150.0, ..Default::default()
+        });
 
-            let uniforms = uniform! {
-                MaterialInfo: &self.material_buffer,
-                LightInfo: &self.light_buffer,
-                ShadowMap: shadowmap.depth.sampled()
-                    .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                    .depth_texture_comparison(Some(glium::uniforms::DepthTextureComparison::LessOrEqual)),
-                ModelViewMatrix: mv.clone().into_col_arrays(),
-                NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-                MVP: (self.projection * mv).into_col_arrays(),
-                ShadowMatrix: (self.light_pv * model).into_col_arrays(),
-            };
+        let uniforms = uniform! {
+            MaterialInfo: &self.material_buffer,
+            LightInfo: &self.light_buffer,
+            ShadowMap: self.shadow_map.sampler(ShadowFilter::Nearest),
+            ModelViewMatrix: mv.clone().into_col_arrays(),
+            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
+            MVP: (self.projection * mv).into_col_arrays(),
+            ShadowMatrix: (shadow_matrix * model).into_col_arrays(),
+        };
+
+        self.teapot.render(framebuffer, program, draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
+
+        // Render Torus ------------------------------------------------------------
+        let model = Mat4F::rotation_x(-45.0_f32.to_radians())
+            .translated_3d(Vec3F::new(0.0, 2.0, 5.0));
+        let mv: Mat4F = self.view * model;
+
+        let uniforms = uniform! {
+            MaterialInfo: &self.material_buffer,
+            LightInfo: &self.light_buffer,
+            ShadowMap: self.shadow_map.sampler(ShadowFilter::Nearest),
+            ModelViewMatrix: mv.clone().into_col_arrays(),
+            NormalMatrix: Mat3F::from(mv).into_col_arrays(),
+            MVP: (self.projection * mv).into_col_arrays(),
+            ShadowMatrix: (shadow_matrix * model).into_col_arrays(),
+        };
+
+        self.torus.render(framebuffer, program, draw_params, &uniforms)?;
+        // ------------------------------------------------------------------------- 
+
+        // Render three Plane ----------------------------------------------------
+        self.material_buffer.write(&MaterialInfo {
+            Ka: [0.05, 0.05, 0.05],
+            Kd: [0.25, 0.25, 0.25],
+            Ks: [0.0, 0.0, 0.0],
+            Shininess: 1.0, ..Default::default()
+        });
 
-            self.teapot.render(framebuffer, program, draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
+        let models: [Mat4F; 3] = [
+            Mat4F::identity(),
+            Mat4F::rotation_z(-90.0_f32.to_radians())
+                .translated_3d(Vec3F::new(-5.0, 5.0, 0.0)),
+            Mat4F::rotation_x(90.0_f32.to_radians())
+                .translated_3d(Vec3F::new(0.0, 5.0, -5.0)),
+        ];
 
-            // Render Torus ------------------------------------------------------------
-            let model = Mat4F::rotation_x(-45.0_f32.to_radians())
-                .translated_3d(Vec3F::new(0.0, 2.0, 5.0));
+        for &model in models.into_iter() {
             let mv: Mat4F = self.view * model;
 
             let uniforms = uniform! {
                 MaterialInfo: &self.material_buffer,
                 LightInfo: &self.light_buffer,
-                ShadowMap: shadowmap.depth.sampled()
-                    .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                    .depth_texture_comparison(Some(glium::uniforms::DepthTextureComparison::LessOrEqual)),
+                ShadowMap: self.shadow_map.sampler(ShadowFilter::Nearest),
                 ModelViewMatrix: mv.clone().into_col_arrays(),
                 NormalMatrix: Mat3F::from(mv).into_col_arrays(),
                 MVP: (self.projection * mv).into_col_arrays(),
-                ShadowMatrix: (self.light_pv * model).into_col_arrays(),
+                ShadowMatrix: (shadow_matrix * model).into_col_arrays(),
             };
 
-            self.torus.render(framebuffer, program, draw_params, &uniforms)?;
-            // ------------------------------------------------------------------------- 
-
-            // Render three Plane ----------------------------------------------------
-            self.material_buffer.write(&MaterialInfo {
-                Ka: [0.05, 0.05, 0.05],
-                Kd: [0.25, 0.25, 0.25],
-                Ks: [0.0, 0.0, 0.0],
-                Shininess: 1.0, ..Default::default()
-            });
-
-            let models: [Mat4F; 3] = [
-                Mat4F::identity(),
-                Mat4F::rotation_z(-90.0_f32.to_radians())
-                    .translated_3d(Vec3F::new(-5.0, 5.0, 0.0)),
-                Mat4F::rotation_x(90.0_f32.to_radians())
-                    .translated_3d(Vec3F::new(0.0, 5.0, -5.0)),
-            ];
-
-            for &model in models.into_iter() {
-                let mv: Mat4F = self.view * model;
-
-                let uniforms = uniform! {
-                    MaterialInfo: &self.material_buffer,
-                    LightInfo: &self.light_buffer,
-                    ShadowMap: shadowmap.depth.sampled()
-                        .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
-                        .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                        .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest)
-                        .depth_texture_comparison(Some(glium::uniforms::DepthTextureComparison::LessOrEqual)),
-                    ModelViewMatrix: mv.clone().into_col_arrays(),
-                    NormalMatrix: Mat3F::from(mv).into_col_arrays(),
-                    MVP: (self.projection * mv).into_col_arrays(),
-                    ShadowMatrix: (self.light_pv * model).into_col_arrays(),
-                };
-
-                self.plane.render(framebuffer, program, draw_params, &uniforms)?;
-            }
-            // ------------------------------------------------------------------------- 
-
-            Ok(())
-        })
+            self.plane.render(framebuffer, program, draw_params, &uniforms)?;
+        }
+        // ------------------------------------------------------------------------- 
+
+        Ok(())
     }
 
     #[allow(dead_code)]
@@ -434,13 +391,14 @@ impl SceneShadowMap {
             ..Default::default()
         };
 
-        let mv = self.view * self.frustum.get_inverse_view_matrix();
+        let frustum = self.shadow_map.frustum();
+        let mv = self.view * frustum.get_inverse_view_matrix();
 
         let uniforms = uniform! {
             Color: [1.0_f32, 0.0, 0.0, 1.0],
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        self.frustum.render(frame, &self.solid_program, &draw_params, &uniforms)
+        frustum.render(frame, &self.solid_program, &draw_params, &uniforms)
     }
 }
@@ -196,14 +196,11 @@ impl SceneNightVision {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let teapot = &self.teapot;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
+        let mut framebuffer = self.render_fbo.framebuffer()?;
+        framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
+        framebuffer.clear_depth(1.0);
 
-            framebuffer.clear_color(0.5, 0.5, 0.5, 1.0);
-            framebuffer.clear_depth(1.0);
-
-            teapot.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.teapot.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Plane ------------------------------------------------------------
@@ -225,10 +222,7 @@ impl SceneNightVision {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let plane = &self.plane;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            plane.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.plane.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         // Render Torus ------------------------------------------------------------
@@ -251,10 +245,7 @@ impl SceneNightVision {
             MVP: (self.projection * mv).into_col_arrays(),
         };
 
-        let torus = &self.torus;
-        self.render_fbo.rent_mut(|(framebuffer, _)| -> GLResult<()> {
-            torus.render(framebuffer, program_pass1, &draw_params, &uniforms)
-        })?;
+        self.torus.render(&mut framebuffer, program_pass1, &draw_params, &uniforms)?;
         // ------------------------------------------------------------------------- 
 
         Ok(())
@@ -264,22 +255,21 @@ impl SceneNightVision {
 
         frame.clear_color(0.5, 0.5, 0.5, 1.0);
 
-        self.render_fbo.rent(|(_, attachment)| -> GLResult<()> {
-
-            let uniforms = uniform! {
-                Width : self.screen_width,
-                Height: self.screen_height,
-                Radius: self.screen_width as f32 / 3.5,
-                RenderTex: attachment.color.sampled()
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
-                NoiseTex: self.noise_tex.sampled()
-                    .wrap_function(glium::uniforms::SamplerWrapFunction::Repeat)
-                    .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
-                    .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear), 
-            };
-
-            self.fs_quad.render(frame, &self.programs[1], &Default::default(), &uniforms)
-        })
+        let attachment = &self.render_fbo.attachment;
+
+        let uniforms = uniform! {
+            Width : self.screen_width,
+            Height: self.screen_height,
+            Radius: self.screen_width as f32 / 3.5,
+            RenderTex: attachment.color.sampled()
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest),
+            NoiseTex: self.noise_tex.sampled()
+                .wrap_function(glium::uniforms::SamplerWrapFunction::Repeat)
+                .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+                .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear), 
+        };
+
+        self.fs_quad.render(frame, &self.programs[1], &Default::default(), &uniforms)
     }
 }
@@ -0,0 +1,52 @@
+// Bake the cubemaps of image-based lighting on CPU.
+// Run at project root directory:
+// $ cargo run --release --bin bakeibl -- equirect media/texture/panorama.hdr media/texture/cube/panorama/panorama 512
+// $ cargo run --release --bin bakeibl -- irradiance media/texture/cube/grace/grace media/texture/cube/grace-diffuse/grace-diffuse 32
+// $ cargo run --release --bin bakeibl -- prefilter media/texture/cube/grace/grace media/texture/cube/grace-specular/grace-specular 128 6
+// $ cargo run --release --bin bakeibl -- brdf media/texture/brdf_lut.hdr 128
+
+extern crate glsl_cookbook_rs as cookbook;
+
+use cookbook::ibl::{equirect, irradiance, specular};
+use cookbook::error::{GLResult, GLError};
+
+
+const USAGE: &str = "Usage:
+    bakeibl equirect   <panorama.hdr> <output prefix> <face size>
+    bakeibl irradiance <environment prefix> <output prefix> <face size>
+    bakeibl prefilter  <environment prefix> <output prefix> <face size> <mip levels>
+    bakeibl brdf       <output.hdr> <size>";
+
+fn main() -> GLResult<()> {
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = args.first().map(String::as_str).unwrap_or_default();
+
+    match (command, args.len()) {
+        | ("equirect", 4) => {
+            equirect::load_equirect_faces(&args[1], parse_size(&args[3])?)?
+                .write_hdr(&args[2])?;
+        },
+        | ("irradiance", 4) => {
+            irradiance::bake_irradiance(&args[1], &args[2], parse_size(&args[3])?)?;
+        },
+        | ("prefilter", 5) => {
+            specular::bake_prefiltered(&args[1], &args[2], parse_size(&args[3])?, parse_size(&args[4])?)?;
+        },
+        | ("brdf", 3) => {
+            specular::bake_brdf_lut(&args[1], parse_size(&args[2])?)?;
+        },
+        | _ => {
+            println!("{}", USAGE);
+            std::process::exit(1);
+        },
+    }
+
+    println!("Done.");
+    Ok(())
+}
+
+fn parse_size(arg: &str) -> GLResult<u32> {
+    arg.parse()
+        .map_err(|_| GLError::args(format!("Invalid size: {}", arg)))
+}
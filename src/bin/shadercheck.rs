@@ -0,0 +1,36 @@
+
+// Check the shaders of all recipes without OpenGL context.
+// Run at project root directory:
+// $ cargo run --bin shadercheck
+
+extern crate glsl_cookbook_rs as cookbook;
+
+use cookbook::shadercheck;
+use cookbook::error::GLResult;
+
+use std::path::PathBuf;
+
+
+fn main() -> GLResult<()> {
+
+    // The repository root can be given as the first argument, otherwise use the current directory.
+    let root = std::env::args().nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let diagnostics = shadercheck::check_examples(&root)?;
+
+    for diagnostic in diagnostics.iter() {
+        println!("{}", diagnostic);
+    }
+
+    let error_count = diagnostics.iter().filter(|diagnostic| diagnostic.is_error).count();
+    println!("-------------------------------------------------------------");
+    println!("Shader check finished: {} error(s), {} warning(s).", error_count, diagnostics.len() - error_count);
+
+    if error_count > 0 {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
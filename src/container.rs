@@ -0,0 +1,883 @@
+
+//! Load textures with pre-built mipmaps from KTX, KTX2 and DDS containers.
+//!
+//! Unlike the images loaded by `texture::load_texture`, the container holds every mipmap level, and possibly the
+//! faces of cubemap or the layers of texture array, in a format ready for GPU(including BC compressed formats):
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::container;
+//!
+//! let container = container::load_container("media/texture/cube/pisa.ktx2")?;
+//! let env_map = container.upload(display)?;
+//!
+//! let mut uniforms = DynamicUniforms::new();
+//! uniforms.add("EnvMap", &env_map);
+//! ```
+//!
+//! The compressed 2D textures are uploaded as they are when the context supports the format. Otherwise, and for
+//! compressed cubemaps and arrays(glium can only write compressed data to 2D textures), the BC1-BC5 blocks
+//! are decompressed on CPU before upload. BC6H and BC7 have no CPU fallback.
+//!
+//! The containers tagged as sRGB are uploaded to sRGB textures, except for the formats without an sRGB variant(1 and 2
+//! channels, BC4-BC6H and floating-point). A container with only the top level gets its mip chain generated on CPU,
+//! see `TextureContainer::with_generated_mipmaps`.
+//!
+//! The rows are uploaded in file order, which is the same as `TextureOrigin::TopLeft`.
+
+mod ktx;
+mod dds;
+mod bcn;
+
+use std::path::Path;
+
+use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::image::{Image, PixelData, ColorSpace};
+use crate::mipmap::{MipmapGenerator, MipFilter};
+use crate::uniforms::IntoUniformValue;
+
+use glium::backend::Facade;
+use glium::texture::{RawImage2d, ClientFormat, CubeLayer, MipmapsOption, CompressedMipmapsOption, UncompressedFloatFormat, CompressedFormat};
+use glium::texture::{SrgbFormat, CompressedSrgbFormat};
+use glium::texture::{Texture2d, CompressedTexture2d, Cubemap, Texture2dArray};
+use glium::texture::{SrgbTexture2d, CompressedSrgbTexture2d, SrgbCubemap, SrgbTexture2dArray};
+use glium::uniforms::{AsUniformValue, UniformValue, MagnifySamplerFilter};
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::{Rect, BlitTarget, Surface};
+
+use std::borrow::Cow;
+
+
+/// The faces of cubemap, in the order stored by KTX and DDS.
+const CUBEMAP_LAYERS: [CubeLayer; 6] = [
+    CubeLayer::PositiveX, CubeLayer::NegativeX,
+    CubeLayer::PositiveY, CubeLayer::NegativeY,
+    CubeLayer::PositiveZ, CubeLayer::NegativeZ,
+];
+
+/// The most layers of texture array accepted from a container, which is the minimum `GL_MAX_ARRAY_TEXTURE_LAYERS`
+/// required by OpenGL 4.5.
+const MAX_ARRAY_LAYERS: u32 = 2048;
+
+
+/// The shape of texture stored in a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerKind {
+    Texture2d,
+    Cubemap,
+    /// A 2D texture array with the given count of layers.
+    Texture2dArray(u32),
+}
+
+impl ContainerKind {
+
+    /// The count of images in each mipmap level.
+    pub fn image_count(&self) -> usize {
+        match self {
+            | ContainerKind::Texture2d => 1,
+            | ContainerKind::Cubemap   => 6,
+            | ContainerKind::Texture2dArray(layers) => *layers as usize,
+        }
+    }
+}
+
+
+/// The block compressed formats, each block encodes 4x4 pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    /// BC1(DXT1) without alpha.
+    Bc1,
+    /// BC1(DXT1) with 1-bit alpha.
+    Bc1Alpha,
+    /// BC2(DXT3).
+    Bc2,
+    /// BC3(DXT5).
+    Bc3,
+    /// BC4(RGTC1), single unsigned channel.
+    Bc4,
+    /// BC4(RGTC1), single signed channel.
+    Bc4Signed,
+    /// BC5(RGTC2), two unsigned channels.
+    Bc5,
+    /// BC5(RGTC2), two signed channels.
+    Bc5Signed,
+    /// BC6H, unsigned half float RGB.
+    Bc6hUnsigned,
+    /// BC6H, signed half float RGB.
+    Bc6hSigned,
+    /// BC7, RGBA.
+    Bc7,
+}
+
+impl BlockFormat {
+
+    /// The bytes of each 4x4 block.
+    pub fn block_bytes(&self) -> usize {
+        match self {
+            | BlockFormat::Bc1
+            | BlockFormat::Bc1Alpha
+            | BlockFormat::Bc4
+            | BlockFormat::Bc4Signed => 8,
+            | _ => 16,
+        }
+    }
+
+    fn compressed_format(&self) -> CompressedFormat {
+        match self {
+            | BlockFormat::Bc1          => CompressedFormat::S3tcDxt1NoAlpha,
+            | BlockFormat::Bc1Alpha     => CompressedFormat::S3tcDxt1Alpha,
+            | BlockFormat::Bc2          => CompressedFormat::S3tcDxt3Alpha,
+            | BlockFormat::Bc3          => CompressedFormat::S3tcDxt5Alpha,
+            | BlockFormat::Bc4          => CompressedFormat::RgtcFormatU,
+            | BlockFormat::Bc4Signed    => CompressedFormat::RgtcFormatI,
+            | BlockFormat::Bc5          => CompressedFormat::RgtcFormatUU,
+            | BlockFormat::Bc5Signed    => CompressedFormat::RgtcFormatII,
+            | BlockFormat::Bc6hUnsigned => CompressedFormat::BptcUnsignedFloat3,
+            | BlockFormat::Bc6hSigned   => CompressedFormat::BptcSignedFloat3,
+            | BlockFormat::Bc7          => CompressedFormat::BptcUnorm4,
+        }
+    }
+
+    /// The sRGB variant of `compressed_format`, only BC1-BC3 and BC7 have one.
+    fn compressed_srgb_format(&self) -> Option<CompressedSrgbFormat> {
+        match self {
+            | BlockFormat::Bc1      => Some(CompressedSrgbFormat::S3tcDxt1NoAlpha),
+            | BlockFormat::Bc1Alpha => Some(CompressedSrgbFormat::S3tcDxt1Alpha),
+            | BlockFormat::Bc2      => Some(CompressedSrgbFormat::S3tcDxt3Alpha),
+            | BlockFormat::Bc3      => Some(CompressedSrgbFormat::S3tcDxt5Alpha),
+            | BlockFormat::Bc7      => Some(CompressedSrgbFormat::Bptc),
+            | _ => None,
+        }
+    }
+}
+
+
+/// The type of each channel of uncompressed pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    /// Unsigned normalized byte.
+    U8,
+    /// Half float, stored as the bits in `u16`.
+    F16,
+    F32,
+}
+
+impl ComponentType {
+
+    pub fn bytes(&self) -> usize {
+        match self {
+            | ComponentType::U8  => 1,
+            | ComponentType::F16 => 2,
+            | ComponentType::F32 => 4,
+        }
+    }
+}
+
+
+/// The pixel format of the images in a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceFormat {
+    /// Interleaved channels(1 to 4) in RGBA order.
+    Uncompressed { channels: u8, component: ComponentType },
+    Compressed(BlockFormat),
+}
+
+impl SurfaceFormat {
+
+    /// The bytes of an image with the given dimension.
+    pub fn image_bytes(&self, width: u32, height: u32) -> usize {
+        match self {
+            | SurfaceFormat::Uncompressed { channels, component } => {
+                width as usize * height as usize * *channels as usize * component.bytes()
+            },
+            | SurfaceFormat::Compressed(block) => {
+                let blocks_x = (width as usize + 3) / 4;
+                let blocks_y = (height as usize + 3) / 4;
+                blocks_x * blocks_y * block.block_bytes()
+            },
+        }
+    }
+
+    /// Same as `image_bytes`, but `None` if the size overflows.
+    fn checked_image_bytes(&self, width: u32, height: u32) -> Option<usize> {
+        match self {
+            | SurfaceFormat::Uncompressed { channels, component } => {
+                (width as usize).checked_mul(height as usize)?
+                    .checked_mul(*channels as usize * component.bytes())
+            },
+            | SurfaceFormat::Compressed(block) => {
+                let blocks_x = (width as usize + 3) / 4;
+                let blocks_y = (height as usize + 3) / 4;
+                blocks_x.checked_mul(blocks_y)?.checked_mul(block.block_bytes())
+            },
+        }
+    }
+
+    fn client_format(channels: u8, component: ComponentType) -> ClientFormat {
+        match (component, channels) {
+            | (ComponentType::U8, 1)  => ClientFormat::U8,
+            | (ComponentType::U8, 2)  => ClientFormat::U8U8,
+            | (ComponentType::U8, 3)  => ClientFormat::U8U8U8,
+            | (ComponentType::U8, _)  => ClientFormat::U8U8U8U8,
+            | (ComponentType::F16, 1) => ClientFormat::F16,
+            | (ComponentType::F16, 2) => ClientFormat::F16F16,
+            | (ComponentType::F16, 3) => ClientFormat::F16F16F16,
+            | (ComponentType::F16, _) => ClientFormat::F16F16F16F16,
+            | (ComponentType::F32, 1) => ClientFormat::F32,
+            | (ComponentType::F32, 2) => ClientFormat::F32F32,
+            | (ComponentType::F32, 3) => ClientFormat::F32F32F32,
+            | (ComponentType::F32, _) => ClientFormat::F32F32F32F32,
+        }
+    }
+
+    fn gpu_format(channels: u8, component: ComponentType) -> UncompressedFloatFormat {
+        match (component, channels) {
+            | (ComponentType::U8, 1)  => UncompressedFloatFormat::U8,
+            | (ComponentType::U8, 2)  => UncompressedFloatFormat::U8U8,
+            | (ComponentType::U8, 3)  => UncompressedFloatFormat::U8U8U8,
+            | (ComponentType::U8, _)  => UncompressedFloatFormat::U8U8U8U8,
+            | (ComponentType::F16, 1) => UncompressedFloatFormat::F16,
+            | (ComponentType::F16, 2) => UncompressedFloatFormat::F16F16,
+            | (ComponentType::F16, 3) => UncompressedFloatFormat::F16F16F16,
+            | (ComponentType::F16, _) => UncompressedFloatFormat::F16F16F16F16,
+            | (ComponentType::F32, 1) => UncompressedFloatFormat::F32,
+            | (ComponentType::F32, 2) => UncompressedFloatFormat::F32F32,
+            | (ComponentType::F32, 3) => UncompressedFloatFormat::F32F32F32,
+            | (ComponentType::F32, _) => UncompressedFloatFormat::F32F32F32F32,
+        }
+    }
+}
+
+
+/// The GPU format of uncompressed images.
+#[derive(Debug, Clone, Copy)]
+enum UploadFormat {
+    Linear(UncompressedFloatFormat),
+    Srgb(SrgbFormat),
+}
+
+
+/// A mipmap level of container.
+#[derive(Debug, Clone)]
+pub struct MipLevel {
+    width : u32,
+    height: u32,
+    /// One image for 2D texture, the faces for cubemap, or the layers for texture array.
+    images: Vec<Vec<u8>>,
+}
+
+impl MipLevel {
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn images(&self) -> &[Vec<u8>] {
+        &self.images
+    }
+}
+
+
+/// Check the header of a container before reading its images, and return the total bytes of the images.
+///
+/// A corrupted header may declare a huge texture, so the count of levels is bounded by the dimension, the count of
+/// layers by `MAX_ARRAY_LAYERS`, and the total bytes by the `available` bytes of file, before anything is allocated.
+pub(crate) fn check_container_size(kind: ContainerKind, format: SurfaceFormat, width: u32, height: u32, level_count: u32, available: usize) -> GLResult<usize> {
+
+    if width == 0 || height == 0 {
+        return Err(GLError::custom("The texture container has no image."))
+    }
+
+    // A full mipmap chain has floor(log2(max(width, height))) + 1 levels.
+    let max_level_count = 32 - width.max(height).leading_zeros();
+    if level_count > max_level_count {
+        return Err(GLError::custom(format!("The {}x{} texture container has {} mipmap levels, but at most {} are possible.", width, height, level_count, max_level_count)))
+    }
+    if let ContainerKind::Texture2dArray(layers) = kind {
+        if layers > MAX_ARRAY_LAYERS {
+            return Err(GLError::custom(format!("The texture array has {} layers, but at most {} are supported.", layers, MAX_ARRAY_LAYERS)))
+        }
+    }
+
+    let total_bytes = (0..level_count).try_fold(0_usize, |total, level| {
+        format.checked_image_bytes((width >> level).max(1), (height >> level).max(1))?
+            .checked_mul(kind.image_count())?
+            .checked_add(total)
+    });
+    match total_bytes {
+        | Some(total_bytes) if total_bytes <= available => Ok(total_bytes),
+        | _ => Err(GLError::custom(format!("The images of the {}x{} texture container exceed the file size.", width, height))),
+    }
+}
+
+
+/// The texture decoded from a container file, whose data stays on CPU until `upload`.
+#[derive(Debug, Clone)]
+pub struct TextureContainer {
+    kind: ContainerKind,
+    format: SurfaceFormat,
+    is_srgb: bool,
+    levels: Vec<MipLevel>,
+}
+
+impl TextureContainer {
+
+    /// Check the levels against the kind and format. The level 0 is the full size image, and each following level
+    /// halves the dimension(at least 1).
+    fn new(kind: ContainerKind, format: SurfaceFormat, is_srgb: bool, width: u32, height: u32, level_images: Vec<Vec<Vec<u8>>>) -> GLResult<TextureContainer> {
+
+        if width == 0 || height == 0 || level_images.is_empty() {
+            return Err(GLError::custom("The texture container has no image."))
+        }
+        if kind == ContainerKind::Cubemap && width != height {
+            return Err(GLError::custom("The cubemap face must share the same width and height."))
+        }
+
+        let mut levels = Vec::with_capacity(level_images.len());
+        for (i, images) in level_images.into_iter().enumerate() {
+            let level_width  = (width  >> i).max(1);
+            let level_height = (height >> i).max(1);
+
+            if images.len() != kind.image_count() {
+                return Err(GLError::custom(format!("The mipmap level {} has {} images, but {} are expected.", i, images.len(), kind.image_count())))
+            }
+            if images.iter().any(|image| image.len() != format.image_bytes(level_width, level_height)) {
+                return Err(GLError::custom(format!("The image size of mipmap level {} does not match its dimension.", i)))
+            }
+
+            levels.push(MipLevel { width: level_width, height: level_height, images });
+        }
+
+        Ok(TextureContainer { kind, format, is_srgb, levels })
+    }
+
+    pub fn kind(&self) -> ContainerKind {
+        self.kind
+    }
+
+    pub fn format(&self) -> SurfaceFormat {
+        self.format
+    }
+
+    /// Whether the color channels are sRGB encoded according to the container.
+    pub fn is_srgb(&self) -> bool {
+        self.is_srgb
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.levels[0].width, self.levels[0].height)
+    }
+
+    pub fn mip_levels(&self) -> &[MipLevel] {
+        &self.levels
+    }
+
+    /// Decompress the BC1-BC5 blocks to uncompressed pixels. The container is returned as it is if not compressed.
+    ///
+    /// BC1-BC3 are decompressed to 4 channels. BC4 and BC5 are decompressed to 1 and 2 channels, as bytes for
+    /// unsigned variants and as floats within [-1, 1] for signed variants.
+    pub fn decompress(self) -> GLResult<TextureContainer> {
+
+        let block = match self.format {
+            | SurfaceFormat::Uncompressed { .. } => return Ok(self),
+            | SurfaceFormat::Compressed(block) => block,
+        };
+
+        let format = bcn::decompressed_format(block)?;
+        let mut levels = Vec::with_capacity(self.levels.len());
+        for level in self.levels {
+            let images = level.images.iter()
+                .map(|image| bcn::decompress(block, image, level.width, level.height))
+                .collect::<GLResult<Vec<_>>>()?;
+            levels.push(MipLevel { images, ..level });
+        }
+
+        Ok(TextureContainer { levels, format, ..self })
+    }
+
+    /// Generate the complete mip chain on CPU by `generator`, see the `mipmap` module. The container is returned as it
+    /// is if it already has more than one level.
+    ///
+    /// The compressed images are decompressed first, and the color channels are filtered in linear space if `is_srgb`
+    /// (unless `generator` says otherwise). Half float images are not supported, since `Image` has no such pixels.
+    pub fn with_generated_mipmaps(self, generator: &MipmapGenerator) -> GLResult<TextureContainer> {
+
+        if self.levels.len() > 1 {
+            return Ok(self)
+        }
+
+        let container = self.decompress()?;
+        let (channels, component) = match container.format {
+            | SurfaceFormat::Uncompressed { channels, component } => (channels, component),
+            | SurfaceFormat::Compressed(_) => unreachable!(),
+        };
+        let color_space = if container.is_srgb { ColorSpace::Srgb } else { ColorSpace::Linear };
+        let (width, height) = container.dimensions();
+
+        let chains = container.levels[0].images.iter().map(|bytes| {
+            let data = match component {
+                | ComponentType::U8  => PixelData::U8(bytes.clone()),
+                | ComponentType::F32 => PixelData::F32(bytes.chunks(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()),
+                | ComponentType::F16 => return Err(GLError::unsupported("CPU mipmap generation of half float containers")),
+            };
+            let image = Image::new(width, height, channels, data)?
+                .with_color_space(color_space);
+            generator.generate(&image)
+        }).collect::<GLResult<Vec<_>>>()?;
+
+        let level_images = (0..chains[0].levels().len()).map(|level| {
+            chains.iter().map(|chain| match chain.levels()[level].data() {
+                | PixelData::U8(data)  => data.clone(),
+                | PixelData::F32(data) => data.iter().flat_map(|value| value.to_le_bytes().to_vec()).collect(),
+                | PixelData::U16(_) => unreachable!("the levels keep the component type of the top level"),
+            }).collect()
+        }).collect();
+
+        TextureContainer::new(container.kind, container.format, container.is_srgb, width, height, level_images)
+    }
+
+    /// Upload all levels to GPU.
+    ///
+    /// The mip chain of a container with only one level is generated on CPU by a box filter, except for the half float
+    /// images, whose mipmaps are generated by the driver.
+    pub fn upload(&self, display: &impl Facade) -> GLResult<ContainerTexture> {
+
+        match (self.kind, self.format) {
+            | (ContainerKind::Texture2d, SurfaceFormat::Compressed(block)) if self.is_compressed_supported(display, block) => {
+                self.upload_compressed_2d(display, block)
+            },
+            | (_, SurfaceFormat::Compressed(_)) => {
+                self.clone().decompress()?.upload(display)
+            },
+            | (_, SurfaceFormat::Uncompressed { component, .. }) if self.levels.len() == 1 && component != ComponentType::F16 => {
+                self.clone().with_generated_mipmaps(&MipmapGenerator::new(MipFilter::Box))?.upload_uncompressed(display)
+            },
+            | (_, SurfaceFormat::Uncompressed { .. }) => {
+                self.upload_uncompressed(display)
+            },
+        }
+    }
+
+    fn upload_uncompressed(&self, display: &impl Facade) -> GLResult<ContainerTexture> {
+        match (self.kind, self.format) {
+            | (ContainerKind::Texture2d, SurfaceFormat::Uncompressed { channels, component }) => {
+                self.upload_2d(display, channels, component)
+            },
+            | (_, SurfaceFormat::Uncompressed { channels, component }) => {
+                self.upload_layered(display, channels, component)
+            },
+            | (_, SurfaceFormat::Compressed(_)) => unreachable!("the compressed containers are decompressed before"),
+        }
+    }
+
+    fn mipmaps_option(&self) -> MipmapsOption {
+        if self.levels.len() == 1 {
+            MipmapsOption::AutoGeneratedMipmaps
+        } else {
+            MipmapsOption::EmptyMipmapsMax(self.levels.len() as u32 - 1)
+        }
+    }
+
+    /// The sRGB format for the uncompressed images, `None` if not `is_srgb` or the format has no sRGB variant.
+    fn srgb_format(&self, channels: u8, component: ComponentType) -> Option<SrgbFormat> {
+        match (self.is_srgb, component, channels) {
+            | (true, ComponentType::U8, 3) => Some(SrgbFormat::U8U8U8),
+            | (true, ComponentType::U8, 4) => Some(SrgbFormat::U8U8U8U8),
+            | _ => None,
+        }
+    }
+
+    fn upload_format(&self, channels: u8, component: ComponentType) -> UploadFormat {
+        match self.srgb_format(channels, component) {
+            | Some(srgb_format) => UploadFormat::Srgb(srgb_format),
+            | None => UploadFormat::Linear(SurfaceFormat::gpu_format(channels, component)),
+        }
+    }
+
+    fn compressed_srgb_format(&self, block: BlockFormat) -> Option<CompressedSrgbFormat> {
+        block.compressed_srgb_format().filter(|_| self.is_srgb)
+    }
+
+    fn is_compressed_supported(&self, display: &impl Facade, block: BlockFormat) -> bool {
+        let context = &**display.get_context();
+        match self.compressed_srgb_format(block) {
+            | Some(format) => format.is_supported(context),
+            | None => block.compressed_format().is_supported(context),
+        }
+    }
+
+    fn upload_compressed_2d(&self, display: &impl Facade, block: BlockFormat) -> GLResult<ContainerTexture> {
+
+        let (width, height) = self.dimensions();
+        let top = &self.levels[0].images[0];
+        // The compressed textures can not generate mipmaps by themselves.
+        let mipmaps = if self.levels.len() == 1 {
+            CompressedMipmapsOption::NoMipmap
+        } else {
+            CompressedMipmapsOption::EmptyMipmapsMax(self.levels.len() as u32 - 1)
+        };
+
+        match self.compressed_srgb_format(block) {
+            | Some(format) => {
+                let texture = CompressedSrgbTexture2d::with_compressed_data(display, top, width, height, format, mipmaps)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                for (i, level) in self.levels.iter().enumerate().skip(1) {
+                    texture.mipmap(i as u32)
+                        .ok_or_else(|| GLError::custom(format!("The compressed texture has no mipmap level {}.", i)))?
+                        .write_compressed_data(level_rect(level), &level.images[0], level.width, level.height, format)
+                        .map_err(|_| GLError::custom(format!("Failed to upload the mipmap level {} of compressed texture.", i)))?;
+                }
+
+                Ok(ContainerTexture::CompressedSrgbTexture2d(texture))
+            },
+            | None => {
+                let format = block.compressed_format();
+                let texture = CompressedTexture2d::with_compressed_data(display, top, width, height, format, mipmaps)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                for (i, level) in self.levels.iter().enumerate().skip(1) {
+                    texture.mipmap(i as u32)
+                        .ok_or_else(|| GLError::custom(format!("The compressed texture has no mipmap level {}.", i)))?
+                        .write_compressed_data(level_rect(level), &level.images[0], level.width, level.height, format)
+                        .map_err(|_| GLError::custom(format!("Failed to upload the mipmap level {} of compressed texture.", i)))?;
+                }
+
+                Ok(ContainerTexture::CompressedTexture2d(texture))
+            },
+        }
+    }
+
+    fn upload_2d(&self, display: &impl Facade, channels: u8, component: ComponentType) -> GLResult<ContainerTexture> {
+
+        let top = raw_image(&self.levels[0], 0, channels, component);
+
+        match self.upload_format(channels, component) {
+            | UploadFormat::Srgb(srgb_format) => {
+                let texture = SrgbTexture2d::with_format(display, top, srgb_format, self.mipmaps_option())
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                for (i, level) in self.levels.iter().enumerate().skip(1) {
+                    texture.mipmap(i as u32)
+                        .ok_or_else(|| GLError::custom(format!("The texture has no mipmap level {}.", i)))?
+                        .write(level_rect(level), raw_image(level, 0, channels, component));
+                }
+
+                Ok(ContainerTexture::SrgbTexture2d(texture))
+            },
+            | UploadFormat::Linear(gpu_format) => {
+                let texture = Texture2d::with_format(display, top, gpu_format, self.mipmaps_option())
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                for (i, level) in self.levels.iter().enumerate().skip(1) {
+                    texture.mipmap(i as u32)
+                        .ok_or_else(|| GLError::custom(format!("The texture has no mipmap level {}.", i)))?
+                        .write(level_rect(level), raw_image(level, 0, channels, component));
+                }
+
+                Ok(ContainerTexture::Texture2d(texture))
+            },
+        }
+    }
+
+    /// Upload cubemap or texture array.
+    ///
+    /// glium can only write pixels to 2D textures, so each image is uploaded to a 2D texture and then blitted to
+    /// the target level(the same as `texture::load_cubemap`).
+    ///
+    /// Only the half float containers arrive here with a single level(apart from 1x1 images), so the driver generates
+    /// their mipmaps, and the sRGB textures never need it.
+    fn upload_layered(&self, display: &impl Facade, channels: u8, component: ComponentType) -> GLResult<ContainerTexture> {
+
+        let (width, height) = self.dimensions();
+        let format = self.upload_format(channels, component);
+        let mipmaps = if self.levels.len() == 1 { MipmapsOption::EmptyMipmaps } else { self.mipmaps_option() };
+
+        let texture = match (self.kind, format) {
+            | (ContainerKind::Cubemap, UploadFormat::Linear(gpu_format)) => {
+                let cubemap = Cubemap::empty_with_format(display, gpu_format, mipmaps, width)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                self.blit_levels(display, channels, component, format, |level, face| {
+                    let mipmap = cubemap.mipmap(level)
+                        .ok_or_else(|| GLError::custom(format!("The cubemap has no mipmap level {}.", level)))?;
+                    Ok(SimpleFrameBuffer::new(display, mipmap.image(CUBEMAP_LAYERS[face])).map_err(BufferCreationErrorKind::FrameBuffer)?)
+                })?;
+
+                if self.levels.len() == 1 {
+                    unsafe {
+                        cubemap.generate_mipmaps();
+                    }
+                }
+                ContainerTexture::Cubemap(cubemap)
+            },
+            | (ContainerKind::Cubemap, UploadFormat::Srgb(srgb_format)) => {
+                let cubemap = SrgbCubemap::empty_with_format(display, srgb_format, mipmaps, width)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                self.blit_levels(display, channels, component, format, |level, face| {
+                    let mipmap = cubemap.mipmap(level)
+                        .ok_or_else(|| GLError::custom(format!("The cubemap has no mipmap level {}.", level)))?;
+                    Ok(SimpleFrameBuffer::new(display, mipmap.image(CUBEMAP_LAYERS[face])).map_err(BufferCreationErrorKind::FrameBuffer)?)
+                })?;
+
+                ContainerTexture::SrgbCubemap(cubemap)
+            },
+            | (ContainerKind::Texture2dArray(layers), UploadFormat::Linear(gpu_format)) => {
+                let array = Texture2dArray::empty_with_format(display, gpu_format, mipmaps, width, height, layers)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                self.blit_levels(display, channels, component, format, |level, layer| {
+                    let image = array.layer(layer as u32)
+                        .and_then(|layer| layer.mipmap(level))
+                        .ok_or_else(|| GLError::custom(format!("The texture array has no layer {} at mipmap level {}.", layer, level)))?;
+                    Ok(SimpleFrameBuffer::new(display, image).map_err(BufferCreationErrorKind::FrameBuffer)?)
+                })?;
+
+                if self.levels.len() == 1 {
+                    unsafe {
+                        array.generate_mipmaps();
+                    }
+                }
+                ContainerTexture::Texture2dArray(array)
+            },
+            | (ContainerKind::Texture2dArray(layers), UploadFormat::Srgb(srgb_format)) => {
+                let array = SrgbTexture2dArray::empty_with_format(display, srgb_format, mipmaps, width, height, layers)
+                    .map_err(GLErrorKind::CreateTexture)?;
+
+                self.blit_levels(display, channels, component, format, |level, layer| {
+                    let image = array.layer(layer as u32)
+                        .and_then(|layer| layer.mipmap(level))
+                        .ok_or_else(|| GLError::custom(format!("The texture array has no layer {} at mipmap level {}.", layer, level)))?;
+                    Ok(SimpleFrameBuffer::new(display, image).map_err(BufferCreationErrorKind::FrameBuffer)?)
+                })?;
+
+                ContainerTexture::SrgbTexture2dArray(array)
+            },
+            | (ContainerKind::Texture2d, _) => unreachable!("2D texture is uploaded by upload_2d"),
+        };
+
+        Ok(texture)
+    }
+
+    /// Blit every image of every level to the framebuffer returned by `target` for the level and the image index.
+    fn blit_levels<'t>(&self, display: &impl Facade, channels: u8, component: ComponentType, format: UploadFormat, mut target: impl FnMut(u32, usize) -> GLResult<SimpleFrameBuffer<'t>>) -> GLResult<()> {
+
+        for (i, level) in self.levels.iter().enumerate() {
+            for index in 0..level.images.len() {
+                let framebuffer = target(i as u32, index)?;
+                blit_image(display, &framebuffer, level, index, channels, component, format)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn level_rect(level: &MipLevel) -> Rect {
+    Rect { left: 0, bottom: 0, width: level.width, height: level.height }
+}
+
+fn raw_image<'a>(level: &'a MipLevel, index: usize, channels: u8, component: ComponentType) -> RawImage2d<'a, u8> {
+    RawImage2d {
+        data  : Cow::Borrowed(&level.images[index]),
+        width : level.width,
+        height: level.height,
+        format: SurfaceFormat::client_format(channels, component),
+    }
+}
+
+fn blit_image(display: &impl Facade, target: &SimpleFrameBuffer, level: &MipLevel, index: usize, channels: u8, component: ComponentType, format: UploadFormat) -> GLResult<()> {
+
+    let blit_target = BlitTarget { left: 0, bottom: 0, width: level.width as i32, height: level.height as i32 };
+
+    // The source has the same format as the target, so the pixels are copied as they are.
+    match format {
+        | UploadFormat::Linear(gpu_format) => {
+            let source = Texture2d::with_format(display, raw_image(level, index, channels, component), gpu_format, MipmapsOption::NoMipmap)
+                .map_err(GLErrorKind::CreateTexture)?;
+            source.as_surface()
+                .blit_whole_color_to(target, &blit_target, MagnifySamplerFilter::Nearest);
+        },
+        | UploadFormat::Srgb(srgb_format) => {
+            let source = SrgbTexture2d::with_format(display, raw_image(level, index, channels, component), srgb_format, MipmapsOption::NoMipmap)
+                .map_err(GLErrorKind::CreateTexture)?;
+            SimpleFrameBuffer::new(display, &source)
+                .map_err(BufferCreationErrorKind::FrameBuffer)?
+                .blit_whole_color_to(target, &blit_target, MagnifySamplerFilter::Nearest);
+        },
+    }
+
+    Ok(())
+}
+
+
+/// The texture uploaded from a container, which can be used as uniform directly.
+pub enum ContainerTexture {
+    Texture2d(Texture2d),
+    SrgbTexture2d(SrgbTexture2d),
+    CompressedTexture2d(CompressedTexture2d),
+    CompressedSrgbTexture2d(CompressedSrgbTexture2d),
+    Cubemap(Cubemap),
+    SrgbCubemap(SrgbCubemap),
+    Texture2dArray(Texture2dArray),
+    SrgbTexture2dArray(SrgbTexture2dArray),
+}
+
+impl<'a> IntoUniformValue<'a> for &'a ContainerTexture {
+    fn into_uniform_value(self) -> UniformValue<'a> {
+        match self {
+            | ContainerTexture::Texture2d(texture)               => UniformValue::Texture2d(texture, None),
+            | ContainerTexture::SrgbTexture2d(texture)           => UniformValue::SrgbTexture2d(texture, None),
+            | ContainerTexture::CompressedTexture2d(texture)     => UniformValue::CompressedTexture2d(texture, None),
+            | ContainerTexture::CompressedSrgbTexture2d(texture) => UniformValue::CompressedSrgbTexture2d(texture, None),
+            | ContainerTexture::Cubemap(texture)                 => UniformValue::Cubemap(texture, None),
+            | ContainerTexture::SrgbCubemap(texture)             => UniformValue::SrgbCubemap(texture, None),
+            | ContainerTexture::Texture2dArray(texture)          => UniformValue::Texture2dArray(texture, None),
+            | ContainerTexture::SrgbTexture2dArray(texture)      => UniformValue::SrgbTexture2dArray(texture, None),
+        }
+    }
+}
+
+impl AsUniformValue for &ContainerTexture {
+    fn as_uniform_value(&self) -> UniformValue<'_> {
+        (*self).into_uniform_value()
+    }
+}
+
+
+/// The container formats recognized by `load_container`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContainerFormat {
+    Ktx,
+    Ktx2,
+    Dds,
+}
+
+impl ContainerFormat {
+
+    /// Recognize the container from the signature at the beginning of file.
+    pub fn from_signature(bytes: &[u8]) -> Option<ContainerFormat> {
+        if bytes.starts_with(&ktx::KTX1_IDENTIFIER) {
+            Some(ContainerFormat::Ktx)
+        } else if bytes.starts_with(&ktx::KTX2_IDENTIFIER) {
+            Some(ContainerFormat::Ktx2)
+        } else if bytes.starts_with(dds::DDS_MAGIC) {
+            Some(ContainerFormat::Dds)
+        } else {
+            None
+        }
+    }
+}
+
+/// Load a KTX, KTX2 or DDS file to CPU.
+pub fn load_container(path: impl AsRef<Path>) -> GLResult<TextureContainer> {
+
+    let bytes = std::fs::read(path.as_ref())
+        .map_err(GLError::io)?;
+
+    decode_container(&bytes)
+        .map_err(|e| GLError::custom(format!("Failed to load texture container at {:?}: {}", path.as_ref(), e)))
+}
+
+/// Decode the content of a KTX, KTX2 or DDS file.
+pub fn decode_container(bytes: &[u8]) -> GLResult<TextureContainer> {
+    match ContainerFormat::from_signature(bytes) {
+        | Some(ContainerFormat::Ktx)  => ktx::decode_ktx1(bytes),
+        | Some(ContainerFormat::Ktx2) => ktx::decode_ktx2(bytes),
+        | Some(ContainerFormat::Dds)  => dds::decode(bytes),
+        | None => Err(GLError::custom("Unrecognized texture container, only KTX, KTX2 and DDS are supported.")),
+    }
+}
+
+/// Load a container file and upload it to GPU.
+pub fn load_container_texture(display: &impl Facade, path: impl AsRef<Path>) -> GLResult<ContainerTexture> {
+    load_container(path)?.upload(display)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A KTX 1.1 file of a 2x2 RGBA8 texture with one level.
+    fn ktx1_file() -> Vec<u8> {
+        let mut bytes = ktx::KTX1_IDENTIFIER.to_vec();
+        let header = [0x0403_0201, 0x1401, 1, 0x1908, 0x8058, 0x1908, 2, 2, 0, 0, 1, 1, 0, 16];
+        for value in header.iter() {
+            bytes.extend_from_slice(&u32::to_le_bytes(*value));
+        }
+        bytes.extend_from_slice(&[255; 16]);
+        bytes
+    }
+
+    /// A KTX 2.0 file of a 2x2 sRGB RGBA8 texture with one level.
+    fn ktx2_file() -> Vec<u8> {
+        let mut bytes = ktx::KTX2_IDENTIFIER.to_vec();
+        for value in [43, 1, 2, 2, 0, 0, 1, 1, 0].iter() {
+            bytes.extend_from_slice(&u32::to_le_bytes(*value));
+        }
+        bytes.extend_from_slice(&[0; 32]);
+        for value in [104, 16, 16].iter() {
+            bytes.extend_from_slice(&u64::to_le_bytes(*value));
+        }
+        bytes.extend_from_slice(&[255; 16]);
+        bytes
+    }
+
+    /// A DDS file of a 4x4 DXT1 texture with one level.
+    fn dds_file() -> Vec<u8> {
+        let mut bytes = dds::DDS_MAGIC.to_vec();
+        let mut header = [0_u32; 31];
+        header[0] = 124;
+        header[2] = 4; // height
+        header[3] = 4; // width
+        header[19] = 0x4; // DDPF_FOURCC
+        header[20] = u32::from_le_bytes(*b"DXT1");
+        for value in header.iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0x00, 0xF8, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        bytes
+    }
+
+    #[test]
+    fn containers_are_decoded() {
+        let ktx1 = decode_container(&ktx1_file()).unwrap();
+        assert_eq!(ktx1.kind(), ContainerKind::Texture2d);
+        assert_eq!(ktx1.format(), SurfaceFormat::Uncompressed { channels: 4, component: ComponentType::U8 });
+        assert!(!ktx1.is_srgb());
+
+        let ktx2 = decode_container(&ktx2_file()).unwrap();
+        assert_eq!(ktx2.dimensions(), (2, 2));
+        assert!(ktx2.is_srgb());
+
+        let dds = decode_container(&dds_file()).unwrap();
+        assert_eq!(dds.format(), SurfaceFormat::Compressed(BlockFormat::Bc1Alpha));
+        assert_eq!(dds.mip_levels()[0].images(), &[vec![0x00, 0xF8, 0x1F, 0x00, 0x00, 0x00, 0x00, 0x00]]);
+    }
+
+    #[test]
+    fn truncated_containers_are_rejected() {
+        for file in [ktx1_file(), ktx2_file(), dds_file()].iter() {
+            for length in 0..file.len() {
+                assert!(decode_container(&file[..length]).is_err(), "a file truncated to {} bytes is accepted", length);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_mipmaps_complete_the_chain() {
+        let container = decode_container(&dds_file()).unwrap()
+            .with_generated_mipmaps(&MipmapGenerator::new(MipFilter::Box))
+            .unwrap();
+
+        assert_eq!(container.format(), SurfaceFormat::Uncompressed { channels: 4, component: ComponentType::U8 });
+        let sizes: Vec<_> = container.mip_levels().iter().map(|level| (level.width(), level.height())).collect();
+        assert_eq!(sizes, vec![(4, 4), (2, 2), (1, 1)]);
+        // Every texel of the block is red.
+        assert_eq!(container.mip_levels()[2].images(), &[vec![255, 0, 0, 255]]);
+    }
+}
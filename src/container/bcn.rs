@@ -0,0 +1,278 @@
+
+use crate::container::{BlockFormat, SurfaceFormat, ComponentType};
+use crate::error::{GLResult, GLError};
+
+
+/// The format of pixels after `decompress`.
+pub fn decompressed_format(block: BlockFormat) -> GLResult<SurfaceFormat> {
+    let format = match block {
+        | BlockFormat::Bc1
+        | BlockFormat::Bc1Alpha
+        | BlockFormat::Bc2
+        | BlockFormat::Bc3       => SurfaceFormat::Uncompressed { channels: 4, component: ComponentType::U8 },
+        | BlockFormat::Bc4       => SurfaceFormat::Uncompressed { channels: 1, component: ComponentType::U8 },
+        | BlockFormat::Bc5       => SurfaceFormat::Uncompressed { channels: 2, component: ComponentType::U8 },
+        | BlockFormat::Bc4Signed => SurfaceFormat::Uncompressed { channels: 1, component: ComponentType::F32 },
+        | BlockFormat::Bc5Signed => SurfaceFormat::Uncompressed { channels: 2, component: ComponentType::F32 },
+        | BlockFormat::Bc6hUnsigned
+        | BlockFormat::Bc6hSigned
+        | BlockFormat::Bc7 => return Err(GLError::unsupported("CPU decompression of BC6H and BC7")),
+    };
+    Ok(format)
+}
+
+/// Decompress an image of `width` x `height` pixels to the format given by `decompressed_format`.
+pub fn decompress(block: BlockFormat, data: &[u8], width: u32, height: u32) -> GLResult<Vec<u8>> {
+
+    let (channels, component_bytes) = match decompressed_format(block)? {
+        | SurfaceFormat::Uncompressed { channels, component } => (channels as usize, component.bytes()),
+        | SurfaceFormat::Compressed(_) => unreachable!(),
+    };
+    let (width, height) = (width as usize, height as usize);
+    let pixel_bytes = channels * component_bytes;
+    let blocks_x = (width + 3) / 4;
+
+    let mut image = vec![0_u8; width * height * pixel_bytes];
+    for (i, block_data) in data.chunks(block.block_bytes()).enumerate() {
+        let (block_x, block_y) = (i % blocks_x * 4, i / blocks_x * 4);
+        let texels = decode_block(block, block_data);
+
+        // The blocks on the right and bottom edges may cover pixels outside the image.
+        for y in 0..4.min(height - block_y) {
+            for x in 0..4.min(width - block_x) {
+                let offset = ((block_y + y) * width + block_x + x) * pixel_bytes;
+                let texel = &texels[y * 4 + x];
+                let target = &mut image[offset..offset + pixel_bytes];
+                match texel {
+                    | Texel::Unorm(values) => target.copy_from_slice(&values[..channels]),
+                    | Texel::Snorm(values) => {
+                        for (bytes, value) in target.chunks_mut(4).zip(values.iter()) {
+                            bytes.copy_from_slice(&value.to_le_bytes());
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+enum Texel {
+    Unorm([u8; 4]),
+    Snorm([f32; 2]),
+}
+
+fn decode_block(block: BlockFormat, data: &[u8]) -> Vec<Texel> {
+    match block {
+        | BlockFormat::Bc1 | BlockFormat::Bc1Alpha => {
+            decode_color(&data[0..8], block).iter().map(|&c| Texel::Unorm(c)).collect()
+        },
+        | BlockFormat::Bc2 => {
+            let mut colors = decode_color(&data[8..16], block);
+            for (i, color) in colors.iter_mut().enumerate() {
+                let alpha = (data[i / 2] >> ((i % 2) * 4)) & 0x0F;
+                color[3] = alpha * 17;
+            }
+            colors.iter().map(|&c| Texel::Unorm(c)).collect()
+        },
+        | BlockFormat::Bc3 => {
+            let mut colors = decode_color(&data[8..16], block);
+            let alphas = decode_unorm_channel(&data[0..8]);
+            for (color, alpha) in colors.iter_mut().zip(alphas.iter()) {
+                color[3] = *alpha;
+            }
+            colors.iter().map(|&c| Texel::Unorm(c)).collect()
+        },
+        | BlockFormat::Bc4 => {
+            decode_unorm_channel(&data[0..8]).iter().map(|&r| Texel::Unorm([r, 0, 0, 255])).collect()
+        },
+        | BlockFormat::Bc5 => {
+            let reds   = decode_unorm_channel(&data[0..8]);
+            let greens = decode_unorm_channel(&data[8..16]);
+            reds.iter().zip(greens.iter()).map(|(&r, &g)| Texel::Unorm([r, g, 0, 255])).collect()
+        },
+        | BlockFormat::Bc4Signed => {
+            decode_snorm_channel(&data[0..8]).iter().map(|&r| Texel::Snorm([r, 0.0])).collect()
+        },
+        | BlockFormat::Bc5Signed => {
+            let reds   = decode_snorm_channel(&data[0..8]);
+            let greens = decode_snorm_channel(&data[8..16]);
+            reds.iter().zip(greens.iter()).map(|(&r, &g)| Texel::Snorm([r, g])).collect()
+        },
+        | BlockFormat::Bc6hUnsigned
+        | BlockFormat::Bc6hSigned
+        | BlockFormat::Bc7 => unreachable!("rejected by decompressed_format"),
+    }
+}
+
+/// Decode the color part of BC1-BC3. Only BC1 has the 3-color mode, whose last color is black, and the black is
+/// transparent only for `BlockFormat::Bc1Alpha`. BC2 and BC3 always use 4 colors.
+fn decode_color(data: &[u8], block: BlockFormat) -> [[u8; 4]; 16] {
+
+    let c0 = u16::from_le_bytes([data[0], data[1]]);
+    let c1 = u16::from_le_bytes([data[2], data[3]]);
+    let (rgb0, rgb1) = (expand_565(c0), expand_565(c1));
+
+    let mix = |a: u8, b: u8, wa: u32, wb: u32| ((u32::from(a) * wa + u32::from(b) * wb) / (wa + wb)) as u8;
+    let mut palette = [[0, 0, 0, 255]; 4];
+    palette[0] = [rgb0[0], rgb0[1], rgb0[2], 255];
+    palette[1] = [rgb1[0], rgb1[1], rgb1[2], 255];
+    let has_three_colors = block == BlockFormat::Bc1 || block == BlockFormat::Bc1Alpha;
+    if c0 > c1 || !has_three_colors {
+        for c in 0..3 {
+            palette[2][c] = mix(rgb0[c], rgb1[c], 2, 1);
+            palette[3][c] = mix(rgb0[c], rgb1[c], 1, 2);
+        }
+    } else {
+        for c in 0..3 {
+            palette[2][c] = mix(rgb0[c], rgb1[c], 1, 1);
+        }
+        palette[3] = [0, 0, 0, if block == BlockFormat::Bc1Alpha { 0 } else { 255 }];
+    }
+
+    let indices = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let mut colors = [[0; 4]; 16];
+    for (i, color) in colors.iter_mut().enumerate() {
+        *color = palette[((indices >> (i * 2)) & 0x3) as usize];
+    }
+    colors
+}
+
+fn expand_565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) & 0x1F;
+    let g = (color >> 5) & 0x3F;
+    let b = color & 0x1F;
+    [(r << 3 | r >> 2) as u8, (g << 2 | g >> 4) as u8, (b << 3 | b >> 2) as u8]
+}
+
+/// The 3-bit indices of the 16 texels in a BC3 alpha or BC4 block.
+fn channel_indices(data: &[u8]) -> [usize; 16] {
+
+    let mut bits = 0_u64;
+    for (i, byte) in data[2..8].iter().enumerate() {
+        bits |= u64::from(*byte) << (i * 8);
+    }
+
+    let mut indices = [0; 16];
+    for (i, index) in indices.iter_mut().enumerate() {
+        *index = ((bits >> (i * 3)) & 0x7) as usize;
+    }
+    indices
+}
+
+/// Decode a single channel block shared by the alpha of BC3 and BC4/BC5.
+fn decode_unorm_channel(data: &[u8]) -> [u8; 16] {
+
+    let (e0, e1) = (u32::from(data[0]), u32::from(data[1]));
+    let mut palette = [0_u8; 8];
+    palette[0] = e0 as u8;
+    palette[1] = e1 as u8;
+    if e0 > e1 {
+        for i in 1..7 {
+            palette[i + 1] = ((e0 * (7 - i as u32) + e1 * i as u32) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = ((e0 * (5 - i as u32) + e1 * i as u32) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut values = [0; 16];
+    for (value, index) in values.iter_mut().zip(channel_indices(data).iter()) {
+        *value = palette[*index];
+    }
+    values
+}
+
+/// Decode a single signed channel block of BC4/BC5 to [-1, 1].
+fn decode_snorm_channel(data: &[u8]) -> [f32; 16] {
+
+    // Both -128 and -127 map to -1.0.
+    let normalize = |value: f32| (value / 127.0).max(-1.0);
+    let (e0, e1) = (f32::from(data[0] as i8), f32::from(data[1] as i8));
+
+    let mut palette = [0.0_f32; 8];
+    palette[0] = normalize(e0);
+    palette[1] = normalize(e1);
+    if e0 > e1 {
+        for i in 1..7 {
+            palette[i + 1] = normalize((e0 * (7 - i) as f32 + e1 * i as f32) / 7.0);
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = normalize((e0 * (5 - i) as f32 + e1 * i as f32) / 5.0);
+        }
+        palette[6] = -1.0;
+        palette[7] = 1.0;
+    }
+
+    let mut values = [0.0; 16];
+    for (value, index) in values.iter_mut().zip(channel_indices(data).iter()) {
+        *value = palette[*index];
+    }
+    values
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The RGBA of texel `i` in a decompressed 4x4 block.
+    fn texel(pixels: &[u8], i: usize) -> [u8; 4] {
+        [pixels[i * 4], pixels[i * 4 + 1], pixels[i * 4 + 2], pixels[i * 4 + 3]]
+    }
+
+    #[test]
+    fn bc1_block_is_decoded() {
+        // Red and blue endpoints, the first 4 texels use the indices 0 to 3.
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0x00, 0x00, 0x00];
+        let pixels = decompress(BlockFormat::Bc1, &block, 4, 4).unwrap();
+
+        assert_eq!(texel(&pixels, 0), [255, 0, 0, 255]);
+        assert_eq!(texel(&pixels, 1), [0, 0, 255, 255]);
+        assert_eq!(texel(&pixels, 2), [170, 0, 85, 255]);
+        assert_eq!(texel(&pixels, 3), [85, 0, 170, 255]);
+        assert_eq!(texel(&pixels, 15), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn bc1_black_is_transparent_only_with_alpha() {
+        // The endpoints in ascending order select the 3-color mode.
+        let block = [0x1F, 0x00, 0x00, 0xF8, 0xE4, 0x00, 0x00, 0x00];
+
+        let opaque = decompress(BlockFormat::Bc1, &block, 4, 4).unwrap();
+        assert_eq!(texel(&opaque, 2), [127, 0, 127, 255]);
+        assert_eq!(texel(&opaque, 3), [0, 0, 0, 255]);
+
+        let transparent = decompress(BlockFormat::Bc1Alpha, &block, 4, 4).unwrap();
+        assert_eq!(texel(&transparent, 2), [127, 0, 127, 255]);
+        assert_eq!(texel(&transparent, 3), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bc3_block_is_decoded() {
+        // The alpha endpoints 255 and 0 with the indices 0, 1 and 2 for the first 3 texels, and white color.
+        let block = [
+            0xFF, 0x00, 0x88, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let pixels = decompress(BlockFormat::Bc3, &block, 4, 4).unwrap();
+
+        assert_eq!(texel(&pixels, 0), [255, 255, 255, 255]);
+        assert_eq!(texel(&pixels, 1), [255, 255, 255, 0]);
+        assert_eq!(texel(&pixels, 2), [255, 255, 255, 218]);
+        assert_eq!(texel(&pixels, 3), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn partial_blocks_are_cropped() {
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0xE4, 0x00, 0x00, 0x00];
+        let pixels = decompress(BlockFormat::Bc1, &block, 2, 1).unwrap();
+
+        assert_eq!(pixels, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+}
@@ -0,0 +1,249 @@
+
+use crate::container::{TextureContainer, ContainerKind, SurfaceFormat, BlockFormat, ComponentType, check_container_size};
+use crate::image::{ByteReader, extract_masked};
+use crate::error::{GLResult, GLError};
+
+
+pub const DDS_MAGIC: &[u8] = b"DDS ";
+
+const DDSD_MIPMAPCOUNT  : u32 = 0x2_0000;
+const DDPF_ALPHAPIXELS  : u32 = 0x1;
+const DDPF_FOURCC       : u32 = 0x4;
+const DDPF_RGB          : u32 = 0x40;
+const DDPF_LUMINANCE    : u32 = 0x2_0000;
+const DDSCAPS2_CUBEMAP  : u32 = 0x200;
+const DDSCAPS2_VOLUME   : u32 = 0x20_0000;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_MISC_TEXTURECUBE   : u32 = 0x4;
+
+
+/// How the stored pixels are converted to the surface format.
+#[derive(Debug, Clone, Copy)]
+enum PixelLayout {
+    /// The bytes are already in the surface format.
+    Direct,
+    /// 8-bit BGRA or BGRX, which is swizzled to RGBA.
+    Bgra { has_alpha: bool },
+    /// The legacy uncompressed pixels described by bit masks, converted to 8-bit channels.
+    Masked { bit_count: u32, masks: [u32; 4], luminance: bool },
+}
+
+
+/// Decode DDS(with or without the DX10 header), whose images are stored by layer(or cubemap face), then by level.
+pub fn decode(bytes: &[u8]) -> GLResult<TextureContainer> {
+
+    let mut reader = ByteReader::new(bytes);
+    reader.skip(DDS_MAGIC.len())?;
+
+    if reader.u32()? != 124 {
+        return Err(GLError::custom("Invalid DDS header size."))
+    }
+    let flags  = reader.u32()?;
+    let height = reader.u32()?;
+    let width  = reader.u32()?;
+    reader.skip(8)?; // pitchOrLinearSize and depth
+    let mip_count = reader.u32()?;
+    reader.skip(44)?; // reserved
+
+    // DDS_PIXELFORMAT
+    reader.skip(4)?;
+    let pixel_flags = reader.u32()?;
+    let four_cc = reader.take(4)?;
+    let bit_count = reader.u32()?;
+    let masks = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+
+    reader.skip(4)?; // caps
+    let caps2 = reader.u32()?;
+    reader.skip(12)?; // caps3, caps4 and reserved
+
+    let level_count = if flags & DDSD_MIPMAPCOUNT != 0 { mip_count.max(1) } else { 1 };
+    if caps2 & DDSCAPS2_VOLUME != 0 {
+        return Err(GLError::custom("The volume DDS texture is not supported."))
+    }
+
+    let (format, is_srgb, layout, kind) = if pixel_flags & DDPF_FOURCC != 0 && four_cc == b"DX10" {
+        let dxgi_format = reader.u32()?;
+        let dimension   = reader.u32()?;
+        let misc_flags  = reader.u32()?;
+        let array_size  = reader.u32()?.max(1);
+        reader.skip(4)?; // miscFlags2
+
+        if dimension != D3D10_RESOURCE_DIMENSION_TEXTURE2D {
+            return Err(GLError::custom(format!("Unsupported DDS resource dimension {}, only 2D textures are supported.", dimension)))
+        }
+        let kind = match (misc_flags & D3D10_RESOURCE_MISC_TEXTURECUBE != 0, array_size) {
+            | (false, 1) => ContainerKind::Texture2d,
+            | (false, n) => ContainerKind::Texture2dArray(n),
+            | (true,  1) => ContainerKind::Cubemap,
+            | (true,  _) => return Err(GLError::custom("The cubemap array is not supported.")),
+        };
+
+        let (format, is_srgb, layout) = dxgi_format_info(dxgi_format)?;
+        (format, is_srgb, layout, kind)
+    } else {
+        let kind = if caps2 & DDSCAPS2_CUBEMAP != 0 {
+            // All six faces must be present, partial cubemaps are not supported by OpenGL.
+            if caps2 & 0xFC00 != 0xFC00 {
+                return Err(GLError::custom("The DDS cubemap does not have all six faces."))
+            }
+            ContainerKind::Cubemap
+        } else {
+            ContainerKind::Texture2d
+        };
+
+        let (format, is_srgb, layout) = if pixel_flags & DDPF_FOURCC != 0 {
+            four_cc_info(four_cc)?
+        } else if pixel_flags & (DDPF_RGB | DDPF_LUMINANCE) != 0 {
+            let luminance = pixel_flags & DDPF_LUMINANCE != 0;
+            let has_alpha = pixel_flags & DDPF_ALPHAPIXELS != 0 && masks[3] != 0;
+            let channels = match (luminance, has_alpha) {
+                | (true,  false) => 1,
+                | (true,  true)  => 2,
+                | (false, false) => 3,
+                | (false, true)  => 4,
+            };
+            if bit_count == 0 || bit_count > 32 || bit_count % 8 != 0 {
+                return Err(GLError::custom(format!("Unsupported DDS bits per pixel {}.", bit_count)))
+            }
+            let masks = if has_alpha { masks } else { [masks[0], masks[1], masks[2], 0] };
+            (SurfaceFormat::Uncompressed { channels, component: ComponentType::U8 }, false, PixelLayout::Masked { bit_count, masks, luminance })
+        } else {
+            return Err(GLError::custom("Unsupported DDS pixel format."))
+        };
+        (format, is_srgb, layout, kind)
+    };
+
+    // The legacy masked pixels are expanded after reading, so the size in file is checked with the stored bytes.
+    let stored_format = match layout {
+        | PixelLayout::Masked { bit_count, .. } => SurfaceFormat::Uncompressed { channels: (bit_count / 8) as u8, component: ComponentType::U8 },
+        | PixelLayout::Direct | PixelLayout::Bgra { .. } => format,
+    };
+    check_container_size(kind, stored_format, width, height, level_count, reader.remaining())?;
+
+    let mut levels: Vec<Vec<Vec<u8>>> = vec![Vec::with_capacity(kind.image_count()); level_count as usize];
+    for _ in 0..kind.image_count() {
+        for (level, images) in levels.iter_mut().enumerate() {
+            let level_width  = (width  >> level).max(1);
+            let level_height = (height >> level).max(1);
+            let image = read_image(&mut reader, format, layout, level_width, level_height)?;
+            images.push(image);
+        }
+    }
+
+    TextureContainer::new(kind, format, is_srgb, width, height, levels)
+}
+
+fn read_image(reader: &mut ByteReader, format: SurfaceFormat, layout: PixelLayout, width: u32, height: u32) -> GLResult<Vec<u8>> {
+
+    let pixel_count = width as usize * height as usize;
+    let image = match layout {
+        | PixelLayout::Direct => reader.take(format.image_bytes(width, height))?.to_vec(),
+        | PixelLayout::Bgra { has_alpha } => {
+            let mut image = reader.take(pixel_count * 4)?.to_vec();
+            for pixel in image.chunks_mut(4) {
+                pixel.swap(0, 2);
+                if !has_alpha {
+                    pixel[3] = 255;
+                }
+            }
+            image
+        },
+        | PixelLayout::Masked { bit_count, masks, luminance } => {
+            let pixel_bytes = bit_count as usize / 8;
+            let source = reader.take(pixel_count * pixel_bytes)?;
+            // The luminance uses the red mask, and the alpha follows it directly.
+            let used_masks: Vec<u32> = if luminance {
+                masks.iter().cloned().step_by(3).filter(|&mask| mask != 0).collect()
+            } else {
+                masks.iter().cloned().filter(|&mask| mask != 0).collect()
+            };
+
+            let mut image = Vec::with_capacity(pixel_count * used_masks.len());
+            for pixel in source.chunks(pixel_bytes) {
+                let mut value_bytes = [0_u8; 4];
+                value_bytes[..pixel_bytes].copy_from_slice(pixel);
+                let value = u32::from_le_bytes(value_bytes);
+                image.extend(used_masks.iter().map(|&mask| extract_masked(value, mask)));
+            }
+            image
+        },
+    };
+    Ok(image)
+}
+
+fn compressed(block: BlockFormat, is_srgb: bool) -> (SurfaceFormat, bool, PixelLayout) {
+    (SurfaceFormat::Compressed(block), is_srgb, PixelLayout::Direct)
+}
+
+fn uncompressed(channels: u8, component: ComponentType, is_srgb: bool) -> (SurfaceFormat, bool, PixelLayout) {
+    (SurfaceFormat::Uncompressed { channels, component }, is_srgb, PixelLayout::Direct)
+}
+
+/// The formats identified by the FourCC of legacy header. BC1 in DDS always allows 1-bit alpha.
+fn four_cc_info(four_cc: &[u8]) -> GLResult<(SurfaceFormat, bool, PixelLayout)> {
+
+    let info = match four_cc {
+        | b"DXT1" => compressed(BlockFormat::Bc1Alpha, false),
+        | b"DXT2" | b"DXT3" => compressed(BlockFormat::Bc2, false),
+        | b"DXT4" | b"DXT5" => compressed(BlockFormat::Bc3, false),
+        | b"ATI1" | b"BC4U" => compressed(BlockFormat::Bc4, false),
+        | b"BC4S" => compressed(BlockFormat::Bc4Signed, false),
+        | b"ATI2" | b"BC5U" => compressed(BlockFormat::Bc5, false),
+        | b"BC5S" => compressed(BlockFormat::Bc5Signed, false),
+        | _ => {
+            // The floating point formats are written as D3DFORMAT values instead of characters.
+            match u32::from_le_bytes([four_cc[0], four_cc[1], four_cc[2], four_cc[3]]) {
+                | 111 => uncompressed(1, ComponentType::F16, false), // D3DFMT_R16F
+                | 112 => uncompressed(2, ComponentType::F16, false), // D3DFMT_G16R16F
+                | 113 => uncompressed(4, ComponentType::F16, false), // D3DFMT_A16B16G16R16F
+                | 114 => uncompressed(1, ComponentType::F32, false), // D3DFMT_R32F
+                | 115 => uncompressed(2, ComponentType::F32, false), // D3DFMT_G32R32F
+                | 116 => uncompressed(4, ComponentType::F32, false), // D3DFMT_A32B32G32R32F
+                | _ => return Err(GLError::custom(format!("Unsupported DDS FourCC {:?}.", String::from_utf8_lossy(four_cc)))),
+            }
+        },
+    };
+    Ok(info)
+}
+
+/// The formats identified by the `DXGI_FORMAT` of DX10 header.
+fn dxgi_format_info(dxgi_format: u32) -> GLResult<(SurfaceFormat, bool, PixelLayout)> {
+
+    let bgra = |has_alpha: bool, is_srgb: bool| {
+        (SurfaceFormat::Uncompressed { channels: 4, component: ComponentType::U8 }, is_srgb, PixelLayout::Bgra { has_alpha })
+    };
+
+    let info = match dxgi_format {
+        | 2  => uncompressed(4, ComponentType::F32, false), // DXGI_FORMAT_R32G32B32A32_FLOAT
+        | 6  => uncompressed(3, ComponentType::F32, false), // DXGI_FORMAT_R32G32B32_FLOAT
+        | 10 => uncompressed(4, ComponentType::F16, false), // DXGI_FORMAT_R16G16B16A16_FLOAT
+        | 16 => uncompressed(2, ComponentType::F32, false), // DXGI_FORMAT_R32G32_FLOAT
+        | 28 => uncompressed(4, ComponentType::U8, false),  // DXGI_FORMAT_R8G8B8A8_UNORM
+        | 29 => uncompressed(4, ComponentType::U8, true),   // DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+        | 34 => uncompressed(2, ComponentType::F16, false), // DXGI_FORMAT_R16G16_FLOAT
+        | 41 => uncompressed(1, ComponentType::F32, false), // DXGI_FORMAT_R32_FLOAT
+        | 49 => uncompressed(2, ComponentType::U8, false),  // DXGI_FORMAT_R8G8_UNORM
+        | 54 => uncompressed(1, ComponentType::F16, false), // DXGI_FORMAT_R16_FLOAT
+        | 61 => uncompressed(1, ComponentType::U8, false),  // DXGI_FORMAT_R8_UNORM
+        | 71 => compressed(BlockFormat::Bc1Alpha, false),
+        | 72 => compressed(BlockFormat::Bc1Alpha, true),
+        | 74 => compressed(BlockFormat::Bc2, false),
+        | 75 => compressed(BlockFormat::Bc2, true),
+        | 77 => compressed(BlockFormat::Bc3, false),
+        | 78 => compressed(BlockFormat::Bc3, true),
+        | 80 => compressed(BlockFormat::Bc4, false),
+        | 81 => compressed(BlockFormat::Bc4Signed, false),
+        | 83 => compressed(BlockFormat::Bc5, false),
+        | 84 => compressed(BlockFormat::Bc5Signed, false),
+        | 87 => bgra(true,  false), // DXGI_FORMAT_B8G8R8A8_UNORM
+        | 88 => bgra(false, false), // DXGI_FORMAT_B8G8R8X8_UNORM
+        | 91 => bgra(true,  true),  // DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+        | 93 => bgra(false, true),  // DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+        | 95 => compressed(BlockFormat::Bc6hUnsigned, false),
+        | 96 => compressed(BlockFormat::Bc6hSigned, false),
+        | 98 => compressed(BlockFormat::Bc7, false),
+        | 99 => compressed(BlockFormat::Bc7, true),
+        | _ => return Err(GLError::custom(format!("Unsupported DXGI format {}.", dxgi_format))),
+    };
+    Ok(info)
+}
@@ -0,0 +1,235 @@
+
+use crate::container::{TextureContainer, ContainerKind, SurfaceFormat, BlockFormat, ComponentType, check_container_size};
+use crate::image::ByteReader;
+use crate::error::{GLResult, GLError};
+
+
+pub const KTX1_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'1', b'1', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+pub const KTX2_IDENTIFIER: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+
+/// The value of endianness field, when read by the same endianness as the writer.
+const KTX1_ENDIANNESS: u32 = 0x0403_0201;
+
+
+/// Decode KTX 1.1, whose images are stored by level, then by array layer, then by cubemap face.
+pub fn decode_ktx1(bytes: &[u8]) -> GLResult<TextureContainer> {
+
+    let mut reader = ByteReader::new(bytes);
+    reader.skip(KTX1_IDENTIFIER.len())?;
+
+    if reader.u32()? != KTX1_ENDIANNESS {
+        return Err(GLError::custom("The big-endian KTX file is not supported."))
+    }
+    reader.skip(12)?; // glType, glTypeSize and glFormat
+    let internal_format = reader.u32()?;
+    reader.skip(4)?; // glBaseInternalFormat
+    let width  = reader.u32()?;
+    let height = reader.u32()?;
+    let depth  = reader.u32()?;
+    let array_elements = reader.u32()?;
+    let faces  = reader.u32()?;
+    let level_count = reader.u32()?.max(1);
+    let key_value_bytes = reader.u32()? as usize;
+    reader.skip(key_value_bytes)?;
+
+    let (format, is_srgb) = ktx1_format(internal_format)?;
+    let kind = container_kind(height, depth, array_elements, faces)?;
+    check_container_size(kind, format, width, height, level_count, reader.remaining())?;
+
+    let mut levels = Vec::with_capacity(level_count as usize);
+    for level in 0..level_count {
+        let level_width  = (width  >> level).max(1);
+        let level_height = (height >> level).max(1);
+        let image_size = reader.u32()? as usize;
+
+        let images = if kind == ContainerKind::Cubemap {
+            // For cubemaps which are not arrays, the imageSize is the size of a face, and each face is padded to 4 bytes.
+            let mut images = Vec::with_capacity(6);
+            for _ in 0..6 {
+                images.push(unpad_rows(reader.take(image_size)?, format, level_width, level_height));
+                reader.skip(padding(image_size))?;
+            }
+            images
+        } else {
+            let layer_count = kind.image_count();
+            let level_data = reader.take(image_size)?;
+            if image_size == 0 || image_size % layer_count != 0 {
+                return Err(GLError::custom(format!("The image size of mipmap level {} is not divisible by the layer count.", level)))
+            }
+            level_data.chunks(image_size / layer_count)
+                .map(|image| unpad_rows(image, format, level_width, level_height))
+                .collect()
+        };
+        reader.skip(padding(reader.position()))?;
+
+        levels.push(images);
+    }
+
+    TextureContainer::new(kind, format, is_srgb, width, height, levels)
+}
+
+/// Decode KTX 2.0 without supercompression, whose images are stored by level, then by layer, then by face.
+pub fn decode_ktx2(bytes: &[u8]) -> GLResult<TextureContainer> {
+
+    let mut reader = ByteReader::new(bytes);
+    reader.skip(KTX2_IDENTIFIER.len())?;
+
+    let vk_format = reader.u32()?;
+    reader.skip(4)?; // typeSize
+    let width  = reader.u32()?;
+    let height = reader.u32()?;
+    let depth  = reader.u32()?;
+    let layers = reader.u32()?;
+    let faces  = reader.u32()?;
+    let level_count = reader.u32()?.max(1);
+    let supercompression = reader.u32()?;
+    // The data format descriptor, key/value data and supercompression global data are not used.
+    reader.skip(32)?;
+
+    if supercompression != 0 {
+        return Err(GLError::custom(format!("The supercompressed KTX2 file(scheme {}) is not supported.", supercompression)))
+    }
+
+    let (format, is_srgb) = ktx2_format(vk_format)?;
+    let kind = container_kind(height, depth, layers, faces)?;
+    check_container_size(kind, format, width, height, level_count, reader.remaining())?;
+
+    let mut level_ranges = Vec::with_capacity(level_count as usize);
+    for _ in 0..level_count {
+        let offset = reader.u64()? as usize;
+        let length = reader.u64()? as usize;
+        reader.skip(8)?; // uncompressedByteLength
+        level_ranges.push((offset, length));
+    }
+
+    let mut levels = Vec::with_capacity(level_count as usize);
+    for (level, (offset, length)) in level_ranges.into_iter().enumerate() {
+        let image_bytes = format.image_bytes((width >> level).max(1), (height >> level).max(1));
+        if length != image_bytes * kind.image_count() {
+            return Err(GLError::custom(format!("The byte length of mipmap level {} does not match its dimension.", level)))
+        }
+
+        reader.seek(offset)?;
+        let level_data = reader.take(length)?;
+        levels.push(level_data.chunks(image_bytes).map(|image| image.to_vec()).collect());
+    }
+
+    TextureContainer::new(kind, format, is_srgb, width, height, levels)
+}
+
+fn container_kind(height: u32, depth: u32, layers: u32, faces: u32) -> GLResult<ContainerKind> {
+    match (height, depth, layers, faces) {
+        | (0, _, _, _) => Err(GLError::custom("The 1D texture is not supported.")),
+        | (_, d, _, _) if d > 1 => Err(GLError::custom("The 3D texture is not supported.")),
+        | (_, _, 0, 1) => Ok(ContainerKind::Texture2d),
+        | (_, _, 0, 6) => Ok(ContainerKind::Cubemap),
+        | (_, _, l, 1) => Ok(ContainerKind::Texture2dArray(l)),
+        | (_, _, _, 6) => Err(GLError::custom("The cubemap array is not supported.")),
+        | (_, _, _, f) => Err(GLError::custom(format!("Invalid count of faces {}.", f))),
+    }
+}
+
+/// KTX 1 aligns each row of uncompressed images to 4 bytes, as `GL_UNPACK_ALIGNMENT` defaults.
+fn unpad_rows(image: &[u8], format: SurfaceFormat, width: u32, height: u32) -> Vec<u8> {
+
+    let row_bytes = match format {
+        | SurfaceFormat::Compressed(_) => return image.to_vec(),
+        | SurfaceFormat::Uncompressed { .. } => format.image_bytes(width, 1),
+    };
+    let padded_row_bytes = row_bytes + padding(row_bytes);
+
+    if padded_row_bytes == row_bytes || image.len() != padded_row_bytes * height as usize {
+        image.to_vec()
+    } else {
+        image.chunks(padded_row_bytes)
+            .flat_map(|row| row[..row_bytes].iter().cloned())
+            .collect()
+    }
+}
+
+fn padding(size: usize) -> usize {
+    (4 - size % 4) % 4
+}
+
+fn uncompressed(channels: u8, component: ComponentType) -> SurfaceFormat {
+    SurfaceFormat::Uncompressed { channels, component }
+}
+
+/// Map the `glInternalFormat` to the surface format and whether it is sRGB.
+fn ktx1_format(internal_format: u32) -> GLResult<(SurfaceFormat, bool)> {
+
+    let format = match internal_format {
+        | 0x8229 => (uncompressed(1, ComponentType::U8), false),  // GL_R8
+        | 0x822B => (uncompressed(2, ComponentType::U8), false),  // GL_RG8
+        | 0x8051 => (uncompressed(3, ComponentType::U8), false),  // GL_RGB8
+        | 0x8058 => (uncompressed(4, ComponentType::U8), false),  // GL_RGBA8
+        | 0x8C41 => (uncompressed(3, ComponentType::U8), true),   // GL_SRGB8
+        | 0x8C43 => (uncompressed(4, ComponentType::U8), true),   // GL_SRGB8_ALPHA8
+        | 0x822D => (uncompressed(1, ComponentType::F16), false), // GL_R16F
+        | 0x822F => (uncompressed(2, ComponentType::F16), false), // GL_RG16F
+        | 0x881B => (uncompressed(3, ComponentType::F16), false), // GL_RGB16F
+        | 0x881A => (uncompressed(4, ComponentType::F16), false), // GL_RGBA16F
+        | 0x822E => (uncompressed(1, ComponentType::F32), false), // GL_R32F
+        | 0x8230 => (uncompressed(2, ComponentType::F32), false), // GL_RG32F
+        | 0x8815 => (uncompressed(3, ComponentType::F32), false), // GL_RGB32F
+        | 0x8814 => (uncompressed(4, ComponentType::F32), false), // GL_RGBA32F
+        | 0x83F0 => (SurfaceFormat::Compressed(BlockFormat::Bc1), false),
+        | 0x83F1 => (SurfaceFormat::Compressed(BlockFormat::Bc1Alpha), false),
+        | 0x83F2 => (SurfaceFormat::Compressed(BlockFormat::Bc2), false),
+        | 0x83F3 => (SurfaceFormat::Compressed(BlockFormat::Bc3), false),
+        | 0x8C4C => (SurfaceFormat::Compressed(BlockFormat::Bc1), true),
+        | 0x8C4D => (SurfaceFormat::Compressed(BlockFormat::Bc1Alpha), true),
+        | 0x8C4E => (SurfaceFormat::Compressed(BlockFormat::Bc2), true),
+        | 0x8C4F => (SurfaceFormat::Compressed(BlockFormat::Bc3), true),
+        | 0x8DBB => (SurfaceFormat::Compressed(BlockFormat::Bc4), false),
+        | 0x8DBC => (SurfaceFormat::Compressed(BlockFormat::Bc4Signed), false),
+        | 0x8DBD => (SurfaceFormat::Compressed(BlockFormat::Bc5), false),
+        | 0x8DBE => (SurfaceFormat::Compressed(BlockFormat::Bc5Signed), false),
+        | 0x8E8C => (SurfaceFormat::Compressed(BlockFormat::Bc7), false),
+        | 0x8E8D => (SurfaceFormat::Compressed(BlockFormat::Bc7), true),
+        | 0x8E8E => (SurfaceFormat::Compressed(BlockFormat::Bc6hSigned), false),
+        | 0x8E8F => (SurfaceFormat::Compressed(BlockFormat::Bc6hUnsigned), false),
+        | _ => return Err(GLError::custom(format!("Unsupported KTX internal format 0x{:X}.", internal_format))),
+    };
+    Ok(format)
+}
+
+/// Map the `VkFormat` to the surface format and whether it is sRGB.
+fn ktx2_format(vk_format: u32) -> GLResult<(SurfaceFormat, bool)> {
+
+    let format = match vk_format {
+        | 9   => (uncompressed(1, ComponentType::U8), false),  // VK_FORMAT_R8_UNORM
+        | 16  => (uncompressed(2, ComponentType::U8), false),  // VK_FORMAT_R8G8_UNORM
+        | 23  => (uncompressed(3, ComponentType::U8), false),  // VK_FORMAT_R8G8B8_UNORM
+        | 29  => (uncompressed(3, ComponentType::U8), true),   // VK_FORMAT_R8G8B8_SRGB
+        | 37  => (uncompressed(4, ComponentType::U8), false),  // VK_FORMAT_R8G8B8A8_UNORM
+        | 43  => (uncompressed(4, ComponentType::U8), true),   // VK_FORMAT_R8G8B8A8_SRGB
+        | 76  => (uncompressed(1, ComponentType::F16), false), // VK_FORMAT_R16_SFLOAT
+        | 83  => (uncompressed(2, ComponentType::F16), false), // VK_FORMAT_R16G16_SFLOAT
+        | 90  => (uncompressed(3, ComponentType::F16), false), // VK_FORMAT_R16G16B16_SFLOAT
+        | 97  => (uncompressed(4, ComponentType::F16), false), // VK_FORMAT_R16G16B16A16_SFLOAT
+        | 100 => (uncompressed(1, ComponentType::F32), false), // VK_FORMAT_R32_SFLOAT
+        | 103 => (uncompressed(2, ComponentType::F32), false), // VK_FORMAT_R32G32_SFLOAT
+        | 106 => (uncompressed(3, ComponentType::F32), false), // VK_FORMAT_R32G32B32_SFLOAT
+        | 109 => (uncompressed(4, ComponentType::F32), false), // VK_FORMAT_R32G32B32A32_SFLOAT
+        | 131 => (SurfaceFormat::Compressed(BlockFormat::Bc1), false),
+        | 132 => (SurfaceFormat::Compressed(BlockFormat::Bc1), true),
+        | 133 => (SurfaceFormat::Compressed(BlockFormat::Bc1Alpha), false),
+        | 134 => (SurfaceFormat::Compressed(BlockFormat::Bc1Alpha), true),
+        | 135 => (SurfaceFormat::Compressed(BlockFormat::Bc2), false),
+        | 136 => (SurfaceFormat::Compressed(BlockFormat::Bc2), true),
+        | 137 => (SurfaceFormat::Compressed(BlockFormat::Bc3), false),
+        | 138 => (SurfaceFormat::Compressed(BlockFormat::Bc3), true),
+        | 139 => (SurfaceFormat::Compressed(BlockFormat::Bc4), false),
+        | 140 => (SurfaceFormat::Compressed(BlockFormat::Bc4Signed), false),
+        | 141 => (SurfaceFormat::Compressed(BlockFormat::Bc5), false),
+        | 142 => (SurfaceFormat::Compressed(BlockFormat::Bc5Signed), false),
+        | 143 => (SurfaceFormat::Compressed(BlockFormat::Bc6hUnsigned), false),
+        | 144 => (SurfaceFormat::Compressed(BlockFormat::Bc6hSigned), false),
+        | 145 => (SurfaceFormat::Compressed(BlockFormat::Bc7), false),
+        | 146 => (SurfaceFormat::Compressed(BlockFormat::Bc7), true),
+        | 0 => return Err(GLError::custom("The KTX2 file with undefined format(e.g. Basis Universal) is not supported.")),
+        | _ => return Err(GLError::custom(format!("Unsupported KTX2 format {}.", vk_format))),
+    };
+    Ok(format)
+}
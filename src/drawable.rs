@@ -4,6 +4,7 @@ use glium::index::Index;
 use glium::uniforms::Uniforms;
 
 use crate::error::{GLResult, GLErrorKind};
+use crate::vertex::verify_vertex_once;
 
 
 pub trait Drawable {
@@ -21,11 +22,13 @@ pub trait TriangleMesh {
 impl<T, V, I> Drawable for T
     where
         T: TriangleMesh<VertexType = V, IndexType = I>,
-        V: Copy,
+        V: Copy + glium::Vertex + 'static,
         I: Index {
 
     fn render(&self, surface: &mut impl Surface, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
         let (vertices, indices) = self.buffers();
+        // glium reports a mismatch without naming the attribute, so list the mismatched attributes on the first draw.
+        verify_vertex_once::<V>(program, true)?;
         surface.draw(vertices, indices, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
@@ -34,6 +37,8 @@ impl<T, V, I> Drawable for T
     fn render_instanced(&self, surface: &mut impl Surface, per_instanced: glium::vertex::PerInstance, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
 
         let (vertices, indices) = self.buffers();
+        // The per-instance attributes are not known here, so only the attributes of mesh are checked.
+        verify_vertex_once::<V>(program, false)?;
         surface.draw((vertices, per_instanced), indices, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
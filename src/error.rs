@@ -40,6 +40,11 @@ impl GLError {
         GLError::from(GLErrorKind::Path { path: path.as_ref().to_path_buf() })
     }
 
+    /// A convenient routine for creating an error about mismatched interface between shader and Rust code.
+    pub fn interface(description: impl AsRef<str>) -> GLError {
+        GLError::from(GLErrorKind::InterfaceMismatch { description: description.as_ref().to_string() })
+    }
+
     pub fn unimplemented(function: impl AsRef<str>) -> GLError {
         GLError::from(GLErrorKind::Unimplemented { function: function.as_ref().to_string() })
     }
@@ -114,6 +119,9 @@ pub enum GLErrorKind {
     /// An error that occurred while working with a file path.
     #[fail(display = "Failed to locate file at: {:?}", path)]
     Path { path: PathBuf },
+    /// An error indicated the data declared in Rust does not match the interface of shader program.
+    #[fail(display = "Mismatched interface between shader program and Rust code: \n{}", description)]
+    InterfaceMismatch { description: String },
     #[fail(display = "{} is not implemented yet.", function)]
     Unimplemented { function: String },
     /// Other errors.
@@ -1,17 +1,27 @@
 
-use crate::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+pub mod pool;
+
+use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::{Mat4F, Vec3F};
 
 use glium::texture::texture2d::Texture2d;
 use glium::texture::depth_texture2d::DepthTexture2d;
-use glium::framebuffer::{DepthRenderBuffer, SimpleFrameBuffer, MultiOutputFrameBuffer};
-use glium::texture::{MipmapsOption, UncompressedFloatFormat, DepthFormat};
-use glium::backend::Facade;
-
-pub use fbo_rentals::{GLFrameBuffer, GLDeferredFrameBuffer};
-
-// Note: Since glium::framebuffer::SimpleFrameBuffer is need for Texture Rendering, but contains referential member,
-//     there rental crate is used to avoid the self-reference conflit in Rust.
-//     It makes the code more uglier, but it works.
+use glium::texture::depth_stencil_texture2d::DepthStencilTexture2d;
+use glium::framebuffer::{RenderBuffer, DepthRenderBuffer, DepthStencilRenderBuffer, SimpleFrameBuffer, MultiOutputFrameBuffer};
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{Surface, Rect, BlitTarget, BlitMask};
+use glium::texture::cubemap::Cubemap;
+use glium::texture::depth_cubemap::DepthCubemap;
+use glium::texture::texture2d_array::Texture2dArray;
+use glium::texture::depth_texture2d_array::DepthTexture2dArray;
+use glium::texture::{MipmapsOption, UncompressedFloatFormat, DepthFormat, DepthStencilFormat, CubeLayer};
+use glium::backend::{Facade, Context};
+
+use std::rc::Rc;
+
+// Note: glium::framebuffer::SimpleFrameBuffer borrows its attachments, so it can not be stored along with them.
+//     Instead, GLFrameBuffer owns the attachments and builds the framebuffer on demand.
+//     This is cheap, since the framebuffer objects are cached by glium according to their attachments.
 // See https://github.com/glium/glium/blob/master/examples/deferred.rs for an example of this use case.
 
 /// Attachment with Color and Depth components used for single output framebuffer rendering.
@@ -30,14 +40,17 @@ pub struct ShadowDepthAttachment {
     pub depth: DepthTexture2d,
 }
 
-pub trait GLColorAttachment: Sized {
-    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat) -> GLResult<Self>;
+/// Attachment which can be bound to a single output framebuffer.
+pub trait GLAttachment: Sized {
     fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a Self) -> GLResult<SimpleFrameBuffer<'a>>;
 }
 
-pub trait GLDepthAttachment: Sized {
+pub trait GLColorAttachment: GLAttachment {
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat) -> GLResult<Self>;
+}
+
+pub trait GLDepthAttachment: GLAttachment {
     fn new_attachment(display: &impl Facade, width: u32, height: u32, depth_format: DepthFormat) -> GLResult<Self>;
-    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a Self) -> GLResult<SimpleFrameBuffer<'a>>;
 }
 
 impl GLColorAttachment for ColorAttachment {
@@ -49,6 +62,9 @@ impl GLColorAttachment for ColorAttachment {
         let attachment = ColorAttachment { color: color_compoenent };
         Ok(attachment)
     }
+}
+
+impl GLAttachment for ColorAttachment {
 
     fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a ColorAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
         let framebuffer = SimpleFrameBuffer::new(display, &attachment.color)
@@ -68,6 +84,9 @@ impl GLColorAttachment for ColorDepthAttachment {
         let attachment = ColorDepthAttachment { color: color_compoenent, depth: depth_component };
         Ok(attachment)
     }
+}
+
+impl GLAttachment for ColorDepthAttachment {
 
     fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a ColorDepthAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
         let framebuffer = SimpleFrameBuffer::with_depth_buffer(display, &attachment.color, &attachment.depth)
@@ -86,6 +105,9 @@ impl GLDepthAttachment for ShadowDepthAttachment {
         let attachment = ShadowDepthAttachment { depth: depth_compoenent };
         Ok(attachment)
     }
+}
+
+impl GLAttachment for ShadowDepthAttachment {
 
     fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a ShadowDepthAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
         let framebuffer = SimpleFrameBuffer::depth_only(display, &attachment.depth)
@@ -95,128 +117,746 @@ impl GLDepthAttachment for ShadowDepthAttachment {
 }
 
 
-/// Attachment with Position, Normal, Color components used for deferred rendering.
-pub struct DeferredPNCAttachment {
-    pub position: Texture2d, // RGBF32
-    pub normal  : Texture2d, // RGBF32
-    pub color   : Texture2d, // RGB8
-    pub depth   : DepthRenderBuffer,
+// Depth-Stencil ---------------------------------------------------------------------------------
+/// The format used by the attachments with Color and Depth-Stencil components.
+pub const DEFAULT_DEPTH_STENCIL_FORMAT: DepthStencilFormat = DepthStencilFormat::I24I8;
+
+/// Attachment with Color and combined Depth-Stencil components, used for stencil effects(e.g. outline or portal).
+pub struct ColorDepthStencilAttachment {
+    pub color: Texture2d,
+    pub depth_stencil: DepthStencilRenderBuffer,
+}
+
+/// Attachment with Color and combined Depth-Stencil texture, whose depth can be sampled by later passes.
+pub struct ColorDepthStencilTextureAttachment {
+    pub color: Texture2d,
+    pub depth_stencil: DepthStencilTexture2d,
+}
+
+/// Attachment with only Depth-Stencil texture, used for stencil-only passes such as shadow volumes.
+pub struct DepthStencilAttachment {
+    pub depth_stencil: DepthStencilTexture2d,
 }
 
-impl GLDeferredAttachment for DeferredPNCAttachment {
+pub trait GLDepthStencilAttachment: GLAttachment {
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, depth_stencil_format: DepthStencilFormat) -> GLResult<Self>;
+}
 
-   fn new_attachment(display: &impl Facade, width: u32, height: u32) -> GLResult<DeferredPNCAttachment> {
+impl GLColorAttachment for ColorDepthStencilAttachment {
 
-        let position = Texture2d::empty_with_format(display, UncompressedFloatFormat::F32F32F32, MipmapsOption::NoMipmap, width, height)
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat) -> GLResult<ColorDepthStencilAttachment> {
+
+        let color_compoenent = Texture2d::empty_with_format(display, color_format, MipmapsOption::NoMipmap, width, height)
             .map_err(GLErrorKind::CreateTexture)?;
-        let normal   = Texture2d::empty_with_format(display, UncompressedFloatFormat::F32F32F32, MipmapsOption::NoMipmap, width, height)
+        let depth_stencil_component = DepthStencilRenderBuffer::new(display, DEFAULT_DEPTH_STENCIL_FORMAT, width, height)
+            .map_err(BufferCreationErrorKind::RenderBuffer)?;
+        let attachment = ColorDepthStencilAttachment { color: color_compoenent, depth_stencil: depth_stencil_component };
+        Ok(attachment)
+    }
+}
+
+impl GLAttachment for ColorDepthStencilAttachment {
+
+    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a ColorDepthStencilAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
+        let framebuffer = SimpleFrameBuffer::with_depth_stencil_buffer(display, &attachment.color, &attachment.depth_stencil)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+
+impl GLColorAttachment for ColorDepthStencilTextureAttachment {
+
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat) -> GLResult<ColorDepthStencilTextureAttachment> {
+
+        let color_compoenent = Texture2d::empty_with_format(display, color_format, MipmapsOption::NoMipmap, width, height)
             .map_err(GLErrorKind::CreateTexture)?;
-        let color    = Texture2d::empty_with_format(display, UncompressedFloatFormat::U8U8U8, MipmapsOption::NoMipmap, width, height)
+        let depth_stencil_component = DepthStencilTexture2d::empty_with_format(display, DEFAULT_DEPTH_STENCIL_FORMAT, MipmapsOption::NoMipmap, width, height)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let attachment = ColorDepthStencilTextureAttachment { color: color_compoenent, depth_stencil: depth_stencil_component };
+        Ok(attachment)
+    }
+}
+
+impl GLAttachment for ColorDepthStencilTextureAttachment {
+
+    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a ColorDepthStencilTextureAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
+        let framebuffer = SimpleFrameBuffer::with_depth_stencil_buffer(display, &attachment.color, &attachment.depth_stencil)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+
+impl GLDepthStencilAttachment for DepthStencilAttachment {
+
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, depth_stencil_format: DepthStencilFormat) -> GLResult<DepthStencilAttachment> {
+
+        let depth_stencil_component = DepthStencilTexture2d::empty_with_format(display, depth_stencil_format, MipmapsOption::NoMipmap, width, height)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let attachment = DepthStencilAttachment { depth_stencil: depth_stencil_component };
+        Ok(attachment)
+    }
+}
+
+impl GLAttachment for DepthStencilAttachment {
+
+    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a DepthStencilAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
+        let framebuffer = SimpleFrameBuffer::depth_stencil_only(display, &attachment.depth_stencil)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+
+// Multisample ----------------------------------------------------------------------------------
+// Note: Only single output attachments are multisampled. A multisample G-buffer for deferred shading would need
+//     the lighting pass to read each sample by `sampler2DMS`, because averaging positions and normals by resolving
+//     them is meaningless, so the deferred recipes still render their G-buffer without MSAA.
+
+/// Attachment with multisample Color and Depth components, which are resolved into `color` and `depth` after rendering.
+///
+/// Render into it like `ColorDepthAttachment`, call `GLFrameBuffer::resolve` and then sample `color` in later passes.
+pub struct MultisampleColorDepthAttachment {
+    /// The single-sample texture which receives the resolved color.
+    pub color: Texture2d,
+    /// The single-sample texture which receives the resolved depth, e.g. for soft particles or depth of field.
+    /// Depth samples are not averaged, each texel takes one of its samples.
+    pub depth: DepthTexture2d,
+    pub color_ms: RenderBuffer,
+    pub depth_ms: DepthRenderBuffer,
+    samples: u32,
+}
+
+/// Attachment with only multisample Color component, which is resolved into `color` after rendering.
+pub struct MultisampleColorAttachment {
+    /// The single-sample texture which receives the resolved color.
+    pub color: Texture2d,
+    pub color_ms: RenderBuffer,
+    samples: u32,
+}
+
+/// Attachment whose multisample components must be resolved before being sampled.
+pub trait GLMultisampleAttachment: GLAttachment {
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat, samples: u32) -> GLResult<Self>;
+    /// Blit the multisample components into the single-sample textures.
+    fn resolve(display: &impl Facade, attachment: &Self) -> GLResult<()>;
+    fn samples(&self) -> u32;
+}
+
+fn new_multisample_color(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat, samples: u32) -> GLResult<(Texture2d, RenderBuffer)> {
+
+    if samples == 0 {
+        return Err(GLError::args("The sample count of multisample attachment must be at least 1."))
+    }
+
+    let color_compoenent = Texture2d::empty_with_format(display, color_format, MipmapsOption::NoMipmap, width, height)
+        .map_err(GLErrorKind::CreateTexture)?;
+    let color_ms_component = RenderBuffer::new_multisample(display, color_format, width, height, samples)
+        .map_err(BufferCreationErrorKind::RenderBuffer)?;
+    Ok((color_compoenent, color_ms_component))
+}
+
+fn resolve_multisample_color(display: &impl Facade, color_ms: &RenderBuffer, color: &Texture2d) -> GLResult<()> {
+
+    let source = SimpleFrameBuffer::new(display, color_ms)
+        .map_err(BufferCreationErrorKind::FrameBuffer)?;
+    let target = SimpleFrameBuffer::new(display, color)
+        .map_err(BufferCreationErrorKind::FrameBuffer)?;
+
+    // Resolving requires the source and target to have the same size, so the filter is irrelevant here.
+    source.fill(&target, MagnifySamplerFilter::Nearest);
+    Ok(())
+}
+
+impl GLMultisampleAttachment for MultisampleColorDepthAttachment {
+
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat, samples: u32) -> GLResult<MultisampleColorDepthAttachment> {
+
+        let (color, color_ms) = new_multisample_color(display, width, height, color_format, samples)?;
+        let depth_ms = DepthRenderBuffer::new_multisample(display, DepthFormat::F32, width, height, samples)
+            .map_err(BufferCreationErrorKind::RenderBuffer)?;
+        // The formats of depth must match for blitting.
+        let depth = DepthTexture2d::empty_with_format(display, DepthFormat::F32, MipmapsOption::NoMipmap, width, height)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let attachment = MultisampleColorDepthAttachment { color, depth, color_ms, depth_ms, samples };
+        Ok(attachment)
+    }
+
+    fn resolve(display: &impl Facade, attachment: &MultisampleColorDepthAttachment) -> GLResult<()> {
+
+        resolve_multisample_color(display, &attachment.color_ms, &attachment.color)?;
+
+        let source = SimpleFrameBuffer::depth_only(display, &attachment.depth_ms)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        let target = SimpleFrameBuffer::depth_only(display, &attachment.depth)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+
+        // Depth can only be blitted with the nearest filter.
+        let (width, height) = attachment.depth.dimensions();
+        let rect = Rect { left: 0, bottom: 0, width, height };
+        let blit_target = BlitTarget { left: 0, bottom: 0, width: width as i32, height: height as i32 };
+        target.blit_buffers_from_simple_framebuffer(&source, &rect, &blit_target, MagnifySamplerFilter::Nearest, BlitMask::depth());
+        Ok(())
+    }
+
+    fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl GLAttachment for MultisampleColorDepthAttachment {
+
+    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a MultisampleColorDepthAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
+        let framebuffer = SimpleFrameBuffer::with_depth_buffer(display, &attachment.color_ms, &attachment.depth_ms)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+
+impl GLMultisampleAttachment for MultisampleColorAttachment {
+
+    fn new_attachment(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat, samples: u32) -> GLResult<MultisampleColorAttachment> {
+
+        let (color, color_ms) = new_multisample_color(display, width, height, color_format, samples)?;
+        let attachment = MultisampleColorAttachment { color, color_ms, samples };
+        Ok(attachment)
+    }
+
+    fn resolve(display: &impl Facade, attachment: &MultisampleColorAttachment) -> GLResult<()> {
+        resolve_multisample_color(display, &attachment.color_ms, &attachment.color)
+    }
+
+    fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl GLAttachment for MultisampleColorAttachment {
+
+    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a MultisampleColorAttachment) -> GLResult<SimpleFrameBuffer<'a>> {
+        let framebuffer = SimpleFrameBuffer::new(display, &attachment.color_ms)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+
+// Layered ---------------------------------------------------------------------------------------
+/// The faces of cubemap, in the order of their layer index.
+pub const CUBEMAP_FACES: [CubeLayer; 6] = [
+    CubeLayer::PositiveX, CubeLayer::NegativeX,
+    CubeLayer::PositiveY, CubeLayer::NegativeY,
+    CubeLayer::PositiveZ, CubeLayer::NegativeZ,
+];
+
+/// The view matrices looking from `position` towards each face of cubemap, in the order of `CUBEMAP_FACES`.
+///
+/// Use them with `cubemap_projection` to render a face of `CubemapColorDepthAttachment` or `CubemapDepthAttachment`.
+pub fn cubemap_view_matrices(position: Vec3F) -> [Mat4F; 6] {
+
+    // The faces of cubemap follow the convention of RenderMan, whose up vectors point to -Y except the Y faces.
+    [
+        Mat4F::look_at_rh(position, position + Vec3F::unit_x(),  -Vec3F::unit_y()),
+        Mat4F::look_at_rh(position, position - Vec3F::unit_x(),  -Vec3F::unit_y()),
+        Mat4F::look_at_rh(position, position + Vec3F::unit_y(),   Vec3F::unit_z()),
+        Mat4F::look_at_rh(position, position - Vec3F::unit_y(),  -Vec3F::unit_z()),
+        Mat4F::look_at_rh(position, position + Vec3F::unit_z(),  -Vec3F::unit_y()),
+        Mat4F::look_at_rh(position, position - Vec3F::unit_z(),  -Vec3F::unit_y()),
+    ]
+}
+
+/// The projection matrix covering exactly one face of cubemap(90 degree field of view with square aspect).
+pub fn cubemap_projection(near: f32, far: f32) -> Mat4F {
+    Mat4F::perspective_rh_zo(std::f32::consts::FRAC_PI_2, 1.0, near, far)
+}
+
+/// Attachment whose layers(the faces of cubemap, or the layers of texture array) are rendered one at a time.
+pub trait GLLayeredAttachment: Sized {
+    fn layer_count(&self) -> u32;
+    /// Build the framebuffer rendering into the `layer`-th layer of `attachment`.
+    fn new_layer_framebuffer<'a>(display: &impl Facade, attachment: &'a Self, layer: u32) -> GLResult<SimpleFrameBuffer<'a>>;
+}
+
+/// Attachment with a Color cubemap and a Depth component shared by all faces, used for dynamic environment mapping.
+pub struct CubemapColorDepthAttachment {
+    pub color: Cubemap,
+    pub depth: DepthRenderBuffer,
+}
+
+/// Attachment with only Depth cubemap, used for omnidirectional shadow mapping.
+pub struct CubemapDepthAttachment {
+    pub depth: DepthCubemap,
+}
+
+/// Attachment with a Color texture array and a Depth component shared by all layers.
+pub struct LayeredColorDepthAttachment {
+    pub color: Texture2dArray,
+    pub depth: DepthRenderBuffer,
+}
+
+/// Attachment with only Depth texture array, used for cascaded shadow mapping.
+pub struct LayeredDepthAttachment {
+    pub depth: DepthTexture2dArray,
+}
+
+impl CubemapColorDepthAttachment {
+
+    pub fn new(display: &impl Facade, dimension: u32, color_format: UncompressedFloatFormat) -> GLResult<CubemapColorDepthAttachment> {
+
+        let color_compoenent = Cubemap::empty_with_format(display, color_format, MipmapsOption::NoMipmap, dimension)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let depth_component = DepthRenderBuffer::new(display, DepthFormat::F32, dimension, dimension)
+            .map_err(BufferCreationErrorKind::RenderBuffer)?;
+        let attachment = CubemapColorDepthAttachment { color: color_compoenent, depth: depth_component };
+        Ok(attachment)
+    }
+}
+
+impl CubemapDepthAttachment {
+
+    pub fn new(display: &impl Facade, dimension: u32, depth_format: DepthFormat) -> GLResult<CubemapDepthAttachment> {
+
+        let depth_compoenent = DepthCubemap::empty_with_format(display, depth_format, MipmapsOption::NoMipmap, dimension)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let attachment = CubemapDepthAttachment { depth: depth_compoenent };
+        Ok(attachment)
+    }
+}
+
+impl LayeredColorDepthAttachment {
+
+    pub fn new(display: &impl Facade, width: u32, height: u32, layers: u32, color_format: UncompressedFloatFormat) -> GLResult<LayeredColorDepthAttachment> {
+
+        let color_compoenent = Texture2dArray::empty_with_format(display, color_format, MipmapsOption::NoMipmap, width, height, layers)
             .map_err(GLErrorKind::CreateTexture)?;
-        let depth = DepthRenderBuffer::new(display, glium::texture::DepthFormat::F32, width, height)
+        let depth_component = DepthRenderBuffer::new(display, DepthFormat::F32, width, height)
             .map_err(BufferCreationErrorKind::RenderBuffer)?;
-        let attachment = DeferredPNCAttachment { position, normal, color, depth };
+        let attachment = LayeredColorDepthAttachment { color: color_compoenent, depth: depth_component };
         Ok(attachment)
     }
+}
+
+impl LayeredDepthAttachment {
+
+    pub fn new(display: &impl Facade, width: u32, height: u32, layers: u32, depth_format: DepthFormat) -> GLResult<LayeredDepthAttachment> {
+
+        let depth_compoenent = DepthTexture2dArray::empty_with_format(display, depth_format, MipmapsOption::NoMipmap, width, height, layers)
+            .map_err(GLErrorKind::CreateTexture)?;
+        let attachment = LayeredDepthAttachment { depth: depth_compoenent };
+        Ok(attachment)
+    }
+}
+
+fn cubemap_face(layer: u32) -> GLResult<CubeLayer> {
+    CUBEMAP_FACES.get(layer as usize).cloned()
+        .ok_or_else(|| GLError::args(format!("Cubemap has only 6 faces, but face {} is requested.", layer)))
+}
+
+fn out_of_layers(layer: u32, layer_count: u32) -> GLError {
+    GLError::args(format!("Texture array has {} layers, but layer {} is requested.", layer_count, layer))
+}
+
+impl GLLayeredAttachment for CubemapColorDepthAttachment {
+
+    fn layer_count(&self) -> u32 { 6 }
+
+    fn new_layer_framebuffer<'a>(display: &impl Facade, attachment: &'a CubemapColorDepthAttachment, layer: u32) -> GLResult<SimpleFrameBuffer<'a>> {
+        let face = attachment.color.main_level().image(cubemap_face(layer)?);
+        let framebuffer = SimpleFrameBuffer::with_depth_buffer(display, face, &attachment.depth)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+
+impl GLLayeredAttachment for CubemapDepthAttachment {
 
-    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a DeferredPNCAttachment) -> GLResult<MultiOutputFrameBuffer<'a>> {
+    fn layer_count(&self) -> u32 { 6 }
 
-        // https://github.com/glium/glium/blob/master/examples/deferred.rs
-        let framebuffer = MultiOutputFrameBuffer::with_depth_buffer(display, [
-            ("PositionData", &attachment.position),
-            ("NormalData",   &attachment.normal),
-            ("ColorData",    &attachment.color),
-        ].into_iter().cloned(), &attachment.depth).map_err(BufferCreationErrorKind::FrameBuffer)?;
+    fn new_layer_framebuffer<'a>(display: &impl Facade, attachment: &'a CubemapDepthAttachment, layer: u32) -> GLResult<SimpleFrameBuffer<'a>> {
+        let face = attachment.depth.main_level().image(cubemap_face(layer)?);
+        let framebuffer = SimpleFrameBuffer::depth_only(display, face)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
         Ok(framebuffer)
     }
 }
 
+impl GLLayeredAttachment for LayeredColorDepthAttachment {
+
+    fn layer_count(&self) -> u32 {
+        self.color.array_size()
+    }
 
-pub trait GLDeferredAttachment: Sized {
-    fn new_attachment(display: &impl Facade, width: u32, height: u32) -> GLResult<Self>;
-    fn new_framebuffer<'a>(display: &impl Facade, attachment: &'a Self) -> GLResult<MultiOutputFrameBuffer<'a>>;
+    fn new_layer_framebuffer<'a>(display: &impl Facade, attachment: &'a LayeredColorDepthAttachment, layer: u32) -> GLResult<SimpleFrameBuffer<'a>> {
+        let color_layer = attachment.color.main_level().layer(layer)
+            .ok_or_else(|| out_of_layers(layer, attachment.layer_count()))?;
+        let framebuffer = SimpleFrameBuffer::with_depth_buffer(display, color_layer, &attachment.depth)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
+}
+
+impl GLLayeredAttachment for LayeredDepthAttachment {
+
+    fn layer_count(&self) -> u32 {
+        self.depth.array_size()
+    }
+
+    fn new_layer_framebuffer<'a>(display: &impl Facade, attachment: &'a LayeredDepthAttachment, layer: u32) -> GLResult<SimpleFrameBuffer<'a>> {
+        let depth_layer = attachment.depth.main_level().layer(layer)
+            .ok_or_else(|| out_of_layers(layer, attachment.layer_count()))?;
+        let framebuffer = SimpleFrameBuffer::depth_only(display, depth_layer)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        Ok(framebuffer)
+    }
 }
+// ----------------------------------------------------------------------------------------------
 
-rental! {
-    mod fbo_rentals {
 
-        #[rental]
-        pub struct GLFrameBuffer<A: 'static> {
-            attachment: Box<A>,
-            framebuffer: (
-                glium::framebuffer::SimpleFrameBuffer<'attachment>,
-                &'attachment A,
-            ),
+/// Attachment with multiple color outputs used for deferred rendering.
+pub trait GLDeferredAttachment {
+    /// The color textures, each of them is bound to the fragment output of the same name.
+    fn color_outputs(&self) -> Vec<(&str, &Texture2d)>;
+    /// The depth component, if depth test is needed when rendering into the attachment.
+    fn depth_output(&self) -> Option<DepthOutput<'_>>;
+}
+
+/// The depth component of a multiple outputs attachment.
+pub enum DepthOutput<'a> {
+    RenderBuffer(&'a DepthRenderBuffer),
+    Texture(&'a DepthTexture2d),
+}
+
+fn new_deferred_framebuffer<'a, A: GLDeferredAttachment>(display: &impl Facade, attachment: &'a A) -> GLResult<MultiOutputFrameBuffer<'a>> {
+
+    // https://github.com/glium/glium/blob/master/examples/deferred.rs
+    let outputs = attachment.color_outputs();
+    let framebuffer = match attachment.depth_output() {
+        | Some(DepthOutput::RenderBuffer(depth)) => MultiOutputFrameBuffer::with_depth_buffer(display, outputs, depth),
+        | Some(DepthOutput::Texture(depth))      => MultiOutputFrameBuffer::with_depth_buffer(display, outputs, depth),
+        | None                                   => MultiOutputFrameBuffer::new(display, outputs),
+    }.map_err(BufferCreationErrorKind::FrameBuffer)?;
+    Ok(framebuffer)
+}
+
+
+// G-buffer -------------------------------------------------------------------------------------
+/// The depth component of G-buffer.
+#[derive(Debug, Clone, Copy)]
+pub enum GBufferDepth {
+    /// No depth test.
+    None,
+    /// Depth test only, the depth can not be sampled later.
+    RenderBuffer(DepthFormat),
+    /// Depth test, and the depth can be sampled by later passes(e.g. to reconstruct position).
+    Texture(DepthFormat),
+}
+
+/// The description of G-buffer, listing its color outputs by name.
+///
+/// ```ignore
+/// let layout = GBufferLayout::new()
+///     .with_output("PositionData", UncompressedFloatFormat::F32F32F32)
+///     .with_output("NormalData",   UncompressedFloatFormat::F32F32F32)
+///     .with_output("ColorData",    UncompressedFloatFormat::U8U8U8)
+///     .with_depth_buffer(DepthFormat::F32);
+/// let gbuffer = layout.build(display, width, height)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct GBufferLayout {
+    outputs: Vec<(String, UncompressedFloatFormat)>,
+    depth: GBufferDepth,
+}
+
+impl GBufferLayout {
+
+    pub fn new() -> GBufferLayout {
+        GBufferLayout { outputs: Vec::new(), depth: GBufferDepth::None }
+    }
+
+    /// Append a color output, which is bound to the fragment output named `name`.
+    pub fn with_output(mut self, name: impl Into<String>, format: UncompressedFloatFormat) -> GBufferLayout {
+        self.outputs.push((name.into(), format));
+        self
+    }
+
+    pub fn with_depth_buffer(mut self, format: DepthFormat) -> GBufferLayout {
+        self.depth = GBufferDepth::RenderBuffer(format);
+        self
+    }
+
+    pub fn with_depth_texture(mut self, format: DepthFormat) -> GBufferLayout {
+        self.depth = GBufferDepth::Texture(format);
+        self
+    }
+
+    pub fn outputs(&self) -> impl Iterator<Item = (&str, UncompressedFloatFormat)> {
+        self.outputs.iter().map(|(name, format)| (name.as_str(), *format))
+    }
+
+    pub fn depth(&self) -> GBufferDepth {
+        self.depth
+    }
+
+    /// Check that every output is written by the fragment shader of `program`.
+    pub fn verify(&self, program: &glium::Program) -> GLResult<()> {
+
+        let missing: Vec<&str> = self.outputs()
+            .filter(|(name, _)| program.get_frag_data_location(name).is_none())
+            .map(|(name, _)| name)
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(GLError::interface(format!("G-buffer outputs [{}] are not written by the fragment shader.", missing.join(", "))))
         }
+    }
 
-        #[rental]
-        pub struct GLDeferredFrameBuffer<A: 'static> {
-            attachment: Box<A>,
-            framebuffer: (
-                glium::framebuffer::MultiOutputFrameBuffer<'attachment>,
-                &'attachment A,
-            ),
+    /// Create the textures of G-buffer, and the render target for them.
+    pub fn build(&self, display: &impl Facade, width: u32, height: u32) -> GLResult<GLDeferredFrameBuffer<GBufferAttachment>> {
+        GLDeferredFrameBuffer::from_attachment(display, GBufferAttachment::new(display, width, height, self.clone())?)
+    }
+}
+
+impl Default for GBufferLayout {
+    fn default() -> GBufferLayout {
+        GBufferLayout::new()
+    }
+}
+
+/// The textures of G-buffer described by `GBufferLayout`.
+pub struct GBufferAttachment {
+    layout: GBufferLayout,
+    colors: Vec<Texture2d>,
+    depth: Option<GBufferDepthComponent>,
+}
+
+enum GBufferDepthComponent {
+    RenderBuffer(DepthRenderBuffer),
+    Texture(DepthTexture2d),
+}
+
+impl GBufferAttachment {
+
+    pub fn new(display: &impl Facade, width: u32, height: u32, layout: GBufferLayout) -> GLResult<GBufferAttachment> {
+
+        if layout.outputs.is_empty() {
+            return Err(GLError::args("G-buffer must have at least one color output."))
+        }
+
+        for (i, (name, _)) in layout.outputs.iter().enumerate() {
+            if layout.outputs[..i].iter().any(|(previous, _)| previous == name) {
+                return Err(GLError::args(format!("G-buffer output '{}' is declared more than once.", name)))
+            }
         }
+
+        let colors = layout.outputs.iter().map(|(_, format)| {
+            Texture2d::empty_with_format(display, *format, MipmapsOption::NoMipmap, width, height)
+                .map_err(|error| GLError::from(GLErrorKind::CreateTexture(error)))
+        }).collect::<GLResult<Vec<_>>>()?;
+
+        let depth = match layout.depth {
+            | GBufferDepth::None => None,
+            | GBufferDepth::RenderBuffer(format) => {
+                let depth = DepthRenderBuffer::new(display, format, width, height)
+                    .map_err(BufferCreationErrorKind::RenderBuffer)?;
+                Some(GBufferDepthComponent::RenderBuffer(depth))
+            },
+            | GBufferDepth::Texture(format) => {
+                let depth = DepthTexture2d::empty_with_format(display, format, MipmapsOption::NoMipmap, width, height)
+                    .map_err(GLErrorKind::CreateTexture)?;
+                Some(GBufferDepthComponent::Texture(depth))
+            },
+        };
+
+        let attachment = GBufferAttachment { layout, colors, depth };
+        Ok(attachment)
+    }
+
+    pub fn layout(&self) -> &GBufferLayout {
+        &self.layout
+    }
+
+    /// The texture of color output named `name`.
+    pub fn texture(&self, name: &str) -> GLResult<&Texture2d> {
+
+        self.layout.outputs.iter()
+            .position(|(output, _)| output == name)
+            .map(|index| &self.colors[index])
+            .ok_or_else(|| GLError::args(format!("G-buffer has no output named '{}'.", name)))
+    }
+
+    /// The depth texture, if the depth is declared by `GBufferLayout::with_depth_texture`.
+    pub fn depth_texture(&self) -> Option<&DepthTexture2d> {
+        match &self.depth {
+            | Some(GBufferDepthComponent::Texture(depth)) => Some(depth),
+            | _ => None,
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.colors[0].dimensions()
     }
 }
 
+impl GLDeferredAttachment for GBufferAttachment {
+
+    fn color_outputs(&self) -> Vec<(&str, &Texture2d)> {
+        self.layout.outputs.iter()
+            .map(|(name, _)| name.as_str())
+            .zip(self.colors.iter())
+            .collect()
+    }
+
+    fn depth_output(&self) -> Option<DepthOutput<'_>> {
+        match &self.depth {
+            | Some(GBufferDepthComponent::RenderBuffer(depth)) => Some(DepthOutput::RenderBuffer(depth)),
+            | Some(GBufferDepthComponent::Texture(depth))      => Some(DepthOutput::Texture(depth)),
+            | None => None,
+        }
+    }
+}
+// ----------------------------------------------------------------------------------------------
+
+/// Render target which owns its attachment.
+///
+/// Use `framebuffer` to render into it, and read the textures from `attachment` directly.
+pub struct GLFrameBuffer<A> {
+    pub attachment: A,
+    context: Rc<Context>,
+}
+
+/// Render target with multiple outputs, which owns its attachment.
+pub struct GLDeferredFrameBuffer<A> {
+    pub attachment: A,
+    context: Rc<Context>,
+}
+
 impl<A> GLFrameBuffer<A>
     where
-        A: 'static + GLColorAttachment {
+        A: GLColorAttachment {
 
     pub fn setup(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat) -> GLResult<GLFrameBuffer<A>> {
+        GLFrameBuffer::from_attachment(display, A::new_attachment(display, width, height, color_format)?)
+    }
+}
 
-        // Build the self-referential struct using rental crate.
-        let fbo = fbo_rentals::GLFrameBuffer::new(
-            Box::new(A::new_attachment(display, width, height, color_format)?),
-            |attachment| { 
-                // TODO: handle unwrap()
-                let framebuffer = A::new_framebuffer(display, &attachment).unwrap();
-                (framebuffer, &attachment)
-            }
-        );
+impl<A> GLFrameBuffer<A>
+    where
+        A: GLDepthAttachment {
 
-        Ok(fbo)
+    pub fn setup_depth(display: &impl Facade, width: u32, height: u32, depth_format: DepthFormat) -> GLResult<GLFrameBuffer<A>> {
+        GLFrameBuffer::from_attachment(display, A::new_attachment(display, width, height, depth_format)?)
     }
 }
 
 impl<A> GLFrameBuffer<A>
     where
-        A: 'static + GLDepthAttachment {
+        A: GLDepthStencilAttachment {
 
-    pub fn setup_depth(display: &impl Facade, width: u32, height: u32, depth_format: DepthFormat) -> GLResult<GLFrameBuffer<A>> {
+    pub fn setup_depth_stencil(display: &impl Facade, width: u32, height: u32, depth_stencil_format: DepthStencilFormat) -> GLResult<GLFrameBuffer<A>> {
+        GLFrameBuffer::from_attachment(display, A::new_attachment(display, width, height, depth_stencil_format)?)
+    }
+}
 
-        // Build the self-referential struct using rental crate.
-        let fbo = fbo_rentals::GLFrameBuffer::new(
-            Box::new(A::new_attachment(display, width, height, depth_format)?),
-            |attachment| { 
-                // TODO: handle unwrap()
-                let framebuffer = A::new_framebuffer(display, &attachment).unwrap();
-                (framebuffer, &attachment)
-            }
-        );
+impl<A> GLFrameBuffer<A>
+    where
+        A: GLMultisampleAttachment {
+
+    pub fn setup_multisample(display: &impl Facade, width: u32, height: u32, color_format: UncompressedFloatFormat, samples: u32) -> GLResult<GLFrameBuffer<A>> {
+        GLFrameBuffer::from_attachment(display, A::new_attachment(display, width, height, color_format, samples)?)
+    }
 
+    /// Resolve the multisample components, call this after rendering and before sampling the attachment.
+    pub fn resolve(&self) -> GLResult<()> {
+        A::resolve(&self.context, &self.attachment)
+    }
+}
+
+impl<A> GLFrameBuffer<A>
+    where
+        A: GLAttachment {
+
+    /// Wrap an existing attachment, the framebuffer is validated once here.
+    pub fn from_attachment(display: &impl Facade, attachment: A) -> GLResult<GLFrameBuffer<A>> {
+
+        A::new_framebuffer(display, &attachment)?;
+        let fbo = GLFrameBuffer { attachment, context: display.get_context().clone() };
         Ok(fbo)
     }
+
+    /// Build the framebuffer for rendering into the attachment.
+    pub fn framebuffer(&self) -> GLResult<SimpleFrameBuffer<'_>> {
+        A::new_framebuffer(&self.context, &self.attachment)
+    }
 }
 
-impl<A> GLDeferredFrameBuffer<A>
+/// Render target whose layers are rendered one at a time, which owns its attachment.
+///
+/// ```ignore
+/// let env_fbo = GLLayeredFrameBuffer::from_attachment(display, CubemapColorDepthAttachment::new(display, 256, UncompressedFloatFormat::F16F16F16)?)?;
+/// let projection = cubemap_projection(0.1, 100.0);
+///
+/// for (face, view) in cubemap_view_matrices(position).iter().enumerate() {
+///     let mut framebuffer = env_fbo.framebuffer(face as u32)?;
+///     // render the scene with `projection * view`...
+/// }
+/// ```
+pub struct GLLayeredFrameBuffer<A> {
+    pub attachment: A,
+    context: Rc<Context>,
+}
+
+impl<A> GLLayeredFrameBuffer<A>
     where
-        A: 'static + GLDeferredAttachment {
+        A: GLLayeredAttachment {
 
-    pub fn setup(display: &impl Facade, width: u32, height: u32) -> GLResult<GLDeferredFrameBuffer<A>> {
+    /// Wrap an existing attachment, the framebuffer of each layer is validated once here.
+    pub fn from_attachment(display: &impl Facade, attachment: A) -> GLResult<GLLayeredFrameBuffer<A>> {
 
-        // Build the self-referential struct using rental crate.
-        let fbo = fbo_rentals::GLDeferredFrameBuffer::new(
-            Box::new(A::new_attachment(display, width, height)?),
-            |attachment| { 
-                // TODO: handle unwrap()
-                let framebuffer = A::new_framebuffer(display, &attachment).unwrap();
-                (framebuffer, &attachment)
-            }
-        );
+        for layer in 0..attachment.layer_count() {
+            A::new_layer_framebuffer(display, &attachment, layer)?;
+        }
+        let fbo = GLLayeredFrameBuffer { attachment, context: display.get_context().clone() };
+        Ok(fbo)
+    }
+
+    pub fn layer_count(&self) -> u32 {
+        self.attachment.layer_count()
+    }
+
+    /// Build the framebuffer for rendering into the `layer`-th layer of the attachment.
+    pub fn framebuffer(&self, layer: u32) -> GLResult<SimpleFrameBuffer<'_>> {
+        A::new_layer_framebuffer(&self.context, &self.attachment, layer)
+    }
+}
+
+impl<A> GLDeferredFrameBuffer<A>
+    where
+        A: GLDeferredAttachment {
+
+    /// Wrap an existing attachment, the framebuffer is validated once here.
+    pub fn from_attachment(display: &impl Facade, attachment: A) -> GLResult<GLDeferredFrameBuffer<A>> {
 
+        new_deferred_framebuffer(display, &attachment)?;
+        let fbo = GLDeferredFrameBuffer { attachment, context: display.get_context().clone() };
         Ok(fbo)
     }
+
+    /// Build the framebuffer for rendering into the attachment.
+    pub fn framebuffer(&self) -> GLResult<MultiOutputFrameBuffer<'_>> {
+        new_deferred_framebuffer(&self.context, &self.attachment)
+    }
 }
 
+impl GLDeferredFrameBuffer<GBufferAttachment> {
+
+    /// Recreate the textures of G-buffer with new size, keeping its layout.
+    pub fn resize(&mut self, width: u32, height: u32) -> GLResult<()> {
+
+        let layout = self.attachment.layout.clone();
+        *self = layout.build(&self.context, width, height)?;
+        Ok(())
+    }
+}
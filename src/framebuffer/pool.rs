@@ -0,0 +1,398 @@
+
+//! Pool of render targets which are described by their format and size relative to the screen.
+//!
+//! The targets are acquired every frame instead of being stored by the scene, so the scene does not need
+//! to reallocate them in `Scene::resize`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::framebuffer::pool::{RenderTargetPool, TargetDescriptor};
+//!
+//! let mut pool = RenderTargetPool::new(display);
+//! let hdr_desc = TargetDescriptor::new(UncompressedFloatFormat::F32F32F32).with_depth().with_samples(4);
+//!
+//! // In `Scene::render`:
+//! pool.begin_frame();
+//! let hdr = pool.acquire(hdr_desc)?;
+//! pool.framebuffer(hdr)?.clear_color(0.0, 0.0, 0.0, 1.0);
+//! // render the scene into `pool.framebuffer(hdr)?`...
+//! pool.resolve(hdr)?;
+//! let hdr_texture = pool.color(hdr)?;
+//! ```
+//!
+//! `begin_frame` makes all the targets available again, so a target acquired in the previous frame may hold other
+//! content. The targets not acquired for a few frames, or whose size no longer matches the screen, are released.
+
+use super::{ColorAttachment, ColorDepthAttachment, MultisampleColorAttachment, MultisampleColorDepthAttachment};
+use super::GLFrameBuffer;
+use crate::error::{GLResult, GLError};
+
+use glium::texture::texture2d::Texture2d;
+use glium::texture::UncompressedFloatFormat;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::backend::{Facade, Context};
+
+use std::rc::Rc;
+
+
+/// The size of render target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetSize {
+    /// Scaled from the size of screen(e.g. 0.5 for half resolution), which follows the resize of window.
+    Relative(f32),
+    /// Fixed size in pixels.
+    Absolute(u32, u32),
+}
+
+impl TargetSize {
+
+    fn dimensions(&self, screen_dimensions: (u32, u32)) -> (u32, u32) {
+        match *self {
+            | TargetSize::Relative(scale) => {
+                let width  = ((screen_dimensions.0 as f32 * scale).round() as u32).max(1);
+                let height = ((screen_dimensions.1 as f32 * scale).round() as u32).max(1);
+                (width, height)
+            },
+            | TargetSize::Absolute(width, height) => (width, height),
+        }
+    }
+}
+
+/// The description of render target, the pool only shares targets with the same descriptor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetDescriptor {
+    format: UncompressedFloatFormat,
+    size: TargetSize,
+    depth: bool,
+    samples: u32,
+}
+
+impl TargetDescriptor {
+
+    /// A single-sample color target without depth, in the same size as screen.
+    pub fn new(format: UncompressedFloatFormat) -> TargetDescriptor {
+        TargetDescriptor { format, size: TargetSize::Relative(1.0), depth: false, samples: 0 }
+    }
+
+    /// Scale the size of screen by `scale`.
+    pub fn with_scale(mut self, scale: f32) -> TargetDescriptor {
+        self.size = TargetSize::Relative(scale);
+        self
+    }
+
+    /// Use fixed size instead of following the screen.
+    pub fn with_size(mut self, width: u32, height: u32) -> TargetDescriptor {
+        self.size = TargetSize::Absolute(width, height);
+        self
+    }
+
+    /// Add a 32-bit float depth buffer, for the targets rendering geometry.
+    pub fn with_depth(mut self) -> TargetDescriptor {
+        self.depth = true;
+        self
+    }
+
+    /// Render into multisample buffers, which must be resolved by `RenderTargetPool::resolve` before sampling.
+    ///
+    /// 0 means single-sample.
+    pub fn with_samples(mut self, samples: u32) -> TargetDescriptor {
+        self.samples = samples;
+        self
+    }
+
+    pub fn format(&self) -> UncompressedFloatFormat {
+        self.format
+    }
+
+    pub fn size(&self) -> TargetSize {
+        self.size
+    }
+
+    pub fn has_depth(&self) -> bool {
+        self.depth
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// The bytes of memory used by a target of this descriptor with `dimensions`, which is an estimation since
+    /// the driver may pad the storage.
+    fn memory_usage(&self, dimensions: (u32, u32)) -> usize {
+
+        let pixels = dimensions.0 as usize * dimensions.1 as usize;
+        let samples = self.samples.max(1) as usize;
+
+        let mut bytes = pixels * format_bytes(self.format);
+        if self.samples > 0 {
+            bytes += pixels * samples * format_bytes(self.format);
+        }
+        if self.depth {
+            // `DepthFormat::F32` is used by all the attachments with depth.
+            bytes += pixels * samples * 4;
+            if self.samples > 0 {
+                // The multisample depth is resolved into a single-sample depth texture too.
+                bytes += pixels * 4;
+            }
+        }
+        bytes
+    }
+}
+
+/// The bytes per pixel of `format`, assuming the components are packed in whole bytes.
+fn format_bytes(format: UncompressedFloatFormat) -> usize {
+    use glium::texture::UncompressedFloatFormat::*;
+
+    match format {
+        | U8 | I8 | U3U3U2 | U2U2U2U2 => 1,
+        | U16 | I16 | U8U8 | I8I8 | U4U4U4 | U5U5U5 | U4U4U4U4 | U5U5U5U1 | F16 => 2,
+        | U8U8U8 | I8I8I8 => 3,
+        | U16U16 | I16I16 | U10U10U10 | U8U8U8U8 | I8I8I8I8 | U10U10U10U2 | F16F16 | F32 | F11F11F10 | F9F9F9 => 4,
+        | U12U12U12 => 5,
+        | U16U16U16 | I16I16I16 | U12U12U12U12 | F16F16F16 => 6,
+        | U16U16U16U16 | I16I16I16I16 | F16F16F16F16 | F32F32 => 8,
+        | F32F32F32 => 12,
+        | F32F32F32F32 => 16,
+    }
+}
+
+enum PooledAttachment {
+    Color(GLFrameBuffer<ColorAttachment>),
+    ColorDepth(GLFrameBuffer<ColorDepthAttachment>),
+    MultisampleColor(GLFrameBuffer<MultisampleColorAttachment>),
+    MultisampleColorDepth(GLFrameBuffer<MultisampleColorDepthAttachment>),
+}
+
+impl PooledAttachment {
+
+    fn new(display: &impl Facade, descriptor: &TargetDescriptor, dimensions: (u32, u32)) -> GLResult<PooledAttachment> {
+
+        let (width, height) = dimensions;
+        let format = descriptor.format;
+
+        let attachment = match (descriptor.samples, descriptor.depth) {
+            | (0, false) => PooledAttachment::Color(GLFrameBuffer::setup(display, width, height, format)?),
+            | (0, true)  => PooledAttachment::ColorDepth(GLFrameBuffer::setup(display, width, height, format)?),
+            | (samples, false) => PooledAttachment::MultisampleColor(GLFrameBuffer::setup_multisample(display, width, height, format, samples)?),
+            | (samples, true)  => PooledAttachment::MultisampleColorDepth(GLFrameBuffer::setup_multisample(display, width, height, format, samples)?),
+        };
+        Ok(attachment)
+    }
+
+    fn framebuffer(&self) -> GLResult<SimpleFrameBuffer<'_>> {
+        match self {
+            | PooledAttachment::Color(fbo)                 => fbo.framebuffer(),
+            | PooledAttachment::ColorDepth(fbo)            => fbo.framebuffer(),
+            | PooledAttachment::MultisampleColor(fbo)      => fbo.framebuffer(),
+            | PooledAttachment::MultisampleColorDepth(fbo) => fbo.framebuffer(),
+        }
+    }
+
+    fn color(&self) -> &Texture2d {
+        match self {
+            | PooledAttachment::Color(fbo)                 => &fbo.attachment.color,
+            | PooledAttachment::ColorDepth(fbo)            => &fbo.attachment.color,
+            | PooledAttachment::MultisampleColor(fbo)      => &fbo.attachment.color,
+            | PooledAttachment::MultisampleColorDepth(fbo) => &fbo.attachment.color,
+        }
+    }
+
+    fn resolve(&self) -> GLResult<()> {
+        match self {
+            | PooledAttachment::Color(_)
+            | PooledAttachment::ColorDepth(_)              => Ok(()),
+            | PooledAttachment::MultisampleColor(fbo)      => fbo.resolve(),
+            | PooledAttachment::MultisampleColorDepth(fbo) => fbo.resolve(),
+        }
+    }
+}
+
+struct PooledTarget {
+    descriptor: TargetDescriptor,
+    dimensions: (u32, u32),
+    attachment: PooledAttachment,
+    /// The serial number of the `acquire` holding the target, `None` when it is available.
+    acquisition: Option<u64>,
+    last_used_frame: u64,
+}
+
+/// A render target acquired from `RenderTargetPool`, which is valid until the next `begin_frame` or `release`.
+///
+/// Each `acquire` returns a distinct handle, so a released handle stays invalid even if the same target is acquired
+/// again in the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetHandle {
+    index: usize,
+    acquisition: u64,
+}
+
+/// Render targets shared by the passes of a scene, see the module documentation.
+pub struct RenderTargetPool {
+    /// The slots of targets, a released slot is left empty instead of being removed, so the indices of handles
+    /// stay valid until the next `begin_frame`.
+    targets: Vec<Option<PooledTarget>>,
+    screen_dimensions: (u32, u32),
+    frame: u64,
+    /// The count of `acquire` calls, which numbers the handles.
+    acquisition_count: u64,
+    /// The targets not acquired for more than this number of frames are released.
+    max_idle_frames: u64,
+    context: Rc<Context>,
+}
+
+impl RenderTargetPool {
+
+    /// An empty pool following the size of default framebuffer of `display`.
+    pub fn new(display: &impl Facade) -> RenderTargetPool {
+        RenderTargetPool {
+            targets: Vec::new(),
+            screen_dimensions: display.get_context().get_framebuffer_dimensions(),
+            frame: 0,
+            acquisition_count: 0,
+            max_idle_frames: 3,
+            context: display.get_context().clone(),
+        }
+    }
+
+    /// Release the targets which are not acquired for more than `frames` frames, the default is 3.
+    pub fn with_max_idle_frames(mut self, frames: u64) -> RenderTargetPool {
+        self.max_idle_frames = frames;
+        self
+    }
+
+    /// Start a new frame, making all the targets available again.
+    ///
+    /// The size of screen is queried from the context here, so the resize of window is picked up automatically.
+    pub fn begin_frame(&mut self) {
+
+        self.frame += 1;
+        self.screen_dimensions = self.context.get_framebuffer_dimensions();
+
+        let (frame, max_idle_frames, screen_dimensions) = (self.frame, self.max_idle_frames, self.screen_dimensions);
+        for slot in self.targets.iter_mut() {
+            let is_kept = slot.as_ref().is_some_and(|target| {
+                frame - target.last_used_frame <= max_idle_frames && target.descriptor.size.dimensions(screen_dimensions) == target.dimensions
+            });
+
+            match slot {
+                | Some(target) if is_kept => target.acquisition = None,
+                | _ => *slot = None,
+            }
+        }
+
+        // The handles of earlier frames are invalid now, so the empty slots at the end can be dropped.
+        while let Some(None) = self.targets.last() {
+            self.targets.pop();
+        }
+    }
+
+    /// Update the size of screen explicitly, e.g. from `Scene::resize`.
+    ///
+    /// The later `acquire` allocates targets in the new size. The mismatched targets are released in the next
+    /// `begin_frame`, so the handles acquired in this frame stay valid.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.screen_dimensions = (width, height);
+    }
+
+    /// Get an available target matching `descriptor`, or allocate a new one.
+    pub fn acquire(&mut self, descriptor: TargetDescriptor) -> GLResult<TargetHandle> {
+
+        let dimensions = descriptor.size.dimensions(self.screen_dimensions);
+        if dimensions.0 == 0 || dimensions.1 == 0 {
+            return Err(GLError::args(format!("Render target of size {:?} is not allowed.", dimensions)))
+        }
+
+        let available = self.targets.iter().position(|slot| {
+            slot.as_ref().is_some_and(|target| {
+                target.acquisition.is_none() && target.descriptor == descriptor && target.dimensions == dimensions
+            })
+        });
+
+        let index = match available {
+            | Some(index) => index,
+            | None => {
+                let attachment = PooledAttachment::new(&self.context, &descriptor, dimensions)?;
+                let target = PooledTarget {
+                    descriptor, dimensions, attachment,
+                    acquisition: None,
+                    last_used_frame: self.frame,
+                };
+
+                match self.targets.iter().position(Option::is_none) {
+                    | Some(index) => {
+                        self.targets[index] = Some(target);
+                        index
+                    },
+                    | None => {
+                        self.targets.push(Some(target));
+                        self.targets.len() - 1
+                    },
+                }
+            },
+        };
+
+        self.acquisition_count += 1;
+        if let Some(target) = self.targets[index].as_mut() {
+            target.acquisition = Some(self.acquisition_count);
+            target.last_used_frame = self.frame;
+        }
+
+        let handle = TargetHandle { index, acquisition: self.acquisition_count };
+        Ok(handle)
+    }
+
+    /// Make the target available to the later `acquire` of this frame, once its content is no longer needed.
+    pub fn release(&mut self, handle: TargetHandle) -> GLResult<()> {
+        self.check_handle_mut(handle)?.acquisition = None;
+        Ok(())
+    }
+
+    fn check_handle(&self, handle: TargetHandle) -> GLResult<&PooledTarget> {
+        self.targets.get(handle.index)
+            .and_then(Option::as_ref)
+            .filter(|target| target.acquisition == Some(handle.acquisition))
+            .ok_or_else(|| GLError::args("The render target handle is released or acquired in an earlier frame."))
+    }
+
+    fn check_handle_mut(&mut self, handle: TargetHandle) -> GLResult<&mut PooledTarget> {
+        self.targets.get_mut(handle.index)
+            .and_then(Option::as_mut)
+            .filter(|target| target.acquisition == Some(handle.acquisition))
+            .ok_or_else(|| GLError::args("The render target handle is released or acquired in an earlier frame."))
+    }
+
+    /// Build the framebuffer for rendering into the target.
+    pub fn framebuffer(&self, handle: TargetHandle) -> GLResult<SimpleFrameBuffer<'_>> {
+        self.check_handle(handle)?.attachment.framebuffer()
+    }
+
+    /// The color texture of the target, which must be resolved first for the multisample targets.
+    pub fn color(&self, handle: TargetHandle) -> GLResult<&Texture2d> {
+        Ok(self.check_handle(handle)?.attachment.color())
+    }
+
+    /// Resolve the multisample components, which does nothing for the single-sample targets.
+    pub fn resolve(&self, handle: TargetHandle) -> GLResult<()> {
+        self.check_handle(handle)?.attachment.resolve()
+    }
+
+    pub fn dimensions(&self, handle: TargetHandle) -> GLResult<(u32, u32)> {
+        Ok(self.check_handle(handle)?.dimensions)
+    }
+
+    /// The number of targets allocated by the pool, including the available ones.
+    pub fn target_count(&self) -> usize {
+        self.targets.iter().flatten().count()
+    }
+
+    /// The estimated bytes of GPU memory used by all the targets of the pool.
+    pub fn memory_usage(&self) -> usize {
+        self.targets.iter()
+            .flatten()
+            .map(|target| target.descriptor.memory_usage(target.dimensions))
+            .sum()
+    }
+
+    pub fn screen_dimensions(&self) -> (u32, u32) {
+        self.screen_dimensions
+    }
+}
@@ -0,0 +1,280 @@
+
+//! Tools to prepare environment cubemaps for image-based lighting.
+//!
+//! `CubeFaces` holds the six faces of an HDR cubemap on CPU, which can be written as face files in the naming
+//! convention of `texture::load_cubemap`(e.g. `grace_posx.hdr`), or uploaded directly:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::ibl::equirect;
+//!
+//! // Bake a panorama to face files once.
+//! let faces = equirect::load_equirect_faces("media/texture/panorama.hdr", 512)?;
+//! faces.write_hdr("media/texture/cube/panorama/panorama")?;
+//!
+//! // Or convert on GPU when the scene starts.
+//! let cube = equirect::load_equirect_cubemap(display, "media/texture/panorama.hdr", 512)?;
+//! ```
+//!
+//! `irradiance` bakes diffuse irradiance cubemaps from the environment, and `specular` bakes the prefiltered environment
+//! and BRDF lookup table of specular lighting.
+//!
+//! The faces store the rows from top to bottom, as the HDR faces loaded by `load_cubemap`(`TextureOrigin::TopLeft`).
+
+pub mod equirect;
+pub mod irradiance;
+pub mod specular;
+
+use crate::readback::{self, RgbImageF};
+use crate::texture::{CUBEMAP_FACES_SUFFIXES, blit_cubemap_faces};
+use crate::image::{Image, PixelData, TextureOrigin, load_image};
+use crate::mipmap::{MipmapGenerator, MipFilter};
+use crate::error::{GLResult, GLError, GLErrorKind};
+use crate::Vec3F;
+
+use glium::backend::Facade;
+use glium::texture::{RawImage2d, ClientFormat, MipmapsOption, UncompressedFloatFormat};
+use glium::texture::texture2d::Texture2d;
+use glium::texture::cubemap::Cubemap;
+
+use std::borrow::Cow;
+
+
+/// The direction from cube center to the point `(s, t)` of `face`, where `s` and `t` are within [-1, 1],
+/// `s` increases to the right and `t` increases downwards.
+///
+/// The faces are ordered as +X, -X, +Y, -Y, +Z, -Z, following the cubemap convention of OpenGL.
+/// The returned direction is not normalized.
+pub fn face_direction(face: usize, s: f32, t: f32) -> Vec3F {
+    match face {
+        | 0 => Vec3F::new( 1.0,   -t,   -s),
+        | 1 => Vec3F::new(-1.0,   -t,    s),
+        | 2 => Vec3F::new(   s,  1.0,    t),
+        | 3 => Vec3F::new(   s, -1.0,   -t),
+        | 4 => Vec3F::new(   s,   -t,  1.0),
+        | _ => Vec3F::new(  -s,   -t, -1.0),
+    }
+}
+
+/// The face hit by `direction` and the point `(s, t)` on it, the inverse of `face_direction`.
+pub fn direction_to_face(direction: Vec3F) -> (usize, f32, f32) {
+
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+
+    if ax >= ay && ax >= az {
+        if x > 0.0 { (0, -z / ax, -y / ax) } else { (1, z / ax, -y / ax) }
+    } else if ay >= az {
+        if y > 0.0 { (2, x / ay, z / ay) } else { (3, x / ay, -z / ay) }
+    } else if z > 0.0 {
+        (4, x / az, -y / az)
+    } else {
+        (5, -x / az, -y / az)
+    }
+}
+
+/// The normalized direction through the center of texel `(x, y)` on a face of `size` x `size` texels.
+pub fn texel_direction(face: usize, x: u32, y: u32, size: u32) -> Vec3F {
+    let s = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    let t = (y as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+    face_direction(face, s, t).normalized()
+}
+
+/// The solid angle covered by texel `(x, y)` on a face of `size` x `size` texels.
+///
+/// The six faces sum up to 4π, the texels near the face corners cover less than those at the center.
+pub fn texel_solid_angle(x: u32, y: u32, size: u32) -> f32 {
+
+    // The solid angle of the rectangle from the face center to (s, t).
+    let corner_area = |s: f32, t: f32| (s * t).atan2((s * s + t * t + 1.0).sqrt());
+
+    let texel = 2.0 / size as f32;
+    let (s0, t0) = (x as f32 * texel - 1.0, y as f32 * texel - 1.0);
+    let (s1, t1) = (s0 + texel, t0 + texel);
+    corner_area(s1, t1) - corner_area(s0, t1) - corner_area(s1, t0) + corner_area(s0, t0)
+}
+
+
+/// The six square faces of an HDR cubemap on CPU, in the order of `face_direction`.
+#[derive(Debug, Clone)]
+pub struct CubeFaces {
+    size: u32,
+    faces: Vec<RgbImageF>,
+}
+
+impl CubeFaces {
+
+    /// The faces must be 6 square images of the same size.
+    pub fn new(faces: Vec<RgbImageF>) -> GLResult<CubeFaces> {
+
+        if faces.len() != 6 {
+            return Err(GLError::args(format!("A cubemap requires 6 faces, but {} faces are given.", faces.len())))
+        }
+
+        let size = faces[0].width();
+        if size == 0 || faces.iter().any(|face| face.dimensions() != (size, size)) {
+            return Err(GLError::args("The cubemap faces must be non-empty squares of the same size."))
+        }
+
+        Ok(CubeFaces { size, faces })
+    }
+
+    /// Build the faces by evaluating `radiance` at the direction through the center of each texel.
+    pub fn from_fn(size: u32, radiance: impl Fn(Vec3F) -> [f32; 3]) -> GLResult<CubeFaces> {
+
+        let mut faces = Vec::with_capacity(6);
+        for face in 0..6 {
+            let mut pixels = Vec::with_capacity(size as usize * size as usize);
+            for y in 0..size {
+                for x in 0..size {
+                    pixels.push(radiance(texel_direction(face, x, y, size)));
+                }
+            }
+            faces.push(RgbImageF::new(size, size, pixels)?);
+        }
+
+        CubeFaces::new(faces)
+    }
+
+    /// Load the faces named as `path_prefix` + `_posx.hdr` etc.
+    pub fn load_hdr(path_prefix: &str) -> GLResult<CubeFaces> {
+
+        let mut faces = Vec::with_capacity(6);
+        for suffix in CUBEMAP_FACES_SUFFIXES.iter() {
+            let image = load_image(format!("{}_{}.hdr", path_prefix, suffix))?;
+            faces.push(image.to_rgb_f32()?);
+        }
+
+        CubeFaces::new(faces)
+    }
+
+    /// Write the faces as `path_prefix` + `_posx.hdr` etc, which can be loaded by `load_cubemap`.
+    pub fn write_hdr(&self, path_prefix: &str) -> GLResult<()> {
+
+        for (face, suffix) in self.faces.iter().zip(CUBEMAP_FACES_SUFFIXES.iter()) {
+            readback::write_hdr(format!("{}_{}.hdr", path_prefix, suffix), face)?;
+        }
+        Ok(())
+    }
+
+    /// The width and height of each face.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn faces(&self) -> &[RgbImageF] {
+        &self.faces
+    }
+
+    /// Halve the size of faces by averaging each 2x2 texels. The size must be even.
+    pub fn downsampled(&self) -> GLResult<CubeFaces> {
+
+        if self.size % 2 != 0 {
+            return Err(GLError::args(format!("Cubemap faces of size {} can not be halved.", self.size)))
+        }
+
+        let size = self.size / 2;
+        let mut faces = Vec::with_capacity(6);
+        for face in self.faces.iter() {
+            let mut pixels = Vec::with_capacity(size as usize * size as usize);
+            for y in 0..size {
+                for x in 0..size {
+                    let mut sum = [0.0; 3];
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                        let pixel = face.pixel(x * 2 + dx, y * 2 + dy);
+                        for c in 0..3 {
+                            sum[c] += pixel[c] * 0.25;
+                        }
+                    }
+                    pixels.push(sum);
+                }
+            }
+            faces.push(RgbImageF::new(size, size, pixels)?);
+        }
+
+        CubeFaces::new(faces)
+    }
+
+    /// Call `f` with the direction, solid angle and radiance of every texel.
+    pub fn for_each_texel(&self, mut f: impl FnMut(Vec3F, f32, [f32; 3])) {
+        for (face, image) in self.faces.iter().enumerate() {
+            for y in 0..self.size {
+                for x in 0..self.size {
+                    f(texel_direction(face, x, y, self.size), texel_solid_angle(x, y, self.size), image.pixel(x, y));
+                }
+            }
+        }
+    }
+
+    /// Bilinearly sample the radiance in `direction`. The filter does not cross the edges of faces.
+    pub fn sample(&self, direction: Vec3F) -> [f32; 3] {
+
+        let (face, s, t) = direction_to_face(direction);
+        let image = &self.faces[face];
+
+        let max = (self.size - 1) as f32;
+        let x = ((s + 1.0) * 0.5 * self.size as f32 - 0.5).max(0.0).min(max);
+        let y = ((t + 1.0) * 0.5 * self.size as f32 - 0.5).max(0.0).min(max);
+        bilinear(image, x, y, |x| x.min(self.size - 1))
+    }
+
+    /// Upload the faces to a floating-point cubemap, whose mipmaps are averaged on CPU by a box filter.
+    pub fn upload(&self, display: &impl Facade) -> GLResult<Cubemap> {
+
+        let generator = MipmapGenerator::new(MipFilter::Box);
+        let chains = self.faces.iter().map(|face| {
+            let data = face.pixels().iter().flat_map(|pixel| pixel.iter().cloned()).collect();
+            generator.generate(&Image::new(self.size, self.size, 3, PixelData::F32(data))?)
+        }).collect::<GLResult<Vec<_>>>()?;
+
+        let level_count = chains[0].levels().len();
+        let cubemap = Cubemap::empty_with_format(display, UncompressedFloatFormat::F32F32F32, MipmapsOption::EmptyMipmapsMax(level_count as u32 - 1), self.size)
+            .map_err(GLErrorKind::CreateTexture)?;
+
+        for level in 0..level_count {
+            let faces = chains.iter()
+                .map(|chain| chain.levels()[level].upload_with_origin(display, MipmapsOption::NoMipmap, TextureOrigin::TopLeft))
+                .collect::<GLResult<Vec<_>>>()?;
+            blit_cubemap_faces(display, &cubemap, level as u32, &faces)?;
+        }
+
+        Ok(cubemap)
+    }
+}
+
+/// Upload a face image in its row order, as the cubemap faces expect.
+pub(crate) fn upload_face(display: &impl Facade, face: &RgbImageF) -> GLResult<Texture2d> {
+
+    let data: Vec<f32> = face.pixels().iter().flat_map(|pixel| pixel.iter().cloned()).collect();
+    let raw_image = RawImage2d {
+        data  : Cow::Owned(data),
+        width : face.width(),
+        height: face.height(),
+        format: ClientFormat::F32F32F32,
+    };
+
+    let texture = Texture2d::with_format(display, raw_image, UncompressedFloatFormat::F32F32F32, MipmapsOption::NoMipmap)
+        .map_err(GLErrorKind::CreateTexture)?;
+    Ok(texture)
+}
+
+/// Bilinear interpolation at the continuous texel coordinate `(x, y)`, where `wrap_x` maps the right neighbour
+/// column into the image.
+fn bilinear(image: &RgbImageF, x: f32, y: f32, wrap_x: impl Fn(u32) -> u32) -> [f32; 3] {
+
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as u32, y0 as u32);
+    let x1 = wrap_x(x0 + 1);
+    let y1 = (y0 + 1).min(image.height() - 1);
+
+    let (p00, p10) = (image.pixel(x0, y0), image.pixel(x1, y0));
+    let (p01, p11) = (image.pixel(x0, y1), image.pixel(x1, y1));
+
+    let mut result = [0.0; 3];
+    for c in 0..3 {
+        let top    = p00[c] + (p10[c] - p00[c]) * fx;
+        let bottom = p01[c] + (p11[c] - p01[c]) * fx;
+        result[c] = top + (bottom - top) * fy;
+    }
+    result
+}
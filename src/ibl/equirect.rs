@@ -0,0 +1,202 @@
+
+//! Convert equirectangular(latitude-longitude) panoramas to cubemaps.
+//!
+//! The top row of the panorama is the +Y pole, and the horizontal center looks at +X, with the longitude
+//! increasing towards +Z. The left and right edges meet at -X. The CPU and GPU paths use the same mapping.
+
+use crate::ibl::{CubeFaces, bilinear};
+use crate::readback::RgbImageF;
+use crate::texture::CUBEMAP_LAYWERS;
+use crate::scene::GLSourceCode;
+use crate::objects::Quad;
+use crate::image::{load_image, TextureOrigin};
+use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::{Drawable, Vec3F};
+
+use glium::backend::Facade;
+use glium::texture::{MipmapsOption, UncompressedFloatFormat};
+use glium::texture::texture2d::Texture2d;
+use glium::texture::cubemap::Cubemap;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::{Program, uniform};
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+
+/// The texture coordinate(u to the right, v downwards, both within [0, 1]) of `direction` on the panorama.
+pub fn direction_to_equirect(direction: Vec3F) -> (f32, f32) {
+    let direction = direction.normalized();
+    let u = direction.z.atan2(direction.x) / (2.0 * PI) + 0.5;
+    let v = direction.y.max(-1.0).min(1.0).acos() / PI;
+    (u, v)
+}
+
+/// Sample the panorama in `direction` bilinearly, wrapping around horizontally.
+pub fn sample_equirect(equirect: &RgbImageF, direction: Vec3F) -> [f32; 3] {
+
+    let (width, height) = equirect.dimensions();
+    let (u, v) = direction_to_equirect(direction);
+
+    // `rem_euclid` rounds up to `width` for tiny negative values, which is the same column as 0.
+    let x = (u * width as f32 - 0.5).rem_euclid(width as f32);
+    let x = if x >= width as f32 { 0.0 } else { x };
+    let y = (v * height as f32 - 0.5).max(0.0).min((height - 1) as f32);
+    bilinear(equirect, x, y, |x| x % width)
+}
+
+/// Resample the panorama to cubemap faces of `face_size` x `face_size` texels on CPU.
+///
+/// Each texel takes a single bilinear sample, so the faces should not be much smaller than a quarter of the
+/// panorama width, or the result aliases.
+pub fn equirect_to_cube_faces(equirect: &RgbImageF, face_size: u32) -> GLResult<CubeFaces> {
+
+    if equirect.width() == 0 || equirect.height() == 0 || face_size == 0 {
+        return Err(GLError::args("Both the panorama and the cubemap faces must not be empty."))
+    }
+
+    CubeFaces::from_fn(face_size, |direction| sample_equirect(equirect, direction))
+}
+
+/// Load a panorama file(usually `.hdr`) and convert it to cubemap faces on CPU.
+pub fn load_equirect_faces(path: impl AsRef<Path>, face_size: u32) -> GLResult<CubeFaces> {
+    let equirect = load_image(path)?.to_rgb_f32()?;
+    equirect_to_cube_faces(&equirect, face_size)
+}
+
+
+/// Convert panoramas to cubemaps on GPU by rendering each face.
+pub struct EquirectConverter {
+    program: Program,
+    quad: Quad,
+}
+
+impl EquirectConverter {
+
+    pub fn new(display: &impl Facade) -> GLResult<EquirectConverter> {
+
+        let vertex_shader = include_str!("../postprocess/shaders/fullscreen.vert.glsl");
+        let fragment_shader = include_str!("shaders/equirect.frag.glsl");
+        let program = Program::new(display, GLSourceCode::new(vertex_shader, fragment_shader).with_srgb_output(false))
+            .map_err(GLErrorKind::CreateProgram)?;
+
+        let converter = EquirectConverter {
+            program,
+            quad: Quad::new(display)?,
+        };
+        Ok(converter)
+    }
+
+    /// Render `equirect` to a half-float RGBA cubemap of `face_size`, with generated mipmaps.
+    ///
+    /// The panorama texture must be uploaded with `TextureOrigin::BottomLeft`, as `load_texture` does.
+    ///
+    /// The faces only exist on GPU, so the driver generates the mipmaps instead of the `mipmap` module, which would
+    /// need a readback of every face. The faces are linear HDR, so only the filter of the driver may differ from
+    /// `MipFilter::Box`. Convert with `load_equirect_faces` and `CubeFaces::upload` for the mipmaps built on CPU.
+    pub fn convert(&self, display: &impl Facade, equirect: &Texture2d, face_size: u32) -> GLResult<Cubemap> {
+
+        if face_size == 0 {
+            return Err(GLError::args("The size of cubemap faces must not be zero."))
+        }
+
+        // RGB16F is not required to be color-renderable, so the faces are rendered to RGBA16F.
+        let cubemap = Cubemap::empty_with_format(display, UncompressedFloatFormat::F16F16F16F16, MipmapsOption::EmptyMipmaps, face_size)
+            .map_err(GLErrorKind::CreateTexture)?;
+
+        // Sample the top level only, the mipmaps would blur the seam where the longitude wraps around.
+        let sampler = equirect.sampled()
+            .wrap_function(glium::uniforms::SamplerWrapFunction::Repeat)
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear);
+
+        for (face, layer) in CUBEMAP_LAYWERS.iter().enumerate() {
+            let mut framebuffer = SimpleFrameBuffer::new(display, cubemap.main_level().image(*layer))
+                .map_err(BufferCreationErrorKind::FrameBuffer)?;
+
+            let uniforms = uniform! {
+                EquirectTex: sampler,
+                Face: face as i32,
+            };
+            self.quad.render(&mut framebuffer, &self.program, &Default::default(), &uniforms)?;
+        }
+
+        unsafe {
+            cubemap.generate_mipmaps();
+        }
+
+        Ok(cubemap)
+    }
+}
+
+/// Load a panorama file and convert it to a cubemap on GPU.
+pub fn load_equirect_cubemap(display: &impl Facade, path: impl AsRef<Path>, face_size: u32) -> GLResult<Cubemap> {
+
+    let equirect = load_image(path)?
+        .upload_with_origin(display, MipmapsOption::NoMipmap, TextureOrigin::BottomLeft)?;
+
+    EquirectConverter::new(display)?
+        .convert(display, &equirect, face_size)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ibl::texel_direction;
+
+    /// A panorama whose texels store the direction through their centers, so the faces show where they look at.
+    fn direction_panorama(width: u32, height: u32) -> RgbImageF {
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+                let polar = (y as f32 + 0.5) / height as f32 * PI;
+                pixels.push([polar.sin() * longitude.cos(), polar.cos(), polar.sin() * longitude.sin()]);
+            }
+        }
+        RgbImageF::new(width, height, pixels).unwrap()
+    }
+
+    #[test]
+    fn panorama_center_looks_at_positive_x() {
+
+        let (u, v) = direction_to_equirect(Vec3F::unit_x());
+        assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+
+        let (u, _) = direction_to_equirect(Vec3F::unit_z());
+        assert!((u - 0.75).abs() < 1e-6);
+
+        let (_, v) = direction_to_equirect(Vec3F::unit_y());
+        assert!(v.abs() < 1e-6);
+    }
+
+    #[test]
+    fn cube_faces_follow_face_direction() {
+
+        let size = 16;
+        let faces = equirect_to_cube_faces(&direction_panorama(256, 128), size).unwrap();
+
+        for face in 0..6 {
+            for y in 0..size {
+                for x in 0..size {
+                    let expected = texel_direction(face, x, y, size);
+                    let sampled = Vec3F::from(faces.faces()[face].pixel(x, y)).normalized();
+                    assert!(sampled.dot(expected) > 0.99, "face {} texel ({}, {}) looks at {:?} instead of {:?}", face, x, y, sampled, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sampling_at_the_seam_stays_in_the_panorama() {
+
+        let panorama = direction_panorama(64, 32);
+        for &z in [-1e-7, -1e-30, 0.0, 1e-30, 1e-7].iter() {
+            let sampled = Vec3F::from(sample_equirect(&panorama, Vec3F::new(-1.0, 0.0, z)));
+            assert!(sampled.x < -0.99);
+        }
+    }
+}
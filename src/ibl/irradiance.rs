@@ -0,0 +1,206 @@
+
+//! Diffuse irradiance of environment cubemaps.
+//!
+//! The baked faces store the irradiance divided by π, which is the radiance reflected by a white Lambertian
+//! surface. So a shader only multiplies the lookup by the albedo, as `diffuseibl.frag.glsl` does:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::ibl::irradiance;
+//!
+//! // Regenerate media/texture/cube/grace-diffuse from the original environment.
+//! irradiance::bake_irradiance("media/texture/cube/grace/grace", "media/texture/cube/grace-diffuse/grace-diffuse", 32)?;
+//! ```
+//!
+//! `convolve_irradiance` integrates the environment directly, while `ShCoefficients` approximates it by the
+//! 9 spherical harmonics of the first 3 bands, which is much faster and accurate within a few percent.
+
+use crate::ibl::CubeFaces;
+use crate::error::{GLResult, GLError};
+use crate::Vec3F;
+
+use std::f32::consts::PI;
+
+/// The environment is downsampled until its faces are not larger than this size before the convolution.
+/// The irradiance is so smooth that the details of the environment hardly matter.
+pub const CONVOLUTION_SOURCE_SIZE: u32 = 32;
+
+
+/// Integrate the cosine-weighted radiance of `environment` for the normal through each texel of `face_size` faces.
+pub fn convolve_irradiance(environment: &CubeFaces, face_size: u32) -> GLResult<CubeFaces> {
+
+    if face_size == 0 {
+        return Err(GLError::args("The size of cubemap faces must not be zero."))
+    }
+
+    let mut source = environment.clone();
+    while source.size() > CONVOLUTION_SOURCE_SIZE && source.size() % 2 == 0 {
+        source = source.downsampled()?;
+    }
+
+    // Premultiply the radiance by the solid angle of each texel.
+    let mut texels = Vec::with_capacity(6 * source.size() as usize * source.size() as usize);
+    source.for_each_texel(|direction, solid_angle, radiance| {
+        texels.push((direction, [radiance[0] * solid_angle, radiance[1] * solid_angle, radiance[2] * solid_angle]));
+    });
+
+    CubeFaces::from_fn(face_size, |normal| {
+        let mut irradiance = [0.0; 3];
+        for (direction, radiance) in texels.iter() {
+            let cos_theta = normal.dot(*direction);
+            if cos_theta > 0.0 {
+                for c in 0..3 {
+                    irradiance[c] += radiance[c] * cos_theta;
+                }
+            }
+        }
+        [irradiance[0] / PI, irradiance[1] / PI, irradiance[2] / PI]
+    })
+}
+
+/// Load the environment faces named as `environment_prefix` + `_posx.hdr` etc, and write the convolved irradiance
+/// faces of `face_size` as `output_prefix` + `_posx.hdr` etc.
+pub fn bake_irradiance(environment_prefix: &str, output_prefix: &str, face_size: u32) -> GLResult<()> {
+    let environment = CubeFaces::load_hdr(environment_prefix)?;
+    convolve_irradiance(&environment, face_size)?
+        .write_hdr(output_prefix)
+}
+
+
+/// The RGB coefficients of the real spherical harmonics up to band 2, projected from an environment.
+///
+/// The coefficients are ordered as (l, m) = (0, 0), (1, -1), (1, 0), (1, 1), (2, -2), (2, -1), (2, 0), (2, 1), (2, 2).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShCoefficients {
+    coefficients: [[f32; 3]; 9],
+}
+
+impl ShCoefficients {
+
+    /// Project the radiance of `environment` to the basis functions.
+    pub fn from_faces(environment: &CubeFaces) -> ShCoefficients {
+
+        let mut coefficients = [[0.0; 3]; 9];
+        environment.for_each_texel(|direction, solid_angle, radiance| {
+            for (coefficient, basis) in coefficients.iter_mut().zip(sh_basis(direction).iter()) {
+                for c in 0..3 {
+                    coefficient[c] += radiance[c] * basis * solid_angle;
+                }
+            }
+        });
+
+        ShCoefficients { coefficients }
+    }
+
+    /// The radiance coefficients, which can be uploaded as `vec3[9]` to evaluate the lighting in shaders.
+    pub fn coefficients(&self) -> &[[f32; 3]; 9] {
+        &self.coefficients
+    }
+
+    /// The irradiance for `normal` divided by π, following Ramamoorthi and Hanrahan.
+    pub fn irradiance(&self, normal: Vec3F) -> [f32; 3] {
+
+        // The convolution with the clamped cosine lobe scales each band, then divide by π.
+        const BAND_SCALES: [f32; 9] = [1.0, 2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 0.25, 0.25, 0.25, 0.25, 0.25];
+
+        let basis = sh_basis(normal.normalized());
+        let mut irradiance = [0.0; 3];
+        for ((coefficient, basis), scale) in self.coefficients.iter().zip(basis.iter()).zip(BAND_SCALES.iter()) {
+            for c in 0..3 {
+                irradiance[c] += coefficient[c] * basis * scale;
+            }
+        }
+
+        // The truncated series may ring below zero behind very bright and small lights.
+        [irradiance[0].max(0.0), irradiance[1].max(0.0), irradiance[2].max(0.0)]
+    }
+
+    /// Evaluate `irradiance` for each texel of `face_size` faces.
+    pub fn to_faces(&self, face_size: u32) -> GLResult<CubeFaces> {
+        CubeFaces::from_fn(face_size, |normal| self.irradiance(normal))
+    }
+}
+
+/// The real spherical harmonics basis of band 0 to 2 for the unit vector `d`.
+fn sh_basis(d: Vec3F) -> [f32; 9] {
+    [
+        0.282_095,
+        0.488_603 * d.y,
+        0.488_603 * d.z,
+        0.488_603 * d.x,
+        1.092_548 * d.x * d.y,
+        1.092_548 * d.y * d.z,
+        0.315_392 * (3.0 * d.z * d.z - 1.0),
+        1.092_548 * d.x * d.z,
+        0.546_274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ibl::texel_direction;
+
+    const RADIANCE: [f32; 3] = [0.5, 1.0, 2.0];
+
+    fn assert_near(actual: [f32; 3], expected: [f32; 3], tolerance: f32) {
+        for c in 0..3 {
+            assert!((actual[c] - expected[c]).abs() <= tolerance * expected[c].abs().max(1.0), "{:?} is not near {:?}", actual, expected);
+        }
+    }
+
+    fn normals() -> Vec<Vec3F> {
+        vec![
+            Vec3F::unit_x(), -Vec3F::unit_x(), Vec3F::unit_y(), -Vec3F::unit_y(), Vec3F::unit_z(), -Vec3F::unit_z(),
+            Vec3F::new(1.0, 1.0, 1.0).normalized(), Vec3F::new(-0.3, 0.8, -0.5).normalized(),
+        ]
+    }
+
+    #[test]
+    fn constant_environment_reflects_its_radiance() {
+
+        let environment = CubeFaces::from_fn(16, |_| RADIANCE).unwrap();
+        let irradiance = convolve_irradiance(&environment, 4).unwrap();
+
+        for face in irradiance.faces().iter() {
+            for pixel in face.pixels().iter() {
+                assert_near(*pixel, RADIANCE, 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn sh_projection_reproduces_constant_environment() {
+
+        let environment = CubeFaces::from_fn(16, |_| RADIANCE).unwrap();
+        let sh = ShCoefficients::from_faces(&environment);
+
+        // Only the constant band is left, whose basis is 1 / (2√π).
+        assert_near(sh.coefficients()[0], [RADIANCE[0] * 2.0 * PI.sqrt(), RADIANCE[1] * 2.0 * PI.sqrt(), RADIANCE[2] * 2.0 * PI.sqrt()], 0.01);
+        for coefficient in sh.coefficients()[1..].iter() {
+            assert_near(*coefficient, [0.0; 3], 1e-3);
+        }
+        for normal in normals() {
+            assert_near(sh.irradiance(normal), RADIANCE, 0.01);
+        }
+    }
+
+    #[test]
+    fn sh_irradiance_approximates_convolution() {
+
+        // A bright sky over a dark ground.
+        let environment = CubeFaces::from_fn(16, |direction| if direction.y > 0.0 { [1.0; 3] } else { [0.1; 3] }).unwrap();
+        let sh = ShCoefficients::from_faces(&environment);
+        let convolved = convolve_irradiance(&environment, 8).unwrap();
+
+        for face in 0..6 {
+            for y in 0..8 {
+                for x in 0..8 {
+                    let normal = texel_direction(face, x, y, 8);
+                    assert_near(sh.irradiance(normal), convolved.faces()[face].pixel(x, y), 0.05);
+                }
+            }
+        }
+    }
+}
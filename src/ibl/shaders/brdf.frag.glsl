@@ -0,0 +1,56 @@
+
+#version 410
+
+layout (location = 0) in vec2 TexCoord;  // (dot(n, v), roughness)
+
+layout (location = 0) out vec4 FragColor;
+
+uniform int SampleCount;
+
+const float PI = 3.14159265358979323846;
+
+
+vec2 hammersley(uint i, uint count) {
+    return vec2(float(i) / float(count), float(bitfieldReverse(i)) * 2.3283064365386963e-10);
+}
+
+vec3 importanceSampleGgx(vec2 xi, float roughness) {
+    float alpha = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    float sinTheta = sqrt(max(1.0 - cosTheta * cosTheta, 0.0));
+    return vec3(sinTheta * cos(phi), sinTheta * sin(phi), cosTheta);
+}
+
+float geomSmith(float dotProd, float k) {
+    return dotProd / (dotProd * (1.0 - k) + k);
+}
+
+void main() {
+
+    float nDotV = max(TexCoord.x, 1e-4);
+    float roughness = TexCoord.y;
+    vec3 v = vec3(sqrt(1.0 - nDotV * nDotV), 0.0, nDotV);
+
+    // The remapping of k for image-based lighting.
+    float k = roughness * roughness / 2.0;
+    float scale = 0.0;
+    float bias = 0.0;
+
+    for (int i = 0; i < SampleCount; i++) {
+        vec3 h = importanceSampleGgx(hammersley(uint(i), uint(SampleCount)), roughness);
+        float vDotH = max(dot(v, h), 0.0);
+        vec3 l = 2.0 * vDotH * h - v;
+        float nDotL = l.z;
+
+        if (nDotL > 0.0) {
+            float nDotH = max(h.z, 0.0);
+            float visibility = geomSmith(nDotV, k) * geomSmith(nDotL, k) * vDotH / (nDotH * nDotV);
+            float fresnel = pow(1.0 - vDotH, 5.0);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+
+    FragColor = vec4(vec2(scale, bias) / float(SampleCount), 0.0, 1.0);
+}
@@ -0,0 +1,36 @@
+
+#version 410
+
+layout (location = 0) in vec2 TexCoord;
+
+layout (location = 0) out vec4 FragColor;
+
+uniform sampler2D EquirectTex;
+uniform int Face;
+
+const float PI = 3.14159265358979323846;
+
+
+// Keep the face orientation in sync with `ibl::face_direction`, where t increases downwards.
+vec3 faceDirection(int face, vec2 st) {
+    float s = st.x;
+    float t = st.y;
+    if (face == 0) return vec3( 1.0,   -t,   -s);
+    if (face == 1) return vec3(-1.0,   -t,    s);
+    if (face == 2) return vec3(   s,  1.0,    t);
+    if (face == 3) return vec3(   s, -1.0,   -t);
+    if (face == 4) return vec3(   s,   -t,  1.0);
+    return vec3(-s, -t, -1.0);
+}
+
+void main() {
+
+    vec3 dir = normalize(faceDirection(Face, TexCoord * 2.0 - 1.0));
+
+    float u = atan(dir.z, dir.x) / (2.0 * PI) + 0.5;
+    float v = acos(clamp(dir.y, -1.0, 1.0)) / PI;
+
+    // The panorama is uploaded bottom-left, so its top row is at v = 1.
+    vec3 color = textureLod(EquirectTex, vec2(u, 1.0 - v), 0.0).rgb;
+    FragColor = vec4(color, 1.0);
+}
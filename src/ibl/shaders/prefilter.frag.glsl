@@ -0,0 +1,85 @@
+
+#version 410
+
+layout (location = 0) in vec2 TexCoord;
+
+layout (location = 0) out vec4 FragColor;
+
+uniform samplerCube EnvTex;
+uniform int Face;
+uniform float Roughness;
+uniform float EnvSize;      // The face size of the top level of EnvTex.
+uniform int SampleCount;
+
+const float PI = 3.14159265358979323846;
+
+
+// Keep the face orientation in sync with `ibl::face_direction`, where t increases downwards.
+vec3 faceDirection(int face, vec2 st) {
+    float s = st.x;
+    float t = st.y;
+    if (face == 0) return vec3( 1.0,   -t,   -s);
+    if (face == 1) return vec3(-1.0,   -t,    s);
+    if (face == 2) return vec3(   s,  1.0,    t);
+    if (face == 3) return vec3(   s, -1.0,   -t);
+    if (face == 4) return vec3(   s,   -t,  1.0);
+    return vec3(-s, -t, -1.0);
+}
+
+vec2 hammersley(uint i, uint count) {
+    return vec2(float(i) / float(count), float(bitfieldReverse(i)) * 2.3283064365386963e-10);
+}
+
+vec3 importanceSampleGgx(vec2 xi, vec3 n, float roughness) {
+
+    float alpha = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y));
+    float sinTheta = sqrt(max(1.0 - cosTheta * cosTheta, 0.0));
+    vec3 h = vec3(sinTheta * cos(phi), sinTheta * sin(phi), cosTheta);
+
+    vec3 up = abs(n.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, n));
+    vec3 bitangent = cross(n, tangent);
+    return tangent * h.x + bitangent * h.y + n * h.z;
+}
+
+float ggxDistribution(float nDotH, float roughness) {
+    float alpha2 = roughness * roughness * roughness * roughness;
+    float d = nDotH * nDotH * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * d * d);
+}
+
+void main() {
+
+    vec3 n = normalize(faceDirection(Face, TexCoord * 2.0 - 1.0));
+
+    if (Roughness == 0.0) {
+        FragColor = vec4(textureLod(EnvTex, n, 0.0).rgb, 1.0);
+        return;
+    }
+
+    // Assume the view direction equals the normal and the reflection direction.
+    float texelSolidAngle = 4.0 * PI / (6.0 * EnvSize * EnvSize);
+    vec3 sum = vec3(0.0);
+    float weight = 0.0;
+
+    for (int i = 0; i < SampleCount; i++) {
+        vec3 h = importanceSampleGgx(hammersley(uint(i), uint(SampleCount)), n, Roughness);
+        float nDotH = max(dot(n, h), 0.0);
+        vec3 l = 2.0 * nDotH * h - n;
+        float nDotL = dot(n, l);
+
+        if (nDotL > 0.0) {
+            // Filtered importance sampling, pick the mip level covering the solid angle of the sample.
+            float pdf = ggxDistribution(nDotH, Roughness) * 0.25;
+            float sampleSolidAngle = 1.0 / (float(SampleCount) * pdf + 0.0001);
+            float lod = max(0.5 * log2(sampleSolidAngle / texelSolidAngle) + 1.0, 0.0);
+
+            sum += textureLod(EnvTex, l, lod).rgb * nDotL;
+            weight += nDotL;
+        }
+    }
+
+    FragColor = vec4(sum / weight, 1.0);
+}
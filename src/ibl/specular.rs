@@ -0,0 +1,422 @@
+
+//! Specular image-based lighting with the split-sum approximation of Karis, "Real Shading in Unreal Engine 4".
+//!
+//! The lighting integral of GGX is split into two parts:
+//!
+//! - the prefiltered environment, a cubemap whose mip levels hold the environment convolved with the GGX lobe of
+//!   increasing roughness(level `i` of `n` levels has roughness `i / (n - 1)`).
+//! - the BRDF lookup table, indexed by `(dot(n, v), roughness)`, which stores the scale and bias applied to F0.
+//!
+//! The roughness follows `pbr.frag.glsl`, where the GGX alpha is the square of the roughness. A shader combines them as:
+//!
+//! ```glsl
+//! vec3 prefiltered = textureLod(PrefilterTex, reflect(-v, n), roughness * MaxLevel).rgb;
+//! vec2 brdf = texture(BrdfLutTex, vec2(max(dot(n, v), 0.0), roughness)).rg;
+//! vec3 specular = prefiltered * (f0 * brdf.x + brdf.y);
+//! ```
+//!
+//! Both can be baked on CPU and loaded later, or generated on GPU by `SpecularBaker`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::ibl::specular;
+//!
+//! specular::bake_prefiltered("media/texture/cube/grace/grace", "media/texture/cube/grace-specular/grace-specular", 128, 6)?;
+//! specular::bake_brdf_lut("media/texture/brdf_lut.hdr", 128)?;
+//!
+//! let prefiltered = specular::load_prefiltered_cubemap(display, "media/texture/cube/grace-specular/grace-specular", 6)?;
+//! let brdf_lut = load_texture(display, "media/texture/brdf_lut.hdr")?;
+//! ```
+
+use crate::ibl::{CubeFaces, upload_face};
+use crate::readback::{self, RgbImageF};
+use crate::texture::{CubeMapFaceExtension, CUBEMAP_LAYWERS, load_cubemap_faces, blit_cubemap_faces};
+use crate::scene::GLSourceCode;
+use crate::objects::Quad;
+use crate::image::TextureOrigin;
+use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::{Drawable, Vec3F};
+
+use glium::backend::Facade;
+use glium::texture::{MipmapsOption, UncompressedFloatFormat};
+use glium::texture::texture2d::Texture2d;
+use glium::texture::cubemap::Cubemap;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::{MinifySamplerFilter, MagnifySamplerFilter};
+use glium::{Program, uniform};
+
+use std::f32::consts::PI;
+use std::path::Path;
+
+/// The number of GGX samples per texel used by the bake functions.
+pub const DEFAULT_SAMPLE_COUNT: u32 = 256;
+
+
+/// The roughness stored in mip `level` of a prefiltered environment with `mip_levels` levels.
+pub fn level_roughness(level: u32, mip_levels: u32) -> f32 {
+    if mip_levels <= 1 {
+        0.0
+    } else {
+        level as f32 / (mip_levels - 1) as f32
+    }
+}
+
+/// The `i`-th point of the Hammersley sequence of `count` points in [0, 1)^2.
+pub fn hammersley(i: u32, count: u32) -> (f32, f32) {
+    let radical_inverse = i.reverse_bits() as f32 * 2.328_306_4e-10;
+    (i as f32 / count as f32, radical_inverse)
+}
+
+/// Importance sample a half vector of the GGX distribution around +Z, where `xi` is a uniform sample in [0, 1)^2.
+pub fn importance_sample_ggx(xi: (f32, f32), roughness: f32) -> Vec3F {
+
+    let alpha = roughness * roughness;
+    let phi = 2.0 * PI * xi.0;
+    let cos_theta = ((1.0 - xi.1) / (1.0 + (alpha * alpha - 1.0) * xi.1)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+    Vec3F::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// The GGX normal distribution, with the same roughness mapping as `pbr.frag.glsl`.
+fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    let alpha2 = roughness.powi(4);
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * d * d)
+}
+
+/// Rotate `v` from the tangent space around +Z to the space around `n`.
+fn tangent_to_world(v: Vec3F, n: Vec3F) -> Vec3F {
+    let up = if n.z.abs() < 0.999 { Vec3F::unit_z() } else { Vec3F::unit_x() };
+    let tangent = up.cross(n).normalized();
+    let bitangent = n.cross(tangent);
+    tangent * v.x + bitangent * v.y + n * v.z
+}
+
+
+/// The mip chain of an environment convolved with GGX lobes of increasing roughness.
+#[derive(Debug, Clone)]
+pub struct PrefilteredEnvironment {
+    levels: Vec<CubeFaces>,
+}
+
+impl PrefilteredEnvironment {
+
+    /// Convolve `environment` to `mip_levels` levels, the top level has faces of `face_size`.
+    ///
+    /// Each sample is taken from a box-filtered level of the environment matching its solid angle(filtered importance
+    /// sampling), so a few hundred samples are enough to avoid noise. The top level is a plain resampling.
+    pub fn from_environment(environment: &CubeFaces, face_size: u32, mip_levels: u32, sample_count: u32) -> GLResult<PrefilteredEnvironment> {
+
+        validate_mip_levels(face_size, mip_levels)?;
+        if sample_count == 0 {
+            return Err(GLError::args("The sample count must not be zero."))
+        }
+
+        let mut sources = vec![environment.clone()];
+        while let Some(source) = sources.last().filter(|source| source.size() > 1 && source.size() % 2 == 0) {
+            let downsampled = source.downsampled()?;
+            sources.push(downsampled);
+        }
+
+        // The solid angle of a texel at the top level of the environment.
+        let texel_solid_angle = 4.0 * PI / (6.0 * (environment.size() * environment.size()) as f32);
+
+        let mut levels = Vec::with_capacity(mip_levels as usize);
+        for level in 0..mip_levels {
+            let size = face_size >> level;
+            let roughness = level_roughness(level, mip_levels);
+
+            let faces = if level == 0 {
+                CubeFaces::from_fn(size, |direction| environment.sample(direction))?
+            } else {
+                CubeFaces::from_fn(size, |n| {
+                    // Assume the view direction equals the normal and the reflection direction.
+                    let mut sum = [0.0; 3];
+                    let mut weight = 0.0;
+                    for i in 0..sample_count {
+                        let h = tangent_to_world(importance_sample_ggx(hammersley(i, sample_count), roughness), n);
+                        let n_dot_h = n.dot(h).max(0.0);
+                        let l = h * (2.0 * n_dot_h) - n;
+                        let n_dot_l = n.dot(l);
+                        if n_dot_l <= 0.0 {
+                            continue
+                        }
+
+                        // With n = v, the pdf of l is D(h) * dot(n, h) / (4 * dot(v, h)) = D(h) / 4.
+                        let pdf = ggx_distribution(n_dot_h, roughness) * 0.25;
+                        let sample_solid_angle = 1.0 / (sample_count as f32 * pdf + 0.0001);
+                        let lod = (0.5 * (sample_solid_angle / texel_solid_angle).log2() + 1.0).max(0.0);
+
+                        let radiance = sample_lod(&sources, l, lod);
+                        for c in 0..3 {
+                            sum[c] += radiance[c] * n_dot_l;
+                        }
+                        weight += n_dot_l;
+                    }
+
+                    // All samples may fall below the horizon when there are few of them, use the direct lookup then.
+                    if weight <= 0.0 {
+                        return sample_lod(&sources, n, 0.0)
+                    }
+                    [sum[0] / weight, sum[1] / weight, sum[2] / weight]
+                })?
+            };
+            levels.push(faces);
+        }
+
+        Ok(PrefilteredEnvironment { levels })
+    }
+
+    /// The levels must halve in size from the top level.
+    pub fn new(levels: Vec<CubeFaces>) -> GLResult<PrefilteredEnvironment> {
+
+        let face_size = levels.first().map_or(0, CubeFaces::size);
+        validate_mip_levels(face_size, levels.len() as u32)?;
+        if levels.iter().enumerate().any(|(level, faces)| faces.size() != face_size >> level) {
+            return Err(GLError::args("Each level of the prefiltered environment must be half the size of the previous level."))
+        }
+
+        Ok(PrefilteredEnvironment { levels })
+    }
+
+    /// Load the levels named as `path_prefix` + `_mip0_posx.hdr` etc.
+    pub fn load_hdr(path_prefix: &str, mip_levels: u32) -> GLResult<PrefilteredEnvironment> {
+        let levels = (0..mip_levels)
+            .map(|level| CubeFaces::load_hdr(&level_prefix(path_prefix, level)))
+            .collect::<GLResult<Vec<_>>>()?;
+        PrefilteredEnvironment::new(levels)
+    }
+
+    /// Write the levels as `path_prefix` + `_mip0_posx.hdr` etc.
+    pub fn write_hdr(&self, path_prefix: &str) -> GLResult<()> {
+        for (level, faces) in self.levels.iter().enumerate() {
+            faces.write_hdr(&level_prefix(path_prefix, level as u32))?;
+        }
+        Ok(())
+    }
+
+    pub fn levels(&self) -> &[CubeFaces] {
+        &self.levels
+    }
+
+    /// The roughness stored in mip `level`.
+    pub fn roughness(&self, level: u32) -> f32 {
+        level_roughness(level, self.levels.len() as u32)
+    }
+
+    /// Upload all levels to a floating-point cubemap with the same number of mip levels.
+    pub fn upload(&self, display: &impl Facade) -> GLResult<Cubemap> {
+
+        let cubemap = empty_prefiltered_cubemap(display, self.levels[0].size(), self.levels.len() as u32)?;
+        for (level, faces) in self.levels.iter().enumerate() {
+            let textures = faces.faces().iter()
+                .map(|face| upload_face(display, face))
+                .collect::<GLResult<Vec<_>>>()?;
+            blit_cubemap_faces(display, &cubemap, level as u32, &textures)?;
+        }
+
+        Ok(cubemap)
+    }
+}
+
+/// Trilinearly sample the box-filtered environment chain at the continuous `lod`.
+fn sample_lod(sources: &[CubeFaces], direction: Vec3F, lod: f32) -> [f32; 3] {
+
+    let max_level = (sources.len() - 1) as f32;
+    let lod = lod.min(max_level);
+    let (level, fraction) = (lod.floor() as usize, lod.fract());
+
+    let lower = sources[level].sample(direction);
+    if fraction == 0.0 || level + 1 >= sources.len() {
+        return lower
+    }
+
+    let upper = sources[level + 1].sample(direction);
+    [
+        lower[0] + (upper[0] - lower[0]) * fraction,
+        lower[1] + (upper[1] - lower[1]) * fraction,
+        lower[2] + (upper[2] - lower[2]) * fraction,
+    ]
+}
+
+fn level_prefix(path_prefix: &str, level: u32) -> String {
+    format!("{}_mip{}", path_prefix, level)
+}
+
+fn validate_mip_levels(face_size: u32, mip_levels: u32) -> GLResult<()> {
+
+    if mip_levels == 0 || mip_levels > 32 || face_size >> (mip_levels - 1) == 0 {
+        return Err(GLError::args(format!("Cubemap faces of size {} can not have {} mip levels.", face_size, mip_levels)))
+    }
+    Ok(())
+}
+
+fn empty_prefiltered_cubemap(display: &impl Facade, face_size: u32, mip_levels: u32) -> GLResult<Cubemap> {
+    // The levels are blitted or rendered into, and RGB16F is not required to be color-renderable.
+    let cubemap = Cubemap::empty_with_format(display, UncompressedFloatFormat::F16F16F16F16, MipmapsOption::EmptyMipmapsMax(mip_levels - 1), face_size)
+        .map_err(GLErrorKind::CreateTexture)?;
+    Ok(cubemap)
+}
+
+/// Load the environment faces named as `environment_prefix` + `_posx.hdr` etc, and write the prefiltered levels as
+/// `output_prefix` + `_mip0_posx.hdr` etc.
+pub fn bake_prefiltered(environment_prefix: &str, output_prefix: &str, face_size: u32, mip_levels: u32) -> GLResult<()> {
+    let environment = CubeFaces::load_hdr(environment_prefix)?;
+    PrefilteredEnvironment::from_environment(&environment, face_size, mip_levels, DEFAULT_SAMPLE_COUNT)?
+        .write_hdr(output_prefix)
+}
+
+/// Load the levels written by `bake_prefiltered` to a cubemap with `mip_levels` mip levels.
+pub fn load_prefiltered_cubemap(display: &impl Facade, path_prefix: &str, mip_levels: u32) -> GLResult<Cubemap> {
+
+    let faces = load_cubemap_faces(display, &level_prefix(path_prefix, 0), CubeMapFaceExtension::Hdr, TextureOrigin::TopLeft)?;
+    let face_size = faces[0].width();
+    validate_mip_levels(face_size, mip_levels)?;
+
+    let cubemap = empty_prefiltered_cubemap(display, face_size, mip_levels)?;
+    blit_cubemap_faces(display, &cubemap, 0, &faces)?;
+
+    for level in 1..mip_levels {
+        let faces = load_cubemap_faces(display, &level_prefix(path_prefix, level), CubeMapFaceExtension::Hdr, TextureOrigin::TopLeft)?;
+        if faces[0].width() != face_size >> level {
+            return Err(GLError::args(format!("The faces of mip level {} must be of size {}.", level, face_size >> level)))
+        }
+        blit_cubemap_faces(display, &cubemap, level, &faces)?;
+    }
+
+    Ok(cubemap)
+}
+
+
+/// The split-sum scale and bias applied to F0 for `n_dot_v` and `roughness`.
+pub fn integrate_brdf(n_dot_v: f32, roughness: f32, sample_count: u32) -> (f32, f32) {
+
+    let n_dot_v = n_dot_v.max(1e-4);
+    let v = Vec3F::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+
+    // The remapping of k for image-based lighting.
+    let k = roughness * roughness / 2.0;
+    let geometry = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+
+    let (mut scale, mut bias) = (0.0, 0.0);
+    for i in 0..sample_count {
+        let h = importance_sample_ggx(hammersley(i, sample_count), roughness);
+        let v_dot_h = v.dot(h).max(0.0);
+        let l = h * (2.0 * v_dot_h) - v;
+
+        let n_dot_l = l.z;
+        if n_dot_l > 0.0 {
+            let n_dot_h = h.z.max(0.0);
+            let visibility = geometry(n_dot_v) * geometry(n_dot_l) * v_dot_h / (n_dot_h * n_dot_v);
+            let fresnel = (1.0 - v_dot_h).powi(5);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+
+    (scale / sample_count as f32, bias / sample_count as f32)
+}
+
+/// The BRDF lookup table of `size` x `size`, storing the scale in red and the bias in green.
+///
+/// `dot(n, v)` increases to the right, and the roughness increases upwards, so the table is indexed by
+/// `(dot(n, v), roughness)` after being loaded with `TextureOrigin::BottomLeft`, as `load_texture` does.
+pub fn brdf_lut(size: u32, sample_count: u32) -> GLResult<RgbImageF> {
+
+    if size == 0 || sample_count == 0 {
+        return Err(GLError::args("Both the size and the sample count of BRDF lookup table must not be zero."))
+    }
+
+    let mut pixels = Vec::with_capacity(size as usize * size as usize);
+    for y in 0..size {
+        let roughness = 1.0 - (y as f32 + 0.5) / size as f32;
+        for x in 0..size {
+            let n_dot_v = (x as f32 + 0.5) / size as f32;
+            let (scale, bias) = integrate_brdf(n_dot_v, roughness, sample_count);
+            pixels.push([scale, bias, 0.0]);
+        }
+    }
+
+    RgbImageF::new(size, size, pixels)
+}
+
+/// Write the BRDF lookup table of `size` x `size` to a `.hdr` file.
+pub fn bake_brdf_lut(path: impl AsRef<Path>, size: u32) -> GLResult<()> {
+    readback::write_hdr(path, &brdf_lut(size, DEFAULT_SAMPLE_COUNT * 4)?)
+}
+
+
+/// Generate the prefiltered environment and BRDF lookup table on GPU, with the same conventions as the CPU bakers.
+pub struct SpecularBaker {
+    prefilter_program: Program,
+    brdf_program: Program,
+    quad: Quad,
+}
+
+impl SpecularBaker {
+
+    pub fn new(display: &impl Facade) -> GLResult<SpecularBaker> {
+
+        let vertex_shader = include_str!("../postprocess/shaders/fullscreen.vert.glsl");
+        let prefilter_program = Program::new(display, GLSourceCode::new(vertex_shader, include_str!("shaders/prefilter.frag.glsl")).with_srgb_output(false))
+            .map_err(GLErrorKind::CreateProgram)?;
+        let brdf_program = Program::new(display, GLSourceCode::new(vertex_shader, include_str!("shaders/brdf.frag.glsl")).with_srgb_output(false))
+            .map_err(GLErrorKind::CreateProgram)?;
+
+        let baker = SpecularBaker {
+            prefilter_program, brdf_program,
+            quad: Quad::new(display)?,
+        };
+        Ok(baker)
+    }
+
+    /// Convolve `environment` to a half-float cubemap of `mip_levels` levels.
+    ///
+    /// `environment` should have complete mipmaps, as the cubemaps of `load_cubemap` and `ibl::equirect` do.
+    pub fn prefilter(&self, display: &impl Facade, environment: &Cubemap, face_size: u32, mip_levels: u32, sample_count: u32) -> GLResult<Cubemap> {
+
+        validate_mip_levels(face_size, mip_levels)?;
+        let cubemap = empty_prefiltered_cubemap(display, face_size, mip_levels)?;
+
+        let sampler = environment.sampled()
+            .minify_filter(MinifySamplerFilter::LinearMipmapLinear)
+            .magnify_filter(MagnifySamplerFilter::Linear);
+
+        for level in 0..mip_levels {
+            let mipmap = cubemap.mipmap(level)
+                .ok_or_else(|| GLError::args(format!("The cubemap does not have mip level {}.", level)))?;
+
+            for (face, layer) in CUBEMAP_LAYWERS.iter().enumerate() {
+                let mut framebuffer = SimpleFrameBuffer::new(display, mipmap.image(*layer))
+                    .map_err(BufferCreationErrorKind::FrameBuffer)?;
+
+                let uniforms = uniform! {
+                    EnvTex: sampler,
+                    Face: face as i32,
+                    Roughness: level_roughness(level, mip_levels),
+                    EnvSize: environment.width() as f32,
+                    SampleCount: sample_count.max(1) as i32,
+                };
+                self.quad.render(&mut framebuffer, &self.prefilter_program, &Default::default(), &uniforms)?;
+            }
+        }
+
+        Ok(cubemap)
+    }
+
+    /// Render the BRDF lookup table to a half-float texture of `size` x `size`, indexed by `(dot(n, v), roughness)`.
+    pub fn brdf_lut(&self, display: &impl Facade, size: u32, sample_count: u32) -> GLResult<Texture2d> {
+
+        let texture = Texture2d::empty_with_format(display, UncompressedFloatFormat::F16F16, MipmapsOption::NoMipmap, size, size)
+            .map_err(GLErrorKind::CreateTexture)?;
+
+        let mut framebuffer = SimpleFrameBuffer::new(display, &texture)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        let uniforms = uniform! {
+            SampleCount: sample_count.max(1) as i32,
+        };
+        self.quad.render(&mut framebuffer, &self.brdf_program, &Default::default(), &uniforms)?;
+
+        Ok(texture)
+    }
+}
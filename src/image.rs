@@ -0,0 +1,590 @@
+
+//! Decode image files into CPU images, and upload them to GPU with a matching texture format.
+//!
+//! PNG(all color types and bit depths), JPEG, TGA, BMP and Radiance HDR files are supported.
+//! The decoded image keeps the channel count and bit depth of the file, and stores the rows from top to bottom:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::image;
+//!
+//! let height_map = image::load_image("media/texture/height.png")?;
+//! assert_eq!(height_map.channels(), 1);
+//! // A 16-bit grayscale PNG is uploaded as an `U16` texture, and sampled from the red channel in shader.
+//! let texture = height_map.upload(display, MipmapsOption::NoMipmap)?;
+//! ```
+//!
+//! Grayscale images of every format keep their 1 or 2 channels, so `Image::upload` stores them in the red(and green)
+//! channels of texture. Sample `.r` in shader, or use `Image::upload_srgb` which replicates the gray to RGB.
+//!
+//! Each image is tagged with a `ColorSpace`. Colors encoded as sRGB should be uploaded by `Image::upload_srgb`,
+//! so that shaders sample linear values, while data such as normal maps are uploaded by `Image::upload` as they are.
+
+mod png_loader;
+mod jpeg_loader;
+mod tga_loader;
+mod bmp_loader;
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::error::{GLResult, GLError, GLErrorKind};
+use crate::readback::RgbImageF;
+
+use glium::backend::Facade;
+use glium::texture::{RawImage2d, ClientFormat, MipmapsOption, UncompressedFloatFormat, SrgbFormat};
+use glium::texture::texture2d::Texture2d;
+use glium::texture::srgb_texture2d::SrgbTexture2d;
+use glium::Rect;
+
+
+/// The file formats recognized by `load_image`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Tga,
+    Bmp,
+    Hdr,
+}
+
+impl ImageFormat {
+
+    /// Guess the format from the file extension(case insensitive).
+    pub fn from_path(path: impl AsRef<Path>) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            | "png"                => Some(ImageFormat::Png),
+            | "jpg" | "jpeg"       => Some(ImageFormat::Jpeg),
+            | "tga" | "targa"      => Some(ImageFormat::Tga),
+            | "bmp" | "dib"        => Some(ImageFormat::Bmp),
+            | "hdr" | "rgbe"       => Some(ImageFormat::Hdr),
+            | _ => None,
+        }
+    }
+
+    /// Guess the format from the signature at the beginning of file.
+    ///
+    /// TGA has no signature, so it can only be recognized by the file extension.
+    pub fn from_signature(bytes: &[u8]) -> Option<ImageFormat> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if bytes.starts_with(b"BM") {
+            Some(ImageFormat::Bmp)
+        } else if bytes.starts_with(b"#?") {
+            Some(ImageFormat::Hdr)
+        } else {
+            None
+        }
+    }
+}
+
+
+/// The placement of the image rows in texture space.
+///
+/// OpenGL takes the first uploaded row as the bottom of texture(t = 0), while image files store the top row first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureOrigin {
+    /// The rows are reversed on upload, so that the image appears upright with the usual texture coordinates
+    /// whose origin is at bottom-left. `load_texture` and `Image::upload` use this.
+    BottomLeft,
+    /// The rows are uploaded in file order, so the top row of image is at t = 0.
+    /// `load_cubemap` uses this for the HDR faces.
+    TopLeft,
+}
+
+/// How the color channels of an image are encoded, which decides whether they are uploaded to an sRGB texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors encoded with the sRGB transfer function, such as albedo and diffuse maps.
+    /// The sampler of an sRGB texture converts them to linear values.
+    Srgb,
+    /// Values used as they are, such as normal maps, height maps and HDR radiance.
+    Linear,
+}
+
+
+/// The pixel components of an image, interleaved by channel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PixelData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    F32(Vec<f32>),
+}
+
+impl PixelData {
+
+    /// The count of components.
+    pub fn len(&self) -> usize {
+        match self {
+            | PixelData::U8(data)  => data.len(),
+            | PixelData::U16(data) => data.len(),
+            | PixelData::F32(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bits of each component.
+    pub fn bit_depth(&self) -> u8 {
+        match self {
+            | PixelData::U8(_)  => 8,
+            | PixelData::U16(_) => 16,
+            | PixelData::F32(_) => 32,
+        }
+    }
+}
+
+
+/// A decoded image on CPU, whose rows are stored from top to bottom.
+#[derive(Debug, Clone)]
+pub struct Image {
+    width : u32,
+    height: u32,
+    /// 1(gray), 2(gray and alpha), 3(RGB) or 4(RGBA).
+    channels: u8,
+    data: PixelData,
+    color_space: ColorSpace,
+}
+
+impl Image {
+
+    /// Create an image from interleaved pixels. The `channels` must be within 1 to 4, and the length of `data`
+    /// must be `width * height * channels`.
+    ///
+    /// Following the convention of image files, 8-bit images are tagged as `ColorSpace::Srgb`, and 16-bit and
+    /// floating-point images as `ColorSpace::Linear`. Use `with_color_space` for data such as normal maps.
+    pub fn new(width: u32, height: u32, channels: u8, data: PixelData) -> GLResult<Image> {
+
+        if channels == 0 || channels > 4 {
+            return Err(GLError::args(format!("The image must have 1 to 4 channels, but {} is given.", channels)))
+        }
+        if data.len() != width as usize * height as usize * channels as usize {
+            return Err(GLError::args(format!("The pixel data of a {}x{} image with {} channels has incorrect length {}.", width, height, channels, data.len())))
+        }
+
+        let color_space = match data {
+            | PixelData::U8(_) => ColorSpace::Srgb,
+            | PixelData::U16(_)
+            | PixelData::F32(_) => ColorSpace::Linear,
+        };
+        Ok(Image { width, height, channels, data, color_space })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// The bits of each channel, 8, 16 or 32(float).
+    pub fn bit_depth(&self) -> u8 {
+        self.data.bit_depth()
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        self.channels == 2 || self.channels == 4
+    }
+
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Tag the encoding of the color channels, the pixels are not converted.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Image {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn data(&self) -> &PixelData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> PixelData {
+        self.data
+    }
+
+    /// The texture format which stores every channel of this image without losing precision.
+    ///
+    /// Gray images use the red channel, and gray-alpha images use the red and green channels.
+    pub fn gpu_format(&self) -> UncompressedFloatFormat {
+        match (&self.data, self.channels) {
+            | (PixelData::U8(_), 1) => UncompressedFloatFormat::U8,
+            | (PixelData::U8(_), 2) => UncompressedFloatFormat::U8U8,
+            | (PixelData::U8(_), 3) => UncompressedFloatFormat::U8U8U8,
+            | (PixelData::U8(_), _) => UncompressedFloatFormat::U8U8U8U8,
+            | (PixelData::U16(_), 1) => UncompressedFloatFormat::U16,
+            | (PixelData::U16(_), 2) => UncompressedFloatFormat::U16U16,
+            | (PixelData::U16(_), 3) => UncompressedFloatFormat::U16U16U16,
+            | (PixelData::U16(_), _) => UncompressedFloatFormat::U16U16U16U16,
+            | (PixelData::F32(_), 1) => UncompressedFloatFormat::F32,
+            | (PixelData::F32(_), 2) => UncompressedFloatFormat::F32F32,
+            | (PixelData::F32(_), 3) => UncompressedFloatFormat::F32F32F32,
+            | (PixelData::F32(_), _) => UncompressedFloatFormat::F32F32F32F32,
+        }
+    }
+
+    /// Convert to floating-point RGB. Integer channels are normalized to [0, 1], gray channels are replicated,
+    /// and alpha channels are dropped.
+    pub fn to_rgb_f32(&self) -> GLResult<RgbImageF> {
+
+        let components: Vec<f32> = match &self.data {
+            | PixelData::U8(data)  => data.iter().map(|&v| f32::from(v) / 255.0).collect(),
+            | PixelData::U16(data) => data.iter().map(|&v| f32::from(v) / 65535.0).collect(),
+            | PixelData::F32(data) => data.clone(),
+        };
+
+        let pixels = components.chunks(self.channels as usize)
+            .map(|pixel| match pixel.len() {
+                | 1 | 2 => [pixel[0], pixel[0], pixel[0]],
+                | _     => [pixel[0], pixel[1], pixel[2]],
+            })
+            .collect();
+        RgbImageF::new(self.width, self.height, pixels)
+    }
+
+    /// Reverse the order of rows in place.
+    pub fn flip_vertically(&mut self) {
+
+        let row_length = self.width as usize * self.channels as usize;
+        match &mut self.data {
+            | PixelData::U8(data)  => reverse_rows(data, row_length),
+            | PixelData::U16(data) => reverse_rows(data, row_length),
+            | PixelData::F32(data) => reverse_rows(data, row_length),
+        }
+    }
+
+    pub fn flipped_vertically(mut self) -> Image {
+        self.flip_vertically();
+        self
+    }
+
+    /// The sRGB texture format of this image, only 8-bit images have one.
+    ///
+    /// Gray images are expanded to RGB, and gray-alpha images to RGBA on upload.
+    pub fn srgb_gpu_format(&self) -> Option<SrgbFormat> {
+        match (&self.data, self.channels) {
+            | (PixelData::U8(_), 1) | (PixelData::U8(_), 3) => Some(SrgbFormat::U8U8U8),
+            | (PixelData::U8(_), _) => Some(SrgbFormat::U8U8U8U8),
+            | _ => None,
+        }
+    }
+
+    /// Upload the image to a texture of `gpu_format`, with the top row at the texture coordinate t = 1.
+    ///
+    /// The values are stored as they are regardless of `color_space`, see `upload_srgb` for sRGB colors.
+    ///
+    /// This is the same as `upload_with_origin` with `TextureOrigin::BottomLeft`.
+    pub fn upload(&self, display: &impl Facade, mipmaps: MipmapsOption) -> GLResult<Texture2d> {
+        self.upload_with_origin(display, mipmaps, TextureOrigin::BottomLeft)
+    }
+
+    /// Upload the image to a texture of `gpu_format`, placing the top row according to `origin`.
+    pub fn upload_with_origin(&self, display: &impl Facade, mipmaps: MipmapsOption, origin: TextureOrigin) -> GLResult<Texture2d> {
+        let reverse_rows = origin == TextureOrigin::BottomLeft;
+        match &self.data {
+            | PixelData::U8(data)  => self.upload_raw(display, data, mipmaps, reverse_rows),
+            | PixelData::U16(data) => self.upload_raw(display, data, mipmaps, reverse_rows),
+            | PixelData::F32(data) => self.upload_raw(display, data, mipmaps, reverse_rows),
+        }
+    }
+
+    fn upload_raw<T: ChannelValue>(&self, display: &impl Facade, data: &[T], mipmaps: MipmapsOption, reverse_rows: bool) -> GLResult<Texture2d> {
+
+        let raw_image = raw_image(Cow::Borrowed(data), self.width, self.height, self.channels, reverse_rows);
+        let texture = Texture2d::with_format(display, raw_image, self.gpu_format(), mipmaps)
+            .map_err(GLErrorKind::CreateTexture)?;
+        Ok(texture)
+    }
+
+    /// Write the image to mipmap `level` of `texture`, whose dimensions at that level must match the image.
+    pub fn write_to_mipmap(&self, texture: &Texture2d, level: u32, origin: TextureOrigin) -> GLResult<()> {
+
+        let mipmap = texture.mipmap(level)
+            .ok_or_else(|| GLError::args(format!("The texture does not have mip level {}.", level)))?;
+        if mipmap.dimensions() != (self.width, self.height) {
+            return Err(GLError::args(format!("The mip level {} of {:?} does not match the image of {}x{}.", level, mipmap.dimensions(), self.width, self.height)))
+        }
+
+        let (width, height, channels) = (self.width, self.height, self.channels);
+        let rect = Rect { left: 0, bottom: 0, width, height };
+        let reverse_rows = origin == TextureOrigin::BottomLeft;
+        match &self.data {
+            | PixelData::U8(data)  => mipmap.write(rect, raw_image(Cow::Borrowed(data), width, height, channels, reverse_rows)),
+            | PixelData::U16(data) => mipmap.write(rect, raw_image(Cow::Borrowed(data), width, height, channels, reverse_rows)),
+            | PixelData::F32(data) => mipmap.write(rect, raw_image(Cow::Borrowed(data), width, height, channels, reverse_rows)),
+        }
+        Ok(())
+    }
+
+    /// Upload the image to an sRGB texture, with the top row at the texture coordinate t = 1.
+    ///
+    /// This is the same as `upload_srgb_with_origin` with `TextureOrigin::BottomLeft`.
+    pub fn upload_srgb(&self, display: &impl Facade, mipmaps: MipmapsOption) -> GLResult<SrgbTexture2d> {
+        self.upload_srgb_with_origin(display, mipmaps, TextureOrigin::BottomLeft)
+    }
+
+    /// Upload the image to an sRGB texture, whose sampler returns linear colors. The image must be 8-bit.
+    ///
+    /// The image is uploaded as sRGB even if tagged as `ColorSpace::Linear`, the caller makes the final choice.
+    pub fn upload_srgb_with_origin(&self, display: &impl Facade, mipmaps: MipmapsOption, origin: TextureOrigin) -> GLResult<SrgbTexture2d> {
+
+        let (format, raw_image) = self.srgb_raw_image(origin)?;
+        let texture = SrgbTexture2d::with_format(display, raw_image, format, mipmaps)
+            .map_err(GLErrorKind::CreateTexture)?;
+        Ok(texture)
+    }
+
+    /// Write the image to mipmap `level` of an sRGB texture, as `write_to_mipmap` does for linear textures.
+    pub fn write_to_srgb_mipmap(&self, texture: &SrgbTexture2d, level: u32, origin: TextureOrigin) -> GLResult<()> {
+
+        let mipmap = texture.mipmap(level)
+            .ok_or_else(|| GLError::args(format!("The texture does not have mip level {}.", level)))?;
+        if mipmap.dimensions() != (self.width, self.height) {
+            return Err(GLError::args(format!("The mip level {} of {:?} does not match the image of {}x{}.", level, mipmap.dimensions(), self.width, self.height)))
+        }
+
+        let (_, raw_image) = self.srgb_raw_image(origin)?;
+        mipmap.write(Rect { left: 0, bottom: 0, width: self.width, height: self.height }, raw_image);
+        Ok(())
+    }
+
+    fn srgb_raw_image(&self, origin: TextureOrigin) -> GLResult<(SrgbFormat, RawImage2d<'_, u8>)> {
+
+        let (format, data) = match (self.srgb_gpu_format(), &self.data) {
+            | (Some(format), PixelData::U8(data)) => (format, data),
+            | _ => return Err(GLError::args(format!("sRGB textures require 8-bit images, but the image is {}-bit.", self.bit_depth()))),
+        };
+
+        // There is no sRGB format with 1 or 2 channels, so replicate the gray channel.
+        let (channels, expanded): (u8, Cow<[u8]>) = match self.channels {
+            | 1 => {
+                let mut rgb = Vec::with_capacity(data.len() * 3);
+                for &gray in data.iter() {
+                    rgb.extend_from_slice(&[gray, gray, gray]);
+                }
+                (3, Cow::Owned(rgb))
+            },
+            | 2 => {
+                let mut rgba = Vec::with_capacity(data.len() * 2);
+                for pixel in data.chunks(2) {
+                    rgba.extend_from_slice(&[pixel[0], pixel[0], pixel[0], pixel[1]]);
+                }
+                (4, Cow::Owned(rgba))
+            },
+            | channels => (channels, Cow::Borrowed(data)),
+        };
+
+        let raw_image = raw_image(expanded, self.width, self.height, channels, origin == TextureOrigin::BottomLeft);
+        Ok((format, raw_image))
+    }
+}
+
+/// The pixels of a `width` x `height` image for upload, with the rows reversed if `reverse_rows`.
+fn raw_image<T: ChannelValue>(data: Cow<[T]>, width: u32, height: u32, channels: u8, reverse_rows: bool) -> RawImage2d<T> {
+
+    let row_length = (width * channels as u32) as usize;
+    let pixels = if reverse_rows && row_length > 0 {
+        Cow::Owned(data.chunks(row_length).rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect())
+    } else {
+        data
+    };
+
+    RawImage2d {
+        data  : pixels,
+        width,
+        height,
+        format: T::client_format(channels),
+    }
+}
+
+fn reverse_rows<T>(data: &mut [T], row_length: usize) {
+
+    if row_length == 0 {
+        return
+    }
+
+    let row_count = data.len() / row_length;
+    for y in 0..row_count / 2 {
+        let (upper, lower) = data.split_at_mut((row_count - 1 - y) * row_length);
+        upper[y * row_length..(y + 1) * row_length].swap_with_slice(&mut lower[..row_length]);
+    }
+}
+
+/// The component types of `PixelData` which can be uploaded by `RawImage2d`.
+trait ChannelValue: glium::texture::PixelValue + Clone {
+    fn client_format(channels: u8) -> ClientFormat;
+}
+
+impl ChannelValue for u8 {
+    fn client_format(channels: u8) -> ClientFormat {
+        match channels {
+            | 1 => ClientFormat::U8,
+            | 2 => ClientFormat::U8U8,
+            | 3 => ClientFormat::U8U8U8,
+            | _ => ClientFormat::U8U8U8U8,
+        }
+    }
+}
+
+impl ChannelValue for u16 {
+    fn client_format(channels: u8) -> ClientFormat {
+        match channels {
+            | 1 => ClientFormat::U16,
+            | 2 => ClientFormat::U16U16,
+            | 3 => ClientFormat::U16U16U16,
+            | _ => ClientFormat::U16U16U16U16,
+        }
+    }
+}
+
+impl ChannelValue for f32 {
+    fn client_format(channels: u8) -> ClientFormat {
+        match channels {
+            | 1 => ClientFormat::F32,
+            | 2 => ClientFormat::F32F32,
+            | 3 => ClientFormat::F32F32F32,
+            | _ => ClientFormat::F32F32F32F32,
+        }
+    }
+}
+
+
+/// Load an image file to CPU.
+///
+/// The format is guessed from the file extension first, and then from the signature of file content.
+pub fn load_image(path: impl AsRef<Path>) -> GLResult<Image> {
+
+    let bytes = std::fs::read(path.as_ref())
+        .map_err(GLError::io)?;
+
+    let format = ImageFormat::from_path(path.as_ref())
+        .or_else(|| ImageFormat::from_signature(&bytes))
+        .ok_or_else(|| GLError::custom(format!("Unable to recognize the format of image at {:?}.", path.as_ref())))?;
+
+    decode_image(&bytes, format)
+        .map_err(|e| GLError::custom(format!("Failed to load image at {:?}: {}", path.as_ref(), e)))
+}
+
+/// Decode the content of an image file with the given format.
+pub fn decode_image(bytes: &[u8], format: ImageFormat) -> GLResult<Image> {
+    match format {
+        | ImageFormat::Png  => png_loader::decode(bytes),
+        | ImageFormat::Jpeg => jpeg_loader::decode(bytes),
+        | ImageFormat::Tga  => tga_loader::decode(bytes),
+        | ImageFormat::Bmp  => bmp_loader::decode(bytes),
+        | ImageFormat::Hdr  => decode_hdr(bytes),
+    }
+}
+
+fn decode_hdr(mut bytes: &[u8]) -> GLResult<Image> {
+
+    let hdr_image = hdrldr::load(&mut bytes)
+        .map_err(|e| GLError::custom(format!("Invalid HDR image: {:?}", e)))?;
+
+    let mut data = Vec::with_capacity(hdr_image.data.len() * 3);
+    for pixel in hdr_image.data {
+        data.extend([pixel.r, pixel.g, pixel.b].iter());
+    }
+    Image::new(hdr_image.width as u32, hdr_image.height as u32, 3, PixelData::F32(data))
+}
+
+
+/// Little-endian reader over the bytes of an image file, used by the hand-written decoders.
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        ByteReader { bytes, position: 0 }
+    }
+
+    pub(crate) fn seek(&mut self, position: usize) -> GLResult<()> {
+        if position > self.bytes.len() {
+            return Err(truncated_error())
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    pub(crate) fn skip(&mut self, count: usize) -> GLResult<()> {
+        self.seek(self.position + count)
+    }
+
+    pub(crate) fn take(&mut self, count: usize) -> GLResult<&'a [u8]> {
+        let end = self.position.checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(truncated_error)?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn u8(&mut self) -> GLResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> GLResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> GLResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn i32(&mut self) -> GLResult<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    pub(crate) fn u64(&mut self) -> GLResult<u64> {
+        let low  = u64::from(self.u32()?);
+        let high = u64::from(self.u32()?);
+        Ok(high << 32 | low)
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The count of bytes after the current position.
+    pub(crate) fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+}
+
+fn truncated_error() -> GLError {
+    GLError::custom("Unexpected end of image data.")
+}
+
+/// Extract the bits selected by `mask` and scale them to 8 bits.
+pub(crate) fn extract_masked(value: u32, mask: u32) -> u8 {
+
+    if mask == 0 {
+        return 0
+    }
+
+    let shift = mask.trailing_zeros();
+    let max = mask >> shift;
+    let component = (value & mask) >> shift;
+    ((u64::from(component) * 255 + u64::from(max) / 2) / u64::from(max)) as u8
+}
@@ -0,0 +1,181 @@
+
+use crate::image::{Image, PixelData, ByteReader, extract_masked};
+use crate::error::{GLResult, GLError};
+
+
+const BMP_FILE_HEADER_SIZE: usize = 14;
+/// The size of `BITMAPCOREHEADER`, whose palette entries have no reserved byte.
+const BMP_CORE_HEADER_SIZE: u32 = 12;
+/// The size of `BITMAPINFOHEADER`.
+const BMP_INFO_HEADER_SIZE: u32 = 40;
+
+const BI_RGB            : u32 = 0;
+const BI_BITFIELDS      : u32 = 3;
+const BI_ALPHABITFIELDS : u32 = 6;
+
+
+/// Decode uncompressed BMP with 1, 4, 8(paletted), 16, 24 or 32 bits per pixel.
+///
+/// The images with alpha mask are decoded to RGBA, and the others to RGB, including the paletted images whose
+/// palette is grayscale. Run-length encoded BMP is not supported.
+pub fn decode(bytes: &[u8]) -> GLResult<Image> {
+
+    let mut reader = ByteReader::new(bytes);
+
+    if reader.take(2)? != b"BM" {
+        return Err(GLError::custom("Invalid BMP image: missing the 'BM' signature."))
+    }
+    reader.skip(8)?; // file size and reserved
+    let pixel_offset = reader.u32()? as usize;
+
+    let header_size = reader.u32()?;
+    let (width, height, bits, compression, palette_size) = if header_size == BMP_CORE_HEADER_SIZE {
+        let width  = i32::from(reader.u16()?);
+        let height = i32::from(reader.u16()?);
+        reader.skip(2)?; // planes
+        let bits = reader.u16()?;
+        (width, height, bits, BI_RGB, 0)
+    } else if header_size >= BMP_INFO_HEADER_SIZE {
+        let width  = reader.i32()?;
+        let height = reader.i32()?;
+        reader.skip(2)?; // planes
+        let bits = reader.u16()?;
+        let compression = reader.u32()?;
+        reader.skip(12)?; // image size and resolution
+        let palette_size = reader.u32()? as usize;
+        (width, height, bits, compression, palette_size)
+    } else {
+        return Err(GLError::custom(format!("Unsupported BMP header size {}.", header_size)))
+    };
+
+    if width <= 0 || height == 0 {
+        return Err(GLError::custom(format!("Invalid BMP image dimension {}x{}.", width, height)))
+    }
+    // The rows are stored from bottom to top, unless the height is negative.
+    let is_top_down = height < 0;
+    let width  = width as u32;
+    let height = if is_top_down { height.wrapping_neg() as u32 } else { height as u32 };
+
+    // The channel masks follow the info header for BI_BITFIELDS, and are part of the header since V4.
+    let masks = match compression {
+        | BI_RGB => match bits {
+            | 16 => [0x7C00, 0x03E0, 0x001F, 0],
+            | 32 => [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0],
+            | _  => [0; 4],
+        },
+        | BI_BITFIELDS | BI_ALPHABITFIELDS => {
+            reader.seek(BMP_FILE_HEADER_SIZE + BMP_INFO_HEADER_SIZE as usize)?;
+            let mask_count = if compression == BI_ALPHABITFIELDS || header_size > BMP_INFO_HEADER_SIZE { 4 } else { 3 };
+            let mut masks = [0; 4];
+            for mask in masks.iter_mut().take(mask_count) {
+                *mask = reader.u32()?;
+            }
+            masks
+        },
+        | _ => return Err(GLError::custom(format!("Unsupported BMP compression {}.", compression))),
+    };
+
+    let palette = if bits <= 8 {
+        let entry_bytes = if header_size == BMP_CORE_HEADER_SIZE { 3 } else { 4 };
+        let entry_count = if palette_size == 0 { 1 << bits } else { palette_size };
+        reader.seek(BMP_FILE_HEADER_SIZE + header_size as usize)?;
+        let entries = reader.take(entry_count.saturating_mul(entry_bytes))?;
+        entries.chunks(entry_bytes).map(|entry| [entry[2], entry[1], entry[0]]).collect()
+    } else {
+        Vec::new()
+    };
+
+    let channels: u8 = if bits > 8 && masks[3] != 0 { 4 } else { 3 };
+
+    // Check the size of pixels against the file before allocating, so a corrupted header fails instead of
+    // requesting a huge amount of memory.
+    reader.seek(pixel_offset)?;
+    let row_bytes = (width as usize).checked_mul(bits as usize)
+        .and_then(|row_bits| row_bits.checked_add(31))
+        .map(|row_bits| row_bits / 32 * 4);
+    let pixel_bytes = row_bytes.and_then(|row_bytes| row_bytes.checked_mul(height as usize));
+    let data_length = (width as usize).checked_mul(height as usize)
+        .and_then(|pixel_count| pixel_count.checked_mul(channels as usize));
+    let (row_bytes, data_length) = match (row_bytes, pixel_bytes, data_length) {
+        | (Some(row_bytes), Some(pixel_bytes), Some(data_length)) if pixel_bytes <= reader.remaining() => (row_bytes, data_length),
+        | _ => return Err(GLError::custom(format!("Invalid BMP image: the pixels of {}x{} image exceed the file size.", width, height))),
+    };
+
+    let mut data = vec![0_u8; data_length];
+    for y in 0..height as usize {
+        let row = reader.take(row_bytes)?;
+        let target_y = if is_top_down { y } else { height as usize - 1 - y };
+        let target = &mut data[target_y * width as usize * channels as usize..][..width as usize * channels as usize];
+
+        for (x, pixel) in target.chunks_mut(channels as usize).enumerate() {
+            match bits {
+                | 1 | 2 | 4 | 8 => {
+                    let bit_offset = x * bits as usize;
+                    let index = (row[bit_offset / 8] >> (8 - bits as usize - bit_offset % 8)) & ((1 << bits) - 1) as u8;
+                    let color = palette.get(index as usize)
+                        .ok_or_else(|| GLError::custom("Invalid BMP image: the color index is out of the palette."))?;
+                    pixel.copy_from_slice(color);
+                },
+                | 16 | 32 => {
+                    let value = if bits == 16 {
+                        u32::from(u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]))
+                    } else {
+                        u32::from_le_bytes([row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]])
+                    };
+                    for (component, &mask) in pixel.iter_mut().zip(masks.iter()) {
+                        *component = extract_masked(value, mask);
+                    }
+                },
+                | 24 => pixel.copy_from_slice(&[row[x * 3 + 2], row[x * 3 + 1], row[x * 3]]),
+                | _ => return Err(GLError::custom(format!("Unsupported BMP bits per pixel {}.", bits))),
+            }
+        }
+    }
+
+    Image::new(width, height, channels, PixelData::U8(data))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a BMP file with `BITMAPINFOHEADER` of the given fields, followed by `pixels`.
+    fn bmp(width: i32, height: i32, bits: u16, pixels: &[u8]) -> Vec<u8> {
+        let pixel_offset = BMP_FILE_HEADER_SIZE as u32 + BMP_INFO_HEADER_SIZE;
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&(pixel_offset + pixels.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&pixel_offset.to_le_bytes());
+        bytes.extend_from_slice(&BMP_INFO_HEADER_SIZE.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1_u16.to_le_bytes());
+        bytes.extend_from_slice(&bits.to_le_bytes());
+        bytes.extend_from_slice(&BI_RGB.to_le_bytes());
+        bytes.extend_from_slice(&[0; 16]); // image size, resolution and palette size
+        bytes.extend_from_slice(&[0; 4]);  // important colors
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    #[test]
+    fn rows_are_padded_and_stored_from_bottom() {
+        // BGR pixels, each row of 1 pixel is padded to 4 bytes.
+        let pixels = [255, 0, 0, 0, 0, 0, 255, 0];
+        let image = decode(&bmp(1, 2, 24, &pixels)).unwrap();
+
+        assert_eq!(image.dimensions(), (1, 2));
+        match image.data() {
+            | PixelData::U8(data) => assert_eq!(data, &[255, 0, 0, 0, 0, 255]),
+            | _ => panic!("BMP is decoded to 8-bit pixels."),
+        }
+    }
+
+    #[test]
+    fn pixels_exceeding_the_file_are_rejected() {
+        assert!(decode(&bmp(i32::MAX, i32::MAX, 32, &[0; 16])).is_err());
+        assert!(decode(&bmp(4, -4, 24, &[0; 16])).is_err());
+        assert!(decode(&bmp(0, 1, 24, &[0; 4])).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+
+use crate::image::{Image, PixelData};
+use crate::error::{GLResult, GLError};
+
+
+/// Decode baseline or progressive JPEG to 8-bit gray or RGB image.
+///
+/// CMYK images(usually written by Adobe software) are converted to RGB.
+pub fn decode(bytes: &[u8]) -> GLResult<Image> {
+
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let pixels = decoder.decode()
+        .map_err(|e| GLError::custom(format!("Invalid JPEG image: {}", e)))?;
+    let info = decoder.info()
+        .ok_or_else(|| GLError::custom("Invalid JPEG image: missing frame header."))?;
+
+    let (channels, data) = match info.pixel_format {
+        | jpeg_decoder::PixelFormat::L8     => (1, pixels),
+        | jpeg_decoder::PixelFormat::RGB24  => (3, pixels),
+        | jpeg_decoder::PixelFormat::CMYK32 => (3, cmyk_to_rgb(&pixels)),
+    };
+
+    Image::new(u32::from(info.width), u32::from(info.height), channels, PixelData::U8(data))
+}
+
+fn cmyk_to_rgb(pixels: &[u8]) -> Vec<u8> {
+
+    let mut rgb = Vec::with_capacity(pixels.len() / 4 * 3);
+    for cmyk in pixels.chunks(4) {
+        let k = 255 - u32::from(cmyk[3]);
+        for &c in &cmyk[..3] {
+            rgb.push(((255 - u32::from(c)) * k / 255) as u8);
+        }
+    }
+    rgb
+}
@@ -0,0 +1,47 @@
+
+use crate::image::{Image, PixelData};
+use crate::error::{GLResult, GLError};
+
+
+/// Decode PNG of any color type and bit depth.
+///
+/// The indexed images are expanded to RGB(or RGBA with transparency chunk), and the gray images with less than
+/// 8 bits are expanded to 8 bits. The 16-bit images are kept in 16 bits.
+pub fn decode(bytes: &[u8]) -> GLResult<Image> {
+
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info()
+        .map_err(|e| GLError::custom(format!("Invalid PNG image: {}", e)))?;
+
+    let mut buffer = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buffer)
+        .map_err(|e| GLError::custom(format!("Invalid PNG image: {}", e)))?;
+
+    let channels = match info.color_type {
+        | png::ColorType::Grayscale      => 1,
+        | png::ColorType::GrayscaleAlpha => 2,
+        | png::ColorType::RGB            => 3,
+        | png::ColorType::RGBA           => 4,
+        | png::ColorType::Indexed => return Err(GLError::custom("The indexed PNG image is not expanded to RGB.")),
+    };
+
+    // Drop the padding at the end of each row, if there is any.
+    let row_bytes = info.width as usize * channels as usize * (info.bit_depth as usize / 8);
+    let packed: Vec<u8> = if row_bytes == info.line_size {
+        buffer
+    } else {
+        buffer.chunks(info.line_size)
+            .flat_map(|row| row[..row_bytes].iter().cloned())
+            .collect()
+    };
+
+    let data = match info.bit_depth {
+        | png::BitDepth::Eight   => PixelData::U8(packed),
+        // PNG stores 16-bit samples in big-endian.
+        | png::BitDepth::Sixteen => PixelData::U16(packed.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()),
+        | _ => return Err(GLError::custom("The PNG image with less than 8 bits is not expanded.")),
+    };
+
+    Image::new(info.width, info.height, channels, data)
+}
@@ -0,0 +1,232 @@
+
+use crate::image::{Image, PixelData, ByteReader};
+use crate::error::{GLResult, GLError};
+
+
+const TGA_HEADER_SIZE: usize = 18;
+
+const TGA_COLOR_MAPPED: u8 = 1;
+const TGA_TRUE_COLOR  : u8 = 2;
+const TGA_GRAYSCALE   : u8 = 3;
+/// The run-length encoded version of above types set this bit.
+const TGA_RLE_BIT     : u8 = 8;
+
+/// The bit of image descriptor which indicates the first row is the top row.
+const TGA_TOP_ORIGIN_BIT  : u8 = 0x20;
+/// The bit of image descriptor which indicates the first column is the right column.
+const TGA_RIGHT_ORIGIN_BIT: u8 = 0x10;
+
+
+/// Decode uncompressed or run-length encoded TGA, which is true-color(15, 16, 24 or 32 bits),
+/// grayscale(8 or 16 bits with alpha) or color-mapped.
+///
+/// The grayscale images keep 1 or 2 channels, and the others are decoded to RGB or RGBA.
+pub fn decode(bytes: &[u8]) -> GLResult<Image> {
+
+    let mut reader = ByteReader::new(bytes);
+
+    let id_length     = reader.u8()?;
+    let colormap_type = reader.u8()?;
+    let image_type    = reader.u8()?;
+    let colormap_first  = reader.u16()? as usize;
+    let colormap_length = reader.u16()? as usize;
+    let colormap_depth  = reader.u8()?;
+    reader.skip(4)?; // x and y origin
+    let width  = reader.u16()? as u32;
+    let height = reader.u16()? as u32;
+    let pixel_depth = reader.u8()?;
+    let descriptor  = reader.u8()?;
+    reader.seek(TGA_HEADER_SIZE + id_length as usize)?;
+
+    let is_rle = image_type & TGA_RLE_BIT != 0;
+    let base_type = image_type & !TGA_RLE_BIT;
+
+    // The entries of color map are converted to the output channels, and the pixels only hold the index.
+    let colormap = if colormap_type == 1 {
+        if !matches!(colormap_depth, 15 | 16 | 24 | 32) {
+            return Err(GLError::args(format!("Invalid TGA image: unsupported color map depth {}.", colormap_depth)))
+        }
+        let entry_bytes = ((colormap_depth as usize) + 7) / 8;
+        let entries = reader.take(colormap_length * entry_bytes)?;
+        Some(entries.chunks(entry_bytes).map(|entry| true_color(entry, colormap_depth)).collect::<GLResult<Vec<_>>>()?)
+    } else {
+        None
+    };
+
+    let channels: u8 = match (base_type, pixel_depth) {
+        | (TGA_TRUE_COLOR, 24) | (TGA_TRUE_COLOR, 15) => 3,
+        | (TGA_TRUE_COLOR, 32) => 4,
+        // The 16-bit true color uses the top bit as alpha only if the descriptor says there is an alpha bit.
+        | (TGA_TRUE_COLOR, 16) => if descriptor & 0x0F != 0 { 4 } else { 3 },
+        | (TGA_GRAYSCALE, 8)  => 1,
+        | (TGA_GRAYSCALE, 16) => 2,
+        | (TGA_COLOR_MAPPED, 8) | (TGA_COLOR_MAPPED, 16) => {
+            if colormap.is_none() {
+                return Err(GLError::custom("Invalid TGA image: the color-mapped image has no color map."))
+            }
+            if colormap_depth == 32 || (colormap_depth == 16 && descriptor & 0x0F != 0) { 4 } else { 3 }
+        },
+        | _ => return Err(GLError::custom(format!("Unsupported TGA image type {} with {} bits per pixel.", image_type, pixel_depth))),
+    };
+
+    // Check the size of pixels against the file before allocating, so a corrupted header fails instead of
+    // requesting a huge amount of memory.
+    let pixel_bytes = ((pixel_depth as usize) + 7) / 8;
+    let pixel_count = width as usize * height as usize;
+    let sizes = pixel_count.checked_mul(pixel_bytes)
+        .zip(pixel_count.checked_mul(channels as usize));
+    // A run-length packet of 1 + `pixel_bytes` bytes expands to at most 128 pixels, which bounds the encoded size.
+    let encoded_length = |raw_length: usize| if is_rle { (pixel_count + 127) / 128 * (1 + pixel_bytes) } else { raw_length };
+    let (raw_length, data_length) = match sizes {
+        | Some((raw_length, data_length)) if encoded_length(raw_length) <= reader.remaining() => (raw_length, data_length),
+        | _ => return Err(GLError::custom(format!("Invalid TGA image: the pixels of {}x{} image exceed the file size.", width, height))),
+    };
+
+    let raw = if is_rle {
+        decode_rle(&mut reader, pixel_bytes, raw_length)?
+    } else {
+        reader.take(raw_length)?.to_vec()
+    };
+
+    let mut data = Vec::with_capacity(data_length);
+    for pixel in raw.chunks(pixel_bytes) {
+        match base_type {
+            | TGA_GRAYSCALE => data.extend_from_slice(pixel),
+            | TGA_TRUE_COLOR => {
+                let color = true_color(pixel, pixel_depth)?;
+                data.extend_from_slice(&color[..channels as usize]);
+            },
+            | _ => {
+                let index = if pixel_bytes == 1 { pixel[0] as usize } else { u16::from_le_bytes([pixel[0], pixel[1]]) as usize };
+                let color = index.checked_sub(colormap_first)
+                    .and_then(|i| colormap.as_ref().and_then(|entries| entries.get(i)))
+                    .ok_or_else(|| GLError::custom("Invalid TGA image: the color index is out of the color map."))?;
+                data.extend_from_slice(&color[..channels as usize]);
+            },
+        }
+    }
+
+    // Reorder the pixels so that the rows are from top to bottom and the columns are from left to right.
+    let row_length = width as usize * channels as usize;
+    if descriptor & TGA_RIGHT_ORIGIN_BIT != 0 {
+        for row in data.chunks_mut(row_length) {
+            reverse_pixels(row, channels as usize);
+        }
+    }
+    if descriptor & TGA_TOP_ORIGIN_BIT == 0 && row_length > 0 {
+        data = data.chunks(row_length).rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect();
+    }
+
+    Image::new(width, height, channels, PixelData::U8(data))
+}
+
+/// Expand the run-length packets to `raw_length` bytes of raw pixels.
+fn decode_rle(reader: &mut ByteReader, pixel_bytes: usize, raw_length: usize) -> GLResult<Vec<u8>> {
+
+    let mut raw = Vec::with_capacity(raw_length);
+    while raw.len() < raw_length {
+        let packet = reader.u8()?;
+        let count = (packet & 0x7F) as usize + 1;
+
+        if packet & 0x80 != 0 {
+            let pixel = reader.take(pixel_bytes)?;
+            for _ in 0..count {
+                raw.extend_from_slice(pixel);
+            }
+        } else {
+            raw.extend_from_slice(reader.take(count * pixel_bytes)?);
+        }
+    }
+
+    // A packet may cross the end of image in some broken writers.
+    raw.truncate(raw_length);
+    Ok(raw)
+}
+
+/// Convert a BGR(A) pixel or color map entry to RGBA.
+fn true_color(pixel: &[u8], depth: u8) -> GLResult<[u8; 4]> {
+    match depth {
+        | 15 | 16 => {
+            let value = u16::from_le_bytes([pixel[0], pixel[1]]);
+            let expand = |bits: u16| ((bits & 0x1F) << 3 | (bits & 0x1F) >> 2) as u8;
+            let alpha = if depth == 16 && value & 0x8000 == 0 { 0 } else { 255 };
+            Ok([expand(value >> 10), expand(value >> 5), expand(value), alpha])
+        },
+        | 24 => Ok([pixel[2], pixel[1], pixel[0], 255]),
+        | 32 => Ok([pixel[2], pixel[1], pixel[0], pixel[3]]),
+        | _ => Err(GLError::custom(format!("Unsupported TGA color depth {}.", depth))),
+    }
+}
+
+fn reverse_pixels(row: &mut [u8], channels: usize) {
+    let pixel_count = row.len() / channels;
+    for i in 0..pixel_count / 2 {
+        for c in 0..channels {
+            row.swap(i * channels + c, (pixel_count - 1 - i) * channels + c);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a TGA file of the given header fields, followed by `body`.
+    fn tga(colormap: (u8, u16, u8), image_type: u8, (width, height): (u16, u16), pixel_depth: u8, descriptor: u8, body: &[u8]) -> Vec<u8> {
+        let (colormap_type, colormap_length, colormap_depth) = colormap;
+        let mut bytes = vec![0, colormap_type, image_type, 0, 0];
+        bytes.extend_from_slice(&colormap_length.to_le_bytes());
+        bytes.push(colormap_depth);
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.push(pixel_depth);
+        bytes.push(descriptor);
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    #[test]
+    fn true_color_is_decoded_from_top_row() {
+        // BGR pixels, stored from the bottom row.
+        let body = [0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255];
+        let image = decode(&tga((0, 0, 0), TGA_TRUE_COLOR, (2, 2), 24, 0, &body)).unwrap();
+
+        assert_eq!(image.dimensions(), (2, 2));
+        assert_eq!(image.channels(), 3);
+        match image.data() {
+            | PixelData::U8(data) => assert_eq!(data, &[0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 255, 0]),
+            | _ => panic!("TGA is decoded to 8-bit pixels."),
+        }
+    }
+
+    #[test]
+    fn rle_color_mapped_is_decoded() {
+        // Two 24-bit color map entries, then a run of 3 pixels with index 1 and a raw pixel with index 0.
+        let body = [0, 0, 255, 255, 0, 0, 0x82, 1, 0x00, 0];
+        let image = decode(&tga((1, 2, 24), TGA_COLOR_MAPPED | TGA_RLE_BIT, (4, 1), 8, TGA_TOP_ORIGIN_BIT, &body)).unwrap();
+
+        match image.data() {
+            | PixelData::U8(data) => assert_eq!(data, &[0, 0, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0]),
+            | _ => panic!("TGA is decoded to 8-bit pixels."),
+        }
+    }
+
+    #[test]
+    fn malformed_color_map_depth_is_rejected() {
+        for &depth in &[0, 8, 12] {
+            assert!(decode(&tga((1, 2, depth), TGA_COLOR_MAPPED, (1, 1), 8, 0, &[0, 0, 0])).is_err());
+        }
+    }
+
+    #[test]
+    fn pixels_exceeding_the_file_are_rejected() {
+        assert!(decode(&tga((0, 0, 0), TGA_TRUE_COLOR, (u16::MAX, u16::MAX), 32, 0, &[0; 16])).is_err());
+        assert!(decode(&tga((0, 0, 0), TGA_TRUE_COLOR | TGA_RLE_BIT, (u16::MAX, u16::MAX), 32, 0, &[0xFF, 0, 0, 0, 0])).is_err());
+        // One pixel short of the 2x2 image.
+        assert!(decode(&tga((0, 0, 0), TGA_GRAYSCALE, (2, 2), 8, 0, &[0; 3])).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+
+//! Memory layout of uniform blocks and shader storage blocks.
+//!
+//! Declare the Rust counterpart of a block with `#[uniform_block]`(std140) or `#[uniform_block(std430)]`,
+//! the padding required by the layout will be inserted automatically:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::layout::uniform_block;
+//!
+//! #[uniform_block]
+//! #[allow(non_snake_case)]
+//! #[derive(Debug, Clone, Copy, Default)]
+//! struct MaterialInfo {
+//!     Ka: [f32; 3],
+//!     Kd: [f32; 3],
+//!     Ks: [f32; 3],
+//!     Shininess: f32,
+//! }
+//!
+//! let material = MaterialInfo { Ka: [0.9, 0.5, 0.3], ..Default::default() };
+//! ```
+//!
+//! The supported field types are:
+//! - `f32`, `i32`, `u32`, and their vectors `[f32; 2]`, `[i32; 3]`, `[u32; 4]`...
+//! - `GLBool` for `bool`, since `bool` of Rust takes only 1 byte.
+//! - `[[f32; 4]; 4]` for `mat4`.
+//! - Another struct declared by `#[uniform_block]` with the same layout.
+//! - Arrays of the types above, with 5 to 32 elements(the same as glium). In std140, the elements must be
+//!   `[f32; 4]`(or other 4-component vectors), `[[f32; 4]; 4]` or a `#[uniform_block]` struct, see below.
+//!
+//! The arrays of scalars, `vec2` and `vec3` have a stride of 16 bytes in std140, which no Rust array matches, so they
+//! fail the size assertion at compile time. Neither has `mat3` a matching Rust type. Declare them as `vec4` in shader
+//! instead. In std430, the arrays of scalars and `vec2` are tightly packed, and only the arrays of `vec3` need the change.
+//! Since the layout in shader can still differ from the one computed here(e.g. a block missing `layout(std140)`),
+//! use `verify_uniform_block` after the program is created.
+
+use crate::error::{GLResult, GLError};
+use crate::utils::{flatten_uniform_block_layout, BlockMember};
+
+use glium::program::{BlockLayout, UniformBlock as ReflectedBlock};
+use glium::uniforms::{UniformBlock, UniformType, LayoutMismatchError};
+use glium::Program;
+
+pub use glsl_cookbook_derive::uniform_block;
+
+
+/// The alignment and size of a type when it is a member of a std140 block.
+pub trait Std140 {
+    /// The alignment in bytes.
+    const ALIGN: usize;
+    /// The size in bytes, without the padding after the member.
+    const SIZE : usize;
+}
+
+/// The alignment and size of a type when it is a member of a std430 block.
+pub trait Std430 {
+    /// The alignment in bytes.
+    const ALIGN: usize;
+    /// The size in bytes, without the padding after the member.
+    const SIZE : usize;
+}
+
+/// The alignment of structs and arrays in std140, which is rounded up to the alignment of `vec4`.
+pub const STD140_STRUCT_ALIGN: usize = 16;
+
+/// Round `offset` up to the nearest multiple of `align`, which must be a power of 2.
+pub const fn align_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) & !(align - 1)
+}
+
+/// The larger one of the two alignments.
+pub const fn max_align(lhs: usize, rhs: usize) -> usize {
+    if lhs > rhs { lhs } else { rhs }
+}
+
+macro_rules! impl_basic_layout {
+    ($($ty:ty => ($align:expr, $size:expr)),+ $(,)?) => {
+        $(
+            impl Std140 for $ty {
+                const ALIGN: usize = $align;
+                const SIZE : usize = $size;
+            }
+
+            impl Std430 for $ty {
+                const ALIGN: usize = $align;
+                const SIZE : usize = $size;
+            }
+        )+
+    };
+}
+
+impl_basic_layout! {
+    f32 => (4, 4), [f32; 2] => (8, 8), [f32; 3] => (16, 12), [f32; 4] => (16, 16),
+    i32 => (4, 4), [i32; 2] => (8, 8), [i32; 3] => (16, 12), [i32; 4] => (16, 16),
+    u32 => (4, 4), [u32; 2] => (8, 8), [u32; 3] => (16, 12), [u32; 4] => (16, 16),
+    [[f32; 4]; 4] => (16, 64),
+}
+
+macro_rules! impl_array_layout {
+    ($($len:expr),+) => {
+        $(
+            impl<T: Std140> Std140 for [T; $len] {
+                const ALIGN: usize = STD140_STRUCT_ALIGN;
+                const SIZE : usize = $len * align_up(T::SIZE, STD140_STRUCT_ALIGN);
+            }
+
+            impl<T: Std430> Std430 for [T; $len] {
+                const ALIGN: usize = T::ALIGN;
+                const SIZE : usize = $len * align_up(T::SIZE, T::ALIGN);
+            }
+        )+
+    };
+}
+
+impl_array_layout!(5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32);
+
+/// A boolean member of uniform block, which takes 4 bytes like `bool` in GLSL.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GLBool(u32);
+
+impl From<bool> for GLBool {
+    fn from(value: bool) -> GLBool {
+        GLBool(value as u32)
+    }
+}
+
+impl From<GLBool> for bool {
+    fn from(value: GLBool) -> bool {
+        value.0 != 0
+    }
+}
+
+impl UniformBlock for GLBool {
+
+    fn matches(layout: &BlockLayout, base_offset: usize) -> Result<(), LayoutMismatchError> {
+
+        match layout {
+            | BlockLayout::BasicType { ty: UniformType::Bool, offset_in_buffer } if *offset_in_buffer == base_offset => Ok(()),
+            | BlockLayout::BasicType { ty: UniformType::Bool, offset_in_buffer } => {
+                Err(LayoutMismatchError::OffsetMismatch { expected: *offset_in_buffer, obtained: base_offset })
+            },
+            | BlockLayout::BasicType { ty, .. } => {
+                Err(LayoutMismatchError::TypeMismatch { expected: *ty, obtained: UniformType::Bool })
+            },
+            | _ => Err(LayoutMismatchError::LayoutMismatch { expected: layout.clone(), obtained: GLBool::build_layout(base_offset) }),
+        }
+    }
+
+    fn build_layout(base_offset: usize) -> BlockLayout {
+        BlockLayout::BasicType { ty: UniformType::Bool, offset_in_buffer: base_offset }
+    }
+}
+
+impl_basic_layout! {
+    GLBool => (4, 4),
+}
+
+
+/// Check the layout of uniform block named `block_name` in `program` against the Rust struct `T`.
+///
+/// Unlike the check of glium at draw time, all the mismatched members are reported at once.
+pub fn verify_uniform_block<T: UniformBlock>(program: &Program, block_name: &str) -> GLResult<()> {
+
+    let blocks = program.get_uniform_blocks();
+    let block = blocks.get(block_name)
+        .ok_or_else(|| GLError::interface(format!("Uniform block '{}' is not active in the shader program.", block_name)))?;
+
+    verify_block_layout::<T>("Uniform block", block_name, block)
+}
+
+/// Check the layout of shader storage block named `block_name` in `program` against the Rust struct `T`.
+pub fn verify_shader_storage_block<T: UniformBlock>(program: &Program, block_name: &str) -> GLResult<()> {
+
+    let blocks = program.get_shader_storage_blocks();
+    let block = blocks.get(block_name)
+        .ok_or_else(|| GLError::interface(format!("Shader storage block '{}' is not active in the shader program.", block_name)))?;
+
+    verify_block_layout::<T>("Shader storage block", block_name, block)
+}
+
+fn verify_block_layout<T: UniformBlock>(kind: &str, block_name: &str, block: &ReflectedBlock) -> GLResult<()> {
+
+    // Like glium, a block with single member can be bound to the type of that member directly(e.g. `[LightElement; 5]`).
+    let obtained_layout = match (&block.layout, T::build_layout(0)) {
+        | (BlockLayout::Struct { members }, layout @ BlockLayout::Array { .. }) if members.len() == 1 => {
+            BlockLayout::Struct { members: vec![(members[0].0.clone(), layout)] }
+        },
+        | (_, layout) => layout,
+    };
+
+    let expected = flatten_uniform_block_layout(&block.layout);
+    let obtained = flatten_uniform_block_layout(&obtained_layout);
+
+    let mut mismatches = Vec::new();
+
+    for (name, member) in expected.iter() {
+        match obtained.iter().find(|(rust_name, _)| rust_name == name) {
+            | Some((_, rust_member)) if rust_member != member => {
+                mismatches.push(format!("'{}' is {} in shader, but {} in Rust.", name, describe_member(member), describe_member(rust_member)));
+            },
+            | Some(_) => {},
+            | None => {
+                mismatches.push(format!("'{}' is declared in shader, but missing in Rust.", name));
+            },
+        }
+    }
+
+    for (name, _) in obtained.iter() {
+        if expected.iter().all(|(shader_name, _)| shader_name != name) {
+            mismatches.push(format!("'{}' is declared in Rust, but missing or inactive in shader.", name));
+        }
+    }
+
+    let rust_size = ::std::mem::size_of::<T>();
+    if block.size > rust_size {
+        mismatches.push(format!("The block takes {} bytes in shader, but only {} bytes in Rust.", block.size, rust_size));
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(GLError::interface(format!("{} '{}' does not match `{}`:\n\t{}", kind, block_name, ::std::any::type_name::<T>(), mismatches.join("\n\t"))))
+    }
+}
+
+fn describe_member(member: &BlockMember) -> String {
+
+    match member {
+        | BlockMember::Basic { ty, offset } => format!("{:?} at offset {}", ty, offset),
+        | BlockMember::Array { length }    => format!("an array of {} elements", length),
+        | BlockMember::DynamicArray        => String::from("a runtime-sized array"),
+    }
+}
+
+#[doc(hidden)]
+pub mod __private {
+    // `implement_uniform_block!` calls itself without `$crate`, so it must be imported by the generated code.
+    pub use glium::implement_uniform_block;
+}
@@ -1,13 +1,30 @@
 
-#[macro_use] extern crate rental;
 #[macro_use] extern crate itertools;
 
+// Let the code generated by glsl-cookbook-derive refer to this crate by name inside itself.
+extern crate self as glsl_cookbook_rs;
+
 pub mod scene;
 pub mod scenerunner;
 pub mod error;
 pub mod utils;
 pub mod texture;
+pub mod image;
+pub mod container;
+pub mod mipmap;
 pub mod framebuffer;
+pub mod layout;
+pub mod vertex;
+pub mod lighting;
+pub mod uniforms;
+pub mod subroutine;
+pub mod postprocess;
+pub mod ibl;
+pub mod readback;
+pub mod shadow;
+pub mod stencil;
+pub mod spirv;
+pub mod shadercheck;
 
 pub mod objects;
 pub mod aabb;
@@ -0,0 +1,464 @@
+
+//! Lights and materials shared by recipes, and their std140 uniform blocks.
+//!
+//! The lights are described in world space, and transformed to camera space while packing into uniform blocks.
+//! The names of members in each block follow the shaders of recipes, e.g. `LightInfo` matches
+//!
+//! ```glsl
+//! uniform LightInfo {
+//!     vec4 LightPosition;
+//!     vec3 La;
+//!     vec3 L;
+//! };
+//! ```
+
+use crate::layout::{uniform_block, GLBool};
+use crate::error::{GLResult, GLError};
+use crate::{Mat4F, Mat3F, Vec4F, Vec3F};
+
+
+// Lights --------------------------------------------------------------------------------------
+/// A light emitting from a position in all directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    /// The position in world space.
+    pub position: Vec3F,
+    /// The ambient intensity.
+    pub ambient: Vec3F,
+    /// The diffuse and specular intensity.
+    pub intensity: Vec3F,
+}
+
+/// A light at infinity, whose rays are parallel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// The direction in world space that the light travels along(not the direction towards the light).
+    pub direction: Vec3F,
+    /// The ambient intensity.
+    pub ambient: Vec3F,
+    /// The diffuse and specular intensity.
+    pub intensity: Vec3F,
+}
+
+/// A light emitting from a position, whose intensity is restricted to a cone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    /// The position in world space.
+    pub position: Vec3F,
+    /// The direction in world space that the cone points to.
+    pub direction: Vec3F,
+    /// The ambient intensity.
+    pub ambient: Vec3F,
+    /// The diffuse and specular intensity.
+    pub intensity: Vec3F,
+    /// The angular attenuation exponent.
+    pub exponent: f32,
+    /// The half angle of the cone in radians, between 0 and pi/2.
+    pub cutoff: f32,
+}
+
+impl PointLight {
+
+    pub fn new(position: Vec3F, intensity: Vec3F) -> PointLight {
+        PointLight { position, intensity, ambient: Vec3F::zero() }
+    }
+
+    pub fn with_ambient(self, ambient: Vec3F) -> PointLight {
+        PointLight { ambient, ..self }
+    }
+
+    /// The position in camera space, as the `vec4` expected by shaders.
+    pub fn camera_position(&self, view: &Mat4F) -> [f32; 4] {
+        (*view * Vec4F::from_point(self.position)).into_array()
+    }
+
+    pub fn to_block(&self, view: &Mat4F) -> LightInfo {
+        LightInfo {
+            LightPosition: self.camera_position(view),
+            La: self.ambient.into_array(),
+            L : self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_element(&self, view: &Mat4F) -> LightElement {
+        LightElement {
+            Position: self.camera_position(view),
+            La: self.ambient.into_array(),
+            L : self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_pbr_element(&self, view: &Mat4F) -> PbrLightElement {
+        PbrLightElement {
+            Position: self.camera_position(view),
+            L: self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+}
+
+impl DirectionalLight {
+
+    pub fn new(direction: Vec3F, intensity: Vec3F) -> DirectionalLight {
+        DirectionalLight { direction, intensity, ambient: Vec3F::zero() }
+    }
+
+    pub fn with_ambient(self, ambient: Vec3F) -> DirectionalLight {
+        DirectionalLight { ambient, ..self }
+    }
+
+    /// The direction towards the light in camera space, with `w` = 0 to distinguish it from the position of point light.
+    pub fn camera_position(&self, view: &Mat4F) -> [f32; 4] {
+        (*view * Vec4F::from_direction(-self.direction.normalized())).into_array()
+    }
+
+    pub fn to_block(&self, view: &Mat4F) -> LightInfo {
+        LightInfo {
+            LightPosition: self.camera_position(view),
+            La: self.ambient.into_array(),
+            L : self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_element(&self, view: &Mat4F) -> LightElement {
+        LightElement {
+            Position: self.camera_position(view),
+            La: self.ambient.into_array(),
+            L : self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_pbr_element(&self, view: &Mat4F) -> PbrLightElement {
+        PbrLightElement {
+            Position: self.camera_position(view),
+            L: self.intensity.into_array(),
+            ..Default::default()
+        }
+    }
+}
+
+impl SpotLight {
+
+    pub fn new(position: Vec3F, direction: Vec3F, intensity: Vec3F, exponent: f32, cutoff: f32) -> SpotLight {
+        SpotLight { position, direction, intensity, exponent, cutoff, ambient: Vec3F::zero() }
+    }
+
+    pub fn with_ambient(self, ambient: Vec3F) -> SpotLight {
+        SpotLight { ambient, ..self }
+    }
+
+    pub fn to_block(&self, view: &Mat4F) -> SpotLightInfo {
+
+        let position = *view * Vec4F::from_point(self.position);
+        let direction = Mat3F::from(*view) * self.direction;
+
+        SpotLightInfo {
+            SpotPosition: Vec3F::from(position).into_array(),
+            L : self.intensity.into_array(),
+            La: self.ambient.into_array(),
+            SpotDirection: direction.into_array(),
+            Exponent: self.exponent,
+            Cutoff: self.cutoff,
+            ..Default::default()
+        }
+    }
+}
+
+/// Pack the elements of lights into a fixed size array for uniform block, e.g. `[LightElement; 5]`.
+///
+/// The rest elements are left as default(black lights). Returns error if there are more elements than the array can hold.
+pub fn pack_light_array<A, E>(elements: impl IntoIterator<Item = E>) -> GLResult<A>
+    where
+        A: Default + AsMut<[E]> {
+
+    let mut array = A::default();
+    let slots = array.as_mut();
+    let capacity = slots.len();
+
+    for (i, element) in elements.into_iter().enumerate() {
+        if i >= capacity {
+            return Err(GLError::args(format!("Too many lights for the light array of {} elements.", capacity)))
+        }
+        slots[i] = element;
+    }
+
+    Ok(array)
+}
+// ---------------------------------------------------------------------------------------------
+
+
+// Materials -----------------------------------------------------------------------------------
+/// The reflectivity of surface for Phong and Blinn-Phong shading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhongMaterial {
+    /// Ambient reflectivity.
+    pub ka: Vec3F,
+    /// Diffuse reflectivity.
+    pub kd: Vec3F,
+    /// Specular reflectivity.
+    pub ks: Vec3F,
+    /// Specular shininess factor.
+    pub shininess: f32,
+}
+
+/// The material for the metallic/roughness PBR model in chapter 4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PbrMaterial {
+    /// The diffuse color for dielectrics, or F0 for metals.
+    pub color: Vec3F,
+    /// The roughness, between 0 and 1.
+    pub roughness: f32,
+    pub is_metal: bool,
+}
+
+impl PhongMaterial {
+
+    pub const fn new(ka: Vec3F, kd: Vec3F, ks: Vec3F, shininess: f32) -> PhongMaterial {
+        PhongMaterial { ka, kd, ks, shininess }
+    }
+
+    pub fn to_block(&self) -> MaterialInfo {
+        MaterialInfo {
+            Ka: self.ka.into_array(),
+            Kd: self.kd.into_array(),
+            Ks: self.ks.into_array(),
+            Shininess: self.shininess,
+            ..Default::default()
+        }
+    }
+
+    /// Find the preset material by its name in `PHONG_PRESETS`, ignoring case.
+    pub fn preset(name: &str) -> Option<PhongMaterial> {
+        PHONG_PRESETS.iter()
+            .find(|(preset_name, _)| preset_name.eq_ignore_ascii_case(name))
+            .map(|(_, material)| *material)
+    }
+}
+
+impl PbrMaterial {
+
+    pub const fn dielectric(color: Vec3F, roughness: f32) -> PbrMaterial {
+        PbrMaterial { color, roughness, is_metal: false }
+    }
+
+    pub const fn metal(color: Vec3F, roughness: f32) -> PbrMaterial {
+        PbrMaterial { color, roughness, is_metal: true }
+    }
+
+    pub fn with_roughness(self, roughness: f32) -> PbrMaterial {
+        PbrMaterial { roughness, ..self }
+    }
+
+    pub fn to_block(&self) -> PbrMaterialInfo {
+        PbrMaterialInfo {
+            MaterialColor: self.color.into_array(),
+            MaterialRough: self.roughness,
+            IsMetal: GLBool::from(self.is_metal),
+            ..Default::default()
+        }
+    }
+
+    /// Find the preset material by its name in `PBR_PRESETS`, ignoring case.
+    pub fn preset(name: &str) -> Option<PbrMaterial> {
+        PBR_PRESETS.iter()
+            .find(|(preset_name, _)| preset_name.eq_ignore_ascii_case(name))
+            .map(|(_, material)| *material)
+    }
+}
+
+/// The classic materials for Phong shading(from the OpenGL material table, with shininess scaled by 128).
+pub const PHONG_PRESETS: [(&str, PhongMaterial); 14] = [
+    ("emerald", PhongMaterial::new(Vec3F::new(0.0215, 0.1745, 0.0215), Vec3F::new(0.07568, 0.61424, 0.07568), Vec3F::new(0.633, 0.727811, 0.633), 76.8)),
+    ("jade", PhongMaterial::new(Vec3F::new(0.135, 0.2225, 0.1575), Vec3F::new(0.54, 0.89, 0.63), Vec3F::new(0.316228, 0.316228, 0.316228), 12.8)),
+    ("obsidian", PhongMaterial::new(Vec3F::new(0.05375, 0.05, 0.06625), Vec3F::new(0.18275, 0.17, 0.22525), Vec3F::new(0.332741, 0.328634, 0.346435), 38.4)),
+    ("pearl", PhongMaterial::new(Vec3F::new(0.25, 0.20725, 0.20725), Vec3F::new(1.0, 0.829, 0.829), Vec3F::new(0.296648, 0.296648, 0.296648), 11.264)),
+    ("ruby", PhongMaterial::new(Vec3F::new(0.1745, 0.01175, 0.01175), Vec3F::new(0.61424, 0.04136, 0.04136), Vec3F::new(0.727811, 0.626959, 0.626959), 76.8)),
+    ("turquoise", PhongMaterial::new(Vec3F::new(0.1, 0.18725, 0.1745), Vec3F::new(0.396, 0.74151, 0.69102), Vec3F::new(0.297254, 0.30829, 0.306678), 12.8)),
+    ("brass", PhongMaterial::new(Vec3F::new(0.329412, 0.223529, 0.027451), Vec3F::new(0.780392, 0.568627, 0.113725), Vec3F::new(0.992157, 0.941176, 0.807843), 27.897436)),
+    ("bronze", PhongMaterial::new(Vec3F::new(0.2125, 0.1275, 0.054), Vec3F::new(0.714, 0.4284, 0.18144), Vec3F::new(0.393548, 0.271906, 0.166721), 25.6)),
+    ("chrome", PhongMaterial::new(Vec3F::new(0.25, 0.25, 0.25), Vec3F::new(0.4, 0.4, 0.4), Vec3F::new(0.774597, 0.774597, 0.774597), 76.8)),
+    ("copper", PhongMaterial::new(Vec3F::new(0.19125, 0.0735, 0.0225), Vec3F::new(0.7038, 0.27048, 0.0828), Vec3F::new(0.256777, 0.137622, 0.086014), 12.8)),
+    ("gold", PhongMaterial::new(Vec3F::new(0.24725, 0.1995, 0.0745), Vec3F::new(0.75164, 0.60648, 0.22648), Vec3F::new(0.628281, 0.555802, 0.366065), 51.2)),
+    ("silver", PhongMaterial::new(Vec3F::new(0.19225, 0.19225, 0.19225), Vec3F::new(0.50754, 0.50754, 0.50754), Vec3F::new(0.508273, 0.508273, 0.508273), 51.2)),
+    ("black plastic", PhongMaterial::new(Vec3F::new(0.0, 0.0, 0.0), Vec3F::new(0.01, 0.01, 0.01), Vec3F::new(0.5, 0.5, 0.5), 32.0)),
+    ("white rubber", PhongMaterial::new(Vec3F::new(0.05, 0.05, 0.05), Vec3F::new(0.5, 0.5, 0.5), Vec3F::new(0.7, 0.7, 0.7), 10.0)),
+];
+
+/// The metals used in the PBR recipe of chapter 4, whose colors are their F0 reflectance.
+pub const PBR_PRESETS: [(&str, PbrMaterial); 5] = [
+    ("gold",     PbrMaterial::metal(Vec3F::new(1.0, 0.71, 0.29), 0.43)),
+    ("copper",   PbrMaterial::metal(Vec3F::new(0.95, 0.64, 0.54), 0.43)),
+    ("aluminum", PbrMaterial::metal(Vec3F::new(0.91, 0.92, 0.92), 0.43)),
+    ("titanium", PbrMaterial::metal(Vec3F::new(0.542, 0.497, 0.449), 0.43)),
+    ("silver",   PbrMaterial::metal(Vec3F::new(0.95, 0.93, 0.88), 0.43)),
+];
+// ---------------------------------------------------------------------------------------------
+
+
+// Fog -----------------------------------------------------------------------------------------
+/// The linear fog between `min_distance` and `max_distance` from camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub color: Vec3F,
+}
+
+impl Fog {
+
+    pub fn to_block(&self) -> FogInfo {
+        FogInfo {
+            MaxDist: self.max_distance,
+            MinDist: self.min_distance,
+            FogColor: self.color.into_array(),
+            ..Default::default()
+        }
+    }
+}
+// ---------------------------------------------------------------------------------------------
+
+
+// Uniform blocks ------------------------------------------------------------------------------
+/// The block of a single point or directional light.
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LightInfo {
+    pub LightPosition: [f32; 4],
+    pub La: [f32; 3],
+    pub L : [f32; 3],
+}
+
+/// The element of light array for Phong shading, e.g. `LightInfo lights[5]` in shader.
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LightElement {
+    pub Position: [f32; 4],
+    pub La: [f32; 3],
+    pub L : [f32; 3],
+}
+
+/// The element of light array for PBR, which has no ambient term.
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PbrLightElement {
+    pub Position: [f32; 4],
+    pub L: [f32; 3],
+}
+
+/// The block of a single spot light.
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpotLightInfo {
+    pub SpotPosition: [f32; 3],
+    pub L : [f32; 3],
+    pub La: [f32; 3],
+    pub SpotDirection: [f32; 3],
+    pub Exponent: f32,
+    pub Cutoff: f32,
+}
+
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaterialInfo {
+    pub Ka: [f32; 3],
+    pub Kd: [f32; 3],
+    pub Ks: [f32; 3],
+    pub Shininess: f32,
+}
+
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PbrMaterialInfo {
+    pub MaterialColor: [f32; 3],
+    pub MaterialRough: f32,
+    pub IsMetal: GLBool,
+}
+
+#[uniform_block]
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FogInfo {
+    pub MaxDist: f32,
+    pub MinDist: f32,
+    pub FogColor: [f32; 3],
+}
+// ---------------------------------------------------------------------------------------------
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::{flatten_uniform_block_layout, BlockMember};
+
+    use glium::uniforms::UniformBlock;
+
+    /// The offsets of the basic members of block `T`, in the order of declaration.
+    fn offsets<T: UniformBlock>() -> Vec<(String, usize)> {
+        flatten_uniform_block_layout(&T::build_layout(0)).into_iter()
+            .filter_map(|(name, member)| match member {
+                | BlockMember::Basic { offset, .. } => Some((name, offset)),
+                | _ => None,
+            })
+            .collect()
+    }
+
+    fn expected(members: &[(&str, usize)]) -> Vec<(String, usize)> {
+        members.iter().map(|(name, offset)| (name.to_string(), *offset)).collect()
+    }
+
+    #[test]
+    fn light_blocks_follow_std140() {
+
+        assert_eq!(offsets::<LightInfo>(), expected(&[("LightPosition", 0), ("La", 16), ("L", 32)]));
+        assert_eq!(std::mem::size_of::<LightInfo>(), 48);
+
+        assert_eq!(offsets::<PbrLightElement>(), expected(&[("Position", 0), ("L", 16)]));
+        assert_eq!(std::mem::size_of::<PbrLightElement>(), 32);
+
+        assert_eq!(offsets::<SpotLightInfo>(), expected(&[
+            ("SpotPosition", 0), ("L", 16), ("La", 32), ("SpotDirection", 48), ("Exponent", 60), ("Cutoff", 64),
+        ]));
+        assert_eq!(std::mem::size_of::<SpotLightInfo>(), 80);
+    }
+
+    #[test]
+    fn light_arrays_have_vec4_aligned_stride() {
+
+        assert_eq!(std::mem::size_of::<LightElement>(), 48);
+        assert_eq!(std::mem::size_of::<[LightElement; 5]>(), 5 * 48);
+        assert_eq!(std::mem::size_of::<[PbrLightElement; 5]>(), 5 * 32);
+    }
+
+    #[test]
+    fn material_and_fog_blocks_follow_std140() {
+
+        assert_eq!(offsets::<MaterialInfo>(), expected(&[("Ka", 0), ("Kd", 16), ("Ks", 32), ("Shininess", 44)]));
+        assert_eq!(std::mem::size_of::<MaterialInfo>(), 48);
+
+        assert_eq!(offsets::<PbrMaterialInfo>(), expected(&[("MaterialColor", 0), ("MaterialRough", 12), ("IsMetal", 16)]));
+        assert_eq!(offsets::<FogInfo>(), expected(&[("MaxDist", 0), ("MinDist", 4), ("FogColor", 16)]));
+    }
+
+    #[test]
+    fn pack_light_array_rejects_too_many_lights() {
+
+        let light = PointLight::new(Vec3F::one(), Vec3F::one()).to_element(&Mat4F::identity());
+
+        let packed: [LightElement; 5] = pack_light_array(vec![light; 2]).unwrap();
+        assert_eq!(packed[1].L, [1.0; 3]);
+        assert_eq!(packed[2].L, [0.0; 3]);
+
+        assert!(pack_light_array::<[LightElement; 5], _>(vec![light; 6]).is_err());
+    }
+}
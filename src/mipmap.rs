@@ -0,0 +1,493 @@
+
+//! Generate mip chains on CPU, as a deterministic replacement of `AutoGeneratedMipmaps` and `generate_mipmaps`.
+//!
+//! The driver generated mipmaps usually use a box filter, average sRGB colors as if they were linear, and differ between
+//! drivers. `MipmapGenerator` filters with a selectable kernel, can filter sRGB colors in linear space, and can preserve
+//! the alpha-test coverage of cutout textures, whose leaves and fences otherwise fade out in the distance:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::mipmap::{MipmapGenerator, MipFilter};
+//! use glsl_cookbook_rs::texture::load_texture_with_mipmaps;
+//!
+//! // The shader discards the fragments whose alpha is less than 0.15.
+//! let generator = MipmapGenerator::new(MipFilter::Kaiser)
+//!     .with_srgb(true)
+//!     .with_alpha_coverage(0.15);
+//! let moss = load_texture_with_mipmaps(display, "media/texture/moss.png", &generator)?;
+//! ```
+//!
+//! Each level halves the size of the previous level(rounding down, at least 1) until 1x1, which is the chain that
+//! OpenGL expects. Unlike the driver generated mipmaps, the result does not depend on the GPU, although the last bits
+//! may still differ between platforms, since `powf` and the trigonometric functions come from the platform math library.
+
+use crate::image::{Image, PixelData, ColorSpace, TextureOrigin};
+use crate::error::{GLResult, GLError};
+
+use glium::backend::Facade;
+use glium::texture::MipmapsOption;
+use glium::texture::texture2d::Texture2d;
+use glium::texture::srgb_texture2d::SrgbTexture2d;
+
+use std::f32::consts::PI;
+
+/// The Kaiser window parameter, a larger value suppresses ringing but blurs more.
+const KAISER_ALPHA: f32 = 4.0;
+/// The number of iterations to search the alpha scale which preserves the coverage.
+const COVERAGE_SEARCH_STEPS: usize = 20;
+
+
+/// The reconstruction filters of downsampling. The radius is measured in pixels of the smaller level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipFilter {
+    /// Average the pixels covered by each output pixel, the same as most drivers. Blurry but never rings.
+    Box,
+    /// Sinc windowed by Kaiser window of radius 3. Sharp, with mild ringing.
+    Kaiser,
+    /// Lanczos with radius 3. The sharpest, with the strongest ringing.
+    Lanczos,
+}
+
+impl MipFilter {
+
+    fn radius(&self) -> f32 {
+        match self {
+            | MipFilter::Box => 0.5,
+            | MipFilter::Kaiser
+            | MipFilter::Lanczos => 3.0,
+        }
+    }
+
+    fn weight(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            | MipFilter::Box => {
+                if x < 0.5 { 1.0 } else if x == 0.5 { 0.5 } else { 0.0 }
+            },
+            | MipFilter::Kaiser => {
+                if x >= 3.0 {
+                    0.0
+                } else {
+                    let ratio = x / 3.0;
+                    sinc(x) * bessel_i0(KAISER_ALPHA * (1.0 - ratio * ratio).sqrt()) / bessel_i0(KAISER_ALPHA)
+                }
+            },
+            | MipFilter::Lanczos => {
+                if x >= 3.0 { 0.0 } else { sinc(x) * sinc(x / 3.0) }
+            },
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// The modified Bessel function of the first kind of order 0, by its power series.
+fn bessel_i0(x: f32) -> f32 {
+
+    let (mut sum, mut term) = (1.0, 1.0);
+    let half_x_squared = x * x * 0.25;
+    for k in 1..50 {
+        term *= half_x_squared / (k * k) as f32;
+        sum += term;
+        if term < sum * 1e-8 {
+            break
+        }
+    }
+    sum
+}
+
+
+/// The options of mip chain generation.
+#[derive(Debug, Clone, Copy)]
+pub struct MipmapGenerator {
+    filter: MipFilter,
+    is_srgb: Option<bool>,
+    alpha_cutoff: Option<f32>,
+}
+
+impl MipmapGenerator {
+
+    pub fn new(filter: MipFilter) -> MipmapGenerator {
+        MipmapGenerator {
+            filter,
+            is_srgb: None,
+            alpha_cutoff: None,
+        }
+    }
+
+    /// Whether the color channels of 8-bit and 16-bit images are sRGB encoded, so they are converted to linear before
+    /// filtering and back after. Alpha channels and floating-point images are always filtered as they are.
+    ///
+    /// By default, this follows the `ColorSpace` tag of the image.
+    pub fn with_srgb(mut self, is_srgb: bool) -> MipmapGenerator {
+        self.is_srgb = Some(is_srgb);
+        self
+    }
+
+    /// Scale the alpha of each level, so that the fraction of pixels whose alpha is greater than `cutoff` stays the
+    /// same as the top level. `cutoff` is the threshold used by the alpha test in shader.
+    pub fn with_alpha_coverage(mut self, cutoff: f32) -> MipmapGenerator {
+        self.alpha_cutoff = Some(cutoff);
+        self
+    }
+
+    /// Generate the complete mip chain of `image`, the first level is `image` itself.
+    pub fn generate(&self, image: &Image) -> GLResult<MipChain> {
+
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return Err(GLError::args("Can not generate mipmaps for an empty image."))
+        }
+
+        let channels = image.channels() as usize;
+        let is_integer = image.bit_depth() != 32;
+        let color_channels = if image.has_alpha() { channels - 1 } else { channels };
+        let alpha_cutoff = self.alpha_cutoff.filter(|_| image.has_alpha());
+        let is_srgb = self.is_srgb.unwrap_or(image.color_space() == ColorSpace::Srgb);
+        let linearize = is_srgb && is_integer;
+
+        let mut current = LevelBuffer::from_image(image);
+        if linearize {
+            current.map_colors(color_channels, srgb_to_linear);
+        }
+        let target_coverage = alpha_cutoff.map(|cutoff| current.alpha_coverage(cutoff, 1.0));
+
+        let mut levels = vec![image.clone()];
+        while current.width > 1 || current.height > 1 {
+
+            current = current.downsampled(self.filter);
+            let mut output = current.clone();
+
+            if let (Some(cutoff), Some(coverage)) = (alpha_cutoff, target_coverage) {
+                let scale = output.coverage_scale(cutoff, coverage);
+                output.map_alpha(|alpha| alpha * scale);
+            }
+            if linearize {
+                output.map_colors(color_channels, linear_to_srgb);
+            }
+
+            levels.push(output.to_image(image, is_integer)?);
+        }
+
+        Ok(MipChain { levels })
+    }
+}
+
+
+/// The levels of a mipmapped image, from the largest to 1x1.
+#[derive(Debug, Clone)]
+pub struct MipChain {
+    levels: Vec<Image>,
+}
+
+impl MipChain {
+
+    pub fn levels(&self) -> &[Image] {
+        &self.levels
+    }
+
+    pub fn into_levels(self) -> Vec<Image> {
+        self.levels
+    }
+
+    /// Upload every level to a texture of the `gpu_format` of the first level.
+    pub fn upload(&self, display: &impl Facade, origin: TextureOrigin) -> GLResult<Texture2d> {
+
+        let mipmaps = MipmapsOption::EmptyMipmapsMax(self.levels.len() as u32 - 1);
+        let texture = self.levels[0].upload_with_origin(display, mipmaps, origin)?;
+
+        for (level, image) in self.levels.iter().enumerate().skip(1) {
+            image.write_to_mipmap(&texture, level as u32, origin)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Upload every level to an sRGB texture, the levels must be 8-bit.
+    pub fn upload_srgb(&self, display: &impl Facade, origin: TextureOrigin) -> GLResult<SrgbTexture2d> {
+
+        let mipmaps = MipmapsOption::EmptyMipmapsMax(self.levels.len() as u32 - 1);
+        let texture = self.levels[0].upload_srgb_with_origin(display, mipmaps, origin)?;
+
+        for (level, image) in self.levels.iter().enumerate().skip(1) {
+            image.write_to_srgb_mipmap(&texture, level as u32, origin)?;
+        }
+
+        Ok(texture)
+    }
+}
+
+
+/// A level in floating-point, with integer channels normalized to [0, 1].
+#[derive(Debug, Clone)]
+struct LevelBuffer {
+    width: usize,
+    height: usize,
+    channels: usize,
+    data: Vec<f32>,
+}
+
+impl LevelBuffer {
+
+    fn from_image(image: &Image) -> LevelBuffer {
+        let data = match image.data() {
+            | PixelData::U8(data)  => data.iter().map(|&v| f32::from(v) / 255.0).collect(),
+            | PixelData::U16(data) => data.iter().map(|&v| f32::from(v) / 65535.0).collect(),
+            | PixelData::F32(data) => data.clone(),
+        };
+        LevelBuffer {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            channels: image.channels() as usize,
+            data,
+        }
+    }
+
+    /// Convert back to the component type and color space of `like`, clamping integer channels to [0, 1].
+    fn to_image(&self, like: &Image, is_integer: bool) -> GLResult<Image> {
+
+        let clamp = |v: f32| if is_integer { v.max(0.0).min(1.0) } else { v };
+        let data = match like.data() {
+            | PixelData::U8(_)  => PixelData::U8(self.data.iter().map(|&v| (clamp(v) * 255.0).round() as u8).collect()),
+            | PixelData::U16(_) => PixelData::U16(self.data.iter().map(|&v| (clamp(v) * 65535.0).round() as u16).collect()),
+            | PixelData::F32(_) => PixelData::F32(self.data.clone()),
+        };
+        let image = Image::new(self.width as u32, self.height as u32, self.channels as u8, data)?
+            .with_color_space(like.color_space());
+        Ok(image)
+    }
+
+    fn map_colors(&mut self, color_channels: usize, f: impl Fn(f32) -> f32) {
+        for pixel in self.data.chunks_mut(self.channels) {
+            for value in pixel[..color_channels].iter_mut() {
+                *value = f(*value);
+            }
+        }
+    }
+
+    fn map_alpha(&mut self, f: impl Fn(f32) -> f32) {
+        let alpha = self.channels - 1;
+        for pixel in self.data.chunks_mut(self.channels) {
+            pixel[alpha] = f(pixel[alpha]).max(0.0).min(1.0);
+        }
+    }
+
+    /// The fraction of pixels that pass the alpha test after their alpha is multiplied by `scale`.
+    fn alpha_coverage(&self, cutoff: f32, scale: f32) -> f32 {
+        let alpha = self.channels - 1;
+        let passed = self.data.chunks(self.channels)
+            .filter(|pixel| pixel[alpha] * scale > cutoff)
+            .count();
+        passed as f32 / (self.width * self.height) as f32
+    }
+
+    /// Binary search the smallest scale of alpha whose coverage reaches `coverage`.
+    fn coverage_scale(&self, cutoff: f32, coverage: f32) -> f32 {
+
+        let (mut low, mut high) = (0.0_f32, 4.0_f32);
+        for _ in 0..COVERAGE_SEARCH_STEPS {
+            let middle = (low + high) * 0.5;
+            if self.alpha_coverage(cutoff, middle) < coverage {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        high
+    }
+
+    /// Filter to half the size, separably along x and then y.
+    fn downsampled(&self, filter: MipFilter) -> LevelBuffer {
+
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let channels = self.channels;
+
+        let weights_x = axis_weights(filter, self.width, width);
+        let mut horizontal = vec![0.0_f32; width * self.height * channels];
+        for y in 0..self.height {
+            for (x, taps) in weights_x.iter().enumerate() {
+                let target = (y * width + x) * channels;
+                for &(source_x, weight) in taps.iter() {
+                    let source = (y * self.width + source_x) * channels;
+                    for c in 0..channels {
+                        horizontal[target + c] += self.data[source + c] * weight;
+                    }
+                }
+            }
+        }
+
+        let weights_y = axis_weights(filter, self.height, height);
+        let mut data = vec![0.0_f32; width * height * channels];
+        for (y, taps) in weights_y.iter().enumerate() {
+            for x in 0..width {
+                let target = (y * width + x) * channels;
+                for &(source_y, weight) in taps.iter() {
+                    let source = (source_y * width + x) * channels;
+                    for c in 0..channels {
+                        data[target + c] += horizontal[source + c] * weight;
+                    }
+                }
+            }
+        }
+
+        LevelBuffer { width, height, channels, data }
+    }
+}
+
+/// The normalized filter taps of each output pixel along an axis, clamping the source indices at the edges.
+fn axis_weights(filter: MipFilter, source_length: usize, target_length: usize) -> Vec<Vec<(usize, f32)>> {
+
+    let scale = source_length as f32 / target_length as f32;
+    let radius = filter.radius() * scale;
+
+    (0..target_length).map(|i| {
+        let center = (i as f32 + 0.5) * scale - 0.5;
+        let first = (center - radius).floor() as i64;
+        let last = (center + radius).ceil() as i64;
+
+        let mut taps: Vec<(usize, f32)> = Vec::new();
+        for j in first..=last {
+            let weight = filter.weight((j as f32 - center) / scale);
+            if weight == 0.0 {
+                continue
+            }
+            let index = j.max(0).min(source_length as i64 - 1) as usize;
+            match taps.iter_mut().find(|(tap_index, _)| *tap_index == index) {
+                | Some(tap) => tap.1 += weight,
+                | None => taps.push((index, weight)),
+            }
+        }
+
+        let sum: f32 = taps.iter().map(|(_, weight)| weight).sum();
+        for tap in taps.iter_mut() {
+            tap.1 /= sum;
+        }
+        taps
+    }).collect()
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.040_45 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.max(0.0);
+    if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn gray_image(width: u32, height: u32, values: Vec<f32>) -> Image {
+        Image::new(width, height, 1, PixelData::F32(values)).unwrap()
+    }
+
+    fn f32_data(image: &Image) -> &[f32] {
+        match image.data() {
+            | PixelData::F32(data) => data,
+            | _ => panic!("The level is expected to be floating-point."),
+        }
+    }
+
+    #[test]
+    fn chain_halves_odd_dimensions_down_to_one() {
+
+        let image = gray_image(13, 5, vec![0.5; 13 * 5]);
+        let chain = MipmapGenerator::new(MipFilter::Kaiser).generate(&image).unwrap();
+
+        let sizes: Vec<(u32, u32)> = chain.levels().iter().map(Image::dimensions).collect();
+        assert_eq!(sizes, vec![(13, 5), (6, 2), (3, 1), (1, 1)]);
+        // floor(log2(max(width, height))) + 1, the same count of levels as OpenGL.
+        assert_eq!(chain.levels().len(), 4);
+    }
+
+    #[test]
+    fn box_filter_averages_each_quad() {
+
+        let image = gray_image(4, 2, vec![
+            0.0, 1.0, 2.0, 4.0,
+            2.0, 3.0, 6.0, 8.0,
+        ]);
+        let chain = MipmapGenerator::new(MipFilter::Box).generate(&image).unwrap();
+
+        assert_eq!(f32_data(&chain.levels()[1]), &[1.5, 5.0]);
+        assert_eq!(f32_data(&chain.levels()[2]), &[3.25]);
+    }
+
+    #[test]
+    fn srgb_colors_are_filtered_in_linear_space() {
+
+        for i in 0..=255 {
+            let v = i as f32 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-5);
+        }
+
+        // The average of black and white is 0.5 in linear space, which is 188 in sRGB rather than 128.
+        let image = Image::new(2, 1, 3, PixelData::U8(vec![0, 0, 0, 255, 255, 255])).unwrap();
+        let averaged = |is_srgb: bool| {
+            let chain = MipmapGenerator::new(MipFilter::Box).with_srgb(is_srgb).generate(&image).unwrap();
+            match chain.levels()[1].data() {
+                | PixelData::U8(data) => data.clone(),
+                | _ => panic!("The level is expected to be 8-bit."),
+            }
+        };
+        assert_eq!(averaged(true), vec![188; 3]);
+        assert_eq!(averaged(false), vec![128; 3]);
+
+        // A constant color stays the same after the round trip.
+        let image = Image::new(4, 4, 3, PixelData::U8(vec![77; 4 * 4 * 3])).unwrap();
+        let chain = MipmapGenerator::new(MipFilter::Lanczos).with_srgb(true).generate(&image).unwrap();
+        assert!(chain.levels().iter().all(|level| *level.data() == PixelData::U8(vec![77; level.data().len()])));
+    }
+
+    #[test]
+    fn alpha_coverage_is_preserved() {
+
+        const CUTOFF: f32 = 0.7;
+        let size = 32;
+
+        // Scattered alpha like foliage, about 30% of which passes the test. The averages of smaller levels gather
+        // around the mean, so fewer of them pass unless the alpha is scaled.
+        let mut data = Vec::with_capacity(size * size * 4);
+        for y in 0..size {
+            for x in 0..size {
+                let alpha = ((x * 73 + y * 151 + x * y * 17) % 256) as u8;
+                data.extend_from_slice(&[255, 255, 255, alpha]);
+            }
+        }
+        let image = Image::new(size as u32, size as u32, 4, PixelData::U8(data)).unwrap();
+
+        let coverage = |level: &Image| match level.data() {
+            | PixelData::U8(data) => {
+                let passed = data.chunks(4).filter(|pixel| f32::from(pixel[3]) / 255.0 > CUTOFF).count();
+                passed as f32 / (level.width() * level.height()) as f32
+            },
+            | _ => panic!("The level is expected to be 8-bit."),
+        };
+
+        let preserved = MipmapGenerator::new(MipFilter::Box).with_alpha_coverage(CUTOFF).generate(&image).unwrap();
+        let top_coverage = coverage(&preserved.levels()[0]);
+        for level in preserved.levels().iter().take_while(|level| level.width() >= 4) {
+            assert!((coverage(level) - top_coverage).abs() < 0.1, "coverage {} of {:?} level", coverage(level), level.dimensions());
+        }
+
+        let faded = MipmapGenerator::new(MipFilter::Box).generate(&image).unwrap();
+        assert!(coverage(&faded.levels()[2]) < top_coverage - 0.1);
+    }
+}
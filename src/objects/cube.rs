@@ -3,14 +3,15 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct CubeVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
     VertexTexCoord: [f32; 2],
 }
 
@@ -26,8 +27,6 @@ impl Cube {
 
     pub fn new(display: &impl Facade, size: f32) -> GLResult<Cube> {
 
-        glium::implement_vertex!(CubeVertex, VertexPosition, VertexNormal, VertexTexCoord);
-
         let vertices = Cube::generate_vertices(size);
         let indices  = Cube::generate_indices();
 
@@ -46,35 +45,35 @@ impl Cube {
 
         [
             // Front
-            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [0.0, 0.0, 1.0], VertexTexCoord: [0.0, 1.0] },
             // Right
-            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [1.0, 0.0, 0.0], VertexTexCoord: [0.0, 1.0] },
             // Back
-            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [0.0, 0.0, -1.0], VertexTexCoord: [0.0, 1.0] },
             // Left
-            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [-1.0, 0.0, 0.0], VertexTexCoord: [0.0, 1.0] },
             // Bottom
-            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [-radius, -radius,  radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [-radius, -radius, -radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius, -radius, -radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [ radius, -radius,  radius], VertexNormal: [0.0, -1.0, 0.0], VertexTexCoord: [0.0, 1.0] },
             // Top
-            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [0.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [1.0, 0.0], ..Default::default() },
-            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [1.0, 1.0], ..Default::default() },
-            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [0.0, 1.0], ..Default::default() },
+            CubeVertex { VertexPosition: [-radius,  radius,  radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [0.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius,  radius,  radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [1.0, 0.0] },
+            CubeVertex { VertexPosition: [ radius,  radius, -radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [1.0, 1.0] },
+            CubeVertex { VertexPosition: [-radius,  radius, -radius], VertexNormal: [0.0, 1.0, 0.0], VertexTexCoord: [0.0, 1.0] },
         ]
     }
 
@@ -3,14 +3,15 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
-use crate::{Vec3F, Mat4F};
+use crate::vertex::Vertex;
+use crate::{Vec3F, Vec4F, Mat4F};
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct FrustumVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
+    VertexPosition: [f32; 3],
 }
 
 #[derive(Debug)]
@@ -36,8 +37,6 @@ impl Frustum {
 
     pub fn new(display: &impl Facade) -> GLResult<Frustum> {
 
-        glium::implement_vertex!(FrustumVertex, VertexPosition);
-
         let vbuffer = glium::VertexBuffer::empty_immutable(display, 9)
             .map_err(BufferCreationErrorKind::Vertex)?;
         let ibuffer = glium::IndexBuffer::empty_immutable(display, glium::index::PrimitiveType::LinesList, 24)
@@ -77,17 +76,17 @@ impl Frustum {
         let fdx = ar * fdy;
 
         let vertices = [
-            FrustumVertex { VertexPosition: [0.0, 0.0, 0.0], ..Default::default() },
+            FrustumVertex { VertexPosition: [0.0, 0.0, 0.0] },
 
-            FrustumVertex { VertexPosition: [ dx,  dy, -near], ..Default::default() },
-            FrustumVertex { VertexPosition: [-dx,  dy, -near], ..Default::default() },
-            FrustumVertex { VertexPosition: [-dx, -dy, -near], ..Default::default() },
-            FrustumVertex { VertexPosition: [ dx, -dy, -near], ..Default::default() },
+            FrustumVertex { VertexPosition: [ dx,  dy, -near] },
+            FrustumVertex { VertexPosition: [-dx,  dy, -near] },
+            FrustumVertex { VertexPosition: [-dx, -dy, -near] },
+            FrustumVertex { VertexPosition: [ dx, -dy, -near] },
             
-            FrustumVertex { VertexPosition: [ fdx,  fdy, -far], ..Default::default() },
-            FrustumVertex { VertexPosition: [-fdx,  fdy, -far], ..Default::default() },
-            FrustumVertex { VertexPosition: [-fdx, -fdy, -far], ..Default::default() },
-            FrustumVertex { VertexPosition: [ fdx, -fdy, -far], ..Default::default() },
+            FrustumVertex { VertexPosition: [ fdx,  fdy, -far] },
+            FrustumVertex { VertexPosition: [-fdx,  fdy, -far] },
+            FrustumVertex { VertexPosition: [-fdx, -fdy, -far] },
+            FrustumVertex { VertexPosition: [ fdx, -fdy, -far] },
         ];
 
         let indices = [
@@ -131,6 +130,40 @@ impl Frustum {
     pub fn get_origin(&self) -> Vec3F {
         self.center
     }
+
+    pub fn get_near(&self) -> f32 {
+        self.near
+    }
+
+    pub fn get_far(&self) -> f32 {
+        self.far
+    }
+
+    /// The 8 corners of the part of frustum between distance `near` and `far`, in world coords.
+    ///
+    /// The 4 corners on the near plane come first, and both planes are ordered like the vertices of the frustum mesh.
+    pub fn get_corners(&self, near: f32, far: f32) -> [Vec3F; 8] {
+
+        let tan_half_fovy = (self.fovy.to_radians() / 2.0).tan();
+        let inverse_view = self.get_inverse_view_matrix();
+
+        let mut corners = [Vec3F::zero(); 8];
+        for (i, &distance) in [near, far].iter().enumerate() {
+            let dy = distance * tan_half_fovy;
+            let dx = self.ar * dy;
+
+            let plane = [
+                Vec4F::new( dx,  dy, -distance, 1.0),
+                Vec4F::new(-dx,  dy, -distance, 1.0),
+                Vec4F::new(-dx, -dy, -distance, 1.0),
+                Vec4F::new( dx, -dy, -distance, 1.0),
+            ];
+            for (j, corner) in plane.iter().enumerate() {
+                corners[i * 4 + j] = Vec3F::from(inverse_view * *corner);
+            }
+        }
+        corners
+    }
 }
 
 impl TriangleMesh for Frustum {
@@ -4,14 +4,15 @@ use glium::{Surface, Program, DrawParameters};
 use glium::uniforms::Uniforms;
 
 use crate::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+use crate::vertex::{Vertex, verify_vertex_once};
 use crate::drawable::Drawable;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct GridVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
+    VertexPosition: [f32; 3],
 }
 
 #[derive(Debug)]
@@ -24,8 +25,6 @@ impl Grid {
 
     pub fn new(display: &impl Facade, size: f32, n_division: usize) -> GLResult<Grid> {
 
-        glium::implement_vertex!(GridVertex, VertexPosition);
-
         let vertices = Grid::generate_vertex(size, n_division);
 
         let vbuffer = glium::VertexBuffer::immutable(display, &vertices)
@@ -46,15 +45,15 @@ impl Grid {
         for row in 0..=n_divisions {
             let z = (row as f32 * division_size) - size2;
 
-            vertices.push(GridVertex { VertexPosition: [-size2, 0.0, z], ..Default::default() });
-            vertices.push(GridVertex { VertexPosition: [ size2, 0.0, z], ..Default::default() });
+            vertices.push(GridVertex { VertexPosition: [-size2, 0.0, z] });
+            vertices.push(GridVertex { VertexPosition: [ size2, 0.0, z] });
         }
 
         for col in 0..=n_divisions {
             let x = (col as f32 * division_size) - size2;
 
-            vertices.push(GridVertex { VertexPosition: [x, 0.0, -size2], ..Default::default() });
-            vertices.push(GridVertex { VertexPosition: [x, 0.0,  size2], ..Default::default() });
+            vertices.push(GridVertex { VertexPosition: [x, 0.0, -size2] });
+            vertices.push(GridVertex { VertexPosition: [x, 0.0,  size2] });
         }
 
         debug_assert_eq!(vertices.len(), 4 * (n_divisions + 1));
@@ -68,6 +67,7 @@ impl Drawable for Grid {
     fn render(&self, surface: &mut impl Surface, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
 
         let draw_lines = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+        verify_vertex_once::<GridVertex>(program, true)?;
         surface.draw(&self.vbuffer, draw_lines, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
@@ -76,6 +76,7 @@ impl Drawable for Grid {
     fn render_instanced(&self, surface: &mut impl Surface, per_instanced: glium::vertex::PerInstance, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
 
         let draw_lines = glium::index::NoIndices(glium::index::PrimitiveType::LinesList);
+        verify_vertex_once::<GridVertex>(program, false)?;
         surface.draw((&self.vbuffer, per_instanced), &draw_lines, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
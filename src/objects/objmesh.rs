@@ -4,6 +4,7 @@ use glium::backend::Facade;
 use crate::aabb::AABB;
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, GLError, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 use crate::{Vec3F, Vec2F};
 
 use std::path::Path;
@@ -11,11 +12,11 @@ use std::path::Path;
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct ObjVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
-    VertexTexCoord: [f32; 2], _padding3: [f32; 2],
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
+    VertexTexCoord: [f32; 2],
     VertexTangent : [f32; 4],
 }
 
@@ -37,8 +38,6 @@ impl ObjMesh {
 
     pub fn load(display: &impl Facade, path: impl AsRef<Path>, config: ObjMeshConfiguration) -> GLResult<ObjMesh> {
 
-        glium::implement_vertex!(ObjVertex, VertexPosition, VertexNormal, VertexTexCoord, VertexTangent);
-
         let meshes = ObjMeshData::load(path, &config)?;
 
         let vbuffer = glium::VertexBuffer::immutable(display, &meshes.vertices)
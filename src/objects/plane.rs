@@ -3,15 +3,16 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct PlaneVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
-    VertexTexCoord: [f32; 2], _padding3: [f32; 2],
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
+    VertexTexCoord: [f32; 2],
     VertexTangent : [f32; 4],
 }
 
@@ -27,8 +28,6 @@ impl Plane {
 
     pub fn new(display: &impl Facade, x_size: f32, z_size: f32, x_divs: usize, z_divs: usize, s_max: f32, t_max: f32) -> GLResult<Plane> {
 
-        glium::implement_vertex!(PlaneVertex, VertexPosition, VertexNormal, VertexTexCoord, VertexTangent);
-
         let vertices = Plane::generate_vertices(x_size, z_size, x_divs, z_divs, s_max, t_max);
         let indices  = Plane::generate_indices(x_divs, z_divs);
 
@@ -65,7 +64,6 @@ impl Plane {
                     VertexNormal  : [0.0, 1.0, 0.0],
                     VertexTexCoord: [j as f32 * tex_i, (z_divs - i) as f32 * tex_j],
                     VertexTangent : [1.0, 0.0, 0.0, 1.0],
-                    ..Default::default()
                 };
                 vertices.push(vertex);
             }
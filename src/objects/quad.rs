@@ -5,14 +5,15 @@ use glium::uniforms::Uniforms;
 
 use crate::drawable::Drawable;
 use crate::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+use crate::vertex::{Vertex, verify_vertex_once};
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct QuadVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
     VertexTexCoord: [f32; 2],
 }
 
@@ -30,8 +31,6 @@ impl Quad {
 
     pub fn new_with_texcoord_scale(display: &impl Facade, scale: f32) -> GLResult<Quad> {
 
-        glium::implement_vertex!(QuadVertex, VertexPosition, VertexNormal, VertexTexCoord);
-
         let vertices = [
             QuadVertex { VertexPosition: [-1.0, -1.0, 0.0], VertexTexCoord: [0.0 * scale, 0.0 * scale], ..Default::default() },
             QuadVertex { VertexPosition: [ 1.0, -1.0, 0.0], VertexTexCoord: [1.0 * scale, 0.0 * scale], ..Default::default() },
@@ -53,6 +52,7 @@ impl Drawable for Quad {
 
     fn render(&self, surface: &mut impl Surface, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
         let no_indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
+        verify_vertex_once::<QuadVertex>(program, true)?;
         surface.draw(&self.vbuffer, no_indices, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
@@ -60,6 +60,7 @@ impl Drawable for Quad {
 
     fn render_instanced(&self, surface: &mut impl Surface, per_instanced: glium::vertex::PerInstance, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
         let no_indices = glium::index::NoIndices(glium::index::PrimitiveType::TrianglesList);
+        verify_vertex_once::<QuadVertex>(program, false)?;
         surface.draw((&self.vbuffer, per_instanced), &no_indices, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
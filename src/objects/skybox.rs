@@ -3,11 +3,12 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct SkyBoxVertex {
     VertexPosition: [f32; 3],
 }
@@ -24,8 +25,6 @@ impl SkyBox {
 
     pub fn new(display: &impl Facade, size: f32) -> GLResult<SkyBox> {
 
-        glium::implement_vertex!(SkyBoxVertex, VertexPosition);
-
         let vertices = SkyBox::generate_vertices(size);
         let indices  = SkyBox::generate_indices();
 
@@ -3,14 +3,15 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct SphereVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
     VertexTexCoord: [f32; 2],
 }
 
@@ -26,8 +27,6 @@ impl Sphere {
 
     pub fn new(display: &impl Facade, radius: f32, slice_count: usize, stack_count: usize) -> GLResult<Sphere> {
 
-        glium::implement_vertex!(SphereVertex, VertexPosition, VertexNormal, VertexTexCoord);
-
         let vertices = Sphere::generate_vertices(radius, slice_count, stack_count);
         let indices  = Sphere::generate_indices(slice_count, stack_count);
 
@@ -63,7 +62,7 @@ impl Sphere {
                 let vertex = SphereVertex {
                     VertexPosition: [radius * nx, radius * ny, radius * nz],
                     VertexNormal  : [nx, ny, nz],
-                    VertexTexCoord: [s, t], ..Default::default()
+                    VertexTexCoord: [s, t],
                 };
                 vertices.push(vertex);
             }
@@ -3,15 +3,16 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 use crate::{Vec3F, Vec4F, Mat3F, Mat4F};
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct TeapotVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
     VertexTexCoord: [f32; 2],
 }
 
@@ -27,8 +28,6 @@ impl Teapot {
 
     pub fn new(display: &impl Facade, grid: usize, lid_transform: Mat4F) -> GLResult<Teapot> {
 
-        glium::implement_vertex!(TeapotVertex, VertexPosition, VertexNormal, VertexTexCoord);
-
         let (mut vertices, indices) = Teapot::generate_patches(grid);
         Teapot::move_lid(grid, &mut vertices, &lid_transform);
 
@@ -187,7 +186,6 @@ impl Teapot {
                 VertexPosition: pt.into_array(),
                 VertexNormal  : norm.into_array(),
                 VertexTexCoord: [i as f32 * tc_factor, j as f32 * tc_factor],
-                ..Default::default()
             };
             vertices.push(vertex);
         }
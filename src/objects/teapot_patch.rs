@@ -5,12 +5,13 @@ use glium::uniforms::Uniforms;
 
 use crate::drawable::Drawable;
 use crate::error::{GLResult, GLErrorKind, BufferCreationErrorKind};
+use crate::vertex::{Vertex, verify_vertex_once};
 use crate::{Vec3F, Mat3F};
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct TeapotPatchVertex {
     VertexPosition: [f32; 3],
 }
@@ -25,8 +26,6 @@ impl TeapotPatch {
 
     pub fn new(display: &impl Facade) -> GLResult<TeapotPatch> {
 
-        glium::implement_vertex!(TeapotPatchVertex, VertexPosition);
-
         let vertices = TeapotPatch::generate_patches();
 
         let vbuffer = glium::VertexBuffer::immutable(display, &vertices)
@@ -140,6 +139,7 @@ impl Drawable for TeapotPatch {
 
         let draw_patches = glium::index::NoIndices(glium::index::PrimitiveType::Patches { vertices_per_patch: 16 });
 
+        verify_vertex_once::<TeapotPatchVertex>(program, true)?;
         surface.draw(&self.vbuffer, draw_patches, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
@@ -148,6 +148,7 @@ impl Drawable for TeapotPatch {
     fn render_instanced(&self, surface: &mut impl Surface, per_instanced: glium::vertex::PerInstance, program: &Program, params: &DrawParameters, uniform: &impl Uniforms) -> GLResult<()> {
 
         let draw_patches = glium::index::NoIndices(glium::index::PrimitiveType::Patches { vertices_per_patch: 16 });
+        verify_vertex_once::<TeapotPatchVertex>(program, false)?;
         surface.draw((&self.vbuffer, per_instanced), draw_patches, program, uniform, params)
             .map_err(GLErrorKind::DrawError)?;
         Ok(())
@@ -3,15 +3,16 @@ use glium::backend::Facade;
 
 use crate::drawable::TriangleMesh;
 use crate::error::{GLResult, BufferCreationErrorKind};
+use crate::vertex::Vertex;
 use crate::Vec3F;
 
 
 #[allow(non_snake_case)]
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Vertex)]
 pub struct TorusVertex {
-    VertexPosition: [f32; 3], _padding1: f32,
-    VertexNormal  : [f32; 3], _padding2: f32,
+    VertexPosition: [f32; 3],
+    VertexNormal  : [f32; 3],
     VertexTexCoord: [f32; 2],
 }
 
@@ -27,8 +28,6 @@ impl Torus {
 
     pub fn new(display: &impl Facade, outer_raidus: f32, inner_radius: f32, n_sides: usize, n_rings: usize) -> GLResult<Torus> {
 
-        glium::implement_vertex!(TorusVertex, VertexPosition, VertexNormal, VertexTexCoord);
-
         let vertices = Torus::generate_vertices(outer_raidus, inner_radius, n_sides, n_rings);
         let indices  = Torus::generate_indices(n_sides, n_rings);
 
@@ -70,7 +69,6 @@ impl Torus {
                     VertexNormal: Vec3F::new(cos_v * cos_u * r, cos_v * sin_u * r, sin_v * r)
                         .normalized().into_array(),
                     VertexTexCoord: [u / TWO_PI, v / TWO_PI],
-                    ..Default::default()
                 };
                 vertices.push(vertex);
             }
@@ -0,0 +1,308 @@
+
+//! Chain of full-screen post-processing passes.
+//!
+//! A scene declares its passes in order, each pass reads the scene image or the outputs of earlier passes,
+//! and the last pass renders into the surface given to `PostProcessChain::run`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::postprocess::{PostProcessChain, PassInput, stages};
+//!
+//! let mut passes = stages::bloom(display, "Bloom", PassInput::Source, 1.7, 25.0, 0.125)?;
+//! passes.push(stages::bloom_composite(display, "Composite", PassInput::Source, PassInput::pass("Bloom"), 0.35, 0.928)?);
+//! let chain = PostProcessChain::new(display, width, height, passes)?;
+//!
+//! chain.run(&hdr_texture, frame, |pass, uniforms| {
+//!     if pass == "Composite" { uniforms.add("AveLum", ave_lum); }
+//! })?;
+//! ```
+//!
+//! The intermediate targets are allocated by the chain. A target is reused once the output it holds is not read by
+//! any later pass, so a sequence of passes with the same format and scale ping-pongs between two targets.
+
+pub mod stages;
+pub mod luminance;
+
+use crate::framebuffer::{ColorAttachment, GLFrameBuffer};
+use crate::uniforms::{DynamicUniforms, IntoUniformValue};
+use crate::objects::Quad;
+use crate::error::{GLResult, GLError};
+use crate::Drawable;
+
+use glium::texture::texture2d::Texture2d;
+use glium::texture::UncompressedFloatFormat;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter, SamplerWrapFunction};
+use glium::backend::{Facade, Context};
+use glium::{Program, Surface};
+
+use std::rc::Rc;
+
+
+/// The texture read by a pass.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PassInput {
+    /// The texture given to `PostProcessChain::run`, usually the rendered scene.
+    Source,
+    /// The output of an earlier pass with this name.
+    Pass(String),
+}
+
+impl PassInput {
+
+    pub fn pass(name: impl Into<String>) -> PassInput {
+        PassInput::Pass(name.into())
+    }
+}
+
+/// A full-screen pass of `PostProcessChain`.
+pub struct PostPass {
+    name: String,
+    program: Program,
+    /// (sampler uniform, input)
+    inputs: Vec<(String, PassInput)>,
+    uniforms: DynamicUniforms<'static>,
+    format: UncompressedFloatFormat,
+    scale: f32,
+    filter: MagnifySamplerFilter,
+}
+
+impl PostPass {
+
+    /// A pass drawing a full-screen `Quad` with `program`, whose output is RGBA8 with the same size as the chain.
+    pub fn new(name: impl Into<String>, program: Program) -> PostPass {
+        PostPass {
+            name: name.into(),
+            program,
+            inputs: Vec::new(),
+            uniforms: DynamicUniforms::new(),
+            format: UncompressedFloatFormat::U8U8U8U8,
+            scale: 1.0,
+            filter: MagnifySamplerFilter::Nearest,
+        }
+    }
+
+    /// Bind `input` to the sampler uniform named `sampler`.
+    pub fn with_input(mut self, sampler: impl Into<String>, input: PassInput) -> PostPass {
+        self.inputs.push((sampler.into(), input));
+        self
+    }
+
+    /// Set a uniform which keeps the same value in every frame.
+    pub fn with_uniform(mut self, name: impl Into<String>, value: impl IntoUniformValue<'static>) -> PostPass {
+        self.uniforms.add(name, value);
+        self
+    }
+
+    /// Set the elements of array uniform which keeps the same value in every frame.
+    pub fn with_uniform_array<V>(mut self, name: &str, values: impl IntoIterator<Item = V>) -> PostPass
+        where
+            V: IntoUniformValue<'static> {
+        self.uniforms.add_array(name, values);
+        self
+    }
+
+    /// The format of output texture, which is ignored by the last pass.
+    pub fn with_format(mut self, format: UncompressedFloatFormat) -> PostPass {
+        self.format = format;
+        self
+    }
+
+    /// The size of output texture relative to the chain(e.g. 0.5 for half resolution), which is ignored by the last pass.
+    pub fn with_scale(mut self, scale: f32) -> PostPass {
+        self.scale = scale;
+        self
+    }
+
+    /// The filter used to sample the inputs, use `Linear` when the inputs have different size from the output.
+    pub fn with_filter(mut self, filter: MagnifySamplerFilter) -> PostPass {
+        self.filter = filter;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+}
+
+/// The intermediate target shared by the passes with the same format and scale.
+struct PassTarget {
+    format: UncompressedFloatFormat,
+    scale: f32,
+    fbo: GLFrameBuffer<ColorAttachment>,
+}
+
+/// A sequence of `PostPass`, which owns the intermediate targets between them.
+pub struct PostProcessChain {
+    passes: Vec<PostPass>,
+    /// The target index of each input of each pass, `None` for `PassInput::Source`.
+    inputs: Vec<Vec<Option<usize>>>,
+    /// The target index written by each pass, except the last one which renders into the surface given to `run`.
+    outputs: Vec<usize>,
+    targets: Vec<PassTarget>,
+    quad: Quad,
+    dimensions: (u32, u32),
+    context: Rc<Context>,
+}
+
+impl PostProcessChain {
+
+    /// Check the inputs of `passes` and allocate the intermediate targets for the chain of size `width` x `height`.
+    pub fn new(display: &impl Facade, width: u32, height: u32, passes: Vec<PostPass>) -> GLResult<PostProcessChain> {
+
+        if passes.is_empty() {
+            return Err(GLError::args("Post-process chain must have at least one pass."))
+        }
+
+        // Find the pass written to each input, which must be declared before the pass reading it.
+        let mut producers: Vec<Vec<Option<usize>>> = Vec::with_capacity(passes.len());
+        for (i, pass) in passes.iter().enumerate() {
+            if passes[..i].iter().any(|previous| previous.name == pass.name) {
+                return Err(GLError::args(format!("Post-process pass '{}' is declared more than once.", pass.name)))
+            }
+
+            let pass_inputs = pass.inputs.iter().map(|(_, input)| {
+                match input {
+                    | PassInput::Source => Ok(None),
+                    | PassInput::Pass(name) => passes[..i].iter()
+                        .position(|previous| previous.name == *name)
+                        .map(Some)
+                        .ok_or_else(|| GLError::args(format!("Post-process pass '{}' reads '{}', which is not an earlier pass.", pass.name, name))),
+                }
+            }).collect::<GLResult<Vec<_>>>()?;
+            producers.push(pass_inputs);
+        }
+
+        // The index of the last pass reading the output of each pass.
+        let mut last_reads: Vec<usize> = (0..passes.len()).collect();
+        for (i, pass_inputs) in producers.iter().enumerate() {
+            for producer in pass_inputs.iter().flatten() {
+                last_reads[*producer] = i;
+            }
+        }
+
+        // Allocate the targets greedily, a target is free once the output it holds has been read for the last time.
+        let last_pass = passes.len() - 1;
+        let mut targets: Vec<PassTarget> = Vec::new();
+        let mut holders: Vec<usize> = Vec::new();
+        let mut outputs = Vec::with_capacity(last_pass);
+
+        for (i, pass) in passes[..last_pass].iter().enumerate() {
+            let free_target = targets.iter().zip(holders.iter())
+                .position(|(target, holder)| target.format == pass.format && target.scale == pass.scale && last_reads[*holder] < i);
+
+            let target_index = match free_target {
+                | Some(index) => index,
+                | None => {
+                    let (target_width, target_height) = scaled_dimensions(width, height, pass.scale);
+                    let fbo = GLFrameBuffer::setup(display, target_width, target_height, pass.format)?;
+                    targets.push(PassTarget { format: pass.format, scale: pass.scale, fbo });
+                    holders.push(i);
+                    targets.len() - 1
+                },
+            };
+            holders[target_index] = i;
+            outputs.push(target_index);
+        }
+
+        let inputs = producers.into_iter()
+            .map(|pass_inputs| pass_inputs.into_iter().map(|producer| producer.map(|index| outputs[index])).collect())
+            .collect();
+
+        let chain = PostProcessChain {
+            passes, inputs, outputs, targets,
+            quad: Quad::new(display)?,
+            dimensions: (width, height),
+            context: display.get_context().clone(),
+        };
+        Ok(chain)
+    }
+
+    /// Recreate the intermediate targets for the new size of chain.
+    pub fn resize(&mut self, width: u32, height: u32) -> GLResult<()> {
+
+        for target in self.targets.iter_mut() {
+            let (target_width, target_height) = scaled_dimensions(width, height, target.scale);
+            target.fbo = GLFrameBuffer::setup(&self.context, target_width, target_height, target.format)?;
+        }
+        self.dimensions = (width, height);
+        Ok(())
+    }
+
+    /// Run all the passes, reading `source` as `PassInput::Source` and rendering the last pass into `target`.
+    ///
+    /// `per_pass` is called before each pass with its name, to set the uniforms which change between frames.
+    pub fn run<'a, F>(&'a self, source: &'a Texture2d, target: &mut impl Surface, mut per_pass: F) -> GLResult<()>
+        where
+            F: FnMut(&str, &mut DynamicUniforms<'a>) {
+
+        let last_pass = self.passes.len() - 1;
+
+        for (i, (pass, pass_inputs)) in self.passes.iter().zip(self.inputs.iter()).enumerate() {
+
+            let mut uniforms: DynamicUniforms<'a> = pass.uniforms.clone();
+            for ((sampler, _), input) in pass.inputs.iter().zip(pass_inputs.iter()) {
+                let texture = match input {
+                    | Some(target_index) => &self.targets[*target_index].fbo.attachment.color,
+                    | None => source,
+                };
+                uniforms.add(sampler.as_str(), sample_texture(texture, pass.filter));
+            }
+            per_pass(&pass.name, &mut uniforms);
+
+            if i == last_pass {
+                self.quad.render(target, &pass.program, &Default::default(), &uniforms)?;
+            } else {
+                let mut framebuffer = self.targets[self.outputs[i]].fbo.framebuffer()?;
+                framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+                self.quad.render(&mut framebuffer, &pass.program, &Default::default(), &uniforms)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The output texture of pass named `name`, which is only valid until another pass reuses its target.
+    ///
+    /// The last pass has no output texture, since it renders into the surface given to `run`.
+    pub fn output(&self, name: &str) -> Option<&Texture2d> {
+        self.passes.iter()
+            .position(|pass| pass.name == name)
+            .and_then(|index| self.outputs.get(index))
+            .map(|target_index| &self.targets[*target_index].fbo.attachment.color)
+    }
+
+    pub fn passes(&self) -> &[PostPass] {
+        &self.passes
+    }
+
+    /// The number of intermediate targets allocated by the chain.
+    pub fn target_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+}
+
+fn scaled_dimensions(width: u32, height: u32, scale: f32) -> (u32, u32) {
+    let scaled_width  = ((width  as f32 * scale).round() as u32).max(1);
+    let scaled_height = ((height as f32 * scale).round() as u32).max(1);
+    (scaled_width, scaled_height)
+}
+
+fn sample_texture(texture: &Texture2d, filter: MagnifySamplerFilter) -> glium::uniforms::Sampler<'_, Texture2d> {
+
+    let minify_filter = match filter {
+        | MagnifySamplerFilter::Nearest => MinifySamplerFilter::Nearest,
+        | MagnifySamplerFilter::Linear  => MinifySamplerFilter::Linear,
+    };
+
+    texture.sampled()
+        .minify_filter(minify_filter)
+        .magnify_filter(filter)
+        .wrap_function(SamplerWrapFunction::Clamp)
+}
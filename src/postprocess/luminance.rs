@@ -0,0 +1,208 @@
+
+//! Luminance statistics of HDR images computed on GPU, for the tone mapping operators.
+//!
+//! The image is reduced by a sequence of passes, each of which halves the size, until a single texel is left.
+//! Only this texel is read back to CPU, instead of the whole image:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::postprocess::luminance::{LuminanceReduction, EyeAdaptation};
+//!
+//! let reduction = LuminanceReduction::new(display, width, height)?;
+//! let mut adaptation = EyeAdaptation::new(1.5);
+//!
+//! let stats = reduction.compute(&hdr_texture)?;
+//! let ave_lum = adaptation.update(stats.log_average, delta_time);
+//! ```
+
+use crate::framebuffer::{ColorAttachment, GLFrameBuffer};
+use crate::readback::{self, PendingReadback};
+use crate::scene::GLSourceCode;
+use crate::objects::Quad;
+use crate::error::{GLResult, GLError, GLErrorKind};
+use crate::Drawable;
+
+use glium::texture::texture2d::Texture2d;
+use glium::texture::UncompressedFloatFormat;
+use glium::backend::{Facade, Context};
+use glium::{Program, uniform};
+
+use std::rc::Rc;
+
+
+/// The luminance statistics of an HDR image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuminanceStats {
+    /// The log-average luminance, i.e. `exp(mean(log(delta + lum)))`, used as `AveLum` by the tone mapping stages.
+    pub log_average: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl LuminanceStats {
+
+    /// Convert from the texel of the last reduction level.
+    fn from_texel(texel: [f32; 4]) -> LuminanceStats {
+        LuminanceStats {
+            log_average: texel[0].exp(),
+            min: texel[1],
+            max: texel[2],
+        }
+    }
+}
+
+/// Reduce HDR images of a given size to their `LuminanceStats`.
+pub struct LuminanceReduction {
+    program: Program,
+    /// The targets of each pass, whose size is halved until 1x1.
+    levels: Vec<GLFrameBuffer<ColorAttachment>>,
+    quad: Quad,
+    dimensions: (u32, u32),
+    context: Rc<Context>,
+}
+
+impl LuminanceReduction {
+
+    /// Allocate the reduction levels for images of size `width` x `height`.
+    pub fn new(display: &impl Facade, width: u32, height: u32) -> GLResult<LuminanceReduction> {
+
+        let vertex_shader = include_str!("shaders/fullscreen.vert.glsl");
+        let fragment_shader = include_str!("shaders/luminance.frag.glsl");
+        let program = Program::new(display, GLSourceCode::new(vertex_shader, fragment_shader).with_srgb_output(false))
+            .map_err(GLErrorKind::CreateProgram)?;
+
+        let reduction = LuminanceReduction {
+            program,
+            levels: LuminanceReduction::create_levels(display, width, height)?,
+            quad: Quad::new(display)?,
+            dimensions: (width, height),
+            context: display.get_context().clone(),
+        };
+        Ok(reduction)
+    }
+
+    fn create_levels(display: &impl Facade, width: u32, height: u32) -> GLResult<Vec<GLFrameBuffer<ColorAttachment>>> {
+
+        if width == 0 || height == 0 {
+            return Err(GLError::args("The image for luminance reduction must not be empty."))
+        }
+
+        let mut levels = Vec::new();
+        let (mut level_width, mut level_height) = (width, height);
+
+        loop {
+            level_width  = (level_width  + 1) / 2;
+            level_height = (level_height + 1) / 2;
+            levels.push(GLFrameBuffer::setup(display, level_width, level_height, UncompressedFloatFormat::F32F32F32F32)?);
+
+            if level_width == 1 && level_height == 1 {
+                break
+            }
+        }
+
+        Ok(levels)
+    }
+
+    /// Recreate the reduction levels for the new size of images.
+    pub fn resize(&mut self, width: u32, height: u32) -> GLResult<()> {
+        self.levels = LuminanceReduction::create_levels(&self.context, width, height)?;
+        self.dimensions = (width, height);
+        Ok(())
+    }
+
+    /// Run the reduction passes on `source`, leaving the statistics in the last level.
+    fn reduce<'a>(&'a self, source: &'a Texture2d) -> GLResult<&'a Texture2d> {
+
+        if source.dimensions() != self.dimensions {
+            return Err(GLError::args(format!("Luminance reduction is created for {:?}, but the image is {:?}.", self.dimensions, source.dimensions())))
+        }
+
+        let mut input = source;
+        for (i, level) in self.levels.iter().enumerate() {
+            let uniforms = uniform! {
+                InputTex: input,
+                FirstPass: i == 0,
+            };
+
+            let mut framebuffer = level.framebuffer()?;
+            self.quad.render(&mut framebuffer, &self.program, &Default::default(), &uniforms)?;
+            input = &level.attachment.color;
+        }
+
+        Ok(input)
+    }
+
+    /// Compute the statistics of `source`, whose size must be the one given to `new` or `resize`.
+    ///
+    /// The CPU waits for the GPU to finish the rendering of `source`, use `compute_async` to avoid it.
+    pub fn compute(&self, source: &Texture2d) -> GLResult<LuminanceStats> {
+
+        let result = self.reduce(source)?;
+        let texel = readback::read_texture::<[f32; 4]>(result).pixel(0, 0);
+        Ok(LuminanceStats::from_texel(texel))
+    }
+
+    /// Start computing the statistics of `source`, see `PendingLuminance::finish` to get the result.
+    pub fn compute_async(&self, source: &Texture2d) -> GLResult<PendingLuminance> {
+
+        let result = self.reduce(source)?;
+        let readback = readback::read_texture_async(&self.context, result);
+        Ok(PendingLuminance { readback })
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+}
+
+/// The statistics being computed by `LuminanceReduction::compute_async`.
+pub struct PendingLuminance {
+    readback: PendingReadback<[f32; 4]>,
+}
+
+impl PendingLuminance {
+
+    /// Wait for the reduction to finish, usually called one frame later.
+    pub fn finish(self) -> GLResult<LuminanceStats> {
+        let texel = self.readback.finish()?.pixel(0, 0);
+        Ok(LuminanceStats::from_texel(texel))
+    }
+}
+
+/// Temporal adaptation of the luminance, simulating the eye adjusting to the change of brightness.
+#[derive(Debug, Clone, Copy)]
+pub struct EyeAdaptation {
+    /// How fast the luminance approaches the target, in 1/second.
+    speed: f32,
+    luminance: Option<f32>,
+}
+
+impl EyeAdaptation {
+
+    /// A larger `speed` adapts faster, about 63% of the change is adapted after `1 / speed` seconds.
+    pub fn new(speed: f32) -> EyeAdaptation {
+        EyeAdaptation { speed, luminance: None }
+    }
+
+    /// Move the adapted luminance towards `target` by the time elapsed, and return it.
+    ///
+    /// The first update adapts to `target` immediately.
+    pub fn update(&mut self, target: f32, delta_time: f32) -> f32 {
+
+        let adapted = match self.luminance {
+            | Some(current) => current + (target - current) * (1.0 - (-delta_time * self.speed).exp()),
+            | None => target,
+        };
+        self.luminance = Some(adapted);
+        adapted
+    }
+
+    /// The adapted luminance, `None` before the first update.
+    pub fn luminance(&self) -> Option<f32> {
+        self.luminance
+    }
+
+    /// Forget the adapted luminance, e.g. when the scene is switched.
+    pub fn reset(&mut self) {
+        self.luminance = None;
+    }
+}
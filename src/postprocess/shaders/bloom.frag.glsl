@@ -6,14 +6,12 @@ layout (location = 0) in vec2 TexCoord;
 layout (location = 0) out vec4 FragColor;
 
 uniform sampler2D HdrTex;
-uniform sampler2D BlurTex1;
-
-uniform float AveLum;
+uniform sampler2D BloomTex;
 
 // XYZ/RGB conversion matrices from:
 // https://www.brucelindbloom.com/index.html?Eqn_RGB_XYZ_Matrix.html
 
-uniform mat3 rgb2xyz = mat3( 
+uniform mat3 rgb2xyz = mat3(
     0.4124564, 0.2126729, 0.0193339,
     0.3575761, 0.7151522, 0.1191920,
     0.1804375, 0.0721750, 0.9503041
@@ -25,20 +23,15 @@ uniform mat3 xyz2rgb = mat3(
     -0.4985314, 0.0415560, 1.0572252
 );
 
-uniform float Exposure = 0.35;
-uniform float White    = 0.928;
- 
-
-// Composite pass, apply tone map to HDR image, then combine with the blurred bright-pass filter.
-// (Read from BlurTex1 and HdrTex, write to default buffer).
-void main() {
+uniform float AveLum;
+uniform float Exposure;
+uniform float White;
 
-    /////////////// Tone mapping ///////////////
-    // Retrieve high-res color from texture
-    vec4 color = texture(HdrTex, TexCoord);
+// Reinhard operator applied to the luminance in xyY space.
+vec3 toneMap(vec3 color) {
 
     // Convert to XYZ
-    vec3 xyzCol = rgb2xyz * vec3(color);
+    vec3 xyzCol = rgb2xyz * color;
 
     // Convert to xyY
     float xyzSum = xyzCol.x + xyzCol.y + xyzCol.z;
@@ -53,18 +46,16 @@ void main() {
     xyzCol.y = L;
     xyzCol.z = (L * (1 - xyYCol.x - xyYCol.y)) / xyYCol.y;
 
-    // Convert back to RGB and send to output buffer
-    vec4 toneMapColor = vec4(xyz2rgb * xyzCol, 1.0);
+    // Convert back to RGB
+    return xyz2rgb * xyzCol;
+}
+
 
-    ///////////// Combine with blurred texture /////////////
-    // We want linear filtering on this texture access so that
-    // we get additional blurring.
-    vec4 blurTex = texture(BlurTex1, TexCoord);
+// Apply tone map to HDR image, then combine with the blurred bright-pass filter.
+void main() {
+
+    vec4 color = texture(HdrTex, TexCoord);
+    vec4 bloom = texture(BloomTex, TexCoord);
 
-    // ivec2 blurSize = textureSize(BlurTex1, 0);
-    // if(gl_FragCoord.x < blurSize.x && gl_FragCoord.y < blurSize.y)
-    //     FragColor = texture(BlurTex1, vec2(gl_FragCoord.x / blurSize.x,
-    //   gl_FragCoord.y / blurSize.y));
-    // else
-    FragColor = toneMapColor + blurTex;
+    FragColor = vec4(toneMap(color.rgb), 1.0) + bloom;
 }
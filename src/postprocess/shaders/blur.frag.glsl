@@ -0,0 +1,30 @@
+
+#version 410
+
+layout (location = 0) in vec2 TexCoord;
+
+layout (location = 0) out vec4 FragColor;
+
+uniform sampler2D Texture0;
+
+// (1, 0) for the horizontal pass, (0, 1) for the vertical pass.
+uniform vec2 Direction;
+// The number of weights in use, at most 10.
+uniform int Radius;
+// Replaced by constants on macOS, where the uniform array fails to be set.
+uniform float Weight[10];
+
+
+// One direction of separable gaussian blur.
+void main() {
+
+    vec2 offset = Direction / vec2(textureSize(Texture0, 0));
+
+    vec4 sum = texture(Texture0, TexCoord) * Weight[0];
+    for(int i = 1; i < Radius; i++) {
+        sum += texture(Texture0, TexCoord + offset * i) * Weight[i];
+        sum += texture(Texture0, TexCoord - offset * i) * Weight[i];
+    }
+
+    FragColor = sum;
+}
@@ -3,9 +3,9 @@
 
 layout (location = 0) in vec2 TexCoord;
 
-layout (location = 0) out vec3 FragColor;
+layout (location = 0) out vec4 FragColor;
 
-uniform sampler2D HdrTex;
+uniform sampler2D Texture0;
 
 uniform float LumThresh;
 
@@ -13,13 +13,14 @@ float luminance(vec3 color) {
     return 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
 }
 
-// Bright-pass filter (write to BlurTex1)
+
+// Keep the pixels brighter than threshold only.
 void main() {
 
-    vec4 val = texture(HdrTex, TexCoord);
+    vec4 val = texture(Texture0, TexCoord);
     if(luminance(val.rgb) > LumThresh) {
-        FragColor = val.rgb;
+        FragColor = vec4(val.rgb, 1.0);
     } else {
-        FragColor = vec3(0.0);
+        FragColor = vec4(0.0, 0.0, 0.0, 1.0);
     }
 }
@@ -0,0 +1,17 @@
+#version 410
+
+layout (location = 0) in vec2 TexCoord;
+
+layout (location = 0) out vec4 FragColor;
+
+uniform sampler2D Texture0;
+
+uniform float Gamma;
+
+
+// Encode the linear color for display.
+void main() {
+
+    vec3 color = texture(Texture0, TexCoord).rgb;
+    FragColor = vec4(pow(color, vec3(1.0 / Gamma)), 1.0);
+}
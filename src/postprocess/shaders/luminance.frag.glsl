@@ -0,0 +1,52 @@
+#version 410
+
+// Each output texel reduces a 2x2 block of the input into
+// (mean of log luminance, min luminance, max luminance, pixel count).
+layout (location = 0) out vec4 FragStats;
+
+uniform sampler2D InputTex;
+// The first pass reads the HDR colors, and the following passes read the statistics of the previous pass.
+uniform bool FirstPass;
+
+// A small value to avoid log(0) on black pixels.
+const float Delta = 0.00001;
+const vec3 LumCoeffs = vec3(0.2126, 0.7152, 0.0722);
+
+
+void main() {
+
+    ivec2 inputSize = textureSize(InputTex, 0);
+    ivec2 base = ivec2(gl_FragCoord.xy) * 2;
+
+    float logSum = 0.0;
+    float minLum = 3.0e38;
+    float maxLum = 0.0;
+    float count  = 0.0;
+
+    for (int y = 0; y < 2; y++) {
+        for (int x = 0; x < 2; x++) {
+
+            ivec2 coord = base + ivec2(x, y);
+            // The last column or row has no neighbour when the input size is odd.
+            if (any(greaterThanEqual(coord, inputSize))) {
+                continue;
+            }
+
+            vec4 texel = texelFetch(InputTex, coord, 0);
+            if (FirstPass) {
+                float lum = dot(texel.rgb, LumCoeffs);
+                logSum += log(lum + Delta);
+                minLum = min(minLum, lum);
+                maxLum = max(maxLum, lum);
+                count  += 1.0;
+            } else {
+                logSum += texel.r * texel.a;
+                minLum = min(minLum, texel.g);
+                maxLum = max(maxLum, texel.b);
+                count  += texel.a;
+            }
+        }
+    }
+
+    FragStats = vec4(logSum / count, minLum, maxLum, count);
+}
@@ -0,0 +1,181 @@
+
+//! Reusable post-processing passes, built on the shaders under `src/postprocess/shaders`.
+//!
+//! The programs declare sRGB output, so the last pass writes to the default framebuffer without extra conversion.
+//! The intermediate targets use linear formats, which are not affected by this flag.
+
+use super::{PostPass, PassInput};
+
+use crate::scene::GLSourceCode;
+use crate::error::{GLResult, GLError, GLErrorKind};
+
+use glium::backend::Facade;
+use glium::texture::UncompressedFloatFormat;
+use glium::uniforms::MagnifySamplerFilter;
+use glium::Program;
+
+
+/// The maximum number of weights of `gaussian_blur`, limited by the shader.
+pub const MAX_BLUR_RADIUS: usize = 10;
+
+fn compile_stage_program(display: &impl Facade, fragment_shader: &str) -> GLResult<Program> {
+
+    let vertex_shader = include_str!("shaders/fullscreen.vert.glsl");
+    let program = Program::new(display, GLSourceCode::new(vertex_shader, fragment_shader).with_srgb_output(true))
+        .map_err(GLErrorKind::CreateProgram)?;
+    Ok(program)
+}
+
+/// The normalized weights of one side of gaussian kernel with variance `sigma2`, including the center.
+pub fn gaussian_weights(radius: usize, sigma2: f32) -> Vec<f32> {
+
+    let gauss = |x: f32| {
+        let coeff = 1.0 / (2.0 * std::f32::consts::PI * sigma2);
+        let expon = -(x * x) / (2.0 * sigma2);
+        coeff * expon.exp()
+    };
+
+    let mut weights: Vec<f32> = (0..radius).map(|i| gauss(i as f32)).collect();
+
+    // The weights except the center are used on both sides.
+    let sum = weights.iter().skip(1).fold(weights[0], |sum, weight| sum + 2.0 * weight);
+    weights.iter_mut().for_each(|weight| *weight /= sum);
+    weights
+}
+
+/// Separable gaussian blur, as a horizontal pass named `{name}Horizontal` and a vertical pass named `name`.
+///
+/// `radius` is the number of weights on each side including the center, at most `MAX_BLUR_RADIUS`.
+pub fn gaussian_blur(display: &impl Facade, name: &str, input: PassInput, radius: usize, sigma2: f32, format: UncompressedFloatFormat, scale: f32) -> GLResult<Vec<PostPass>> {
+
+    if radius == 0 || radius > MAX_BLUR_RADIUS {
+        return Err(GLError::args(format!("The radius of gaussian blur must be in 1..={}, but {} is given.", MAX_BLUR_RADIUS, radius)))
+    }
+
+    let mut weights = gaussian_weights(radius, sigma2);
+    weights.resize(MAX_BLUR_RADIUS, 0.0);
+
+    let horizontal_name = format!("{}Horizontal", name);
+
+    let horizontal = PostPass::new(horizontal_name.as_str(), compile_blur_program(display, &weights)?)
+        .with_input("Texture0", input)
+        .with_uniform("Direction", [1.0_f32, 0.0])
+        .with_uniform("Radius", radius as i32)
+        .with_format(format)
+        .with_scale(scale)
+        .with_filter(MagnifySamplerFilter::Linear);
+
+    let vertical = PostPass::new(name, compile_blur_program(display, &weights)?)
+        .with_input("Texture0", PassInput::pass(horizontal_name))
+        .with_uniform("Direction", [0.0_f32, 1.0])
+        .with_uniform("Radius", radius as i32)
+        .with_format(format)
+        .with_scale(scale)
+        .with_filter(MagnifySamplerFilter::Linear);
+
+    Ok(vec![with_blur_weights(horizontal, &weights), with_blur_weights(vertical, &weights)])
+}
+
+#[cfg(not(target_os = "macos"))]
+fn compile_blur_program(display: &impl Facade, _weights: &[f32]) -> GLResult<Program> {
+    compile_stage_program(display, include_str!("shaders/blur.frag.glsl"))
+}
+
+#[cfg(not(target_os = "macos"))]
+fn with_blur_weights(pass: PostPass, weights: &[f32]) -> PostPass {
+    pass.with_uniform_array("Weight", weights.to_vec())
+}
+
+// There is a issue when transfering the weights to shader on macOS.
+// See https://github.com/unknownue/GLSLCookbook.rs/issues/5 for detail.
+// Here the weights are compiled into the shader as constants instead.
+#[cfg(target_os = "macos")]
+fn compile_blur_program(display: &impl Facade, weights: &[f32]) -> GLResult<Program> {
+
+    let constants: Vec<String> = weights.iter().map(|weight| format!("{:?}", weight)).collect();
+    let declaration = format!("const float Weight[{0}] = float[{0}]({1});", MAX_BLUR_RADIUS, constants.join(", "));
+    let fragment_shader = include_str!("shaders/blur.frag.glsl")
+        .replace(&format!("uniform float Weight[{}];", MAX_BLUR_RADIUS), &declaration);
+    compile_stage_program(display, &fragment_shader)
+}
+
+#[cfg(target_os = "macos")]
+fn with_blur_weights(pass: PostPass, _weights: &[f32]) -> PostPass {
+    pass
+}
+
+/// Keep the pixels whose luminance is above `threshold`, and black out the others.
+pub fn bright_pass(display: &impl Facade, name: &str, input: PassInput, threshold: f32, format: UncompressedFloatFormat, scale: f32) -> GLResult<PostPass> {
+
+    let program = compile_stage_program(display, include_str!("shaders/brightpass.frag.glsl"))?;
+    let pass = PostPass::new(name, program)
+        .with_input("Texture0", input)
+        .with_uniform("LumThresh", threshold)
+        .with_format(format)
+        .with_scale(scale)
+        .with_filter(MagnifySamplerFilter::Linear);
+    Ok(pass)
+}
+
+/// The bright-pass filter followed by gaussian blur in RGB32F, the blurred result is the output of pass `name`.
+///
+/// `scale` is usually less than 1, since the blurred image needs no detail(e.g. 0.125 for the bloom of chapter 6).
+pub fn bloom(display: &impl Facade, name: &str, input: PassInput, threshold: f32, sigma2: f32, scale: f32) -> GLResult<Vec<PostPass>> {
+
+    let format = UncompressedFloatFormat::F32F32F32;
+    let bright_name = format!("{}BrightPass", name);
+
+    let mut passes = vec![bright_pass(display, &bright_name, input, threshold, format, scale)?];
+    passes.extend(gaussian_blur(display, name, PassInput::pass(bright_name), MAX_BLUR_RADIUS, sigma2, format, scale)?);
+    Ok(passes)
+}
+
+/// Tone map the HDR input with the operator of Reinhard.
+///
+/// The log-average luminance of the input must be set as `AveLum` every frame, see `luminance::LuminanceReduction`.
+pub fn tone_map(display: &impl Facade, name: &str, input: PassInput, exposure: f32, white: f32) -> GLResult<PostPass> {
+
+    let program = compile_stage_program(display, include_str!("shaders/tonemap.frag.glsl"))?;
+    let pass = PostPass::new(name, program)
+        .with_input("HdrTex", input)
+        .with_uniform("Exposure", exposure)
+        .with_uniform("White", white)
+        .with_uniform("AveLum", 1.0_f32);
+    Ok(pass)
+}
+
+/// Tone map the HDR input like `tone_map`, then add the output of `bloom` on it.
+///
+/// The log-average luminance of the input must be set as `AveLum` every frame, see `luminance::LuminanceReduction`.
+pub fn bloom_composite(display: &impl Facade, name: &str, hdr: PassInput, bloom: PassInput, exposure: f32, white: f32) -> GLResult<PostPass> {
+
+    let program = compile_stage_program(display, include_str!("shaders/bloom.frag.glsl"))?;
+    let pass = PostPass::new(name, program)
+        .with_input("HdrTex", hdr)
+        .with_input("BloomTex", bloom)
+        .with_uniform("Exposure", exposure)
+        .with_uniform("White", white)
+        .with_uniform("AveLum", 1.0_f32)
+        .with_filter(MagnifySamplerFilter::Linear);
+    Ok(pass)
+}
+
+/// Encode the linear input with `pow(color, 1 / gamma)`, usually as the last pass before display.
+pub fn gamma_correction(display: &impl Facade, name: &str, input: PassInput, gamma: f32) -> GLResult<PostPass> {
+
+    let program = compile_stage_program(display, include_str!("shaders/gamma.frag.glsl"))?;
+    let pass = PostPass::new(name, program)
+        .with_input("Texture0", input)
+        .with_uniform("Gamma", gamma);
+    Ok(pass)
+}
+
+/// Sobel edge detection, the pixels whose squared gradient of luminance is above `threshold` become white.
+pub fn edge_detection(display: &impl Facade, name: &str, input: PassInput, threshold: f32) -> GLResult<PostPass> {
+
+    let program = compile_stage_program(display, include_str!("shaders/edge.frag.glsl"))?;
+    let pass = PostPass::new(name, program)
+        .with_input("RenderTex", input)
+        .with_uniform("EdgeThreshold", threshold);
+    Ok(pass)
+}
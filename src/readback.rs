@@ -0,0 +1,359 @@
+
+//! Read the pixels of textures and framebuffers back to CPU.
+//!
+//! OpenGL returns the rows from bottom to top, while the images here store them from top to bottom,
+//! which is the order of most image files:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::readback;
+//!
+//! let image = readback::read_texture::<[u8; 4]>(&fbo.attachment.color);
+//! readback::write_png("screenshot.png", &image)?;
+//!
+//! // Start the copy now, and collect the pixels some frames later without stalling the pipeline.
+//! let pending = readback::read_texture_async::<[f32; 3]>(display, &hdr_fbo.attachment.color);
+//! let hdr_image = pending.finish()?;
+//! readback::write_hdr("frame.hdr", &hdr_image)?;
+//! ```
+
+use crate::framebuffer::{ColorAttachment, ColorDepthAttachment, MultisampleColorAttachment, MultisampleColorDepthAttachment, GLFrameBuffer};
+use crate::framebuffer::{ColorDepthStencilAttachment, ColorDepthStencilTextureAttachment};
+use crate::objects::Quad;
+use crate::scene::GLSourceCode;
+use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::Drawable;
+
+use glium::texture::texture2d::Texture2d;
+use glium::texture::depth_texture2d::DepthTexture2d;
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::{RawImage2d, PixelValue, MipmapsOption, UncompressedFloatFormat};
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::{MagnifySamplerFilter, MinifySamplerFilter};
+use glium::backend::Facade;
+use glium::{Program, SyncFence, uniform};
+
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::fs::File;
+
+
+/// A pixel type which can be read back from OpenGL.
+pub trait ReadbackPixel: Copy + Default {
+    /// The pixel type transferred by OpenGL.
+    type GLPixel: PixelValue + Copy;
+    fn from_gl_pixel(pixel: Self::GLPixel) -> Self;
+}
+
+impl ReadbackPixel for [u8; 4] {
+    type GLPixel = (u8, u8, u8, u8);
+    fn from_gl_pixel(pixel: (u8, u8, u8, u8)) -> [u8; 4] { [pixel.0, pixel.1, pixel.2, pixel.3] }
+}
+
+impl ReadbackPixel for [f32; 3] {
+    type GLPixel = (f32, f32, f32);
+    fn from_gl_pixel(pixel: (f32, f32, f32)) -> [f32; 3] { [pixel.0, pixel.1, pixel.2] }
+}
+
+impl ReadbackPixel for [f32; 4] {
+    type GLPixel = (f32, f32, f32, f32);
+    fn from_gl_pixel(pixel: (f32, f32, f32, f32)) -> [f32; 4] { [pixel.0, pixel.1, pixel.2, pixel.3] }
+}
+
+impl ReadbackPixel for f32 {
+    type GLPixel = f32;
+    fn from_gl_pixel(pixel: f32) -> f32 { pixel }
+}
+
+/// An image in CPU memory, whose rows are stored from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuImage<P> {
+    width : u32,
+    height: u32,
+    pixels: Vec<P>,
+}
+
+/// 8-bit RGBA image, usually read from LDR textures or the default framebuffer.
+pub type RgbaImage = CpuImage<[u8; 4]>;
+/// Floating-point RGB image, usually read from HDR textures.
+pub type RgbImageF = CpuImage<[f32; 3]>;
+/// Floating-point RGBA image.
+pub type RgbaImageF = CpuImage<[f32; 4]>;
+/// Depth values in [0, 1].
+pub type DepthImage = CpuImage<f32>;
+
+impl<P: Copy> CpuImage<P> {
+
+    /// `pixels` must contain `width * height` pixels, with rows from top to bottom.
+    pub fn new(width: u32, height: u32, pixels: Vec<P>) -> GLResult<CpuImage<P>> {
+
+        if pixels.len() != width as usize * height as usize {
+            return Err(GLError::args(format!("Image of {}x{} requires {} pixels, but {} pixels are given.", width, height, width * height, pixels.len())))
+        }
+
+        let image = CpuImage { width, height, pixels };
+        Ok(image)
+    }
+
+    /// Build the image from the rows returned by OpenGL, which are ordered from bottom to top.
+    fn from_gl_rows<G>(gl_rows: Vec<Vec<G>>, convert: impl Fn(G) -> P) -> CpuImage<P>
+        where
+            G: Copy {
+
+        let height = gl_rows.len() as u32;
+        let width = gl_rows.first().map_or(0, |row| row.len()) as u32;
+        let pixels = gl_rows.into_iter().rev()
+            .flat_map(|row| row.into_iter())
+            .map(convert)
+            .collect();
+
+        CpuImage { width, height, pixels }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The pixel at column `x` and row `y`, where row 0 is the top of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> P {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn pixels(&self) -> &[P] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<P> {
+        self.pixels
+    }
+
+    /// Iterate over the rows from top to bottom.
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[P]> {
+        self.pixels.chunks(self.width.max(1) as usize)
+    }
+
+    /// Reverse the order of rows, e.g. to get the bottom-to-top order of OpenGL.
+    pub fn flipped_vertically(&self) -> CpuImage<P> {
+
+        let pixels = self.rows().rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect();
+        CpuImage { width: self.width, height: self.height, pixels }
+    }
+}
+
+
+// Synchronous readback ----------------------------------------------------------------------------
+/// Read the main level of `texture`, converting the pixels to `P`.
+///
+/// `[u8; 4]` can be read from any color texture, while the floating-point pixels require a context supporting them,
+/// which is always true for the desktop OpenGL used by the recipes.
+pub fn read_texture<P: ReadbackPixel>(texture: &Texture2d) -> CpuImage<P> {
+
+    // Reading in formats other than RGBA8 is not guaranteed by OpenGL ES, thus `unchecked_read` is unsafe in glium.
+    let gl_rows: Vec<Vec<P::GLPixel>> = unsafe { texture.unchecked_read() };
+    CpuImage::from_gl_rows(gl_rows, P::from_gl_pixel)
+}
+
+/// Read the depth of `texture`.
+///
+/// Depth textures can not be read directly in glium, so the depth is copied to a floating-point texture first.
+pub fn read_depth_texture(display: &impl Facade, texture: &DepthTexture2d) -> GLResult<DepthImage> {
+
+    let (width, height) = texture.dimensions();
+    let depth_copy = Texture2d::empty_with_format(display, UncompressedFloatFormat::F32, MipmapsOption::NoMipmap, width, height)
+        .map_err(GLErrorKind::CreateTexture)?;
+
+    {
+        let mut framebuffer = SimpleFrameBuffer::new(display, &depth_copy)
+            .map_err(BufferCreationErrorKind::FrameBuffer)?;
+        let program = Program::new(display, GLSourceCode::new(DEPTH_COPY_VERTEX, DEPTH_COPY_FRAGMENT))
+            .map_err(GLErrorKind::CreateProgram)?;
+        let quad = Quad::new(display)?;
+
+        let uniforms = uniform! {
+            DepthTex: texture.sampled()
+                .minify_filter(MinifySamplerFilter::Nearest)
+                .magnify_filter(MagnifySamplerFilter::Nearest)
+                .depth_texture_comparison(None),
+        };
+        quad.render(&mut framebuffer, &program, &Default::default(), &uniforms)?;
+    }
+
+    Ok(read_texture::<f32>(&depth_copy))
+}
+
+/// Attachment whose color can be read back, see `read_framebuffer`.
+pub trait ReadableAttachment {
+    /// The single-sample color texture holding the rendered image.
+    fn color_texture(&self) -> &Texture2d;
+}
+
+impl ReadableAttachment for ColorAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+impl ReadableAttachment for ColorDepthAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+impl ReadableAttachment for ColorDepthStencilAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+impl ReadableAttachment for ColorDepthStencilTextureAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+impl ReadableAttachment for MultisampleColorAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+impl ReadableAttachment for MultisampleColorDepthAttachment {
+    fn color_texture(&self) -> &Texture2d { &self.color }
+}
+
+/// Read the color of render target, the multisample targets must be resolved before.
+pub fn read_framebuffer<P: ReadbackPixel, A: ReadableAttachment>(fbo: &GLFrameBuffer<A>) -> CpuImage<P> {
+    read_texture(fbo.attachment.color_texture())
+}
+
+/// Read the front buffer of the default framebuffer, which holds the last frame presented to the window.
+pub fn read_front_buffer(display: &impl Facade) -> GLResult<RgbaImage> {
+
+    let raw_image: RawImage2d<u8> = display.get_context().read_front_buffer()
+        .map_err(|e| GLError::custom(format!("Failed to read the front buffer: {:?}", e)))?;
+
+    let width = raw_image.width as usize;
+    let gl_rows: Vec<Vec<[u8; 4]>> = raw_image.data.chunks(width * 4)
+        .map(|row| row.chunks(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect())
+        .collect();
+    Ok(CpuImage::from_gl_rows(gl_rows, |pixel| pixel))
+}
+// -------------------------------------------------------------------------------------------------
+
+
+// Asynchronous readback ---------------------------------------------------------------------------
+/// A readback in progress, whose pixels are copied into a pixel buffer by the GPU.
+///
+/// Keep it for a few frames before calling `finish`, so that the copy is likely done and the CPU does not wait for it.
+pub struct PendingReadback<P: ReadbackPixel> {
+    buffer: PixelBuffer<P::GLPixel>,
+    fence: Option<SyncFence>,
+}
+
+/// Start copying the main level of `texture` into a pixel buffer, see `read_texture` for the supported pixels.
+pub fn read_texture_async<P: ReadbackPixel>(display: &impl Facade, texture: &Texture2d) -> PendingReadback<P> {
+
+    let buffer = unsafe { texture.unchecked_read_to_pixel_buffer() };
+    // Without sync objects, reading the pixel buffer still waits for the copy implicitly.
+    let fence = SyncFence::new(display).ok();
+    PendingReadback { buffer, fence }
+}
+
+impl<P: ReadbackPixel> PendingReadback<P> {
+
+    /// Wait for the copy to finish, and map the pixels to CPU memory.
+    pub fn finish(self) -> GLResult<CpuImage<P>> {
+
+        if let Some(fence) = self.fence {
+            fence.wait();
+        }
+
+        let gl_rows: Vec<Vec<P::GLPixel>> = self.buffer.read_as_texture_2d()
+            .map_err(|e| GLError::custom(format!("Failed to read the pixel buffer: {:?}", e)))?;
+        Ok(CpuImage::from_gl_rows(gl_rows, P::from_gl_pixel))
+    }
+}
+// -------------------------------------------------------------------------------------------------
+
+
+// Image writers -----------------------------------------------------------------------------------
+/// Write 8-bit RGBA image as PNG file.
+pub fn write_png(path: impl AsRef<Path>, image: &RgbaImage) -> GLResult<()> {
+
+    let file = File::create(path.as_ref())
+        .map_err(GLError::io)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let data: Vec<u8> = image.pixels.iter().flat_map(|pixel| pixel.iter().cloned()).collect();
+    let mut writer = encoder.write_header()
+        .map_err(|e| GLError::custom(e.to_string()))?;
+    writer.write_image_data(&data)
+        .map_err(|e| GLError::custom(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Write floating-point RGB image as Radiance HDR(.hdr) file, with uncompressed RGBE pixels.
+pub fn write_hdr(path: impl AsRef<Path>, image: &RgbImageF) -> GLResult<()> {
+
+    let file = File::create(path.as_ref())
+        .map_err(GLError::io)?;
+    let mut writer = BufWriter::new(file);
+
+    write!(writer, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", image.height, image.width)
+        .map_err(GLError::io)?;
+
+    let data: Vec<u8> = image.pixels.iter().flat_map(|pixel| encode_rgbe(*pixel).to_vec()).collect();
+    writer.write_all(&data)
+        .map_err(GLError::io)?;
+    writer.flush()
+        .map_err(GLError::io)?;
+
+    Ok(())
+}
+
+/// Encode a linear RGB color to the shared exponent format of Radiance.
+fn encode_rgbe(color: [f32; 3]) -> [u8; 4] {
+
+    let max_component = color[0].max(color[1]).max(color[2]);
+    if max_component < 1e-32 {
+        return [0, 0, 0, 0]
+    }
+
+    // Find the exponent that max_component = mantissa * 2^exponent, with mantissa in [0.5, 1).
+    let exponent = max_component.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2.0_f32.powi(exponent);
+
+    [
+        (color[0].max(0.0) * scale).min(255.0) as u8,
+        (color[1].max(0.0) * scale).min(255.0) as u8,
+        (color[2].max(0.0) * scale).min(255.0) as u8,
+        (exponent + 128) as u8,
+    ]
+}
+// -------------------------------------------------------------------------------------------------
+
+
+const DEPTH_COPY_VERTEX: &str = r#"
+#version 410
+
+layout (location = 0) in vec3 VertexPosition;
+
+void main() {
+    gl_Position = vec4(VertexPosition, 1.0);
+}
+"#;
+
+const DEPTH_COPY_FRAGMENT: &str = r#"
+#version 410
+
+uniform sampler2D DepthTex;
+
+layout (location = 0) out float FragDepth;
+
+void main() {
+    FragDepth = texelFetch(DepthTex, ivec2(gl_FragCoord.xy), 0).r;
+}
+"#;
@@ -1,5 +1,6 @@
 
 use crate::error::GLResult;
+use crate::spirv::{GLSpirvCode, is_spirv_supported};
 
 use glium::backend::Facade;
 use glium::program;
@@ -88,6 +89,14 @@ impl<'a> GLSourceCode<'a> {
         }
         self
     }
+
+    /// Attach precompiled SPIR-V modules, which are preferred over the GLSL sources.
+    ///
+    /// The result can only be turned into a program input by `GLSpirvSourceCode::for_context`, so the SPIR-V
+    /// shaders are never dropped silently.
+    pub fn with_spirv(self, spirv: GLSpirvCode<'a>) -> GLSpirvSourceCode<'a> {
+        GLSpirvSourceCode { glsl: self, spirv }
+    }
 }
 
 impl<'a> From<GLSourceCode<'a>> for program::ProgramCreationInput<'a> {
@@ -98,6 +107,38 @@ impl<'a> From<GLSourceCode<'a>> for program::ProgramCreationInput<'a> {
 }
 
 
+/// GLSL sources with the SPIR-V counterpart attached by `GLSourceCode::with_spirv`.
+pub struct GLSpirvSourceCode<'a> {
+    glsl: GLSourceCode<'a>,
+    spirv: GLSpirvCode<'a>,
+}
+
+impl<'a> GLSpirvSourceCode<'a> {
+
+    /// Select the SPIR-V shaders if they are supported by the context, or fall back to the GLSL sources.
+    /// The sRGB output, point size and transform feedback settings are shared by both paths.
+    pub fn for_context(self, display: &impl Facade) -> program::ProgramCreationInput<'a> {
+
+        if !is_spirv_supported(display) {
+            println!("ARB_gl_spirv is not supported by current context, fall back to GLSL sources.");
+            return self.glsl.input
+        }
+
+        if let Err(e) = self.spirv.validate() {
+            println!("{} Fall back to GLSL sources.", e);
+            return self.glsl.input
+        }
+
+        match self.glsl.input {
+            | program::ProgramCreationInput::SourceCode { outputs_srgb, uses_point_size, transform_feedback_varyings, .. } => {
+                program::ProgramCreationInput::SpirV(self.spirv.build_program(outputs_srgb, uses_point_size, transform_feedback_varyings))
+            },
+            | input => input,
+        }
+    }
+}
+
+
 
 // .vert - a vertex shader
 // .tesc - a tessellation control shader
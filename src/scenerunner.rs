@@ -20,6 +20,8 @@ pub struct SceneParams {
     width: u32,
     height: u32,
     samples: u16,
+    /// The bits of stencil buffer of window, `None` to use the default of glutin(8 bits).
+    stencil_bits: Option<u8>,
     
     is_debug: bool, // Set true to enable debug messages
 }
@@ -27,7 +29,17 @@ pub struct SceneParams {
 impl From<(String, u32, u32, u16, bool)> for SceneParams {
 
     fn from(v: (String, u32, u32, u16, bool)) -> SceneParams {
-        SceneParams { title: v.0, width: v.1, height: v.2, samples: v.3, is_debug: v.4 }
+        SceneParams { title: v.0, width: v.1, height: v.2, samples: v.3, stencil_bits: None, is_debug: v.4 }
+    }
+}
+
+impl SceneParams {
+
+    /// Request a stencil buffer with `bits` bits for the window, which is required by the stencil effects rendering
+    /// into the default framebuffer(e.g. shadow volumes). 0 requests no stencil buffer.
+    pub fn with_stencil_buffer(mut self, bits: u8) -> SceneParams {
+        self.stencil_bits = Some(bits);
+        self
     }
 }
 
@@ -73,9 +85,12 @@ impl SceneRunner {
             .with_title(params.title.clone())
             .with_inner_size((params.width, params.height).into())
             .with_resizable(true);
-        let cb = glutin::ContextBuilder::new() // Context Builder
+        let mut cb = glutin::ContextBuilder::new() // Context Builder
             .with_gl_profile(glutin::GlProfile::Core)
             .with_multisampling(params.samples);
+        if let Some(stencil_bits) = params.stencil_bits {
+            cb = cb.with_stencil_buffer(stencil_bits);
+        }
 
         let display: glium::Display = if params.is_debug {
             let wc = SceneRunner::with_context_gl_request(cb) // Windows Context
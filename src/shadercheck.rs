@@ -0,0 +1,998 @@
+
+//! A CPU-only checker for the GLSL shaders of recipes.
+//!
+//! It performs a lightweight structural parsing of each shader (balanced delimiters, terminated comments and statements,
+//! `#version` directive and the form of top-level declarations), and then cross-checks the declared interface of shaders
+//! against the Rust code of each recipe:
+//!
+//! - every uniform name used in an `uniform!` macro must be declared by one of the shaders included by the recipe.
+//! - every input of a vertex shader must be provided by a vertex struct available to the recipe.
+//!
+//! No OpenGL context is required, so it can be run on machines without GPU. This is not a complete GLSL compiler.
+
+use crate::error::{GLResult, GLError};
+
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fmt;
+
+const QUALIFIERS: [&str; 18] = [
+    "flat", "smooth", "noperspective", "centroid", "sample", "patch", "invariant", "precise",
+    "highp", "mediump", "lowp", "const", "readonly", "writeonly", "coherent", "volatile", "restrict", "shared",
+];
+const STORAGES: [&str; 6] = ["in", "out", "uniform", "buffer", "attribute", "varying"];
+
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub line: usize,
+    pub is_error: bool,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = if self.is_error { "error" } else { "warning" };
+        write!(f, "{}: {}:{}: {}", severity, self.path.display(), self.line, self.message)
+    }
+}
+
+
+/// The shader stage, which is deduced from the file name(e.g. `basic.vert.glsl`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+
+    fn from_path(path: &Path) -> Option<ShaderStage> {
+        let file_name = path.file_name()?.to_str()?;
+        let stage = file_name.trim_end_matches(".glsl").rsplit('.').next()?;
+        match stage {
+            | "vert" => Some(ShaderStage::Vertex),
+            | "tesc" => Some(ShaderStage::TessellationControl),
+            | "tese" => Some(ShaderStage::TessellationEvaluation),
+            | "geom" => Some(ShaderStage::Geometry),
+            | "frag" => Some(ShaderStage::Fragment),
+            | "comp" => Some(ShaderStage::Compute),
+            | _ => None,
+        }
+    }
+}
+
+/// A variable declared in the global scope of a shader.
+#[derive(Debug, Clone)]
+pub struct Declaration {
+    pub name: String,
+    pub ty: String,
+    pub line: usize,
+}
+
+/// A uniform block or shader storage block.
+#[derive(Debug, Clone)]
+pub struct InterfaceBlock {
+    pub name: String,
+    pub instance: Option<String>,
+    pub members: Vec<Declaration>,
+    pub line: usize,
+}
+
+/// The global interface declared by a shader.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderInterface {
+    pub stage: Option<ShaderStage>,
+    pub version: Option<String>,
+    pub inputs : Vec<Declaration>,
+    pub outputs: Vec<Declaration>,
+    pub uniforms: Vec<Declaration>,
+    pub uniform_blocks: Vec<InterfaceBlock>,
+    pub buffer_blocks : Vec<InterfaceBlock>,
+    pub subroutine_uniforms: Vec<Declaration>,
+    /// The functions declared with `subroutine (Type)` qualifier, with the names of their subroutine types.
+    pub subroutine_functions: Vec<(String, Vec<String>)>,
+}
+
+impl ShaderInterface {
+
+    /// Check if `name` can be used as a key in `uniform!` macro for this shader.
+    pub fn is_uniform_name(&self, name: &str) -> bool {
+        self.uniforms.iter().any(|uniform| uniform.name == name) ||
+            self.uniform_blocks.iter().any(|block| block.name == name) ||
+            self.buffer_blocks.iter().any(|block| block.name == name) ||
+            self.subroutine_uniforms.iter().any(|uniform| uniform.name == name)
+    }
+}
+
+
+// Lexer ---------------------------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Punct(&'static str),
+    /// A preprocessor directive of GLSL, without the leading '#'.
+    Directive(String),
+}
+
+#[derive(Debug, Clone)]
+struct Lexeme {
+    token: Token,
+    line: usize,
+}
+
+const PUNCTUATIONS: [&str; 44] = [
+    "<<=", ">>=", "...",
+    "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "^^", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+    "(", ")", "[", "]", "{", "}", ";", ",", ".", ":", "=", "+", "-", "*", "/", "<", ">", "!", "?",
+];
+
+/// Split source code into tokens. Comments are skipped.
+/// For GLSL, preprocessor directives are kept as a whole, and for Rust, string and char literals are recognized.
+fn tokenize(source: &str, is_glsl: bool) -> Result<Vec<Lexeme>, (usize, String)> {
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexemes = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    let mut is_line_start = true;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+            is_line_start = true;
+            continue
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue
+        }
+
+        // Comments
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' { i += 1; }
+            continue
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            let start_line = line;
+            let mut depth = 0;
+            loop {
+                if i >= chars.len() {
+                    return Err((start_line, String::from("Unterminated block comment.")))
+                }
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                    // GLSL does not support nested block comments.
+                    if is_glsl && depth > 1 { depth = 1; }
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 { break }
+                } else {
+                    if chars[i] == '\n' { line += 1; }
+                    i += 1;
+                }
+            }
+            continue
+        }
+
+        let was_line_start = is_line_start;
+        is_line_start = false;
+
+        // Preprocessor directive of GLSL, which may be continued by a trailing backslash.
+        if is_glsl && c == '#' && was_line_start {
+            let start_line = line;
+            let mut directive = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '\n' {
+                if chars[i] == '\\' && chars.get(i + 1) == Some(&'\n') {
+                    line += 1;
+                    i += 2;
+                    continue
+                }
+                directive.push(chars[i]);
+                i += 1;
+            }
+            lexemes.push(Lexeme { token: Token::Directive(directive.trim().to_string()), line: start_line });
+            continue
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') { i += 1; }
+            let ident: String = chars[start..i].iter().collect();
+
+            // Raw string literal of Rust, e.g. r"..." or r#"..."#.
+            if !is_glsl && ident == "r" && (chars.get(i) == Some(&'"') || chars.get(i) == Some(&'#')) {
+                let mut hashes = 0;
+                while chars.get(i) == Some(&'#') { hashes += 1; i += 1; }
+                if chars.get(i) == Some(&'"') {
+                    i += 1;
+                    let start = i;
+                    let terminator: Vec<char> = std::iter::once('"').chain(std::iter::repeat('#').take(hashes)).collect();
+                    while i < chars.len() && !chars[i..].starts_with(&terminator) {
+                        if chars[i] == '\n' { line += 1; }
+                        i += 1;
+                    }
+                    let content: String = chars[start..i.min(chars.len())].iter().collect();
+                    i += terminator.len();
+                    lexemes.push(Lexeme { token: Token::Str(content), line });
+                    continue
+                }
+            }
+
+            lexemes.push(Lexeme { token: Token::Ident(ident), line });
+            continue
+        }
+
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).map_or(false, |n| n.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() {
+                let n = chars[i];
+                let is_exponent_sign = (n == '+' || n == '-') && (chars[i - 1] == 'e' || chars[i - 1] == 'E') && !chars[start..i].contains(&'x');
+                if n.is_alphanumeric() || n == '.' || n == '_' || is_exponent_sign {
+                    i += 1;
+                } else {
+                    break
+                }
+            }
+            lexemes.push(Lexeme { token: Token::Number(chars[start..i].iter().collect()), line });
+            continue
+        }
+
+        if !is_glsl && c == '"' {
+            let start_line = line;
+            let mut content = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    | None => return Err((start_line, String::from("Unterminated string literal."))),
+                    | Some('"') => { i += 1; break },
+                    | Some('\\') => {
+                        if let Some(escaped) = chars.get(i + 1) {
+                            if *escaped == '\n' { line += 1; }
+                            content.push(*escaped);
+                        }
+                        i += 2;
+                    },
+                    | Some(other) => {
+                        if *other == '\n' { line += 1; }
+                        content.push(*other);
+                        i += 1;
+                    },
+                }
+            }
+            lexemes.push(Lexeme { token: Token::Str(content), line: start_line });
+            continue
+        }
+
+        if !is_glsl && c == '\'' {
+            // Distinguish char literals('a', '\n') from lifetimes('a).
+            if chars.get(i + 1) == Some(&'\\') {
+                i += 2;
+                while i < chars.len() && chars[i] != '\'' { i += 1; }
+                i += 1;
+            } else if chars.get(i + 2) == Some(&'\'') {
+                i += 3;
+            } else {
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') { i += 1; }
+            }
+            continue
+        }
+
+        match PUNCTUATIONS.iter().find(|p| chars[i..].starts_with(&p.chars().collect::<Vec<_>>())) {
+            | Some(punct) => {
+                lexemes.push(Lexeme { token: Token::Punct(punct), line });
+                i += punct.len();
+            },
+            | None => {
+                // Other characters(e.g. '#', '&', '|', '%', '@', '$') are irrelevant to the checks here.
+                if is_glsl && !"&|^%~".contains(c) {
+                    return Err((line, format!("Unexpected character '{}'.", c)))
+                }
+                i += 1;
+            },
+        }
+    }
+
+    Ok(lexemes)
+}
+
+fn is_punct(lexeme: Option<&Lexeme>, punct: &str) -> bool {
+    match lexeme {
+        | Some(Lexeme { token: Token::Punct(p), .. }) => *p == punct,
+        | _ => false,
+    }
+}
+
+fn ident_of(lexeme: Option<&Lexeme>) -> Option<&str> {
+    match lexeme {
+        | Some(Lexeme { token: Token::Ident(ident), .. }) => Some(ident),
+        | _ => None,
+    }
+}
+
+/// Return the index of the delimiter which closes the one at `open_index`.
+fn matching_delimiter(lexemes: &[Lexeme], open_index: usize) -> Option<usize> {
+
+    let mut depth = 0;
+    for (i, lexeme) in lexemes.iter().enumerate().skip(open_index) {
+        if let Token::Punct(p) = lexeme.token {
+            match p {
+                | "(" | "[" | "{" => depth += 1,
+                | ")" | "]" | "}" => {
+                    depth -= 1;
+                    if depth == 0 { return Some(i) }
+                },
+                | _ => {},
+            }
+        }
+    }
+    None
+}
+// ---------------------------------------------------------------------------------------------
+
+
+// GLSL parsing --------------------------------------------------------------------------------
+/// Parse the source of a shader, and return its global interface and the syntax problems found in it.
+pub fn parse_shader(path: &Path, source: &str) -> (ShaderInterface, Vec<Diagnostic>) {
+
+    let mut interface = ShaderInterface { stage: ShaderStage::from_path(path), ..Default::default() };
+    let mut diagnostics = Vec::new();
+    let mut report = |line: usize, message: String| {
+        diagnostics.push(Diagnostic { path: path.to_path_buf(), line, is_error: true, message });
+    };
+
+    let lexemes = match tokenize(source, true) {
+        | Ok(lexemes) => lexemes,
+        | Err((line, message)) => {
+            report(line, message);
+            return (interface, diagnostics)
+        },
+    };
+
+    // The #version directive must be the first thing in a shader.
+    match lexemes.first() {
+        | Some(Lexeme { token: Token::Directive(directive), .. }) if directive.starts_with("version") => {
+            interface.version = Some(directive.trim_start_matches("version").trim().to_string());
+        },
+        | first => report(first.map_or(1, |lexeme| lexeme.line), String::from("The shader must start with a #version directive.")),
+    }
+
+    // Check if all the delimiters are balanced.
+    let mut delimiters: Vec<(&str, usize)> = Vec::new();
+    for lexeme in lexemes.iter() {
+        if let Token::Punct(p) = lexeme.token {
+            match p {
+                | "(" | "[" | "{" => delimiters.push((p, lexeme.line)),
+                | ")" | "]" | "}" => {
+                    let expected = match p { ")" => "(", "]" => "[", _ => "{" };
+                    match delimiters.pop() {
+                        | Some((open, _)) if open == expected => {},
+                        | Some((open, line)) => {
+                            report(lexeme.line, format!("Mismatched closing delimiter '{}' for '{}' at line {}.", p, open, line));
+                            return (interface, diagnostics)
+                        },
+                        | None => {
+                            report(lexeme.line, format!("Unexpected closing delimiter '{}'.", p));
+                            return (interface, diagnostics)
+                        },
+                    }
+                },
+                | _ => {},
+            }
+        }
+    }
+    if let Some((open, line)) = delimiters.pop() {
+        report(line, format!("Unclosed delimiter '{}'.", open));
+        return (interface, diagnostics)
+    }
+
+    // Split the global scope into statements, and parse each of them.
+    let tokens: Vec<Lexeme> = lexemes.into_iter()
+        .filter(|lexeme| match lexeme.token { Token::Directive(_) => false, _ => true })
+        .collect();
+
+    let mut start = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if is_punct(tokens.get(i), ";") {
+            parse_declaration(&tokens[start..i], &mut interface, &mut report);
+            i += 1;
+            start = i;
+        } else if is_punct(tokens.get(i), "{") {
+            let close = matching_delimiter(&tokens, i).unwrap_or(tokens.len() - 1);
+            if i > start && is_punct(tokens.get(i - 1), ")") {
+                // Function definition, whose body is not parsed.
+                parse_function_header(&tokens[start..i], &mut interface);
+                i = close + 1;
+                start = i;
+            } else {
+                // Interface block or struct declaration, which continues to the next ';'.
+                i = close + 1;
+            }
+        } else if is_punct(tokens.get(i), "(") || is_punct(tokens.get(i), "[") {
+            i = matching_delimiter(&tokens, i).unwrap_or(tokens.len() - 1) + 1;
+        } else {
+            i += 1;
+        }
+    }
+    if start < tokens.len() {
+        report(tokens[start].line, String::from("Expected ';' at the end of declaration."));
+    }
+
+    (interface, diagnostics)
+}
+
+fn parse_function_header(tokens: &[Lexeme], interface: &mut ShaderInterface) {
+
+    // subroutine (Type1, Type2) vec3 functionName(...)
+    if ident_of(tokens.first()) == Some("subroutine") && is_punct(tokens.get(1), "(") {
+        if let Some(close) = matching_delimiter(tokens, 1) {
+            let types = tokens[2..close].iter()
+                .filter_map(|lexeme| ident_of(Some(lexeme)).map(String::from))
+                .collect();
+            let open = tokens.iter().rposition(|lexeme| is_punct(Some(lexeme), "(")).unwrap_or(0);
+            if let Some(name) = open.checked_sub(1).and_then(|index| ident_of(tokens.get(index))) {
+                interface.subroutine_functions.push((name.to_string(), types));
+            }
+        }
+    }
+}
+
+fn parse_declaration(tokens: &[Lexeme], interface: &mut ShaderInterface, report: &mut impl FnMut(usize, String)) {
+
+    if tokens.is_empty() { return }
+    let line = tokens[0].line;
+
+    // Skip layout qualifiers and other qualifiers which are irrelevant to the interface.
+    let mut i = 0;
+    loop {
+        match ident_of(tokens.get(i)) {
+            | Some("layout") if is_punct(tokens.get(i + 1), "(") => {
+                i = matching_delimiter(tokens, i + 1).map_or(tokens.len(), |close| close + 1);
+            },
+            | Some(qualifier) if QUALIFIERS.contains(&qualifier) => i += 1,
+            | _ => break,
+        }
+    }
+    let tokens = &tokens[i..];
+
+    match ident_of(tokens.first()) {
+        // Layout declarations such as "layout (triangles) in;" or "precision highp float;"
+        | _ if tokens.len() <= 1 => {},
+        | Some("precision") => {},
+        | Some("subroutine") => {
+            // subroutine uniform shadeModelType shadeModel;
+            if ident_of(tokens.get(1)) == Some("uniform") {
+                parse_variables(&tokens[2..], line, report)
+                    .into_iter().for_each(|declaration| interface.subroutine_uniforms.push(declaration));
+            }
+        },
+        | Some("struct") => {},
+        | Some(storage) if STORAGES.contains(&storage) => {
+
+            let storage = storage.to_string();
+            let is_block = ident_of(tokens.get(1)).is_some() && is_punct(tokens.get(2), "{");
+
+            if is_block {
+                let close = matching_delimiter(tokens, 2).unwrap_or(tokens.len() - 1);
+                let members = tokens[3..close]
+                    .split(|lexeme| is_punct(Some(lexeme), ";"))
+                    .filter(|member| !member.is_empty())
+                    .flat_map(|member| {
+                        let member_start = member.iter().position(|lexeme| {
+                            ident_of(Some(lexeme)).map_or(true, |ident| !QUALIFIERS.contains(&ident))
+                        }).unwrap_or(0);
+                        let member = if ident_of(member.get(member_start)) == Some("layout") {
+                            &member[matching_delimiter(member, member_start + 1).map_or(member.len(), |close| close + 1)..]
+                        } else {
+                            &member[member_start..]
+                        };
+                        parse_variables(member, line, report)
+                    })
+                    .collect();
+                let block = InterfaceBlock {
+                    name: ident_of(tokens.get(1)).unwrap_or_default().to_string(),
+                    instance: ident_of(tokens.get(close + 1)).map(String::from),
+                    members, line,
+                };
+
+                match storage.as_str() {
+                    | "uniform" => interface.uniform_blocks.push(block),
+                    | "buffer"  => interface.buffer_blocks.push(block),
+                    | _ => {}, // Input and output blocks between stages are not checked.
+                }
+            } else {
+                let declarations = parse_variables(&tokens[1..], line, report);
+                match storage.as_str() {
+                    | "in" | "attribute" => interface.inputs.extend(declarations),
+                    | "out" | "varying"  => interface.outputs.extend(declarations),
+                    | "uniform" => interface.uniforms.extend(declarations),
+                    | _ => report(line, String::from("Storage block must be declared with a block name and members.")),
+                }
+            }
+        },
+        | Some(_) => {
+            // Global variables or function prototypes, e.g. "const float PI = 3.14;" or "vec3 phongModel(vec3 n);".
+            let is_prototype = tokens.iter().any(|lexeme| is_punct(Some(lexeme), "("));
+            if !is_prototype {
+                parse_variables(tokens, line, report);
+            }
+        },
+        | None => {
+            report(line, String::from("Expected a declaration."));
+        },
+    }
+}
+
+/// Parse declarations like "vec3 Name[2] = ..., Name2".
+fn parse_variables(tokens: &[Lexeme], line: usize, report: &mut impl FnMut(usize, String)) -> Vec<Declaration> {
+
+    let mut declarations = Vec::new();
+
+    let ty = match ident_of(tokens.first()) {
+        | Some(ty) => ty.to_string(),
+        | None => {
+            report(line, String::from("Expected a type name in declaration."));
+            return declarations
+        }
+    };
+
+    // Skip the array specifier of type(e.g. "float[3] weights").
+    let mut i = 1;
+    while is_punct(tokens.get(i), "[") {
+        i = matching_delimiter(tokens, i).map_or(tokens.len(), |close| close + 1);
+    }
+
+    loop {
+        let name = match tokens.get(i) {
+            | Some(Lexeme { token: Token::Ident(name), line }) => Declaration { name: name.clone(), ty: ty.clone(), line: *line },
+            | Some(lexeme) => {
+                report(lexeme.line, format!("Expected a variable name after type '{}'.", ty));
+                return declarations
+            },
+            | None => {
+                report(line, format!("Expected a variable name after type '{}'.", ty));
+                return declarations
+            },
+        };
+        declarations.push(name);
+        i += 1;
+
+        while is_punct(tokens.get(i), "[") {
+            i = matching_delimiter(tokens, i).map_or(tokens.len(), |close| close + 1);
+        }
+
+        // Skip the initializer.
+        if is_punct(tokens.get(i), "=") {
+            i += 1;
+            while i < tokens.len() && !is_punct(tokens.get(i), ",") {
+                if is_punct(tokens.get(i), "(") || is_punct(tokens.get(i), "[") || is_punct(tokens.get(i), "{") {
+                    i = matching_delimiter(tokens, i).map_or(tokens.len(), |close| close + 1);
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        match tokens.get(i) {
+            | None => return declarations,
+            | Some(lexeme) if is_punct(Some(lexeme), ",") => i += 1,
+            | Some(lexeme) => {
+                report(lexeme.line, format!("Expected ';' after the declaration of '{}'.", declarations.last().unwrap().name));
+                return declarations
+            },
+        }
+    }
+}
+// ---------------------------------------------------------------------------------------------
+
+
+// Rust scanning -------------------------------------------------------------------------------
+/// The information extracted from the Rust source of a recipe.
+#[derive(Debug, Clone, Default)]
+struct RustSource {
+    /// The shader files included by `include_str!`, relative to the Rust file.
+    shaders: Vec<(String, usize)>,
+    /// The keys of `uniform!` macros.
+    uniforms: Vec<(String, usize)>,
+    /// The vertex structs declared by `implement_vertex!` or `#[derive(Vertex)]`, with the names of their attributes.
+    vertex_structs: Vec<(String, Vec<String>)>,
+    /// The types implementing `TriangleMesh` or `Drawable`.
+    meshes: Vec<String>,
+    identifiers: Vec<String>,
+}
+
+fn scan_rust_source(source: &str) -> Result<RustSource, (usize, String)> {
+
+    let tokens = tokenize(source, false)?;
+    let mut result = RustSource::default();
+
+    for (i, lexeme) in tokens.iter().enumerate() {
+
+        let ident = match &lexeme.token {
+            | Token::Ident(ident) => ident.as_str(),
+            | _ => continue,
+        };
+        result.identifiers.push(ident.to_string());
+
+        let is_macro = is_punct(tokens.get(i + 1), "!");
+
+        match ident {
+            | "include_str" if is_macro => {
+                if let Some(Lexeme { token: Token::Str(path), line }) = tokens.get(i + 3) {
+                    result.shaders.push((path.clone(), *line));
+                }
+            },
+            | "uniform" if is_macro && is_punct(tokens.get(i + 2), "{") => {
+                let close = matching_delimiter(&tokens, i + 2).unwrap_or(tokens.len());
+                let mut j = i + 3;
+                while j < close {
+                    // Each entry is in the form of "Name: expression,".
+                    if let (Some(name), true) = (ident_of(tokens.get(j)), is_punct(tokens.get(j + 1), ":")) {
+                        result.uniforms.push((name.to_string(), tokens[j].line));
+                    }
+                    while j < close && !is_punct(tokens.get(j), ",") {
+                        if is_punct(tokens.get(j), "(") || is_punct(tokens.get(j), "[") || is_punct(tokens.get(j), "{") {
+                            j = matching_delimiter(&tokens, j).unwrap_or(close);
+                        }
+                        j += 1;
+                    }
+                    j += 1;
+                }
+            },
+            | "implement_vertex" if is_macro && is_punct(tokens.get(i + 2), "(") => {
+                let close = matching_delimiter(&tokens, i + 2).unwrap_or(tokens.len());
+                let mut names = tokens[(i + 3)..close].iter()
+                    .filter_map(|lexeme| ident_of(Some(lexeme)))
+                    .filter(|name| *name != "normalize" && *name != "true" && *name != "false")
+                    .map(String::from);
+                if let Some(struct_name) = names.next() {
+                    result.vertex_structs.push((struct_name, names.collect()));
+                }
+            },
+            | "derive" if is_punct(tokens.get(i + 1), "(") => {
+                if let Some(vertex_struct) = scan_derived_vertex(&tokens, i + 1) {
+                    result.vertex_structs.push(vertex_struct);
+                }
+            },
+            | "TriangleMesh" | "Drawable" if i > 0 && ident_of(tokens.get(i - 1)) == Some("impl") && ident_of(tokens.get(i + 1)) == Some("for") => {
+                if let Some(mesh) = ident_of(tokens.get(i + 2)) {
+                    result.meshes.push(mesh.to_string());
+                }
+            },
+            | _ => {},
+        }
+    }
+
+    Ok(result)
+}
+
+/// Scan the struct after `#[derive(..., Vertex)]`, whose derive list begins at `open`.
+/// The attributes are named by the fields, or by `#[vertex(rename = "...")]`.
+fn scan_derived_vertex(tokens: &[Lexeme], open: usize) -> Option<(String, Vec<String>)> {
+
+    let close = matching_delimiter(tokens, open)?;
+    if !tokens[open..close].iter().any(|lexeme| ident_of(Some(lexeme)) == Some("Vertex")) {
+        return None
+    }
+
+    let keyword = (close..tokens.len()).find(|&j| ident_of(tokens.get(j)) == Some("struct"))?;
+    let struct_name = ident_of(tokens.get(keyword + 1))?.to_string();
+    if !is_punct(tokens.get(keyword + 2), "{") { return None }
+    let end = matching_delimiter(tokens, keyword + 2)?;
+
+    let mut attributes = Vec::new();
+    let mut rename = None;
+    let mut j = keyword + 3;
+    while j < end {
+        // The attributes of field(the leading '#' is dropped by lexer).
+        if is_punct(tokens.get(j), "[") {
+            let attribute_end = matching_delimiter(tokens, j)?;
+            if ident_of(tokens.get(j + 1)) == Some("vertex") {
+                for k in (j + 2)..attribute_end {
+                    if ident_of(tokens.get(k)) == Some("rename") && is_punct(tokens.get(k + 1), "=") {
+                        if let Some(Lexeme { token: Token::Str(name), .. }) = tokens.get(k + 2) {
+                            rename = Some(name.clone());
+                        }
+                    }
+                }
+            }
+            j = attribute_end + 1;
+            continue
+        }
+
+        if ident_of(tokens.get(j)) == Some("pub") {
+            j += 1;
+            if is_punct(tokens.get(j), "(") {
+                j = matching_delimiter(tokens, j)? + 1;
+            }
+        }
+
+        let field = ident_of(tokens.get(j))?;
+        attributes.push(rename.take().unwrap_or_else(|| field.to_string()));
+
+        // Skip the type of field.
+        while j < end && !is_punct(tokens.get(j), ",") {
+            if is_punct(tokens.get(j), "(") || is_punct(tokens.get(j), "[") {
+                j = matching_delimiter(tokens, j)?;
+            }
+            j += 1;
+        }
+        j += 1;
+    }
+
+    Some((struct_name, attributes))
+}
+// ---------------------------------------------------------------------------------------------
+
+
+/// Check all the shaders under `examples/*/shaders` and the recipes using them, and the library shaders under
+/// `src/**/shaders`, which are only parsed. `root` is the root directory of this repository.
+pub fn check_examples(root: &Path) -> GLResult<Vec<Diagnostic>> {
+
+    let mut diagnostics = Vec::new();
+
+    // Parse all shaders.
+    let mut shader_paths = Vec::new();
+    for chapter in read_sorted_dir(&root.join("examples"))? {
+        if chapter.is_dir() {
+            collect_shader_files(&chapter.join("shaders"), &mut shader_paths)?;
+        }
+    }
+    collect_library_shader_files(&root.join("src"), &mut shader_paths)?;
+
+    let mut shaders: HashMap<PathBuf, ShaderInterface> = HashMap::new();
+    for path in shader_paths {
+        let source = std::fs::read_to_string(&path)
+            .map_err(GLError::io)?;
+        let (interface, shader_diagnostics) = parse_shader(&path, &source);
+        diagnostics.extend(shader_diagnostics);
+        shaders.insert(path, interface);
+    }
+
+    // Collect the meshes provided by library. Each mesh is assumed to use the vertex structs declared in the same file.
+    let mut library = Vec::new();
+    for path in read_sorted_dir(&root.join("src").join("objects"))? {
+        let source = std::fs::read_to_string(&path)
+            .map_err(GLError::io)?;
+        let scanned = scan_rust_source(&source)
+            .map_err(|(line, message)| GLError::custom(format!("{}:{}: {}", path.display(), line, message)))?;
+        library.push(scanned);
+    }
+
+    // Cross-check each recipe with its shaders.
+    for chapter in read_sorted_dir(&root.join("examples"))? {
+        if !chapter.is_dir() { continue }
+
+        for path in read_sorted_dir(&chapter)? {
+            if path.extension().map_or(true, |extension| extension != "rs") { continue }
+
+            let source = std::fs::read_to_string(&path)
+                .map_err(GLError::io)?;
+            match scan_rust_source(&source) {
+                | Ok(recipe) => check_recipe(&path, &recipe, &library, &shaders, &mut diagnostics),
+                | Err((line, message)) => diagnostics.push(Diagnostic { path, line, is_error: true, message }),
+            }
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+fn check_recipe(path: &Path, recipe: &RustSource, library: &[RustSource], shaders: &HashMap<PathBuf, ShaderInterface>, diagnostics: &mut Vec<Diagnostic>) {
+
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut interfaces = Vec::new();
+
+    for (shader, line) in recipe.shaders.iter() {
+        let shader_path = directory.join(shader);
+        match shaders.get(&shader_path) {
+            | Some(interface) => interfaces.push((shader_path, interface)),
+            | None => diagnostics.push(Diagnostic {
+                path: path.to_path_buf(), line: *line, is_error: true,
+                message: format!("Shader file \"{}\" is not found.", shader),
+            }),
+        }
+    }
+
+    if interfaces.is_empty() { return }
+
+    for (uniform, line) in recipe.uniforms.iter() {
+        if !interfaces.iter().any(|(_, interface)| interface.is_uniform_name(uniform)) {
+            diagnostics.push(Diagnostic {
+                path: path.to_path_buf(), line: *line, is_error: true,
+                message: format!("Uniform `{}` is not declared in any shader used by this recipe.", uniform),
+            });
+        }
+    }
+
+    // The vertex attributes available to this recipe: vertex structs declared in recipe and the library meshes it uses.
+    let mut attributes: Vec<&String> = recipe.vertex_structs.iter()
+        .flat_map(|(_, attributes)| attributes.iter())
+        .collect();
+    for objects in library.iter() {
+        if objects.meshes.iter().any(|mesh| recipe.identifiers.contains(mesh)) {
+            objects.vertex_structs.iter()
+                .for_each(|(_, fields)| attributes.extend(fields.iter()));
+        }
+    }
+
+    for (shader_path, interface) in interfaces.iter().filter(|(_, interface)| interface.stage == Some(ShaderStage::Vertex)) {
+        for input in interface.inputs.iter() {
+            if !attributes.contains(&&input.name) {
+                diagnostics.push(Diagnostic {
+                    path: shader_path.clone(), line: input.line, is_error: true,
+                    message: format!("Vertex attribute `{}` is not provided by any vertex type used by {}.", input.name, path.display()),
+                });
+            }
+        }
+    }
+}
+
+fn read_sorted_dir(path: &Path) -> GLResult<Vec<PathBuf>> {
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(GLError::io)? {
+        entries.push(entry.map_err(GLError::io)?.path());
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Collect the shaders of every `shaders` directory under `directory`.
+fn collect_library_shader_files(directory: &Path, paths: &mut Vec<PathBuf>) -> GLResult<()> {
+
+    for path in read_sorted_dir(directory)? {
+        if !path.is_dir() { continue }
+
+        if path.file_name().map_or(false, |name| name == "shaders") {
+            collect_shader_files(&path, paths)?;
+        } else {
+            collect_library_shader_files(&path, paths)?;
+        }
+    }
+    Ok(())
+}
+
+fn collect_shader_files(directory: &Path, paths: &mut Vec<PathBuf>) -> GLResult<()> {
+
+    if !directory.is_dir() { return Ok(()) }
+
+    for path in read_sorted_dir(directory)? {
+        if path.is_dir() {
+            collect_shader_files(&path, paths)?;
+        } else if path.extension().map_or(false, |extension| extension == "glsl") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(file_name: &str, source: &str) -> (ShaderInterface, Vec<Diagnostic>) {
+        parse_shader(Path::new(file_name), source)
+    }
+
+    fn names(declarations: &[Declaration]) -> Vec<&str> {
+        declarations.iter().map(|declaration| declaration.name.as_str()).collect()
+    }
+
+    #[test]
+    fn stage_is_detected_from_file_name() {
+
+        let stage = |file_name| parse(file_name, "#version 410\n").0.stage;
+        assert_eq!(stage("basic.vert.glsl"), Some(ShaderStage::Vertex));
+        assert_eq!(stage("shaders/terrain.tesc.glsl"), Some(ShaderStage::TessellationControl));
+        assert_eq!(stage("terrain.tese"), Some(ShaderStage::TessellationEvaluation));
+        assert_eq!(stage("wireframe.geom.glsl"), Some(ShaderStage::Geometry));
+        assert_eq!(stage("phong.frag.glsl"), Some(ShaderStage::Fragment));
+        assert_eq!(stage("particles.comp.glsl"), Some(ShaderStage::Compute));
+        assert_eq!(stage("common.glsl"), None);
+    }
+
+    #[test]
+    fn interface_is_collected() {
+
+        let source = "\
+#version 410
+#include \"lighting.glsl\"
+#define MAX_LIGHTS 4
+
+layout (location = 0) in vec3 VertexPosition;
+layout (location = 1) in vec3 VertexNormal;
+flat out vec3 Color;
+
+uniform mat4 MVP, ModelViewMatrix;
+uniform float Weight[MAX_LIGHTS];
+uniform LightInfo {
+    vec4 Position;
+    layout (offset = 16) vec3 La;
+} Light;
+buffer Particles { vec4 positions[]; };
+
+subroutine vec3 shadeModelType(vec3 n);
+subroutine uniform shadeModelType shadeModel;
+subroutine (shadeModelType) vec3 diffuseOnly(vec3 n) { return n; }
+
+const float PI = 3.14159265;
+vec3 phongModel(vec3 n);
+
+void main() {
+    Color = phongModel(VertexNormal);
+}
+";
+        let (interface, diagnostics) = parse("phong.vert.glsl", source);
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(interface.version.as_deref(), Some("410"));
+        assert_eq!(names(&interface.inputs), vec!["VertexPosition", "VertexNormal"]);
+        assert_eq!(names(&interface.outputs), vec!["Color"]);
+        assert_eq!(names(&interface.uniforms), vec!["MVP", "ModelViewMatrix", "Weight"]);
+        assert_eq!(interface.uniforms[2].ty, "float");
+
+        assert_eq!(interface.uniform_blocks.len(), 1);
+        assert_eq!(interface.uniform_blocks[0].name, "LightInfo");
+        assert_eq!(interface.uniform_blocks[0].instance.as_deref(), Some("Light"));
+        assert_eq!(names(&interface.uniform_blocks[0].members), vec!["Position", "La"]);
+        assert_eq!(interface.buffer_blocks[0].name, "Particles");
+
+        assert_eq!(names(&interface.subroutine_uniforms), vec!["shadeModel"]);
+        assert_eq!(interface.subroutine_functions, vec![(String::from("diffuseOnly"), vec![String::from("shadeModelType")])]);
+
+        assert!(interface.is_uniform_name("LightInfo"));
+        assert!(interface.is_uniform_name("shadeModel"));
+        assert!(!interface.is_uniform_name("VertexNormal"));
+    }
+
+    #[test]
+    fn errors_point_at_the_source_line() {
+
+        let first_error = |source: &str| {
+            let (_, diagnostics) = parse("test.frag.glsl", source);
+            diagnostics.first().map(|diagnostic| (diagnostic.line, diagnostic.message.clone()))
+        };
+
+        let (line, message) = first_error("#version 410\n\nuniform vec3 Color\nuniform float Scale;\n").unwrap();
+        assert_eq!(line, 4);
+        assert!(message.contains("';'"), "{}", message);
+
+        // A continued directive and a block comment still count their lines.
+        let (line, message) = first_error("#version 410\n#define SCALE \\\n    2.0\n/* line 4\n line 5 */\nvoid main() {\n    if (true) {\n}\n").unwrap();
+        assert_eq!(line, 6);
+        assert!(message.contains("Unclosed"), "{}", message);
+
+        let (line, message) = first_error("#version 410\nvoid main() {\n    vec2 v = vec2(1.0, 2.0];\n}\n").unwrap();
+        assert_eq!(line, 3);
+        assert!(message.contains("Mismatched"), "{}", message);
+
+        let (line, message) = first_error("#version 410\n\n/* never closed\nvoid main() {}\n").unwrap();
+        assert_eq!(line, 3);
+        assert!(message.contains("Unterminated"), "{}", message);
+
+        let (line, message) = first_error("\nuniform float Scale;\n").unwrap();
+        assert_eq!(line, 2);
+        assert!(message.contains("#version"), "{}", message);
+
+        assert_eq!(first_error("#version 410\nvoid main() {}\n"), None);
+    }
+}
@@ -0,0 +1,416 @@
+
+//! Shadow maps of directional, spot and point lights.
+//!
+//! Each shadow map owns its depth target and the matrices of the light. Render the shadow casters into
+//! `framebuffer` with `view_projection`, then sample `depth` in the shading pass with `shadow_matrix`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::shadow::{ShadowMap, ShadowFilter};
+//!
+//! let mut shadow_map = ShadowMap::new(display, 1024, DepthFormat::I24)?;
+//! shadow_map.set_spot_light(light_pos, Vec3F::zero(), Vec3F::unit_y(), 50.0, 1.0, 25.0);
+//!
+//! // Pass 1: render the depth from the light.
+//! let mut framebuffer = shadow_map.framebuffer()?;
+//! framebuffer.clear_depth(1.0);
+//! mesh.render(&mut framebuffer, &depth_program, &shadow::depth_pass_parameters(), &uniform! {
+//!     MVP: (shadow_map.view_projection() * model).into_col_arrays(),
+//! })?;
+//!
+//! // Pass 2: shade with the `sampler2DShadow`.
+//! let uniforms = uniform! {
+//!     ShadowMap: shadow_map.sampler(ShadowFilter::Pcf),
+//!     ShadowMatrix: (shadow_map.shadow_matrix() * model).into_col_arrays(),
+//! };
+//! ```
+//!
+//! The depth bias of `glPolygonOffset` is not supported by glium(see https://github.com/glium/glium/issues/826),
+//! so the recipes cull the front faces during the depth pass instead.
+
+use crate::framebuffer::{ShadowDepthAttachment, CubemapDepthAttachment, LayeredDepthAttachment};
+use crate::framebuffer::{GLFrameBuffer, GLLayeredFrameBuffer, cubemap_view_matrices, cubemap_projection};
+use crate::objects::Frustum;
+use crate::error::{GLResult, GLError};
+use crate::{Mat4F, Vec3F, Vec4F};
+
+use glium::texture::depth_texture2d::DepthTexture2d;
+use glium::texture::depth_texture2d_array::DepthTexture2dArray;
+use glium::texture::depth_cubemap::DepthCubemap;
+use glium::texture::DepthFormat;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::uniforms::{Sampler, SamplerWrapFunction, MinifySamplerFilter, MagnifySamplerFilter, DepthTextureComparison};
+use glium::draw_parameters::{DrawParameters, BackfaceCullingMode};
+use glium::backend::Facade;
+
+
+/// How the shading pass filters the depth comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowFilter {
+    /// Compare with the nearest texel, which gives hard and aliased edges.
+    Nearest,
+    /// Percentage-closer filtering, the hardware compares the 4 nearest texels and interpolates the results.
+    ///
+    /// Combine it with a few offset lookups in the shader(e.g. `textureProjOffset`) for softer edges.
+    Pcf,
+}
+
+/// Setup `sampler` of a depth texture for `sampler2DShadow`, `sampler2DArrayShadow` or `samplerCubeShadow`.
+pub fn shadow_sampler<T>(sampler: Sampler<'_, T>, filter: ShadowFilter) -> Sampler<'_, T> {
+
+    let (minify_filter, magnify_filter) = match filter {
+        | ShadowFilter::Nearest => (MinifySamplerFilter::Nearest, MagnifySamplerFilter::Nearest),
+        | ShadowFilter::Pcf     => (MinifySamplerFilter::Linear,  MagnifySamplerFilter::Linear),
+    };
+
+    sampler
+        .wrap_function(SamplerWrapFunction::Clamp)
+        .minify_filter(minify_filter)
+        .magnify_filter(magnify_filter)
+        .depth_texture_comparison(Some(DepthTextureComparison::LessOrEqual))
+}
+
+/// Map the normalized device coords of light to the texture coords of shadow map, and z to the window depth written
+/// by the depth pass, which is `z * 0.5 + 0.5` with the default depth range of OpenGL.
+pub fn shadow_bias_matrix() -> Mat4F {
+    Mat4F::new(
+        0.5, 0.0, 0.0, 0.5,
+        0.0, 0.5, 0.0, 0.5,
+        0.0, 0.0, 0.5, 0.5,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+/// The draw parameters of the depth pass, which culls the front faces to reduce shadow acne.
+pub fn depth_pass_parameters() -> DrawParameters<'static> {
+    DrawParameters {
+        depth: glium::Depth {
+            test: glium::DepthTest::IfLess,
+            write: true,
+            ..Default::default()
+        },
+        backface_culling: BackfaceCullingMode::CullCounterClockwise,
+        ..Default::default()
+    }
+}
+
+/// The up vector for looking along `direction`, which must not be parallel to it.
+fn light_up_vector(direction: Vec3F) -> Vec3F {
+    if direction.normalized().y.abs() > 0.99 { Vec3F::unit_z() } else { Vec3F::unit_y() }
+}
+
+/// The orthographic projection in light view which covers `corners`, with extra `caster_distance` towards the light.
+///
+/// The projection is fitted to the bounding sphere of `corners` and snapped to the texels of shadow map,
+/// so that the shadow does not shimmer when the camera moves or rotates.
+fn fit_orthographic(light_view: Mat4F, corners: &[Vec3F; 8], caster_distance: f32, size: u32) -> Mat4F {
+
+    let center = corners.iter().fold(Vec3F::zero(), |sum, corner| sum + *corner) / corners.len() as f32;
+    let radius = corners.iter().fold(0.0_f32, |radius, corner| radius.max(corner.distance(center)));
+    // Round up the radius, so that its rounding error does not change the size of texel.
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let texel_size = 2.0 * radius / size as f32;
+    let light_center = light_view * Vec4F::new(center.x, center.y, center.z, 1.0);
+    let center_x = (light_center.x / texel_size).floor() * texel_size;
+    let center_y = (light_center.y / texel_size).floor() * texel_size;
+
+    // The light looks along -Z in its view space.
+    Mat4F::orthographic_rh_zo(vek::FrustumPlanes {
+        left  : center_x - radius,
+        right : center_x + radius,
+        bottom: center_y - radius,
+        top   : center_y + radius,
+        near  : -(light_center.z + radius) - caster_distance,
+        far   : -(light_center.z - radius),
+    })
+}
+
+
+// Directional & Spot light ------------------------------------------------------------------------
+/// Shadow map of a directional or spot light, stored in a single depth texture.
+pub struct ShadowMap {
+    fbo: GLFrameBuffer<ShadowDepthAttachment>,
+    /// The view of light, whose perspective is also used as the projection of spot light.
+    frustum: Frustum,
+    projection: Mat4F,
+    size: u32,
+}
+
+impl ShadowMap {
+
+    /// Allocate a `size` x `size` depth texture, the light must be set before rendering.
+    pub fn new(display: &impl Facade, size: u32, depth_format: DepthFormat) -> GLResult<ShadowMap> {
+
+        if size == 0 {
+            return Err(GLError::args("The size of shadow map must not be 0."))
+        }
+
+        let fbo = GLFrameBuffer::setup_depth(display, size, size, depth_format)?;
+        let frustum = Frustum::new(display)?;
+        let projection = frustum.get_projection_matrix();
+
+        let shadow_map = ShadowMap { fbo, frustum, projection, size };
+        Ok(shadow_map)
+    }
+
+    /// Use the perspective projection of spot light at `position` pointing to `target`, `fovy` is in degrees.
+    pub fn set_spot_light(&mut self, position: Vec3F, target: Vec3F, up: Vec3F, fovy: f32, near: f32, far: f32) {
+        self.frustum.orient(position, target, up);
+        self.frustum.set_perspective(fovy, 1.0, near, far);
+        self.projection = self.frustum.get_projection_matrix();
+    }
+
+    /// Use the orthographic projection of directional light shining along `direction`, which covers `camera`.
+    ///
+    /// `caster_distance` extends the projection towards the light, for the casters outside the view of camera.
+    pub fn set_directional_light(&mut self, direction: Vec3F, camera: &Frustum, caster_distance: f32) {
+        let corners = camera.get_corners(camera.get_near(), camera.get_far());
+        self.set_directional_light_with_corners(direction, &corners, caster_distance);
+    }
+
+    /// Like `set_directional_light`, but covers the given corners(e.g. the bounding box of scene) instead of camera.
+    pub fn set_directional_light_with_corners(&mut self, direction: Vec3F, corners: &[Vec3F; 8], caster_distance: f32) {
+        // The position does not matter for orthographic projection, so the light view is placed at origin.
+        self.frustum.orient(-direction, Vec3F::zero(), light_up_vector(direction));
+        self.projection = fit_orthographic(self.frustum.get_view_matrix(), corners, caster_distance, self.size);
+    }
+
+    /// The view of light, which can be drawn to visualize a spot light.
+    pub fn frustum(&self) -> &Frustum {
+        &self.frustum
+    }
+
+    pub fn view_matrix(&self) -> Mat4F {
+        self.frustum.get_view_matrix()
+    }
+
+    pub fn projection_matrix(&self) -> Mat4F {
+        self.projection
+    }
+
+    /// The matrix from world coords to the clip coords of light, used by the depth pass.
+    pub fn view_projection(&self) -> Mat4F {
+        self.projection * self.frustum.get_view_matrix()
+    }
+
+    /// The matrix from world coords to the texture coords of shadow map, used by the shading pass.
+    pub fn shadow_matrix(&self) -> Mat4F {
+        shadow_bias_matrix() * self.view_projection()
+    }
+
+    /// Build the framebuffer for the depth pass.
+    pub fn framebuffer(&self) -> GLResult<SimpleFrameBuffer<'_>> {
+        self.fbo.framebuffer()
+    }
+
+    pub fn depth(&self) -> &DepthTexture2d {
+        &self.fbo.attachment.depth
+    }
+
+    /// The depth texture setup for `sampler2DShadow`.
+    pub fn sampler(&self, filter: ShadowFilter) -> Sampler<'_, DepthTexture2d> {
+        shadow_sampler(self.depth().sampled(), filter)
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+// -------------------------------------------------------------------------------------------------
+
+
+// Point light -------------------------------------------------------------------------------------
+/// Omnidirectional shadow map of a point light, stored in a depth cubemap.
+///
+/// Each face is rendered with `view_projection(face)`. The shading pass looks up `samplerCubeShadow` with the
+/// direction from light to the fragment, and compares with the depth computed from `depth_params`:
+///
+/// ```glsl
+/// vec3 d = WorldPosition - LightPosition;
+/// float ma = max(abs(d.x), max(abs(d.y), abs(d.z)));
+/// float shadow = texture(ShadowCube, vec4(d, DepthParams.x - DepthParams.y / ma));
+/// ```
+pub struct PointShadowMap {
+    fbo: GLLayeredFrameBuffer<CubemapDepthAttachment>,
+    position: Vec3F,
+    near: f32,
+    far: f32,
+}
+
+impl PointShadowMap {
+
+    /// Allocate a depth cubemap whose faces are `size` x `size`, the light must be set before rendering.
+    pub fn new(display: &impl Facade, size: u32, depth_format: DepthFormat) -> GLResult<PointShadowMap> {
+
+        if size == 0 {
+            return Err(GLError::args("The size of shadow map must not be 0."))
+        }
+
+        let attachment = CubemapDepthAttachment::new(display, size, depth_format)?;
+        let fbo = GLLayeredFrameBuffer::from_attachment(display, attachment)?;
+
+        let shadow_map = PointShadowMap { fbo, position: Vec3F::zero(), near: 0.1, far: 100.0 };
+        Ok(shadow_map)
+    }
+
+    pub fn set_light(&mut self, position: Vec3F, near: f32, far: f32) {
+        self.position = position;
+        self.near = near;
+        self.far = far;
+    }
+
+    pub fn position(&self) -> Vec3F {
+        self.position
+    }
+
+    /// The matrices from world coords to the clip coords of each face, in the order of `CUBEMAP_FACES`.
+    pub fn view_projections(&self) -> [Mat4F; 6] {
+
+        let projection = cubemap_projection(self.near, self.far);
+        let mut matrices = cubemap_view_matrices(self.position);
+        matrices.iter_mut().for_each(|view| *view = projection * *view);
+        matrices
+    }
+
+    /// The window depth of a fragment at distance `ma` along the major axis is `depth_params[0] - depth_params[1] / ma`.
+    ///
+    /// `cubemap_projection` maps the depth into [0, 1] of NDC, which the default depth range of OpenGL remaps into
+    /// [0.5, 1] of the depth cubemap, so the parameters include the `0.5 * z + 0.5` remapping too.
+    pub fn depth_params(&self) -> [f32; 2] {
+        let range = self.far - self.near;
+        [0.5 * self.far / range + 0.5, 0.5 * self.far * self.near / range]
+    }
+
+    /// Build the framebuffer for the depth pass of `face`, whose index follows `CUBEMAP_FACES`.
+    pub fn framebuffer(&self, face: u32) -> GLResult<SimpleFrameBuffer<'_>> {
+        self.fbo.framebuffer(face)
+    }
+
+    pub fn depth(&self) -> &DepthCubemap {
+        &self.fbo.attachment.depth
+    }
+
+    /// The depth cubemap setup for `samplerCubeShadow`.
+    pub fn sampler(&self, filter: ShadowFilter) -> Sampler<'_, DepthCubemap> {
+        shadow_sampler(self.depth().sampled(), filter)
+    }
+}
+// -------------------------------------------------------------------------------------------------
+
+
+// Cascaded shadow map ------------------------------------------------------------------------------
+/// Shadow map of a directional light split into cascades along the view of camera, one layer of depth texture array
+/// for each cascade.
+///
+/// The near cascades cover small parts of the view, so the shadow near the camera gets more resolution.
+/// The shading pass selects the first cascade whose split distance is beyond the depth of fragment in view space,
+/// and looks up `sampler2DArrayShadow` with `vec4(ShadowCoord.xy, cascade, ShadowCoord.z)`.
+pub struct CascadedShadowMap {
+    fbo: GLLayeredFrameBuffer<LayeredDepthAttachment>,
+    frustum: Frustum,
+    /// The far distance of each cascade in view space.
+    splits: Vec<f32>,
+    projections: Vec<Mat4F>,
+    split_lambda: f32,
+    caster_distance: f32,
+    size: u32,
+}
+
+impl CascadedShadowMap {
+
+    /// Allocate `cascade_count` layers of `size` x `size`, the light must be updated before rendering.
+    pub fn new(display: &impl Facade, size: u32, cascade_count: u32, depth_format: DepthFormat) -> GLResult<CascadedShadowMap> {
+
+        if size == 0 || cascade_count == 0 {
+            return Err(GLError::args("The size and cascade count of shadow map must not be 0."))
+        }
+
+        let attachment = LayeredDepthAttachment::new(display, size, size, cascade_count, depth_format)?;
+        let fbo = GLLayeredFrameBuffer::from_attachment(display, attachment)?;
+        let frustum = Frustum::new(display)?;
+
+        let shadow_map = CascadedShadowMap {
+            fbo, frustum,
+            splits: vec![0.0; cascade_count as usize],
+            projections: vec![Mat4F::identity(); cascade_count as usize],
+            split_lambda: 0.75,
+            caster_distance: 0.0,
+            size,
+        };
+        Ok(shadow_map)
+    }
+
+    /// Blend between the logarithmic(1.0) and uniform(0.0) split of the view, the default is 0.75.
+    pub fn with_split_lambda(mut self, lambda: f32) -> CascadedShadowMap {
+        self.split_lambda = lambda.max(0.0).min(1.0);
+        self
+    }
+
+    /// Extend each cascade towards the light by `distance`, for the casters outside the view of camera.
+    pub fn with_caster_distance(mut self, distance: f32) -> CascadedShadowMap {
+        self.caster_distance = distance;
+        self
+    }
+
+    /// Split the view of `camera`, and fit each cascade of the light shining along `direction` to its split.
+    pub fn update(&mut self, direction: Vec3F, camera: &Frustum) {
+
+        let (near, far) = (camera.get_near(), camera.get_far());
+        let cascade_count = self.splits.len();
+
+        for (i, split) in self.splits.iter_mut().enumerate() {
+            let ratio = (i + 1) as f32 / cascade_count as f32;
+            let log_split = near * (far / near).powf(ratio);
+            let uniform_split = near + (far - near) * ratio;
+            *split = self.split_lambda * log_split + (1.0 - self.split_lambda) * uniform_split;
+        }
+
+        self.frustum.orient(-direction, Vec3F::zero(), light_up_vector(direction));
+        let light_view = self.frustum.get_view_matrix();
+
+        let mut split_near = near;
+        for (split, projection) in self.splits.iter().zip(self.projections.iter_mut()) {
+            let corners = camera.get_corners(split_near, *split);
+            *projection = fit_orthographic(light_view, &corners, self.caster_distance, self.size);
+            split_near = *split;
+        }
+    }
+
+    pub fn cascade_count(&self) -> u32 {
+        self.splits.len() as u32
+    }
+
+    /// The far distance of each cascade in view space, as positive values.
+    pub fn split_distances(&self) -> &[f32] {
+        &self.splits
+    }
+
+    /// The matrix from world coords to the clip coords of `cascade`, used by its depth pass.
+    pub fn view_projection(&self, cascade: u32) -> Mat4F {
+        self.projections[cascade as usize] * self.frustum.get_view_matrix()
+    }
+
+    /// The matrix from world coords to the texture coords of `cascade`, used by the shading pass.
+    pub fn shadow_matrix(&self, cascade: u32) -> Mat4F {
+        shadow_bias_matrix() * self.view_projection(cascade)
+    }
+
+    /// Build the framebuffer for the depth pass of `cascade`.
+    pub fn framebuffer(&self, cascade: u32) -> GLResult<SimpleFrameBuffer<'_>> {
+        self.fbo.framebuffer(cascade)
+    }
+
+    pub fn depth(&self) -> &DepthTexture2dArray {
+        &self.fbo.attachment.depth
+    }
+
+    /// The depth texture array setup for `sampler2DArrayShadow`.
+    pub fn sampler(&self, filter: ShadowFilter) -> Sampler<'_, DepthTexture2dArray> {
+        shadow_sampler(self.depth().sampled(), filter)
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+// -------------------------------------------------------------------------------------------------
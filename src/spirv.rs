@@ -0,0 +1,442 @@
+
+use std::path::Path;
+
+use crate::error::{GLResult, GLError};
+
+use glium::backend::Facade;
+use glium::program::{SpirvProgram, SpirvEntryPoint};
+use glium::CapabilitiesSource;
+
+const SPIRV_MAGIC_NUMBER: u32 = 0x0723_0203;
+const SPIRV_HEADER_LENGTH: usize = 5;
+
+// The opcodes and enumerants required to inspect and specialize a module.
+// See https://www.khronos.org/registry/spir-v/specs/unified1/SPIRV.html for detail.
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL : u32 = 20;
+const OP_TYPE_INT  : u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_SPEC_CONSTANT_TRUE : u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_DECORATE: u32 = 71;
+const DECORATION_SPEC_ID: u32 = 1;
+
+
+/// The shader stage(the `Execution Model` in SPIR-V specification) of an entry point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpirvStage {
+    Vertex,
+    TessellationControl,
+    TessellationEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl SpirvStage {
+
+    fn from_execution_model(model: u32) -> Option<SpirvStage> {
+        match model {
+            | 0 => Some(SpirvStage::Vertex),
+            | 1 => Some(SpirvStage::TessellationControl),
+            | 2 => Some(SpirvStage::TessellationEvaluation),
+            | 3 => Some(SpirvStage::Geometry),
+            | 4 => Some(SpirvStage::Fragment),
+            | 5 => Some(SpirvStage::Compute),
+            | _ => None, // Kernel and other execution models are not used by OpenGL.
+        }
+    }
+}
+
+/// The value used to override the default value of a specialization constant.
+#[derive(Debug, Clone, Copy)]
+pub enum SpecConstant {
+    Bool(bool),
+    Int(i32),
+    UInt(u32),
+    Float(f32),
+}
+
+
+/// A SPIR-V module loaded in memory, whose words have been converted to host endianness.
+#[derive(Debug, Clone)]
+pub struct SpirvModule {
+    words: Vec<u32>,
+    binary: Vec<u8>,
+}
+
+impl SpirvModule {
+
+    /// Load a SPIR-V module(usually with `.spv` extension) from local file.
+    pub fn load(path: impl AsRef<Path>) -> GLResult<SpirvModule> {
+
+        if !path.as_ref().exists() {
+            return Err(GLError::path(path))
+        }
+
+        let bytes = std::fs::read(path.as_ref())
+            .map_err(GLError::io)?;
+        SpirvModule::from_bytes(&bytes)
+    }
+
+    /// Create a SPIR-V module from raw bytes. The endianness of the module is detected from its magic number.
+    pub fn from_bytes(bytes: &[u8]) -> GLResult<SpirvModule> {
+
+        if bytes.len() % 4 != 0 || bytes.len() < SPIRV_HEADER_LENGTH * 4 {
+            return Err(GLError::custom("The size of SPIR-V module must be a multiple of 4 and contain a complete header."))
+        }
+
+        let mut words: Vec<u32> = bytes.chunks(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+
+        if words[0] != SPIRV_MAGIC_NUMBER {
+            if words[0].swap_bytes() == SPIRV_MAGIC_NUMBER {
+                words.iter_mut().for_each(|word| *word = word.swap_bytes());
+            } else {
+                return Err(GLError::custom("Invalid magic number in SPIR-V module."))
+            }
+        }
+
+        let module = SpirvModule::from_words(words);
+        // Walk through the instructions once to make sure the module is well-formed.
+        module.instructions()?;
+        Ok(module)
+    }
+
+    fn from_words(words: Vec<u32>) -> SpirvModule {
+        let binary = words.iter()
+            .flat_map(|word| word.to_ne_bytes().to_vec())
+            .collect();
+        SpirvModule { words, binary }
+    }
+
+    /// The binary data of this module, which is ready to be uploaded to OpenGL.
+    pub fn binary(&self) -> &[u8] {
+        &self.binary
+    }
+
+    /// Return all the entry points declared in this module.
+    pub fn entry_points(&self) -> Vec<(SpirvStage, String)> {
+
+        let mut entry_points = Vec::new();
+        // The module has been checked in construction, so just ignore the error here.
+        for (opcode, operands) in self.instructions().unwrap_or_default() {
+            if opcode == OP_ENTRY_POINT && operands.len() >= 3 {
+                if let Some(stage) = SpirvStage::from_execution_model(operands[0]) {
+                    entry_points.push((stage, decode_literal_string(&operands[2..])));
+                }
+            }
+        }
+        entry_points
+    }
+
+    /// Check if the entry point of specific stage exists in this module.
+    pub fn has_entry_point(&self, stage: SpirvStage, name: &str) -> bool {
+        self.entry_points().iter()
+            .any(|(entry_stage, entry_name)| *entry_stage == stage && entry_name == name)
+    }
+
+    /// Create a new module whose specialization constants are replaced by the given values.
+    ///
+    /// OpenGL only accepts specialization constants through `glSpecializeShader`, which glium always calls with zero constants.
+    /// Here the default values of `OpSpecConstant*` instructions are patched instead, which produces the same result.
+    /// Each constant is indexed by its `constant_id` in GLSL(e.g. `layout (constant_id = 0) const float Scale = 1.0;`).
+    pub fn specialize(&self, constants: &[(u32, SpecConstant)]) -> GLResult<SpirvModule> {
+
+        let mut words = self.words.clone();
+
+        // Collect the result id of each specialization constant and the declared scalar types.
+        let mut spec_ids: Vec<(u32, u32)> = Vec::new(); // (spec id, result id)
+        let mut scalar_types: Vec<(u32, u32, u32)> = Vec::new(); // (result id, opcode, width)
+        let mut spec_constants: Vec<(usize, u32, u32)> = Vec::new(); // (word index, result type, result id)
+
+        let mut index = SPIRV_HEADER_LENGTH;
+        while index < words.len() {
+            let (opcode, word_count) = (words[index] & 0xFFFF, (words[index] >> 16) as usize);
+            let operands = &words[(index + 1)..(index + word_count)];
+
+            match opcode {
+                | OP_DECORATE if operands.len() >= 3 && operands[1] == DECORATION_SPEC_ID => {
+                    spec_ids.push((operands[2], operands[0]));
+                },
+                | OP_TYPE_BOOL => {
+                    scalar_types.push((operands[0], opcode, 0));
+                },
+                | OP_TYPE_INT | OP_TYPE_FLOAT => {
+                    scalar_types.push((operands[0], opcode, operands[1]));
+                },
+                | OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                    spec_constants.push((index, operands[0], operands[1]));
+                },
+                | _ => {},
+            }
+
+            index += word_count;
+        }
+
+        for &(constant_id, value) in constants {
+
+            let result_id = spec_ids.iter()
+                .find(|(spec_id, _)| *spec_id == constant_id)
+                .map(|(_, result_id)| *result_id)
+                .ok_or_else(|| GLError::custom(format!("No specialization constant with constant_id = {} in SPIR-V module.", constant_id)))?;
+            let &(word_index, result_type, _) = spec_constants.iter()
+                .find(|(_, _, id)| *id == result_id)
+                .ok_or_else(|| GLError::custom(format!("The specialization constant with constant_id = {} is not a scalar constant.", constant_id)))?;
+            let &(_, type_opcode, type_width) = scalar_types.iter()
+                .find(|(id, _, _)| *id == result_type)
+                .ok_or_else(|| GLError::custom(format!("Unknown type of specialization constant with constant_id = {}.", constant_id)))?;
+
+            match (value, type_opcode, type_width) {
+                | (SpecConstant::Bool(v), OP_TYPE_BOOL, _) => {
+                    let opcode = if v { OP_SPEC_CONSTANT_TRUE } else { OP_SPEC_CONSTANT_FALSE };
+                    words[word_index] = (words[word_index] & 0xFFFF_0000) | opcode;
+                },
+                | (SpecConstant::Int(v), OP_TYPE_INT, 32) => {
+                    words[word_index + 3] = v as u32;
+                },
+                | (SpecConstant::UInt(v), OP_TYPE_INT, 32) => {
+                    words[word_index + 3] = v;
+                },
+                | (SpecConstant::Float(v), OP_TYPE_FLOAT, 32) => {
+                    words[word_index + 3] = v.to_bits();
+                },
+                | _ => {
+                    return Err(GLError::custom(format!("The type of value {:?} does not match the specialization constant with constant_id = {}.", value, constant_id)))
+                }
+            }
+        }
+
+        Ok(SpirvModule::from_words(words))
+    }
+
+    /// Split the module into (opcode, operands) pairs.
+    fn instructions(&self) -> GLResult<Vec<(u32, &[u32])>> {
+
+        let mut instructions = Vec::new();
+        let mut index = SPIRV_HEADER_LENGTH;
+
+        while index < self.words.len() {
+            let opcode = self.words[index] & 0xFFFF;
+            let word_count = (self.words[index] >> 16) as usize;
+
+            if word_count == 0 || index + word_count > self.words.len() {
+                return Err(GLError::custom("Found truncated instruction in SPIR-V module."))
+            }
+
+            instructions.push((opcode, &self.words[(index + 1)..(index + word_count)]));
+            index += word_count;
+        }
+
+        Ok(instructions)
+    }
+}
+
+/// Decode a nul-terminated literal string, which is packed into words in little-endian order.
+fn decode_literal_string(words: &[u32]) -> String {
+
+    let bytes: Vec<u8> = words.iter()
+        .flat_map(|word| word.to_le_bytes().to_vec())
+        .take_while(|byte| *byte != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+
+/// An entry point of a SPIR-V module for a specific shader stage.
+#[derive(Debug, Clone, Copy)]
+pub struct SpirvShader<'a> {
+    module: &'a SpirvModule,
+    entry_point: &'a str,
+}
+
+impl<'a> SpirvShader<'a> {
+
+    pub fn new(module: &'a SpirvModule, entry_point: &'a str) -> SpirvShader<'a> {
+        SpirvShader { module, entry_point }
+    }
+
+    fn validate(&self, stage: SpirvStage) -> GLResult<()> {
+        if self.module.has_entry_point(stage, self.entry_point) {
+            Ok(())
+        } else {
+            Err(GLError::custom(format!("Entry point \"{}\" for {:?} stage is not found in SPIR-V module.", self.entry_point, stage)))
+        }
+    }
+
+    fn entry_point(&self) -> SpirvEntryPoint<'a> {
+        SpirvEntryPoint { binary: self.module.binary(), entry_point: self.entry_point }
+    }
+}
+
+
+/// The SPIR-V counterpart of `GLSourceCode`, which is attached to it by `GLSourceCode::with_spirv`.
+#[derive(Debug, Clone)]
+pub struct GLSpirvCode<'a> {
+    vertex_shader  : SpirvShader<'a>,
+    fragment_shader: SpirvShader<'a>,
+    tessellation_control_shader   : Option<SpirvShader<'a>>,
+    tessellation_evaluation_shader: Option<SpirvShader<'a>>,
+    geometry_shader: Option<SpirvShader<'a>>,
+}
+
+impl<'a> GLSpirvCode<'a> {
+
+    pub fn new(vertex_shader: SpirvShader<'a>, fragment_shader: SpirvShader<'a>) -> GLSpirvCode<'a> {
+        GLSpirvCode {
+            vertex_shader, fragment_shader,
+            tessellation_control_shader: None,
+            tessellation_evaluation_shader: None,
+            geometry_shader: None,
+        }
+    }
+
+    pub fn with_geometry_shader(mut self, shader: SpirvShader<'a>) -> GLSpirvCode<'a> {
+        self.geometry_shader = Some(shader);
+        self
+    }
+
+    pub fn with_tessellation_control_shader(mut self, shader: SpirvShader<'a>) -> GLSpirvCode<'a> {
+        self.tessellation_control_shader = Some(shader);
+        self
+    }
+
+    pub fn with_tessellation_evaluation_shader(mut self, shader: SpirvShader<'a>) -> GLSpirvCode<'a> {
+        self.tessellation_evaluation_shader = Some(shader);
+        self
+    }
+
+    /// Make sure each stage refers to an existing entry point of the right execution model.
+    pub fn validate(&self) -> GLResult<()> {
+
+        self.vertex_shader.validate(SpirvStage::Vertex)?;
+        self.fragment_shader.validate(SpirvStage::Fragment)?;
+
+        if let Some(ref shader) = self.tessellation_control_shader {
+            shader.validate(SpirvStage::TessellationControl)?;
+        }
+        if let Some(ref shader) = self.tessellation_evaluation_shader {
+            shader.validate(SpirvStage::TessellationEvaluation)?;
+        }
+        if let Some(ref shader) = self.geometry_shader {
+            shader.validate(SpirvStage::Geometry)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn build_program(&self, outputs_srgb: bool, uses_point_size: bool, transform_feedback_varyings: Option<(Vec<String>, glium::program::TransformFeedbackMode)>) -> SpirvProgram<'a> {
+        SpirvProgram::from_vs_and_fs(self.vertex_shader.entry_point(), self.fragment_shader.entry_point())
+            .tessellation_control_shader(self.tessellation_control_shader.map(|shader| shader.entry_point()))
+            .tessellation_evaluation_shader(self.tessellation_evaluation_shader.map(|shader| shader.entry_point()))
+            .geometry_shader(self.geometry_shader.map(|shader| shader.entry_point()))
+            .transform_feedback_varyings(transform_feedback_varyings)
+            .outputs_srgb(outputs_srgb)
+            .uses_point_size(uses_point_size)
+    }
+}
+
+/// Check if current OpenGL context is able to consume SPIR-V shaders(core in OpenGL 4.6, or ARB_gl_spirv extension).
+pub fn is_spirv_supported(display: &impl Facade) -> bool {
+
+    let context = display.get_context();
+    *context.get_opengl_version() >= glium::Version(glium::Api::Gl, 4, 6) ||
+        (*context.get_opengl_version() >= glium::Version(glium::Api::Gl, 4, 1) && context.get_extensions().gl_arb_gl_spirv)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// A fragment shader module declaring `main`, a float spec constant(id 7, default 1.0) and a bool spec constant(id 8, default true).
+    fn module_words() -> Vec<u32> {
+        vec![
+            // header: magic, version 1.0, generator, bound, schema
+            SPIRV_MAGIC_NUMBER, 0x0001_0000, 0, 6, 0,
+            // OpEntryPoint Fragment %1 "main"
+            (5 << 16) | OP_ENTRY_POINT, 4, 1, u32::from_le_bytes(*b"main"), 0,
+            // OpDecorate %3 SpecId 7
+            (4 << 16) | OP_DECORATE, 3, DECORATION_SPEC_ID, 7,
+            // OpDecorate %5 SpecId 8
+            (4 << 16) | OP_DECORATE, 5, DECORATION_SPEC_ID, 8,
+            // %2 = OpTypeFloat 32
+            (3 << 16) | OP_TYPE_FLOAT, 2, 32,
+            // %3 = OpSpecConstant %2 1.0
+            (4 << 16) | OP_SPEC_CONSTANT, 2, 3, 1.0_f32.to_bits(),
+            // %4 = OpTypeBool
+            (2 << 16) | OP_TYPE_BOOL, 4,
+            // %5 = OpSpecConstantTrue %4
+            (3 << 16) | OP_SPEC_CONSTANT_TRUE, 4, 5,
+        ]
+    }
+
+    fn to_bytes(words: &[u32], big_endian: bool) -> Vec<u8> {
+        words.iter()
+            .flat_map(|word| if big_endian { word.to_be_bytes() } else { word.to_le_bytes() }.to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn entry_points_are_parsed() {
+
+        let module = SpirvModule::from_bytes(&to_bytes(&module_words(), false)).unwrap();
+
+        assert_eq!(module.entry_points(), vec![(SpirvStage::Fragment, String::from("main"))]);
+        assert!(module.has_entry_point(SpirvStage::Fragment, "main"));
+        assert!(!module.has_entry_point(SpirvStage::Vertex, "main"));
+    }
+
+    #[test]
+    fn byte_swapped_module_is_converted() {
+
+        let little = SpirvModule::from_bytes(&to_bytes(&module_words(), false)).unwrap();
+        let big = SpirvModule::from_bytes(&to_bytes(&module_words(), true)).unwrap();
+
+        assert_eq!(big.words, module_words());
+        assert_eq!(big.binary(), little.binary());
+    }
+
+    #[test]
+    fn malformed_modules_are_rejected() {
+
+        let mut words = module_words();
+        words[0] = 0xDEAD_BEEF;
+        assert!(SpirvModule::from_bytes(&to_bytes(&words, false)).is_err());
+
+        // The last instruction declares 3 words, but only 2 are left.
+        let words = module_words();
+        assert!(SpirvModule::from_bytes(&to_bytes(&words[..words.len() - 1], false)).is_err());
+
+        let mut bytes = to_bytes(&module_words(), false);
+        bytes.pop();
+        assert!(SpirvModule::from_bytes(&bytes).is_err());
+
+        assert!(SpirvModule::from_bytes(&to_bytes(&module_words()[..3], false)).is_err());
+    }
+
+    #[test]
+    fn spec_constants_are_patched() {
+
+        let module = SpirvModule::from_bytes(&to_bytes(&module_words(), false)).unwrap();
+        let specialized = module.specialize(&[(7, SpecConstant::Float(2.5)), (8, SpecConstant::Bool(false))]).unwrap();
+
+        let mut expected = module_words();
+        expected[24] = 2.5_f32.to_bits();
+        expected[27] = (3 << 16) | OP_SPEC_CONSTANT_FALSE;
+        assert_eq!(specialized.words, expected);
+    }
+
+    #[test]
+    fn unknown_or_mismatched_spec_constants_are_rejected() {
+
+        let module = SpirvModule::from_bytes(&to_bytes(&module_words(), false)).unwrap();
+
+        assert!(module.specialize(&[(9, SpecConstant::Float(2.5))]).is_err());
+        assert!(module.specialize(&[(7, SpecConstant::Int(2))]).is_err());
+        assert!(module.specialize(&[(8, SpecConstant::Float(0.0))]).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+
+//! Common stencil configurations for `glium::DrawParameters`.
+//!
+//! The surface must have a stencil buffer, either the window requested by `SceneParams::with_stencil_buffer`,
+//! or a render target with Depth-Stencil component(e.g. `ColorDepthStencilAttachment`):
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::stencil;
+//!
+//! framebuffer.clear_color_depth_and_stencil((0.0, 0.0, 0.0, 1.0), 1.0, 0);
+//!
+//! // Mark the pixels covered by the portal with 1, then draw the scene only inside it.
+//! let mark_params = glium::DrawParameters {
+//!     stencil: stencil::write(1),
+//!     color_mask: (false, false, false, false),
+//!     ..Default::default()
+//! };
+//! let draw_params = glium::DrawParameters {
+//!     stencil: stencil::test_equal(1),
+//!     ..Default::default()
+//! };
+//! ```
+
+use glium::draw_parameters::{Stencil, StencilTest, StencilOperation};
+
+
+/// The mask of 8-bit stencil buffer, which is the usual format.
+const STENCIL_MASK: u32 = 0xff;
+
+/// The same test and operations for both front and back faces.
+fn both_faces(test: StencilTest, reference_value: i32, fail: StencilOperation, depth_fail: StencilOperation, pass: StencilOperation) -> Stencil {
+    Stencil {
+        test_clockwise: test,
+        reference_value_clockwise: reference_value,
+        write_mask_clockwise: STENCIL_MASK,
+        fail_operation_clockwise: fail,
+        pass_depth_fail_operation_clockwise: depth_fail,
+        depth_pass_operation_clockwise: pass,
+        test_counter_clockwise: test,
+        reference_value_counter_clockwise: reference_value,
+        write_mask_counter_clockwise: STENCIL_MASK,
+        fail_operation_counter_clockwise: fail,
+        pass_depth_fail_operation_counter_clockwise: depth_fail,
+        depth_pass_operation_counter_clockwise: pass,
+    }
+}
+
+/// Write `value` to the stencil buffer wherever the geometry passes the depth test.
+pub fn write(value: i32) -> Stencil {
+    both_faces(StencilTest::AlwaysPass, value, StencilOperation::Keep, StencilOperation::Keep, StencilOperation::Replace)
+}
+
+/// Draw only where the stencil value equals `value`, keeping the stencil buffer unchanged.
+pub fn test_equal(value: i32) -> Stencil {
+    both_faces(StencilTest::IfEqual { mask: STENCIL_MASK }, value, StencilOperation::Keep, StencilOperation::Keep, StencilOperation::Keep)
+}
+
+/// Draw only where the stencil value differs from `value`(e.g. the outline outside the marked object).
+pub fn test_not_equal(value: i32) -> Stencil {
+    both_faces(StencilTest::IfNotEqual { mask: STENCIL_MASK }, value, StencilOperation::Keep, StencilOperation::Keep, StencilOperation::Keep)
+}
+
+/// Count the shadow volumes in front of each pixel with the depth-pass method.
+///
+/// The front faces increase and the back faces decrease the count when they pass the depth test, so the pixels
+/// with non-zero count are in shadow. Render the volumes after the depth of scene, without writing depth or color.
+/// It fails when the camera is inside a volume, use `shadow_volume_depth_fail` in that case.
+pub fn shadow_volume_depth_pass() -> Stencil {
+    Stencil {
+        // The counter-clockwise faces are the front faces by default.
+        depth_pass_operation_counter_clockwise: StencilOperation::IncrementWrap,
+        depth_pass_operation_clockwise: StencilOperation::DecrementWrap,
+        ..both_faces(StencilTest::AlwaysPass, 0, StencilOperation::Keep, StencilOperation::Keep, StencilOperation::Keep)
+    }
+}
+
+/// Count the shadow volumes behind each pixel with the depth-fail method(Carmack's reverse).
+///
+/// The back faces increase and the front faces decrease the count when they fail the depth test, which works
+/// even when the camera is inside a volume, but the volumes must be closed with caps.
+pub fn shadow_volume_depth_fail() -> Stencil {
+    Stencil {
+        pass_depth_fail_operation_clockwise: StencilOperation::IncrementWrap,
+        pass_depth_fail_operation_counter_clockwise: StencilOperation::DecrementWrap,
+        ..both_faces(StencilTest::AlwaysPass, 0, StencilOperation::Keep, StencilOperation::Keep, StencilOperation::Keep)
+    }
+}
+
+/// Draw only the pixels outside any shadow volume, after counting them by `shadow_volume_depth_pass` or
+/// `shadow_volume_depth_fail`.
+pub fn outside_shadow_volume() -> Stencil {
+    test_equal(0)
+}
@@ -0,0 +1,181 @@
+
+//! Reflection and selection of shader subroutines.
+//!
+//! Query the subroutine uniforms of a linked program with `SubroutineReflection`,
+//! and choose the subroutines for a draw call by name with `SubroutineSelection`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::subroutine::SubroutineSelection;
+//! use glium::program::ShaderStage;
+//!
+//! let phong = SubroutineSelection::new()
+//!     .select("shadeModel", ShaderStage::Vertex, "phongModel");
+//! phong.verify(&program)?;
+//!
+//! let mut uniforms = DynamicUniforms::new();
+//! uniforms.add_subroutines(&phong);
+//! ```
+//!
+//! OpenGL resets the subroutine uniforms whenever the program is bound,
+//! so every subroutine uniform of the program must be assigned for each draw call.
+
+use crate::error::{GLResult, GLError};
+
+use glium::program::ShaderStage;
+use glium::Program;
+
+
+/// A subroutine uniform of a linked program.
+#[derive(Debug, Clone)]
+pub struct SubroutineUniformInfo {
+    pub name: String,
+    pub stage: ShaderStage,
+    /// The length of array if the subroutine uniform is declared as an array.
+    pub size: Option<usize>,
+    /// The names of subroutines which can be assigned to this uniform.
+    pub subroutines: Vec<String>,
+}
+
+/// All the active subroutine uniforms of a linked program.
+#[derive(Debug, Clone)]
+pub struct SubroutineReflection {
+    uniforms: Vec<SubroutineUniformInfo>,
+}
+
+impl SubroutineReflection {
+
+    pub fn new(program: &Program) -> SubroutineReflection {
+
+        let mut uniforms: Vec<SubroutineUniformInfo> = program.get_subroutine_uniforms().iter()
+            .map(|((name, stage), uniform)| {
+                let mut subroutines: Vec<String> = uniform.compatible_subroutines.iter()
+                    .map(|subroutine| subroutine.name.clone())
+                    .collect();
+                subroutines.sort();
+
+                SubroutineUniformInfo { name: name.clone(), stage: *stage, size: uniform.size, subroutines }
+            }).collect();
+
+        // The reflection of glium is stored in HashMap, sort it to keep the output stable.
+        uniforms.sort_by(|lhs, rhs| {
+            (stage_order(lhs.stage), &lhs.name).cmp(&(stage_order(rhs.stage), &rhs.name))
+        });
+
+        SubroutineReflection { uniforms }
+    }
+
+    /// All the subroutine uniforms, ordered by shader stage and name.
+    pub fn uniforms(&self) -> &[SubroutineUniformInfo] {
+        &self.uniforms
+    }
+
+    /// The subroutine uniforms of the given shader stage.
+    pub fn uniforms_of_stage(&self, stage: ShaderStage) -> impl Iterator<Item = &SubroutineUniformInfo> {
+        self.uniforms.iter().filter(move |uniform| uniform.stage == stage)
+    }
+
+    pub fn find(&self, name: &str, stage: ShaderStage) -> Option<&SubroutineUniformInfo> {
+        self.uniforms.iter().find(|uniform| uniform.name == name && uniform.stage == stage)
+    }
+
+    /// The names of subroutines which can be assigned to subroutine uniform `name` of `stage`.
+    pub fn subroutines(&self, name: &str, stage: ShaderStage) -> Option<&[String]> {
+        self.find(name, stage).map(|uniform| uniform.subroutines.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uniforms.is_empty()
+    }
+
+    /// Check that `selection` assigns a compatible subroutine to every subroutine uniform, and nothing else.
+    pub fn verify(&self, selection: &SubroutineSelection) -> GLResult<()> {
+
+        let mut mismatches = Vec::new();
+
+        for uniform in self.uniforms.iter() {
+            if uniform.size.map_or(false, |size| size > 1) {
+                mismatches.push(format!("'{}' in {:?} shader is an array of subroutine uniforms, which is not supported.", uniform.name, uniform.stage));
+                continue
+            }
+
+            match selection.find(&uniform.name, uniform.stage) {
+                | Some(subroutine) if uniform.subroutines.iter().all(|name| name != subroutine) => {
+                    mismatches.push(format!("'{}' is not compatible with '{}' in {:?} shader, expect one of [{}].",
+                        subroutine, uniform.name, uniform.stage, uniform.subroutines.join(", ")));
+                },
+                | Some(_) => {},
+                | None => {
+                    mismatches.push(format!("'{}' in {:?} shader is not assigned, expect one of [{}].",
+                        uniform.name, uniform.stage, uniform.subroutines.join(", ")));
+                },
+            }
+        }
+
+        for (name, stage, _) in selection.bindings.iter() {
+            if self.find(name, *stage).is_none() {
+                mismatches.push(format!("'{}' is not an active subroutine uniform in {:?} shader.", name, stage));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(GLError::interface(format!("Subroutine selection does not match the shader program:\n\t{}", mismatches.join("\n\t"))))
+        }
+    }
+}
+
+/// The subroutines assigned to subroutine uniforms for a draw call.
+#[derive(Debug, Clone, Default)]
+pub struct SubroutineSelection {
+    /// (subroutine uniform, shader stage, subroutine)
+    bindings: Vec<(String, ShaderStage, String)>,
+}
+
+impl SubroutineSelection {
+
+    pub fn new() -> SubroutineSelection {
+        SubroutineSelection { bindings: Vec::new() }
+    }
+
+    /// Assign `subroutine` to the subroutine uniform `uniform` of `stage`, replacing the previous one.
+    pub fn select(mut self, uniform: impl Into<String>, stage: ShaderStage, subroutine: impl Into<String>) -> SubroutineSelection {
+
+        let uniform = uniform.into();
+        let subroutine = subroutine.into();
+
+        match self.bindings.iter_mut().find(|(name, exist_stage, _)| *name == uniform && *exist_stage == stage) {
+            | Some(binding) => binding.2 = subroutine,
+            | None => self.bindings.push((uniform, stage, subroutine)),
+        }
+        self
+    }
+
+    /// The subroutine assigned to subroutine uniform `uniform` of `stage`.
+    pub fn find(&self, uniform: &str, stage: ShaderStage) -> Option<&str> {
+        self.bindings.iter()
+            .find(|(name, exist_stage, _)| name == uniform && *exist_stage == stage)
+            .map(|(_, _, subroutine)| subroutine.as_str())
+    }
+
+    /// Iterate over the (subroutine uniform, shader stage, subroutine) of this selection.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ShaderStage, &str)> {
+        self.bindings.iter().map(|(name, stage, subroutine)| (name.as_str(), *stage, subroutine.as_str()))
+    }
+
+    /// Check this selection against the subroutine uniforms of `program`, see `SubroutineReflection::verify`.
+    pub fn verify(&self, program: &Program) -> GLResult<()> {
+        SubroutineReflection::new(program).verify(self)
+    }
+}
+
+fn stage_order(stage: ShaderStage) -> usize {
+
+    match stage {
+        | ShaderStage::Vertex                 => 0,
+        | ShaderStage::TessellationControl    => 1,
+        | ShaderStage::TessellationEvaluation => 2,
+        | ShaderStage::Geometry               => 3,
+        | ShaderStage::Fragment               => 4,
+    }
+}
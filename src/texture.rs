@@ -1,77 +1,81 @@
 
 use std::path::Path;
-use std::fs::File;
 
 use crate::error::{GLResult, GLError, GLErrorKind, BufferCreationErrorKind};
+use crate::image::{Image, ColorSpace, TextureOrigin, load_image};
+use crate::mipmap::{MipmapGenerator, MipFilter};
 
 use glium::backend::Facade;
 use glium::texture::{RawImage2d, MipmapsOption, UncompressedFloatFormat, CubeLayer};
 use glium::texture::texture2d::Texture2d;
+use glium::texture::srgb_texture2d::SrgbTexture2d;
 use glium::texture::cubemap::Cubemap;
 use glium::Surface;
 
-const CUBEMAP_FACES_SUFFIXES: [&str; 6] = [
+pub(crate) const CUBEMAP_FACES_SUFFIXES: [&str; 6] = [
     "posx", "negx",
     "posy", "negy",
     "posz", "negz",
 ];
-const CUBEMAP_LAYWERS: [CubeLayer; 6] = [
+pub(crate) const CUBEMAP_LAYWERS: [CubeLayer; 6] = [
     CubeLayer::PositiveX, CubeLayer::NegativeX,
     CubeLayer::PositiveY, CubeLayer::NegativeY,
     CubeLayer::PositiveZ, CubeLayer::NegativeZ,
 ];
 
 
-#[derive(Debug, Clone)]
-struct PngContent {
-    width : u32,
-    height: u32,
-    color_type: png::ColorType,
-    buffer: Vec<u8>,
+/// Load an image file from local to GPU, and return the corresponding Texture2d object.
+/// See `image::load_image` for the supported formats. The texture format matches the channels and bit depth of file.
+/// The values are stored as they are in the file, so use this for linear data such as normal maps and height maps,
+/// and `load_srgb_texture` for colors.
+/// The top of image is at the texture coordinate t = 1, see `load_texture_with_origin` for other placement.
+pub fn load_texture(display: &impl Facade, path: impl AsRef<Path>) -> GLResult<Texture2d> {
+    load_texture_with_origin(display, path, TextureOrigin::BottomLeft)
 }
 
-/// Load the image pixels to a buffer. This function only support PNG file.
-fn load_png_pixels(path: impl AsRef<Path>, is_flip: bool) -> GLResult<PngContent> {
-
-    if is_flip {
-        return Err(GLError::unimplemented("Load png image with flipping."))
-    }
+/// Load an image file from local to GPU, placing the top row of image according to `origin`.
+pub fn load_texture_with_origin(display: &impl Facade, path: impl AsRef<Path>, origin: TextureOrigin) -> GLResult<Texture2d> {
 
-    let png_file = File::open(path.as_ref())
-        .map_err(GLError::io)?;
-    let decoder = png::Decoder::new(png_file);
-    let (info, mut reader) = decoder.read_info()
-        .map_err(|e| GLError::custom(e.to_string()))?;
+    let image = load_image(path)?
+        .with_color_space(ColorSpace::Linear);
 
-    if info.color_type != png::ColorType::RGB && info.color_type != png::ColorType::RGBA {
-        println!("The png file at {:?} is not an RGB or RGBA image.", path.as_ref());
-    }
+    // The original C++ implementation does not use mipmap, here use mipmap should be generally ok.
+    image.upload_with_origin(display, MipmapsOption::AutoGeneratedMipmaps, origin)
+}
 
-    let mut content = PngContent {
-        width : info.width,
-        height: info.height,
-        color_type: info.color_type,
-        buffer: vec![0; info.buffer_size()],
-    };
+/// Load an image file from local to GPU, with the mip chain generated on CPU by `generator` instead of the driver.
+/// The top of image is at the texture coordinate t = 1, as `load_texture` does.
+pub fn load_texture_with_mipmaps(display: &impl Facade, path: impl AsRef<Path>, generator: &MipmapGenerator) -> GLResult<Texture2d> {
 
-    reader.next_frame(&mut content.buffer)
-        .map_err(|e| GLError::custom(e.to_string()))?;
+    let image = load_image(path)?
+        .with_color_space(ColorSpace::Linear);
+    generator.generate(&image)?
+        .upload(display, TextureOrigin::BottomLeft)
+}
 
-    Ok(content)
+/// Load an 8-bit color image file from local to GPU as an sRGB texture, whose sampler decodes the colors to linear.
+/// Use this for albedo and other color textures painted on a monitor, so the shaders light linear colors.
+/// The top of image is at the texture coordinate t = 1, see `load_srgb_texture_with_origin` for other placement.
+pub fn load_srgb_texture(display: &impl Facade, path: impl AsRef<Path>) -> GLResult<SrgbTexture2d> {
+    load_srgb_texture_with_origin(display, path, TextureOrigin::BottomLeft)
 }
 
-/// Load an image file from local to GPU, and return the corresponding Texture2d object.
-/// This function only support PNG file.
-pub fn load_texture(display: &impl Facade, path: impl AsRef<Path>) -> GLResult<Texture2d> {
+/// Load an 8-bit color image file from local to GPU as an sRGB texture, placing the top row of image according to `origin`.
+pub fn load_srgb_texture_with_origin(display: &impl Facade, path: impl AsRef<Path>, origin: TextureOrigin) -> GLResult<SrgbTexture2d> {
 
-    let pixels = load_png_pixels(path, false)?;
-    let raw_image = pixels.build_raw_image();
+    let image = load_image(path)?
+        .with_color_space(ColorSpace::Srgb);
+    image.upload_srgb_with_origin(display, MipmapsOption::AutoGeneratedMipmaps, origin)
+}
 
-    // The original C++ implementation does not use mipmap, here use mipmap should be generally ok.
-    let texture = Texture2d::with_mipmaps(display, raw_image, MipmapsOption::AutoGeneratedMipmaps)
-        .map_err(GLErrorKind::CreateTexture)?;
+/// Load an 8-bit color image file from local to GPU as an sRGB texture, with the mip chain generated on CPU by `generator`.
+/// The colors are decoded to linear before filtering, unless `generator` is configured by `with_srgb(false)`.
+pub fn load_srgb_texture_with_mipmaps(display: &impl Facade, path: impl AsRef<Path>, generator: &MipmapGenerator) -> GLResult<SrgbTexture2d> {
 
-    Ok(texture)
+    let image = load_image(path)?
+        .with_color_space(ColorSpace::Srgb);
+    generator.generate(&image)?
+        .upload_srgb(display, TextureOrigin::BottomLeft)
 }
 
 pub fn load_custom_texture<T>(display: &impl Facade, bytes: Vec<T>, width: usize, height: usize, mipmaps: MipmapsOption, format: UncompressedFloatFormat) -> GLResult<Texture2d>
@@ -80,7 +84,7 @@ pub fn load_custom_texture<T>(display: &impl Facade, bytes: Vec<T>, width: usize
     let raw_image = match bytes.len() {
         | len if len == width * height * 3 => RawImage2d::from_raw_rgb(bytes,  (width as u32, height as u32)),
         | len if len == width * height * 4 => RawImage2d::from_raw_rgba(bytes, (width as u32, height as u32)),
-        | _ => panic!("The bytes size of the image is incorrect!")
+        | len => return Err(GLError::args(format!("The bytes size({}) of the {}x{} image is incorrect.", len, width, height))),
     };
     let texture = Texture2d::with_format(display, raw_image, format, mipmaps)
         .map_err(GLErrorKind::CreateTexture)?;
@@ -96,140 +100,117 @@ pub enum CubeMapFaceExtension {
     Png,
 }
 
+impl CubeMapFaceExtension {
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            | CubeMapFaceExtension::Hdr => "hdr",
+            | CubeMapFaceExtension::Png => "png",
+        }
+    }
+
+    /// The face orientation used by `load_cubemap`, which matches the cubemaps shipped in `media/texture/cube`.
+    pub fn default_origin(&self) -> TextureOrigin {
+        match self {
+            | CubeMapFaceExtension::Hdr => TextureOrigin::TopLeft,
+            | CubeMapFaceExtension::Png => TextureOrigin::BottomLeft,
+        }
+    }
+}
+
 /// Load cubemap from local to GPU.
 /// The file extension must be correctly specified.
 /// The path_prefix is the common file path prefix of each face.
 pub fn load_cubemap(display: &impl Facade, path_prefix: &str, extension: CubeMapFaceExtension) -> GLResult<Cubemap> {
-    match extension {
-        | CubeMapFaceExtension::Hdr => load_hdr_cubemap(display, path_prefix),
-        | CubeMapFaceExtension::Png => load_png_cubemap(display, path_prefix),
-   }
+    load_cubemap_with_origin(display, path_prefix, extension, extension.default_origin())
 }
 
-fn load_png_cubemap(display: &impl Facade, path_prefix: &str) -> GLResult<Cubemap> {
-
-    // Load pxiels from png files.
-    let png_images = load_cubemap_faces(path_prefix, "png", |path| load_png_pixels(path, false))?;
+/// Load cubemap from local to GPU, placing the top row of each face according to `origin`,
+/// in the same way as `load_texture_with_origin` does for 2D textures.
+///
+/// The mipmaps are averaged on CPU by a box filter, see `load_cubemap_with_mipmaps` for other filters.
+pub fn load_cubemap_with_origin(display: &impl Facade, path_prefix: &str, extension: CubeMapFaceExtension, origin: TextureOrigin) -> GLResult<Cubemap> {
+    build_cubemap(display, path_prefix, extension, origin, &MipmapGenerator::new(MipFilter::Box))
+}
 
-    // Generate Cubemap texture on GPU.
-    build_cubemap(display, png_images)
+/// Load cubemap from local to GPU, with the mip chain of each face generated on CPU by `generator`,
+/// instead of calling `generate_mipmaps` on the cubemap.
+pub fn load_cubemap_with_mipmaps(display: &impl Facade, path_prefix: &str, extension: CubeMapFaceExtension, generator: &MipmapGenerator) -> GLResult<Cubemap> {
+    build_cubemap(display, path_prefix, extension, extension.default_origin(), generator)
 }
 
-fn load_hdr_cubemap(display: &impl Facade, path_prefix: &str) -> GLResult<Cubemap> {
+fn build_cubemap(display: &impl Facade, path_prefix: &str, extension: CubeMapFaceExtension, origin: TextureOrigin, generator: &MipmapGenerator) -> GLResult<Cubemap> {
+
+    let chains = load_cubemap_images(path_prefix, extension)?.iter()
+        .map(|image| generator.generate(image))
+        .collect::<GLResult<Vec<_>>>()?;
+
+    let top = &chains[0].levels()[0];
+    let level_count = chains[0].levels().len();
+    let cubemap = Cubemap::empty_with_format(display, top.gpu_format(), MipmapsOption::EmptyMipmapsMax(level_count as u32 - 1), top.width())
+        .map_err(GLErrorKind::CreateTexture)?;
+
+    for level in 0..level_count {
+        let faces = chains.iter()
+            .map(|chain| chain.levels()[level].upload_with_origin(display, MipmapsOption::NoMipmap, origin))
+            .collect::<GLResult<Vec<_>>>()?;
+        blit_cubemap_faces(display, &cubemap, level as u32, &faces)?;
+    }
+
+    Ok(cubemap)
+}
 
-    // Load pxiels from hdr files.
-    let hdr_images = load_cubemap_faces(path_prefix, "hdr", |path| {
-        let mut hdr_file = File::open(&path)
-            .map_err(GLError::io)?;
-        hdrldr::load(&mut hdr_file)
-            .map_err(|e| GLError::custom(format!("{:?}", e)))
-    })?;
+/// Load the 6 faces of cubemap as separate 2D textures, in the order of `CUBEMAP_FACES_SUFFIXES`.
+pub(crate) fn load_cubemap_faces(display: &impl Facade, path_prefix: &str, extension: CubeMapFaceExtension, origin: TextureOrigin) -> GLResult<Vec<Texture2d>> {
 
-    // Generate Cubemap texture on GPU.
-    build_cubemap(display, hdr_images)
+    load_cubemap_images(path_prefix, extension)?.iter()
+        .map(|image| image.upload_with_origin(display, MipmapsOption::NoMipmap, origin))
+        .collect()
 }
 
-fn load_cubemap_faces<I, T, F>(path_prefix: &str, extension: &str, load_func: F) -> GLResult<Vec<I>>
-    where
-        I: ImageRawPixels<T>,
-        T: Clone,
-        F: Fn(String) -> GLResult<I> {
+fn load_cubemap_images(path_prefix: &str, extension: CubeMapFaceExtension) -> GLResult<Vec<Image>> {
 
-    let mut images: Vec<I> = Vec::with_capacity(6);
-    for suffix in CUBEMAP_FACES_SUFFIXES.into_iter() {
-        let path: String = path_prefix.to_owned() + "_" + suffix + "." + extension;
-        let image = load_func(path)?;
+    let mut images: Vec<Image> = Vec::with_capacity(6);
+    for suffix in CUBEMAP_FACES_SUFFIXES.iter() {
+        let path: String = path_prefix.to_owned() + "_" + suffix + "." + extension.as_str();
+        let image = load_image(path)?;
 
-        if image.dimension().0 != image.dimension().1 {
+        if image.width() != image.height() {
             return Err(GLError::custom("The cubemap face must share the same width and height."))
         }
 
         images.push(image);
     }
 
-    let dimension = (images[0].dimension().0, images[0].dimension().1);
-    if images.iter().any(|image| image.dimension().0 != dimension.0 || image.dimension().1 != dimension.1) {
+    let dimension = images[0].dimensions();
+    if images.iter().any(|image| image.dimensions() != dimension) {
         return Err(GLError::custom("The image dimension is different among cubemap faces."))
     }
 
     Ok(images)
 }
 
-fn build_cubemap<I, T>(display: &impl Facade, faces_pxiels: Vec<I>) -> GLResult<Cubemap>
-    where
-        I: ImageRawPixels<T>,
-        T: glium::texture::ToClientFormat + glium::texture::PixelValue + Clone {
+/// Copy the 2D textures to the faces of cubemap at mipmap `level`, in the order of `CUBEMAP_LAYWERS`.
+pub(crate) fn blit_cubemap_faces(display: &impl Facade, cubemap: &Cubemap, level: u32, faces: &[Texture2d]) -> GLResult<()> {
 
     // glium currently support upload texture to cubemap faces, so a manual blit is necessary.
     // See https://github.com/glium/glium/issues/644 for detail.
-    assert_eq!(faces_pxiels.len(), 6);
-    let dimension = faces_pxiels[0].dimension().0;
+    assert_eq!(faces.len(), 6);
 
-    let cubemap_texture = Cubemap::empty_with_mipmaps(display, MipmapsOption::EmptyMipmaps, dimension)
-        .map_err(GLErrorKind::CreateTexture)?;
+    let mipmap = cubemap.mipmap(level)
+        .ok_or_else(|| GLError::args(format!("The cubemap has no mipmap level {}.", level)))?;
+    let dimension = mipmap.width();
 
     // blit the face data from framebuffer to cubemap faces
     let blit_target = glium::BlitTarget { left: 0, bottom: 0, width: dimension as i32, height: dimension as i32 };
 
-    for (layer, pixels) in izip!(&CUBEMAP_LAYWERS, faces_pxiels) {
-        let framebuffer = glium::framebuffer::SimpleFrameBuffer::new(display, cubemap_texture.main_level().image(*layer))
+    for (layer, cubemap_face) in izip!(&CUBEMAP_LAYWERS, faces) {
+        let framebuffer = glium::framebuffer::SimpleFrameBuffer::new(display, mipmap.image(*layer))
             .map_err(BufferCreationErrorKind::FrameBuffer)?;
-        let cubemap_face = Texture2d::new(display, pixels.build_raw_image())
-            .map_err(GLErrorKind::CreateTexture)?;
 
         cubemap_face.as_surface()
             .blit_whole_color_to(&framebuffer, &blit_target, glium::uniforms::MagnifySamplerFilter::Linear);
     }
 
-    unsafe {
-        cubemap_texture.generate_mipmaps();
-    }
-
-    Ok(cubemap_texture)
-}
-
-fn flatten_hdr_pixels(pixels: Vec<hdrldr::RGB>) -> Vec<f32> {
-
-    // let pixels: Vec<f32> = hdr_image.data.into_iter()
-    //     .flat_map(|pixel| [pixel.r, pixel.g, pixel.b])
-    //     .collect();
-
-    let mut result = Vec::with_capacity(pixels.len() * 3);
-    for pixel in pixels {
-        result.extend([pixel.r, pixel.g, pixel.b].iter());
-    }
-    result
-}
-
-
-trait ImageRawPixels<T: Clone> {
-    fn dimension(&self) -> (u32, u32);
-    fn build_raw_image<'a>(self) -> RawImage2d<'a, T>;
-}
-
-impl ImageRawPixels<u8> for PngContent {
-
-    fn dimension(&self) -> (u32, u32) {
-        (self.width, self.height)
-    }
-
-    fn build_raw_image<'a>(self) -> RawImage2d<'a, u8> {
-        match self.color_type {
-            | png::ColorType::RGB  => RawImage2d::from_raw_rgb_reversed (&self.buffer, (self.width, self.height)),
-            | png::ColorType::RGBA => RawImage2d::from_raw_rgba_reversed(&self.buffer, (self.width, self.height)),
-            | _ => unreachable!()
-        }
-    }
-}
-
-impl ImageRawPixels<f32> for hdrldr::Image {
-
-    fn dimension(&self) -> (u32, u32) {
-        (self.width as u32, self.height as u32)
-    }
-
-    fn build_raw_image<'a>(self) -> RawImage2d<'a, f32> {
-        let pixels = flatten_hdr_pixels(self.data);
-        RawImage2d::from_raw_rgb(pixels, (self.width as u32, self.height as u32))
-    }
+    Ok(())
 }
@@ -0,0 +1,383 @@
+
+//! Uniforms whose names are decided at runtime.
+//!
+//! The `uniform!` macro of glium only accepts identifiers as names, so uniforms like `lights[2].Position`
+//! can not be set with it. `DynamicUniforms` stores owned names instead, and can be filled in loops:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::uniforms::DynamicUniforms;
+//!
+//! let mut uniforms = DynamicUniforms::new();
+//! uniforms
+//!     .add("MaterialInfo", &material_buffer)
+//!     .add_struct_array("lights", lights.iter())
+//!     .add("MVP", projection * mv);
+//! uniforms.warn_inactive(&program);
+//!
+//! mesh.render(frame, &program, &draw_params, &uniforms)?;
+//! ```
+//!
+//! Unlike the values of uniform blocks, a mismatched name is silently ignored by OpenGL,
+//! so use `warn_inactive` to report the names which are not active in the program.
+
+use crate::{Mat4F, Mat3F, Vec4F, Vec3F, Vec2F};
+use crate::layout::GLBool;
+use crate::subroutine::SubroutineSelection;
+use crate::lighting::{LightInfo, LightElement, PbrLightElement, SpotLightInfo, MaterialInfo, PbrMaterialInfo, FogInfo};
+
+use glium::uniforms::{Uniforms, UniformValue, UniformBuffer, UniformBlock, LayoutMismatchError, Sampler};
+use glium::program::{ShaderStage, UniformBlock as ReflectedBlock};
+use glium::buffer::Content;
+use glium::texture;
+use glium::Program;
+
+use lazy_static::lazy_static;
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::fmt;
+
+
+/// A collection of uniforms with owned names, which implements `glium::uniforms::Uniforms`.
+#[derive(Clone, Default)]
+pub struct DynamicUniforms<'a> {
+    values: Vec<(String, UniformValue<'a>)>,
+}
+
+impl<'a> DynamicUniforms<'a> {
+
+    pub fn new() -> DynamicUniforms<'a> {
+        DynamicUniforms { values: Vec::new() }
+    }
+
+    /// Set the uniform named `name`, replacing the previous value with the same name.
+    pub fn add(&mut self, name: impl Into<String>, value: impl IntoUniformValue<'a>) -> &mut DynamicUniforms<'a> {
+
+        let name = name.into();
+        let value = value.into_uniform_value();
+
+        match self.values.iter_mut().find(|(exist_name, exist)| *exist_name == name && is_same_stage(exist, &value)) {
+            | Some(exist) => exist.1 = value,
+            | None => self.values.push((name, value)),
+        }
+        self
+    }
+
+    /// Assign the subroutines chosen by `selection` to their subroutine uniforms.
+    pub fn add_subroutines(&mut self, selection: &'a SubroutineSelection) -> &mut DynamicUniforms<'a> {
+
+        for (name, stage, subroutine) in selection.iter() {
+            self.add(name, (subroutine, stage));
+        }
+        self
+    }
+
+    /// Set the elements of array uniform as `name[0]`, `name[1]`...
+    pub fn add_array<V>(&mut self, name: &str, values: impl IntoIterator<Item = V>) -> &mut DynamicUniforms<'a>
+        where
+            V: IntoUniformValue<'a> {
+
+        for (i, value) in values.into_iter().enumerate() {
+            self.add(format!("{}[{}]", name, i), value);
+        }
+        self
+    }
+
+    /// Set the members of struct uniform as `name.member`.
+    pub fn add_struct(&mut self, name: &str, value: &impl UniformStruct) -> &mut DynamicUniforms<'a> {
+        value.add_members(name, self);
+        self
+    }
+
+    /// Set the members of each struct in array uniform as `name[0].member`, `name[1].member`...
+    pub fn add_struct_array<'s, S>(&mut self, name: &str, values: impl IntoIterator<Item = &'s S>) -> &mut DynamicUniforms<'a>
+        where
+            S: UniformStruct + 's {
+
+        for (i, value) in values.into_iter().enumerate() {
+            value.add_members(&format!("{}[{}]", name, i), self);
+        }
+        self
+    }
+
+    /// The names which are neither active uniforms, uniform blocks, shader storage blocks nor subroutine uniforms of `program`.
+    pub fn inactive_names(&self, program: &Program) -> Vec<&str> {
+
+        self.values.iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| !is_active_name(program, name))
+            .collect()
+    }
+
+    /// Print a warning for each name which is not active in `program`.
+    ///
+    /// Each name is reported only once, so it is fine to call this every frame.
+    pub fn warn_inactive(&self, program: &Program) {
+
+        lazy_static! {
+            static ref REPORTED: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+        }
+
+        let inactive_names = self.inactive_names(program);
+        if inactive_names.is_empty() { return }
+
+        let mut reported = REPORTED.lock().unwrap();
+        for name in inactive_names {
+            if reported.insert(name.to_string()) {
+                println!("Warning: uniform '{}' is not active in the shader program, its value is ignored.", name);
+            }
+        }
+    }
+}
+
+impl<'a> Uniforms for DynamicUniforms<'a> {
+
+    fn visit_values<'b, F: FnMut(&str, UniformValue<'b>)>(&'b self, mut visit: F) {
+        for (name, value) in self.values.iter() {
+            visit(name, *value);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for DynamicUniforms<'a> {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `UniformValue` does not implement Debug, print the names only.
+        f.debug_list()
+            .entries(self.values.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+/// Subroutine uniforms of different shader stages can share the same name.
+fn is_same_stage(lhs: &UniformValue, rhs: &UniformValue) -> bool {
+
+    match (lhs, rhs) {
+        | (UniformValue::Subroutine(lhs_stage, _), UniformValue::Subroutine(rhs_stage, _)) => lhs_stage == rhs_stage,
+        | _ => true,
+    }
+}
+
+fn is_active_name(program: &Program, name: &str) -> bool {
+
+    if program.get_uniform(name).is_some()
+        || program.get_uniform_blocks().contains_key(name)
+        || program.get_shader_storage_blocks().contains_key(name)
+        || program.get_subroutine_uniforms().keys().any(|(subroutine_name, _)| subroutine_name == name) {
+        return true
+    }
+
+    // Some drivers only report the first element of arrays of basic types(e.g. `Weights[0]` with size 5).
+    if let Some(open) = name.rfind('[') {
+        if let Ok(index) = name[open + 1..].trim_end_matches(']').parse::<usize>() {
+            let first_element = format!("{}[0]", &name[..open]);
+            let array = program.get_uniform(&first_element).or_else(|| program.get_uniform(&name[..open]));
+            return array.and_then(|uniform| uniform.size).map_or(false, |size| index < size)
+        }
+    }
+
+    false
+}
+
+
+/// A value which can be stored in `DynamicUniforms`.
+///
+/// Plain values(scalars, vectors and matrices of `vek`) are copied, while textures and uniform buffers are borrowed.
+/// Textures can also be given as `Sampler` to set the sampler behavior(e.g. `texture.sampled().wrap_function(...)`).
+pub trait IntoUniformValue<'a> {
+    fn into_uniform_value(self) -> UniformValue<'a>;
+}
+
+impl<'a> IntoUniformValue<'a> for UniformValue<'a> {
+    fn into_uniform_value(self) -> UniformValue<'a> { self }
+}
+
+impl<'a, T: UniformBlock + Content + ?Sized> IntoUniformValue<'a> for &'a UniformBuffer<T> {
+
+    fn into_uniform_value(self) -> UniformValue<'a> {
+
+        fn matches<T: UniformBlock + Content + ?Sized>(block: &ReflectedBlock) -> Result<(), LayoutMismatchError> {
+            T::matches(&block.layout, 0)
+        }
+
+        UniformValue::Block(self.as_slice_any(), matches::<T>)
+    }
+}
+
+impl<'a> IntoUniformValue<'a> for (&'a str, ShaderStage) {
+    fn into_uniform_value(self) -> UniformValue<'a> { UniformValue::Subroutine(self.1, self.0) }
+}
+
+macro_rules! impl_plain_uniform_value {
+    ($($ty:ty => |$value:ident| $convert:expr),+ $(,)?) => {
+        $(
+            impl<'a> IntoUniformValue<'a> for $ty {
+                fn into_uniform_value(self) -> UniformValue<'a> {
+                    let $value = self;
+                    $convert
+                }
+            }
+        )+
+    };
+}
+
+impl_plain_uniform_value! {
+    f32 => |v| UniformValue::Float(v),
+    i32 => |v| UniformValue::SignedInt(v),
+    u32 => |v| UniformValue::UnsignedInt(v),
+    bool => |v| UniformValue::Bool(v),
+    GLBool => |v| UniformValue::Bool(v.into()),
+    [f32; 2] => |v| UniformValue::Vec2(v),
+    [f32; 3] => |v| UniformValue::Vec3(v),
+    [f32; 4] => |v| UniformValue::Vec4(v),
+    [i32; 2] => |v| UniformValue::IntVec2(v),
+    [i32; 3] => |v| UniformValue::IntVec3(v),
+    [i32; 4] => |v| UniformValue::IntVec4(v),
+    [u32; 2] => |v| UniformValue::UnsignedIntVec2(v),
+    [u32; 3] => |v| UniformValue::UnsignedIntVec3(v),
+    [u32; 4] => |v| UniformValue::UnsignedIntVec4(v),
+    [[f32; 2]; 2] => |v| UniformValue::Mat2(v),
+    [[f32; 3]; 3] => |v| UniformValue::Mat3(v),
+    [[f32; 4]; 4] => |v| UniformValue::Mat4(v),
+    Vec2F => |v| UniformValue::Vec2(v.into_array()),
+    Vec3F => |v| UniformValue::Vec3(v.into_array()),
+    Vec4F => |v| UniformValue::Vec4(v.into_array()),
+    Mat3F => |v| UniformValue::Mat3(v.into_col_arrays()),
+    Mat4F => |v| UniformValue::Mat4(v.into_col_arrays()),
+}
+
+macro_rules! impl_texture_uniform_value {
+    ($($texture:ident),+ $(,)?) => {
+        $(
+            impl<'a> IntoUniformValue<'a> for &'a texture::$texture {
+                fn into_uniform_value(self) -> UniformValue<'a> {
+                    UniformValue::$texture(self, None)
+                }
+            }
+
+            impl<'a> IntoUniformValue<'a> for Sampler<'a, texture::$texture> {
+                fn into_uniform_value(self) -> UniformValue<'a> {
+                    UniformValue::$texture(self.0, Some(self.1))
+                }
+            }
+        )+
+    };
+}
+
+impl_texture_uniform_value! {
+    Texture2d, SrgbTexture2d, DepthTexture2d, IntegralTexture2d, UnsignedTexture2d,
+    Texture2dArray, DepthTexture2dArray, Texture3d, Cubemap, SrgbCubemap, DepthCubemap, CompressedTexture2d,
+}
+
+
+/// A struct uniform declared outside of blocks, whose members are set one by one(e.g. `lights[0].Position`).
+pub trait UniformStruct {
+    /// Add each member to `uniforms` as `name.member`.
+    fn add_members(&self, name: &str, uniforms: &mut DynamicUniforms<'_>);
+}
+
+macro_rules! impl_uniform_struct {
+    ($($ty:ty { $($member:ident),+ $(,)? }),+ $(,)?) => {
+        $(
+            impl UniformStruct for $ty {
+                fn add_members(&self, name: &str, uniforms: &mut DynamicUniforms<'_>) {
+                    $(
+                        uniforms.add(format!("{}.{}", name, stringify!($member)), self.$member);
+                    )+
+                }
+            }
+        )+
+    };
+}
+
+impl_uniform_struct! {
+    LightInfo { LightPosition, La, L },
+    LightElement { Position, La, L },
+    PbrLightElement { Position, L },
+    SpotLightInfo { SpotPosition, L, La, SpotDirection, Exponent, Cutoff },
+    MaterialInfo { Ka, Kd, Ks, Shininess },
+    PbrMaterialInfo { MaterialColor, MaterialRough, IsMetal },
+    FogInfo { MaxDist, MinDist, FogColor },
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn names(uniforms: &DynamicUniforms) -> Vec<String> {
+        let mut names = Vec::new();
+        uniforms.visit_values(|name, _| names.push(name.to_string()));
+        names
+    }
+
+    fn float_of(uniforms: &DynamicUniforms, name: &str) -> Option<f32> {
+        let mut result = None;
+        uniforms.visit_values(|visited, value| match value {
+            | UniformValue::Float(v) if visited == name => result = Some(v),
+            | _ => {},
+        });
+        result
+    }
+
+    #[test]
+    fn add_replaces_the_value_with_the_same_name() {
+
+        let mut uniforms = DynamicUniforms::new();
+        uniforms
+            .add("Scale", 1.0_f32)
+            .add("Offset", 2.0_f32)
+            .add("Scale", 3.0_f32);
+
+        assert_eq!(names(&uniforms), vec!["Scale", "Offset"]);
+        assert_eq!(float_of(&uniforms, "Scale"), Some(3.0));
+    }
+
+    #[test]
+    fn subroutines_of_different_stages_share_a_name() {
+
+        let mut uniforms = DynamicUniforms::new();
+        uniforms
+            .add("shadeModel", ("phong", ShaderStage::Vertex))
+            .add("shadeModel", ("diffuse", ShaderStage::Fragment))
+            .add("shadeModel", ("toon", ShaderStage::Fragment));
+
+        let mut subroutines = Vec::new();
+        uniforms.visit_values(|name, value| if let UniformValue::Subroutine(stage, subroutine) = value {
+            subroutines.push((name.to_string(), stage, subroutine.to_string()));
+        });
+        assert_eq!(subroutines, vec![
+            (String::from("shadeModel"), ShaderStage::Vertex, String::from("phong")),
+            (String::from("shadeModel"), ShaderStage::Fragment, String::from("toon")),
+        ]);
+    }
+
+    #[test]
+    fn array_and_struct_members_are_flattened() {
+
+        let material = MaterialInfo { Shininess: 100.0, ..Default::default() };
+        let lights = [LightElement::default(), LightElement::default()];
+
+        let mut uniforms = DynamicUniforms::new();
+        uniforms
+            .add_array("Weight", [0.5_f32, 0.25].iter().cloned())
+            .add_struct("Material", &material)
+            .add_struct_array("lights", lights.iter());
+
+        assert_eq!(names(&uniforms), vec![
+            "Weight[0]", "Weight[1]",
+            "Material.Ka", "Material.Kd", "Material.Ks", "Material.Shininess",
+            "lights[0].Position", "lights[0].La", "lights[0].L",
+            "lights[1].Position", "lights[1].La", "lights[1].L",
+        ]);
+        assert_eq!(float_of(&uniforms, "Weight[1]"), Some(0.25));
+        assert_eq!(float_of(&uniforms, "Material.Shininess"), Some(100.0));
+
+        // Setting the struct again replaces its members instead of duplicating them.
+        let brighter = MaterialInfo { Shininess: 50.0, ..Default::default() };
+        uniforms.add_struct("Material", &brighter);
+        assert_eq!(names(&uniforms).len(), 12);
+        assert_eq!(float_of(&uniforms, "Material.Shininess"), Some(50.0));
+    }
+}
@@ -111,6 +111,15 @@ pub fn print_active_uniforms(program: &glium::Program) {
     println!("-------------------------------------------------------------");
 }
 
+pub fn print_active_subroutines(program: &glium::Program) {
+    println!("-------------------------------------------------------------");
+    println!("Active subroutine uniforms:");
+    for uniform in crate::subroutine::SubroutineReflection::new(program).uniforms() {
+        println!("\tName: {:10}  Stage: {:?}  Subroutines: [{}]", uniform.name, uniform.stage, uniform.subroutines.join(", "));
+    }
+    println!("-------------------------------------------------------------");
+}
+
 pub fn print_active_uniform_blocks(program: &glium::Program) {
 
     println!("-------------------------------------------------------------");
@@ -145,3 +154,47 @@ fn print_uniform_block_layout(layout: &glium::program::BlockLayout, indent: usiz
         | _ => unimplemented!(),
     }
 }
+
+/// A member of uniform block, flattened by `flatten_uniform_block_layout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockMember {
+    /// A member of basic type, with its offset in bytes from the beginning of the block.
+    Basic { ty: glium::uniforms::UniformType, offset: usize },
+    /// An array whose first element is flattened as `name[0]`.
+    Array { length: usize },
+    /// A runtime-sized array whose first element is flattened as `name[0]`.
+    DynamicArray,
+}
+
+/// Flatten the layout of a uniform block into its members, named by their path in the block(e.g. `Lights[0].Position`).
+pub fn flatten_uniform_block_layout(layout: &glium::program::BlockLayout) -> Vec<(String, BlockMember)> {
+
+    let mut members = Vec::new();
+    flatten_block_layout(layout, String::new(), &mut members);
+    members
+}
+
+fn flatten_block_layout(layout: &glium::program::BlockLayout, path: String, flattened: &mut Vec<(String, BlockMember)>) {
+
+    use glium::program::BlockLayout;
+
+    match layout {
+        | BlockLayout::Struct { members } => {
+            for (name, member) in members {
+                let member_path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                flatten_block_layout(member, member_path, flattened);
+            }
+        },
+        | BlockLayout::BasicType { ty, offset_in_buffer } => {
+            flattened.push((path, BlockMember::Basic { ty: *ty, offset: *offset_in_buffer }));
+        },
+        | BlockLayout::Array { content, length } => {
+            flattened.push((path.clone(), BlockMember::Array { length: *length }));
+            flatten_block_layout(content, format!("{}[0]", path), flattened);
+        },
+        | BlockLayout::DynamicSizedArray { content } => {
+            flattened.push((path.clone(), BlockMember::DynamicArray));
+            flatten_block_layout(content, format!("{}[0]", path), flattened);
+        },
+    }
+}
@@ -0,0 +1,126 @@
+
+//! Vertex types, and the validation of them against the attributes of shader program.
+//!
+//! Declare a vertex type with `#[derive(Vertex)]` instead of `glium::implement_vertex!`:
+//!
+//! ```ignore
+//! use glsl_cookbook_rs::vertex::Vertex;
+//!
+//! #[allow(non_snake_case)]
+//! #[repr(C)]
+//! #[derive(Debug, Clone, Copy, Default, Vertex)]
+//! pub struct PlaneVertex {
+//!     VertexPosition: [f32; 3],
+//!     VertexNormal  : [f32; 3],
+//!     #[vertex(rename = "VertexTexCoord")]
+//!     uv: [f32; 2],
+//!     #[vertex(normalize)]
+//!     VertexColor: [u8; 4],
+//! }
+//! ```
+//!
+//! The fields are packed as `#[repr(C)]`, so there is no need to pad them to vec4 like uniform blocks.
+
+use crate::error::{GLResult, GLError};
+
+use glium::vertex::{AttributeType, VertexFormat};
+use glium::{Program, Handle, GlObject};
+
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+pub use glsl_cookbook_derive::Vertex;
+
+
+/// Check the vertex type `V` against the active attributes of `program`.
+///
+/// Every attribute of the program must be provided by `V` with a compatible type.
+pub fn verify_vertex<V: glium::Vertex>(program: &Program) -> GLResult<()> {
+    verify_vertex_format(program, &V::build_bindings(), true)
+}
+
+thread_local! {
+    /// The (program, vertex type, is_complete) combinations that have passed `verify_vertex_once`.
+    static VERIFIED_VERTICES: RefCell<HashSet<(Handle, TypeId, bool)>> = RefCell::new(HashSet::new());
+}
+
+/// Check the vertex type `V` against `program` as `verify_vertex_format` does, but only until the combination passes,
+/// so that it can be called on every draw without building the bindings again.
+///
+/// OpenGL may reuse the name of a deleted program, then a new program of that name is not checked, and glium reports
+/// the mismatch as `DrawError::AttributeMissing` or `DrawError::AttributeTypeMismatch` instead.
+pub fn verify_vertex_once<V: glium::Vertex + 'static>(program: &Program, is_complete: bool) -> GLResult<()> {
+
+    let key = (program.get_id(), TypeId::of::<V>(), is_complete);
+    if VERIFIED_VERTICES.with(|verified| verified.borrow().contains(&key)) {
+        return Ok(())
+    }
+
+    verify_vertex_format(program, &V::build_bindings(), is_complete)?;
+    VERIFIED_VERTICES.with(|verified| verified.borrow_mut().insert(key));
+    Ok(())
+}
+
+/// Check the attributes in `format` against the active attributes of `program`.
+///
+/// Set `is_complete` to false if some attributes are provided by other vertex buffers(e.g. the per-instance attributes),
+/// then only the attributes in `format` are checked.
+pub fn verify_vertex_format(program: &Program, format: &VertexFormat, is_complete: bool) -> GLResult<()> {
+
+    let mut mismatches = Vec::new();
+
+    for (name, attribute) in program.attributes() {
+        // built-in inputs such as gl_VertexID are not provided by vertex buffers.
+        if name.starts_with("gl_") { continue }
+
+        match format.iter().find(|binding| binding.0 == name.as_str()) {
+            | Some(binding) => {
+                let (ty, is_normalize) = (binding.2, binding.3);
+
+                if attribute.size != 1 {
+                    mismatches.push(format!("'{}' is an array of {} elements in shader, which is not supported.", name, attribute.size));
+                } else if attribute.ty.get_num_components() != ty.get_num_components() {
+                    mismatches.push(format!("'{}' is {:?} in shader, but {:?} in vertex.", name, attribute.ty, ty));
+                } else if is_integer_input(attribute.ty) && (!is_integer_data(ty) || is_normalize) {
+                    let data = if is_normalize { "normalized integers" } else { "floats" };
+                    mismatches.push(format!("'{}' is {:?} in shader, but the vertex provides {}.", name, attribute.ty, data));
+                }
+            },
+            | None if is_complete => {
+                mismatches.push(format!("'{}' is declared as {:?} in shader, but missing in vertex.", name, attribute.ty));
+            },
+            | None => {},
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(GLError::interface(format!("Vertex attributes do not match the shader program:\n\t{}", mismatches.join("\n\t"))))
+    }
+}
+
+/// Whether the attribute is declared as `int`, `uint` or their vectors in shader.
+fn is_integer_input(ty: AttributeType) -> bool {
+
+    match ty {
+        | AttributeType::I32 | AttributeType::I32I32 | AttributeType::I32I32I32 | AttributeType::I32I32I32I32
+        | AttributeType::U32 | AttributeType::U32U32 | AttributeType::U32U32U32 | AttributeType::U32U32U32U32 => true,
+        | _ => false,
+    }
+}
+
+/// Whether the data of attribute in vertex buffer is integer.
+fn is_integer_data(ty: AttributeType) -> bool {
+
+    match ty {
+        | AttributeType::I8  | AttributeType::I8I8   | AttributeType::I8I8I8    | AttributeType::I8I8I8I8
+        | AttributeType::U8  | AttributeType::U8U8   | AttributeType::U8U8U8    | AttributeType::U8U8U8U8
+        | AttributeType::I16 | AttributeType::I16I16 | AttributeType::I16I16I16 | AttributeType::I16I16I16I16
+        | AttributeType::U16 | AttributeType::U16U16 | AttributeType::U16U16U16 | AttributeType::U16U16U16U16
+        | AttributeType::I32 | AttributeType::I32I32 | AttributeType::I32I32I32 | AttributeType::I32I32I32I32
+        | AttributeType::U32 | AttributeType::U32U32 | AttributeType::U32U32U32 | AttributeType::U32U32U32U32 => true,
+        | _ => false,
+    }
+}